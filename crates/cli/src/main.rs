@@ -10,6 +10,18 @@
 //!
 //! # scan network
 //! sudo r-lancli
+//!
+//! # discover devices without sending any packets
+//! sudo r-lancli --passive --duration 5m
+//!
+//! # attach .local hostnames and advertised services
+//! sudo r-lancli --mdns
+//!
+//! # attach UPnP names and models of TVs, NAS boxes and routers
+//! sudo r-lancli --ssdp
+//!
+//! # without root only TCP connect scanning is available
+//! r-lancli --targets 192.168.1.1-20 --ports 22,80,443
 //! ```
 use clap::Parser;
 use color_eyre::eyre::{Result, eyre};
@@ -17,39 +29,64 @@ use core::time;
 use itertools::Itertools;
 use r_lanlib::{
     error::Result as LibResult,
+    fingerprint, names,
     network::{self, NetworkInterface, get_default_gateway},
     oui,
     scanners::{
-        Device, IDLE_TIMEOUT, ScanMessage, Scanner, arp_scanner::ARPScanner,
-        syn_scanner::SYNScanner,
+        Device, IDLE_TIMEOUT, PortState, ScanMessage, ScanStats, Scanner,
+        arp_scanner::ARPScanner,
+        connect_scanner::ConnectScanner,
+        mdns_scanner::MDNSScanner,
+        passive_scanner::PassiveScanner,
+        rate::{DEFAULT_MAX_RATE, DEFAULT_MIN_RATE, RateBounds},
+        service_scanner::ServiceScanner,
+        ssdp_scanner::SSDPScanner,
+        syn_scanner::{SYNScanner, TcpScanType},
     },
+    services::{self, db::ServicesDb, traits::Services},
     targets::{ips::IPTargets, ports::PortTargets},
 };
 use std::{
     collections::{HashMap, HashSet},
+    io::{self, IsTerminal},
     net::Ipv4Addr,
+    path::PathBuf,
     sync::{
         Arc,
         mpsc::{self, Receiver},
     },
-    time::Duration,
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
 // 30 days
 const OUI_MAX_AGE: Duration = Duration::from_secs(60 * 60 * 24 * 30);
 
+// Number of characters filled by a complete progress bar
+const PROGRESS_BAR_WIDTH: usize = 30;
+
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 /// CLI for LAN Network ARP and SYN scanning
 struct Args {
-    /// Comma separated list of IPs, IP ranges, and CIDR blocks to scan
+    /// Comma separated list of IPs, IP ranges, octet ranges, CIDR blocks and
+    /// hostnames to scan. Entries prefixed with ! are excluded and @file
+    /// (or @- for stdin) reads targets from a file
     #[arg(short, long, use_value_delimiter = true)]
     targets: Vec<String>,
 
-    /// Comma separated list of ports and port ranges to scan
+    /// Comma separated list of IPs, IP ranges, and CIDR blocks never to scan
+    #[arg(long, use_value_delimiter = true)]
+    exclude: Vec<String>,
+
+    /// Comma separated list of ports and port ranges to scan. Entries
+    /// prefixed with ! are excluded
     #[arg(short, long, default_value = "1-65535", use_value_delimiter = true)]
     ports: Vec<String>,
 
+    /// Comma separated list of ports and port ranges never to scan
+    #[arg(long, use_value_delimiter = true)]
+    exclude_ports: Vec<String>,
+
     /// Output final report in json instead of table text
     #[arg(long, default_value_t = false)]
     json: bool,
@@ -62,6 +99,20 @@ struct Args {
     #[arg(long, default_value_t = false)]
     arp_only: bool,
 
+    /// Only listen for traffic to discover devices without sending any
+    /// packets (omits SYN scanning). Host name, mDNS and SSDP lookups send
+    /// packets so can't be combined with it
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with_all = ["host_names", "mdns", "ssdp"]
+    )]
+    passive: bool,
+
+    /// How long to listen for traffic in passive mode
+    #[arg(long, value_parser = humantime::parse_duration, default_value = "30s")]
+    duration: Duration,
+
     /// Perform vendor lookups
     #[arg(long, default_value_t = false)]
     vendor: bool,
@@ -70,6 +121,23 @@ struct Args {
     #[arg(long, default_value_t = false)]
     host_names: bool,
 
+    /// Fall back to NetBIOS and LLMNR queries for hosts without reverse dns
+    /// names
+    #[arg(long, default_value_t = false, requires = "host_names")]
+    netbios_names: bool,
+
+    /// Discover .local hostnames and advertised services via mDNS / DNS-SD
+    #[arg(long, default_value_t = false)]
+    mdns: bool,
+
+    /// Discover UPnP device names and models via SSDP
+    #[arg(long, default_value_t = false)]
+    ssdp: bool,
+
+    /// Probe open ports to detect service names, products and versions
+    #[arg(long, default_value_t = false)]
+    detect_services: bool,
+
     /// Set idle timeout in milliseconds for all scanners
     #[arg(long, default_value_t = IDLE_TIMEOUT)]
     idle_timeout_ms: u16,
@@ -88,6 +156,53 @@ struct Args {
     #[arg(long, value_parser = humantime::parse_duration, default_value = "200µs")]
     throttle: Duration,
 
+    /// Adapts the packet send rate to observed loss instead of using the
+    /// fixed throttle
+    #[arg(long, default_value_t = false)]
+    adaptive_rate: bool,
+
+    /// Lowest rate in packets per second the adaptive rate backs off to
+    #[arg(long, default_value_t = DEFAULT_MIN_RATE)]
+    min_rate: u32,
+
+    /// Highest rate in packets per second the adaptive rate starts at
+    #[arg(long, default_value_t = DEFAULT_MAX_RATE)]
+    max_rate: u32,
+
+    /// Probes targets in a random order, interleaving hosts and ports
+    #[arg(long, default_value_t = false)]
+    randomize: bool,
+
+    /// Seeds the random probe order so scans can be reproduced. Implies
+    /// --randomize
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Number of times ARP and SYN targets that have not answered are
+    /// probed again
+    #[arg(long, default_value_t = 0)]
+    retries: u8,
+
+    /// How long to wait for replies before unanswered targets are probed
+    /// again
+    #[arg(long, value_parser = humantime::parse_duration, default_value = "500ms")]
+    retry_delay: Duration,
+
+    /// Reports closed and filtered ports along with open ports, showing
+    /// which ports are actively rejected
+    #[arg(long, default_value_t = false)]
+    report_non_open: bool,
+
+    /// TCP probes to send: syn finds open ports, while ack, fin, null and
+    /// xmas map firewall rules and report every port
+    #[arg(long, value_parser = parse_scan_type, default_value = "syn")]
+    scan_type: TcpScanType,
+
+    /// File of service names in /etc/services format, replacing the
+    /// built-in names of the ports it lists
+    #[arg(long)]
+    services_file: Option<PathBuf>,
+
     /// Prints debug logs including those from r-lanlib
     #[arg(long, default_value_t = false)]
     debug: bool,
@@ -115,11 +230,19 @@ fn initialize_logger(args: &Args) -> Result<()> {
 fn print_args(args: &Args, interface: &NetworkInterface) {
     log::info!("configuration:");
     log::info!("targets:         {:?}", args.targets);
+    log::info!("exclude:         {:?}", args.exclude);
     log::info!("ports            {:?}", args.ports);
+    log::info!("exclude_ports:   {:?}", args.exclude_ports);
     log::info!("json:            {}", args.json);
     log::info!("arpOnly:         {}", args.arp_only);
+    log::info!("passive:         {}", args.passive);
+    log::info!("duration:        {:?}", args.duration);
     log::info!("vendor:          {}", args.vendor);
     log::info!("host_names:      {}", args.host_names);
+    log::info!("netbios_names:   {}", args.netbios_names);
+    log::info!("mdns:            {}", args.mdns);
+    log::info!("ssdp:            {}", args.ssdp);
+    log::info!("detect_services: {}", args.detect_services);
     log::info!("quiet:           {}", args.quiet);
     log::info!("idle_timeout_ms: {}", args.idle_timeout_ms);
     log::info!(
@@ -130,6 +253,124 @@ fn print_args(args: &Args, interface: &NetworkInterface) {
     log::info!("user_ip:         {}", interface.ipv4);
     log::info!("source_port:     {}", args.source_port);
     log::info!("throttle         {:?}", args.throttle);
+    log::info!("adaptive_rate:   {}", args.adaptive_rate);
+    log::info!("min_rate:        {}", args.min_rate);
+    log::info!("max_rate:        {}", args.max_rate);
+    log::info!("seed:            {:?}", args.seed);
+    log::info!("retries:         {}", args.retries);
+    log::info!("retry_delay:     {:?}", args.retry_delay);
+    log::info!("report_non_open: {}", args.report_non_open);
+    log::info!("scan_type:       {}", args.scan_type);
+    log::info!("services_file:   {:?}", args.services_file);
+}
+
+fn load_services(args: &Args) -> LibResult<Arc<dyn Services>> {
+    let Some(path) = &args.services_file else {
+        return Ok(services::default());
+    };
+
+    let mut db = ServicesDb::default();
+    db.load_file(path)?;
+
+    Ok(Arc::new(db))
+}
+
+fn render_progress(sent: usize, total: usize, eta: Option<Duration>) -> String {
+    let ratio = if total == 0 {
+        1.0
+    } else {
+        (sent as f64 / total as f64).min(1.0)
+    };
+
+    let filled = (ratio * PROGRESS_BAR_WIDTH as f64).round() as usize;
+
+    let eta = eta
+        .map(|d| {
+            humantime::format_duration(Duration::from_secs(d.as_secs()))
+                .to_string()
+        })
+        .unwrap_or_else(|| "?".to_string());
+
+    format!(
+        "[{}{}] {:>3}% {}/{} eta {}",
+        "#".repeat(filled),
+        "-".repeat(PROGRESS_BAR_WIDTH - filled),
+        (ratio * 100.0).round(),
+        sent,
+        total,
+        eta
+    )
+}
+
+/// Draws progress reports on stderr, unless quiet or not on a terminal
+#[derive(Default)]
+struct ProgressBar {
+    drawn: bool,
+}
+
+impl ProgressBar {
+    fn update(&mut self, sent: usize, total: usize, eta: Option<Duration>) {
+        if !log::log_enabled!(log::Level::Info) || !io::stderr().is_terminal() {
+            return;
+        }
+
+        eprint!("\r{}", render_progress(sent, total, eta));
+        self.drawn = true;
+    }
+
+    fn finish(&mut self) {
+        if self.drawn {
+            eprintln!();
+            self.drawn = false;
+        }
+    }
+}
+
+fn parse_scan_type(value: &str) -> std::result::Result<TcpScanType, String> {
+    match value.to_lowercase().as_str() {
+        "syn" => Ok(TcpScanType::Syn),
+        "ack" => Ok(TcpScanType::Ack),
+        "fin" => Ok(TcpScanType::Fin),
+        "null" => Ok(TcpScanType::Null),
+        "xmas" => Ok(TcpScanType::Xmas),
+        _ => Err(format!(
+            "unknown scan type {value:?}, expected syn, ack, fin, null or xmas"
+        )),
+    }
+}
+
+// Picks a seed for --randomize runs that didn't supply one
+fn random_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or_default()
+}
+
+fn rate_bounds(args: &Args) -> Option<RateBounds> {
+    args.adaptive_rate.then_some(RateBounds {
+        min: args.min_rate,
+        max: args.max_rate,
+    })
+}
+
+fn log_stats(stats: &ScanStats) {
+    match stats.rate {
+        Some(rate) => log::info!(
+            "sent {} probes, ending at {} pps after {} backoffs",
+            stats.sent,
+            rate,
+            stats.backoffs
+        ),
+        None => log::info!("sent {} probes unthrottled", stats.sent),
+    }
+
+    if stats.rejected > 0 {
+        log::info!(
+            "rejected {} replies that did not acknowledge a probe",
+            stats.rejected
+        );
+    }
 }
 
 fn process_arp(
@@ -141,18 +382,31 @@ fn process_arp(
     log::info!("starting arp scan...");
 
     let handle = scanner.scan()?;
+    let mut progress = ProgressBar::default();
 
     loop {
         let msg = rx.recv()?;
 
         match msg {
             ScanMessage::Done => {
+                progress.finish();
                 log::debug!("scanning complete");
                 break;
             }
+            ScanMessage::Progress {
+                sent, total, eta, ..
+            } => {
+                progress.update(sent, total, eta);
+            }
+            ScanMessage::Stats(stats) => {
+                progress.finish();
+                log_stats(&stats);
+            }
             ScanMessage::ARPScanDevice(m) => {
                 log::debug!("received scanning message: {:?}", m);
-                arp_results.insert(m.to_owned());
+                // passive scans report a device again once its DHCP
+                // fingerprint is learned
+                arp_results.replace(m.to_owned());
             }
             _ => {}
         }
@@ -169,7 +423,7 @@ fn process_arp(
 fn print_arp(args: &Args, devices: &Vec<Device>) -> Result<()> {
     log::info!("arp results:");
 
-    if args.quiet && !args.arp_only {
+    if args.quiet && !args.arp_only && !args.passive {
         // only print results of SYN scanner
         return Ok(());
     }
@@ -180,9 +434,22 @@ fn print_arp(args: &Args, devices: &Vec<Device>) -> Result<()> {
     } else {
         let mut arp_table = prettytable::Table::new();
 
-        arp_table.add_row(prettytable::row![
-            "IP", "HOSTNAME", "MAC", "VENDOR", "LATENCY",
-        ]);
+        let mut header =
+            prettytable::row!["IP", "HOSTNAME", "MAC", "VENDOR", "LATENCY",];
+
+        if args.passive {
+            header.add_cell(prettytable::cell!("OS"));
+        }
+
+        if args.mdns {
+            header.add_cell(prettytable::cell!("SERVICES"));
+        }
+
+        if args.ssdp {
+            header.add_cell(prettytable::cell!("UPNP"));
+        }
+
+        arp_table.add_row(header);
 
         for d in devices.iter() {
             let ip_field = if d.is_current_host {
@@ -196,9 +463,23 @@ fn print_arp(args: &Args, devices: &Vec<Device>) -> Result<()> {
                 .latency_ms
                 .map(|ms| format!("{}ms", ms))
                 .unwrap_or_default();
-            arp_table.add_row(prettytable::row![
+            let mut row = prettytable::row![
                 ip_field, d.hostname, d.mac, d.vendor, latency
-            ]);
+            ];
+
+            if args.passive {
+                row.add_cell(prettytable::cell!(os_description(d)));
+            }
+
+            if args.mdns {
+                row.add_cell(prettytable::cell!(advertised_services(d)));
+            }
+
+            if args.ssdp {
+                row.add_cell(prettytable::cell!(upnp_description(d)));
+            }
+
+            arp_table.add_row(row);
         }
 
         arp_table.printstd();
@@ -221,26 +502,41 @@ fn process_syn(
     log::info!("starting syn scan...");
 
     let handle = scanner.scan()?;
+    let mut progress = ProgressBar::default();
 
     loop {
         let msg = rx.recv()?;
 
         match msg {
             ScanMessage::Done => {
+                progress.finish();
                 log::debug!("scanning complete");
                 break;
             }
+            ScanMessage::Progress {
+                sent, total, eta, ..
+            } => {
+                progress.update(sent, total, eta);
+            }
+            ScanMessage::Stats(stats) => {
+                progress.finish();
+                log_stats(&stats);
+            }
             ScanMessage::SYNScanDevice(device) => {
                 log::debug!("received syn scanning device: {:?}", device);
                 let found_device = syn_results.get_mut(&device.ip);
                 match found_device {
-                    Some(d) => d.open_ports.0.extend(device.open_ports.0),
-                    None => {
-                        log::warn!(
-                            "received syn result for unknown device: {:?}",
-                            device
+                    Some(d) => {
+                        d.open_ports.0.extend(device.open_ports.0);
+                        d.os_guess = fingerprint::most_confident(
+                            d.os_guess.take(),
+                            device.os_guess,
                         );
                     }
+                    // connect scans probe IPs rather than known devices
+                    None => {
+                        syn_results.insert(device.ip, device);
+                    }
                 }
             }
             _ => {}
@@ -252,6 +548,215 @@ fn process_syn(
     Ok(syn_results)
 }
 
+fn process_services(
+    scanner: &dyn Scanner,
+    device_map: &mut HashMap<Ipv4Addr, Device>,
+    rx: Receiver<ScanMessage>,
+) -> LibResult<()> {
+    log::info!("starting service detection...");
+
+    let handle = scanner.scan()?;
+
+    loop {
+        let msg = rx.recv()?;
+
+        match msg {
+            ScanMessage::Done => {
+                log::debug!("service detection complete");
+                break;
+            }
+            ScanMessage::ServiceScanDevice(device) => {
+                log::debug!("received service scanning device: {:?}", device);
+                if let Some(d) = device_map.get_mut(&device.ip) {
+                    for port in device.open_ports.0 {
+                        d.open_ports.0.replace(port);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    handle.join()??;
+
+    Ok(())
+}
+
+/// Runs service detection on the open ports of each device if requested
+fn detect_services(
+    args: &Args,
+    device_map: &mut HashMap<Ipv4Addr, Device>,
+) -> Result<()> {
+    if !args.detect_services {
+        return Ok(());
+    }
+
+    let (tx, rx) = mpsc::channel::<ScanMessage>();
+
+    let scanner = ServiceScanner::builder()
+        .targets(device_map.values().cloned().collect::<Vec<_>>())
+        .notifier(tx)
+        .build()?;
+
+    process_services(&scanner, device_map, rx)?;
+
+    Ok(())
+}
+
+fn process_mdns<'a>(
+    scanner: &dyn Scanner,
+    devices: impl IntoIterator<Item = &'a mut Device>,
+    rx: Receiver<ScanMessage>,
+) -> LibResult<()> {
+    let mut mdns_results: HashMap<Ipv4Addr, Device> = HashMap::new();
+
+    log::info!("starting mdns discovery...");
+
+    let handle = scanner.scan()?;
+
+    loop {
+        let msg = rx.recv()?;
+
+        match msg {
+            ScanMessage::Done => {
+                log::debug!("mdns discovery complete");
+                break;
+            }
+            ScanMessage::MDNSScanDevice(device) => {
+                log::debug!("received mdns scanning device: {:?}", device);
+                mdns_results.insert(device.ip, device);
+            }
+            _ => {}
+        }
+    }
+
+    handle.join()??;
+
+    // mDNS responses carry no MAC so merge into known devices by IP
+    for d in devices {
+        if let Some(found) = mdns_results.remove(&d.ip) {
+            if d.hostname.is_empty() {
+                d.hostname = found.hostname;
+            }
+            d.services = found.services;
+        }
+    }
+
+    Ok(())
+}
+
+/// Attaches mDNS hostnames and advertised services to devices if requested
+fn discover_mdns<'a>(
+    args: &Args,
+    interface: &Arc<NetworkInterface>,
+    devices: impl IntoIterator<Item = &'a mut Device>,
+) -> Result<()> {
+    if !args.mdns {
+        return Ok(());
+    }
+
+    let (tx, rx) = mpsc::channel::<ScanMessage>();
+
+    let scanner = MDNSScanner::builder()
+        .interface(Arc::clone(interface))
+        .notifier(tx)
+        .build()?;
+
+    process_mdns(&scanner, devices, rx)?;
+
+    Ok(())
+}
+
+fn advertised_services(device: &Device) -> String {
+    device.services.iter().map(|s| s.to_string()).join(", ")
+}
+
+fn process_ssdp<'a>(
+    scanner: &dyn Scanner,
+    devices: impl IntoIterator<Item = &'a mut Device>,
+    rx: Receiver<ScanMessage>,
+) -> LibResult<()> {
+    let mut ssdp_results: HashMap<Ipv4Addr, Device> = HashMap::new();
+
+    log::info!("starting ssdp discovery...");
+
+    let handle = scanner.scan()?;
+
+    loop {
+        let msg = rx.recv()?;
+
+        match msg {
+            ScanMessage::Done => {
+                log::debug!("ssdp discovery complete");
+                break;
+            }
+            ScanMessage::SSDPScanDevice(device) => {
+                log::debug!("received ssdp scanning device: {:?}", device);
+                ssdp_results.insert(device.ip, device);
+            }
+            _ => {}
+        }
+    }
+
+    handle.join()??;
+
+    // SSDP responses carry no MAC so merge into known devices by IP
+    for d in devices {
+        if let Some(found) = ssdp_results.remove(&d.ip) {
+            d.upnp = found.upnp;
+        }
+    }
+
+    Ok(())
+}
+
+/// Attaches UPnP device descriptions to devices if requested
+fn discover_ssdp<'a>(
+    args: &Args,
+    interface: &Arc<NetworkInterface>,
+    devices: impl IntoIterator<Item = &'a mut Device>,
+) -> Result<()> {
+    if !args.ssdp {
+        return Ok(());
+    }
+
+    let (tx, rx) = mpsc::channel::<ScanMessage>();
+
+    let scanner = SSDPScanner::builder()
+        .interface(Arc::clone(interface))
+        .notifier(tx)
+        .build()?;
+
+    process_ssdp(&scanner, devices, rx)?;
+
+    Ok(())
+}
+
+fn os_description(device: &Device) -> String {
+    device
+        .os_guess
+        .as_ref()
+        .map(|g| g.to_string())
+        .unwrap_or_default()
+}
+
+fn upnp_description(device: &Device) -> String {
+    let Some(upnp) = &device.upnp else {
+        return String::new();
+    };
+
+    let model = [upnp.manufacturer.as_str(), upnp.model_name.as_str()]
+        .into_iter()
+        .filter(|s| !s.is_empty())
+        .join(" ");
+
+    match (upnp.friendly_name.is_empty(), model.is_empty()) {
+        (false, false) => format!("{} ({})", upnp.friendly_name, model),
+        (false, true) => upnp.friendly_name.clone(),
+        _ => model,
+    }
+}
+
 fn print_syn(
     args: &Args,
     device_map: &HashMap<Ipv4Addr, Device>,
@@ -266,14 +771,25 @@ fn print_syn(
     } else {
         let mut syn_table: prettytable::Table = prettytable::Table::new();
 
-        syn_table.add_row(prettytable::row![
+        let mut header = prettytable::row![
             "IP",
             "HOSTNAME",
             "MAC",
             "VENDOR",
             "LATENCY",
             "OPEN_PORTS",
-        ]);
+            "OS",
+        ];
+
+        if args.mdns {
+            header.add_cell(prettytable::cell!("SERVICES"));
+        }
+
+        if args.ssdp {
+            header.add_cell(prettytable::cell!("UPNP"));
+        }
+
+        syn_table.add_row(header);
 
         for d in devices {
             let ip_field = if d.is_current_host {
@@ -293,16 +809,30 @@ fn print_syn(
                 .open_ports
                 .to_sorted_vec()
                 .into_iter()
-                .map(|p| p.to_string())
+                .map(|p| match p.state {
+                    PortState::Open => p.to_string(),
+                    state => format!("{} [{}]", p, state),
+                })
                 .collect();
-            syn_table.add_row(prettytable::row![
+            let mut row = prettytable::row![
                 ip_field,
                 d.hostname,
                 d.mac,
                 d.vendor,
                 latency,
-                ports.join(", ")
-            ]);
+                ports.join(", "),
+                os_description(&d)
+            ];
+
+            if args.mdns {
+                row.add_cell(prettytable::cell!(advertised_services(&d)));
+            }
+
+            if args.ssdp {
+                row.add_cell(prettytable::cell!(upnp_description(&d)));
+            }
+
+            syn_table.add_row(row);
         }
         syn_table.printstd();
     }
@@ -310,6 +840,53 @@ fn print_syn(
     Ok(())
 }
 
+/// Performs an unprivileged TCP connect scan on every IP target
+fn process_connect(
+    args: &Args,
+    interface: &Arc<NetworkInterface>,
+    services: Arc<dyn Services>,
+    tx: mpsc::Sender<ScanMessage>,
+    rx: Receiver<ScanMessage>,
+) -> Result<HashMap<Ipv4Addr, Device>> {
+    let targets =
+        IPTargets::with_exclude(args.targets.clone(), args.exclude.clone())
+            .map_err(|e| eyre!("Invalid IP targets: {}", e))?;
+
+    let connect = ConnectScanner::builder()
+        .targets(targets)
+        .interface(Arc::clone(interface))
+        .gateway(get_default_gateway())
+        .ports(
+            PortTargets::with_exclude(
+                args.ports.clone(),
+                args.exclude_ports.clone(),
+            )
+            .map_err(|e| eyre!("Invalid port targets: {}", e))?,
+        )
+        .notifier(tx)
+        .seed(args.seed)
+        .services(services)
+        .build()?;
+
+    // devices are only known once they report open ports
+    let mut results = process_syn(&connect, Vec::new(), rx)?;
+
+    // only hosts with open ports are known to exist
+    results.retain(|_, d| !d.open_ports.0.is_empty());
+
+    if args.host_names {
+        for d in results.values_mut() {
+            d.hostname = if args.netbios_names {
+                names::lookup_hostname_with_fallback(d.ip)
+            } else {
+                names::lookup_hostname(d.ip)
+            };
+        }
+    }
+
+    Ok(results)
+}
+
 #[cfg(unix)]
 fn is_root() -> bool {
     nix::unistd::geteuid().is_root()
@@ -334,8 +911,18 @@ fn main() -> Result<()> {
 
     initialize_logger(&args)?;
 
-    if !is_root() {
-        return Err(eyre!("permission denied: must run with root privileges"));
+    let privileged = is_root();
+
+    if !privileged && args.arp_only {
+        return Err(eyre!(
+            "permission denied: ARP scanning requires root privileges"
+        ));
+    }
+
+    if !privileged && args.passive {
+        return Err(eyre!(
+            "permission denied: passive scanning requires root privileges"
+        ));
     }
 
     let interface = match &args.interface {
@@ -349,13 +936,31 @@ fn main() -> Result<()> {
         args.targets = vec![interface.cidr.clone()]
     }
 
+    // logged with the other args so a randomized scan can be repeated
+    if args.randomize && args.seed.is_none() {
+        args.seed = Some(random_seed());
+    }
+
     print_args(&args, &interface);
 
+    let interface = Arc::new(interface);
+
+    let services = load_services(&args)?;
+
     let (tx, rx) = mpsc::channel::<ScanMessage>();
 
-    let wire = r_lanlib::wire::default(&interface)?;
+    if !privileged {
+        log::warn!("not running as root: falling back to TCP connect scanning");
+        let mut final_results =
+            process_connect(&args, &interface, services, tx, rx)?;
+        discover_mdns(&args, &interface, final_results.values_mut())?;
+        discover_ssdp(&args, &interface, final_results.values_mut())?;
+        detect_services(&args, &mut final_results)?;
+        print_syn(&args, &final_results)?;
+        return Ok(());
+    }
 
-    let interface = Arc::new(interface);
+    let wire = r_lanlib::wire::default(&interface)?;
 
     let oui = if args.vendor {
         Some(oui::default("r-lanscan", OUI_MAX_AGE)?)
@@ -363,24 +968,52 @@ fn main() -> Result<()> {
         None
     };
 
+    if args.passive {
+        let passive = PassiveScanner::builder()
+            .interface(Arc::clone(&interface))
+            .wire(wire)
+            .gateway(get_default_gateway())
+            .duration(args.duration)
+            .include_vendor(args.vendor)
+            .notifier(tx)
+            .oui(oui)
+            .build()?;
+
+        log::info!("listening for {:?}...", args.duration);
+
+        let (passive_results, _) = process_arp(&passive, rx)?;
+
+        print_arp(&args, &passive_results)?;
+
+        return Ok(());
+    }
+
     let arp = ARPScanner::builder()
         .interface(Arc::clone(&interface))
         .wire(wire.clone())
         .gateway(get_default_gateway())
         .targets(
-            IPTargets::new(args.targets.clone())
+            IPTargets::with_exclude(args.targets.clone(), args.exclude.clone())
                 .map_err(|e| eyre!("Invalid IP targets: {}", e))?,
         )
         .source_port(args.source_port)
         .include_vendor(args.vendor)
         .include_host_names(args.host_names)
+        .include_netbios_names(args.netbios_names)
         .idle_timeout(time::Duration::from_millis(args.idle_timeout_ms.into()))
         .notifier(tx.clone())
         .throttle(args.throttle)
+        .adaptive_rate(rate_bounds(&args))
+        .seed(args.seed)
+        .retries(args.retries)
+        .retry_delay(args.retry_delay)
         .oui(oui)
         .build()?;
 
-    let (arp_results, rx) = process_arp(&arp, rx)?;
+    let (mut arp_results, rx) = process_arp(&arp, rx)?;
+
+    discover_mdns(&args, &interface, &mut arp_results)?;
+    discover_ssdp(&args, &interface, &mut arp_results)?;
 
     print_arp(&args, &arp_results)?;
 
@@ -393,16 +1026,27 @@ fn main() -> Result<()> {
         .wire(wire)
         .targets(arp_results.clone())
         .ports(
-            PortTargets::new(args.ports.clone())
-                .map_err(|e| eyre!("Invalid port targets: {}", e))?,
+            PortTargets::with_exclude(
+                args.ports.clone(),
+                args.exclude_ports.clone(),
+            )
+            .map_err(|e| eyre!("Invalid port targets: {}", e))?,
         )
         .source_port(args.source_port)
         .idle_timeout(time::Duration::from_millis(args.idle_timeout_ms.into()))
         .notifier(tx)
         .throttle(args.throttle)
+        .adaptive_rate(rate_bounds(&args))
+        .seed(args.seed)
+        .retries(args.retries)
+        .retry_delay(args.retry_delay)
+        .report_non_open(args.report_non_open)
+        .scan_type(args.scan_type)
+        .services(services)
         .build()?;
 
-    let final_results = process_syn(&syn, arp_results, rx)?;
+    let mut final_results = process_syn(&syn, arp_results, rx)?;
+    detect_services(&args, &mut final_results)?;
     print_syn(&args, &final_results)?;
 
     Ok(())
@@ -1,29 +1,30 @@
 use mockall::mock;
 use mpsc::channel;
 use r_lanlib::{
+    MacAddr,
     error::Result,
-    scanners::{Port, PortSet, Scanner},
+    scanners::{
+        AdvertisedService, DEFAULT_RETRY_DELAY, Port, PortSet, Scanner,
+        UpnpDescription,
+        handle::{ScanControl, ScanHandle},
+    },
     wire::DEFAULT_PACKET_SEND_TIMING,
 };
-use std::{
-    net::Ipv4Addr,
-    thread::{self, JoinHandle},
-    time::Duration,
-};
+use std::{net::Ipv4Addr, thread, time::Duration};
 
 use super::*;
 
 mock! {
     ArpScanner{}
     impl Scanner for ArpScanner {
-        fn scan(&self) -> Result<JoinHandle<r_lanlib::error::Result<()>>>;
+        fn scan(&self) -> Result<ScanHandle>;
     }
 }
 
 mock! {
     SynScanner{}
     impl Scanner for SynScanner {
-        fn scan(&self) -> Result<JoinHandle<r_lanlib::error::Result<()>>>;
+        fn scan(&self) -> Result<ScanHandle>;
     }
 }
 
@@ -34,16 +35,34 @@ fn prints_args() {
     let args = Args {
         json: false,
         arp_only: false,
+        passive: false,
+        duration: Duration::from_secs(30),
         debug: false,
+        services_file: None,
         host_names: true,
+        netbios_names: false,
+        mdns: false,
+        ssdp: false,
+        detect_services: false,
         idle_timeout_ms: 2000,
         interface: Some("interface_name".to_string()),
         ports: vec!["22".to_string()],
+        exclude_ports: vec![],
         quiet: false,
         source_port: 54321,
         targets: vec!["192.168.1.1".to_string()],
+        exclude: vec![],
         vendor: true,
         throttle: DEFAULT_PACKET_SEND_TIMING,
+        adaptive_rate: false,
+        min_rate: DEFAULT_MIN_RATE,
+        max_rate: DEFAULT_MAX_RATE,
+        randomize: false,
+        seed: None,
+        retries: 0,
+        retry_delay: DEFAULT_RETRY_DELAY,
+        report_non_open: false,
+        scan_type: TcpScanType::Syn,
     };
 
     print_args(&args, &interface);
@@ -54,16 +73,34 @@ fn initializes_logger() {
     let args = Args {
         json: false,
         arp_only: false,
+        passive: false,
+        duration: Duration::from_secs(30),
         debug: false,
+        services_file: None,
         host_names: true,
+        netbios_names: false,
+        mdns: false,
+        ssdp: false,
+        detect_services: false,
         idle_timeout_ms: 2000,
         interface: Some("interface_name".to_string()),
         ports: vec!["22".to_string()],
+        exclude_ports: vec![],
         quiet: false,
         source_port: 54321,
         targets: vec!["192.168.1.1".to_string()],
+        exclude: vec![],
         vendor: true,
         throttle: DEFAULT_PACKET_SEND_TIMING,
+        adaptive_rate: false,
+        min_rate: DEFAULT_MIN_RATE,
+        max_rate: DEFAULT_MAX_RATE,
+        randomize: false,
+        seed: None,
+        retries: 0,
+        retry_delay: DEFAULT_RETRY_DELAY,
+        report_non_open: false,
+        scan_type: TcpScanType::Syn,
     };
 
     initialize_logger(&args).unwrap();
@@ -74,16 +111,34 @@ fn prints_arp_table_results() {
     let args = Args {
         json: false,
         arp_only: false,
+        passive: false,
+        duration: Duration::from_secs(30),
         debug: false,
+        services_file: None,
         host_names: true,
+        netbios_names: false,
+        mdns: false,
+        ssdp: false,
+        detect_services: false,
         idle_timeout_ms: 2000,
         interface: Some("interface_name".to_string()),
         ports: vec!["22".to_string()],
+        exclude_ports: vec![],
         quiet: false,
         source_port: 54321,
         targets: vec!["192.168.1.1".to_string()],
+        exclude: vec![],
         vendor: true,
         throttle: DEFAULT_PACKET_SEND_TIMING,
+        adaptive_rate: false,
+        min_rate: DEFAULT_MIN_RATE,
+        max_rate: DEFAULT_MAX_RATE,
+        randomize: false,
+        seed: None,
+        retries: 0,
+        retry_delay: DEFAULT_RETRY_DELAY,
+        report_non_open: false,
+        scan_type: TcpScanType::Syn,
     };
 
     let device = Device {
@@ -101,16 +156,34 @@ fn prints_arp_json_results() {
     let args = Args {
         json: true,
         arp_only: false,
+        passive: false,
+        duration: Duration::from_secs(30),
         debug: false,
+        services_file: None,
         host_names: true,
+        netbios_names: false,
+        mdns: false,
+        ssdp: false,
+        detect_services: false,
         idle_timeout_ms: 2000,
         interface: Some("interface_name".to_string()),
         ports: vec!["22".to_string()],
+        exclude_ports: vec![],
         quiet: false,
         source_port: 54321,
         targets: vec!["192.168.1.1".to_string()],
+        exclude: vec![],
         vendor: true,
         throttle: DEFAULT_PACKET_SEND_TIMING,
+        adaptive_rate: false,
+        min_rate: DEFAULT_MIN_RATE,
+        max_rate: DEFAULT_MAX_RATE,
+        randomize: false,
+        seed: None,
+        retries: 0,
+        retry_delay: DEFAULT_RETRY_DELAY,
+        report_non_open: false,
+        scan_type: TcpScanType::Syn,
     };
 
     let device = Device {
@@ -128,21 +201,40 @@ fn prints_syn_table_results() {
     let args = Args {
         json: false,
         arp_only: false,
+        passive: false,
+        duration: Duration::from_secs(30),
         debug: false,
+        services_file: None,
         host_names: true,
+        netbios_names: false,
+        mdns: false,
+        ssdp: false,
+        detect_services: false,
         idle_timeout_ms: 2000,
         interface: Some("interface_name".to_string()),
         ports: vec!["22".to_string()],
+        exclude_ports: vec![],
         quiet: false,
         source_port: 54321,
         targets: vec!["192.168.1.1".to_string()],
+        exclude: vec![],
         vendor: true,
         throttle: DEFAULT_PACKET_SEND_TIMING,
+        adaptive_rate: false,
+        min_rate: DEFAULT_MIN_RATE,
+        max_rate: DEFAULT_MAX_RATE,
+        randomize: false,
+        seed: None,
+        retries: 0,
+        retry_delay: DEFAULT_RETRY_DELAY,
+        report_non_open: false,
+        scan_type: TcpScanType::Syn,
     };
 
     let port = Port {
         id: 22,
         service: "ssh".to_string(),
+        ..Port::default()
     };
 
     let mut open_ports = PortSet::new();
@@ -165,21 +257,40 @@ fn prints_syn_json_results() {
     let args = Args {
         json: true,
         arp_only: false,
+        passive: false,
+        duration: Duration::from_secs(30),
         debug: false,
+        services_file: None,
         host_names: true,
+        netbios_names: false,
+        mdns: false,
+        ssdp: false,
+        detect_services: false,
         idle_timeout_ms: 2000,
         interface: Some("interface_name".to_string()),
         ports: vec!["22".to_string()],
+        exclude_ports: vec![],
         quiet: false,
         source_port: 54321,
         targets: vec!["192.168.1.1".to_string()],
+        exclude: vec![],
         vendor: true,
         throttle: DEFAULT_PACKET_SEND_TIMING,
+        adaptive_rate: false,
+        min_rate: DEFAULT_MIN_RATE,
+        max_rate: DEFAULT_MAX_RATE,
+        randomize: false,
+        seed: None,
+        retries: 0,
+        retry_delay: DEFAULT_RETRY_DELAY,
+        report_non_open: false,
+        scan_type: TcpScanType::Syn,
     };
 
     let port = Port {
         id: 22,
         service: "ssh".to_string(),
+        ..Port::default()
     };
 
     let mut open_ports = PortSet::new();
@@ -213,15 +324,20 @@ fn performs_arp_scan() {
     let device_clone = device.clone();
 
     thread::spawn(move || {
+        let _ = tx.send(ScanMessage::Progress {
+            sent: 0,
+            total: 1,
+            elapsed: Duration::ZERO,
+            eta: None,
+        });
         let _ = tx.send(ScanMessage::ARPScanDevice(device_clone));
         thread::sleep(Duration::from_millis(500));
         let _ = tx.send(ScanMessage::Done);
     });
 
     arp.expect_scan().returning(|| {
-        let handle: JoinHandle<r_lanlib::error::Result<()>> =
-            thread::spawn(|| Ok(()));
-        Ok(handle)
+        let handle = thread::spawn(|| Ok(()));
+        Ok(ScanHandle::new(handle, ScanControl::new()))
     });
 
     let result = process_arp(&arp, rx);
@@ -243,6 +359,7 @@ fn performs_syn_scan() {
     ports.0.insert(Port {
         id: 22,
         service: "ssh".to_string(),
+        ..Port::default()
     });
 
     let device = Device {
@@ -262,9 +379,8 @@ fn performs_syn_scan() {
     });
 
     syn.expect_scan().returning(|| {
-        let handle: JoinHandle<r_lanlib::error::Result<()>> =
-            thread::spawn(|| Ok(()));
-        Ok(handle)
+        let handle = thread::spawn(|| Ok(()));
+        Ok(ScanHandle::new(handle, ScanControl::new()))
     });
 
     let result = process_syn(&syn, vec![device.clone()], rx);
@@ -275,3 +391,261 @@ fn performs_syn_scan() {
 
     assert_eq!(devices.get(&device.ip), Some(&device));
 }
+
+#[test]
+fn keeps_syn_results_for_devices_not_known_beforehand() {
+    let mut syn = MockSynScanner::new();
+
+    let (tx, rx) = channel();
+
+    let device = Device {
+        ip: Ipv4Addr::new(192, 168, 1, 2),
+        ..Device::default()
+    };
+
+    let device_clone = device.clone();
+
+    thread::spawn(move || {
+        let _ = tx.send(ScanMessage::SYNScanDevice(device_clone));
+        let _ = tx.send(ScanMessage::Done);
+    });
+
+    syn.expect_scan().returning(|| {
+        let handle = thread::spawn(|| Ok(()));
+        Ok(ScanHandle::new(handle, ScanControl::new()))
+    });
+
+    let devices = process_syn(&syn, Vec::new(), rx).unwrap();
+
+    assert_eq!(devices.get(&device.ip), Some(&device));
+}
+
+#[test]
+fn performs_service_detection() {
+    let mut services = MockSynScanner::new();
+
+    let (tx, rx) = channel();
+
+    let mut ports = PortSet::new();
+    ports.0.insert(Port {
+        id: 8080,
+        service: "http-alt".to_string(),
+        ..Port::default()
+    });
+
+    let device = Device {
+        ip: Ipv4Addr::new(192, 168, 1, 1),
+        open_ports: ports,
+        ..Device::default()
+    };
+
+    let mut detected_ports = PortSet::new();
+    detected_ports.0.insert(Port {
+        id: 8080,
+        service: "redis".to_string(),
+        product: Some("Redis".to_string()),
+        version: Some("7.2.4".to_string()),
+        ..Port::default()
+    });
+
+    let detected = Device {
+        open_ports: detected_ports,
+        ..device.clone()
+    };
+
+    thread::spawn(move || {
+        let _ = tx.send(ScanMessage::ServiceScanDevice(detected));
+        let _ = tx.send(ScanMessage::Done);
+    });
+
+    services.expect_scan().returning(|| {
+        let handle = thread::spawn(|| Ok(()));
+        Ok(ScanHandle::new(handle, ScanControl::new()))
+    });
+
+    let mut device_map = HashMap::from([(device.ip, device.clone())]);
+
+    let result = process_services(&services, &mut device_map, rx);
+
+    assert!(result.is_ok());
+
+    let port = device_map[&device.ip].open_ports.to_sorted_vec()[0].clone();
+
+    assert_eq!(port.service, "redis");
+    assert_eq!(port.product, Some("Redis".to_string()));
+    assert_eq!(port.version, Some("7.2.4".to_string()));
+}
+
+#[test]
+fn performs_mdns_discovery() {
+    let mut mdns = MockSynScanner::new();
+
+    let (tx, rx) = channel();
+
+    let device = Device {
+        ip: Ipv4Addr::new(192, 168, 1, 1),
+        mac: MacAddr::new(0x00, 0x00, 0x00, 0x00, 0x00, 0x01),
+        ..Device::default()
+    };
+
+    let service = AdvertisedService {
+        instance: "Office Printer".to_string(),
+        service_type: "_ipp._tcp".to_string(),
+        port: 631,
+        txt: vec![],
+    };
+
+    let discovered = Device {
+        hostname: "printer.local".to_string(),
+        ip: device.ip,
+        services: vec![service.clone()],
+        ..Device::default()
+    };
+
+    thread::spawn(move || {
+        let _ = tx.send(ScanMessage::MDNSScanDevice(discovered));
+        let _ = tx.send(ScanMessage::Done);
+    });
+
+    mdns.expect_scan().returning(|| {
+        let handle = thread::spawn(|| Ok(()));
+        Ok(ScanHandle::new(handle, ScanControl::new()))
+    });
+
+    let mut devices = vec![device.clone()];
+
+    let result = process_mdns(&mdns, &mut devices, rx);
+
+    assert!(result.is_ok());
+    assert_eq!(devices[0].mac, device.mac);
+    assert_eq!(devices[0].hostname, "printer.local");
+    assert_eq!(devices[0].services, vec![service]);
+}
+
+#[test]
+fn performs_ssdp_discovery() {
+    let mut ssdp = MockSynScanner::new();
+
+    let (tx, rx) = channel();
+
+    let device = Device {
+        ip: Ipv4Addr::new(192, 168, 1, 1),
+        mac: MacAddr::new(0x00, 0x00, 0x00, 0x00, 0x00, 0x01),
+        ..Device::default()
+    };
+
+    let upnp = UpnpDescription {
+        friendly_name: "Living Room TV".to_string(),
+        manufacturer: "Acme".to_string(),
+        model_name: "AX-55".to_string(),
+        serial_number: "SN1234".to_string(),
+    };
+
+    let discovered = Device {
+        ip: device.ip,
+        upnp: Some(upnp.clone()),
+        ..Device::default()
+    };
+
+    thread::spawn(move || {
+        let _ = tx.send(ScanMessage::SSDPScanDevice(discovered));
+        let _ = tx.send(ScanMessage::Done);
+    });
+
+    ssdp.expect_scan().returning(|| {
+        let handle = thread::spawn(|| Ok(()));
+        Ok(ScanHandle::new(handle, ScanControl::new()))
+    });
+
+    let mut devices = vec![device.clone()];
+
+    let result = process_ssdp(&ssdp, &mut devices, rx);
+
+    assert!(result.is_ok());
+    assert_eq!(devices[0].mac, device.mac);
+    assert_eq!(devices[0].upnp, Some(upnp));
+    assert_eq!(upnp_description(&devices[0]), "Living Room TV (Acme AX-55)");
+}
+
+#[test]
+fn renders_progress_bar() {
+    assert_eq!(
+        render_progress(0, 200, None),
+        format!("[{}]   0% 0/200 eta ?", "-".repeat(30))
+    );
+
+    assert_eq!(
+        render_progress(50, 200, Some(Duration::from_millis(90_500))),
+        format!(
+            "[{}{}]  25% 50/200 eta 1m 30s",
+            "#".repeat(8),
+            "-".repeat(22)
+        )
+    );
+
+    assert_eq!(
+        render_progress(0, 0, Some(Duration::ZERO)),
+        format!("[{}] 100% 0/0 eta 0s", "#".repeat(30))
+    );
+}
+
+#[test]
+fn bounds_rate_only_when_adaptive() {
+    let args = Args::parse_from(["r-lancli"]);
+    assert_eq!(rate_bounds(&args), None);
+
+    let args =
+        Args::parse_from(["r-lancli", "--adaptive-rate", "--max-rate", "5000"]);
+    assert_eq!(
+        rate_bounds(&args),
+        Some(RateBounds {
+            min: DEFAULT_MIN_RATE,
+            max: 5000,
+        })
+    );
+}
+
+#[test]
+fn parses_exclusions() {
+    let args = Args::parse_from([
+        "r-lancli",
+        "--exclude",
+        "10.0.5.0/24,10.0.0.7",
+        "--exclude-ports",
+        "9100",
+    ]);
+
+    assert_eq!(args.exclude, vec!["10.0.5.0/24", "10.0.0.7"]);
+    assert_eq!(args.exclude_ports, vec!["9100"]);
+}
+
+#[test]
+fn parses_scan_types() {
+    let args = Args::parse_from(["r-lancli"]);
+    assert_eq!(args.scan_type, TcpScanType::Syn);
+
+    let args = Args::parse_from(["r-lancli", "--scan-type", "XMAS"]);
+    assert_eq!(args.scan_type, TcpScanType::Xmas);
+
+    assert!(Args::try_parse_from(["r-lancli", "--scan-type", "udp"]).is_err());
+}
+
+#[test]
+fn rejects_lookups_in_passive_mode() {
+    assert!(
+        Args::try_parse_from(["r-lancli", "--passive", "--vendor"]).is_ok()
+    );
+
+    for flag in ["--host-names", "--mdns", "--ssdp"] {
+        assert!(Args::try_parse_from(["r-lancli", "--passive", flag]).is_err());
+    }
+}
+
+#[test]
+fn requires_host_names_for_netbios_names() {
+    assert!(Args::try_parse_from(["r-lancli", "--netbios-names"]).is_err());
+
+    let args =
+        Args::parse_from(["r-lancli", "--host-names", "--netbios-names"]);
+    assert!(args.netbios_names);
+}
@@ -0,0 +1,79 @@
+#!/usr/bin/env python3
+"""Converts the IANA service names registry CSV into /etc/services format.
+
+Reads the CSV published at
+https://www.iana.org/assignments/service-names-port-numbers/ from stdin, or
+from the path given as the only argument, and writes the embedded services
+table to stdout. Run `just update-services` to regenerate the table.
+"""
+
+import csv
+import sys
+
+# Common names used in place of the registered ones, and ports commonly seen
+# on local networks that are not registered
+OVERRIDES = {
+    (3389, "tcp"): "rdp",
+    (3389, "udp"): "rdp",
+    (5900, "tcp"): "vnc",
+    (5900, "udp"): "vnc",
+    (8443, "tcp"): "https-alt",
+    (8443, "udp"): "https-alt",
+    (25565, "tcp"): "minecraft",
+}
+
+HEADER = """\
+# Service names and transport protocol port numbers
+#
+# Generated from the IANA Service Name and Transport Protocol Port Number
+# Registry by generate-services.py, covering every registered TCP and UDP
+# port. Names of other assignments to the same port follow as aliases. Some
+# ports use their common name in place of the registered one, i.e. rdp
+# instead of ms-wbt-server.
+#
+# <name> <port>/<protocol> [aliases...]
+"""
+
+
+def ports(value):
+    if "-" in value:
+        first, last = value.split("-", 1)
+        return range(int(first), int(last) + 1)
+
+    return [int(value)] if value else []
+
+
+def main():
+    source = open(sys.argv[1], newline="") if len(sys.argv) > 1 else sys.stdin
+    names = {}
+
+    for row in csv.DictReader(source):
+        name = row["Service Name"].strip()
+        protocol = row["Transport Protocol"].strip().lower()
+
+        if not name or protocol not in ("tcp", "udp"):
+            continue
+
+        for port in ports(row["Port Number"].strip()):
+            aliases = names.setdefault((port, protocol), [])
+
+            if name not in aliases:
+                aliases.append(name)
+
+    for key, name in OVERRIDES.items():
+        aliases = names.setdefault(key, [])
+
+        if name in aliases:
+            aliases.remove(name)
+
+        aliases.insert(0, name)
+
+    sys.stdout.write(HEADER + "\n")
+
+    for (port, protocol), (name, *aliases) in sorted(names.items()):
+        entry = f"{name:<23} {port}/{protocol}"
+        sys.stdout.write(" ".join([entry, *aliases]).rstrip() + "\n")
+
+
+if __name__ == "__main__":
+    main()
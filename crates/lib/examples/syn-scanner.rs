@@ -41,6 +41,7 @@ fn main() {
         Device {
             hostname: "".to_string(),
             ip: Ipv4Addr::new(192, 168, 0, 1),
+            ipv6: Vec::new(),
             mac: MacAddr::new(0x00, 0x00, 0x00, 0x00, 0x00, 0x01),
             vendor: "".to_string(),
             is_current_host: false,
@@ -48,10 +49,14 @@ fn main() {
             open_ports: PortSet::new(),
             latency_ms: None,
             response_ttl: None,
+            services: Vec::new(),
+            upnp: None,
+            os_guess: None,
         },
         Device {
             hostname: "".to_string(),
             ip: Ipv4Addr::new(192, 168, 0, 2),
+            ipv6: Vec::new(),
             mac: MacAddr::new(0x00, 0x00, 0x00, 0x00, 0x00, 0x02),
             vendor: "".to_string(),
             is_current_host: false,
@@ -59,10 +64,14 @@ fn main() {
             open_ports: PortSet::new(),
             latency_ms: None,
             response_ttl: None,
+            services: Vec::new(),
+            upnp: None,
+            os_guess: None,
         },
         Device {
             hostname: "".to_string(),
             ip: Ipv4Addr::new(192, 168, 0, 3),
+            ipv6: Vec::new(),
             mac: MacAddr::new(0x00, 0x00, 0x00, 0x00, 0x00, 0x03),
             vendor: "".to_string(),
             is_current_host: false,
@@ -70,6 +79,9 @@ fn main() {
             open_ports: PortSet::new(),
             latency_ms: None,
             response_ttl: None,
+            services: Vec::new(),
+            upnp: None,
+            os_guess: None,
         },
     ];
 
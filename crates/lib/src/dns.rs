@@ -0,0 +1,236 @@
+//! Provides minimal encoding and decoding of DNS wire format messages
+//!
+//! Only what multicast name resolution protocols (mDNS, LLMNR) need is
+//! supported: building queries and reading the A, AAAA, PTR, SRV and TXT
+//! records out of responses.
+
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+const HEADER_SIZE: usize = 12;
+
+// guards against compression pointer loops in malformed messages
+const MAX_POINTER_JUMPS: usize = 16;
+
+const FLAG_RESPONSE: u16 = 0x8000;
+
+// top bit of the question class asks mDNS responders to reply by unicast
+const CLASS_UNICAST_RESPONSE: u16 = 0x8000;
+const CLASS_IN: u16 = 1;
+
+/// A record type
+pub const TYPE_A: u16 = 1;
+/// PTR record type
+pub const TYPE_PTR: u16 = 12;
+/// TXT record type
+pub const TYPE_TXT: u16 = 16;
+/// AAAA record type
+pub const TYPE_AAAA: u16 = 28;
+/// SRV record type
+pub const TYPE_SRV: u16 = 33;
+
+/// The data carried by a resource record
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecordData {
+    /// IPv4 address
+    A(Ipv4Addr),
+    /// IPv6 address
+    Aaaa(Ipv6Addr),
+    /// Domain name pointer
+    Ptr(String),
+    /// Service location
+    Srv {
+        /// Port the service is available on
+        port: u16,
+        /// Host name providing the service
+        target: String,
+    },
+    /// Text strings
+    Txt(Vec<String>),
+    /// Any other record type, identified by its type code
+    Other(u16),
+}
+
+/// A resource record from any section of a response
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    /// The owner name of the record without a trailing dot
+    pub name: String,
+    /// The record data
+    pub data: RecordData,
+}
+
+/// A decoded DNS message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    /// Transaction id
+    pub id: u16,
+    /// Whether the message is a response
+    pub is_response: bool,
+    /// Answer, authority and additional records combined
+    pub records: Vec<Record>,
+}
+
+/// Builds a query message asking each of the (name, type) questions
+pub fn build_query(
+    id: u16,
+    questions: &[(&str, u16)],
+    unicast_response: bool,
+) -> Vec<u8> {
+    let mut msg = Vec::with_capacity(HEADER_SIZE + questions.len() * 32);
+
+    msg.extend_from_slice(&id.to_be_bytes());
+    // standard query, no flags set
+    msg.extend_from_slice(&0_u16.to_be_bytes());
+    msg.extend_from_slice(&(questions.len() as u16).to_be_bytes());
+    // answer, authority and additional counts
+    msg.extend_from_slice(&[0; 6]);
+
+    let class = if unicast_response {
+        CLASS_IN | CLASS_UNICAST_RESPONSE
+    } else {
+        CLASS_IN
+    };
+
+    for (name, qtype) in questions {
+        encode_name(name, &mut msg);
+        msg.extend_from_slice(&qtype.to_be_bytes());
+        msg.extend_from_slice(&class.to_be_bytes());
+    }
+
+    msg
+}
+
+/// Parses a DNS message returning None if it is malformed
+pub fn parse(msg: &[u8]) -> Option<Message> {
+    if msg.len() < HEADER_SIZE {
+        return None;
+    }
+
+    let read_u16 = |pos: usize| -> Option<u16> {
+        Some(u16::from_be_bytes(msg.get(pos..pos + 2)?.try_into().ok()?))
+    };
+
+    let id = read_u16(0)?;
+    let flags = read_u16(2)?;
+    let question_count = read_u16(4)?;
+    let record_count = usize::from(read_u16(6)?)
+        + usize::from(read_u16(8)?)
+        + usize::from(read_u16(10)?);
+
+    let mut pos = HEADER_SIZE;
+
+    for _ in 0..question_count {
+        let (_, next) = decode_name(msg, pos)?;
+        // skip type and class
+        pos = next + 4;
+    }
+
+    let mut records = Vec::with_capacity(record_count);
+
+    for _ in 0..record_count {
+        let (name, next) = decode_name(msg, pos)?;
+        let rtype = read_u16(next)?;
+        // class (2) and ttl (4) are not needed
+        let rdata_len = usize::from(read_u16(next + 8)?);
+        let rdata_start = next + 10;
+        let rdata = msg.get(rdata_start..rdata_start + rdata_len)?;
+
+        let data = match rtype {
+            TYPE_A => {
+                RecordData::A(Ipv4Addr::from(<[u8; 4]>::try_from(rdata).ok()?))
+            }
+            TYPE_AAAA => RecordData::Aaaa(Ipv6Addr::from(
+                <[u8; 16]>::try_from(rdata).ok()?,
+            )),
+            TYPE_PTR => RecordData::Ptr(decode_name(msg, rdata_start)?.0),
+            TYPE_SRV => RecordData::Srv {
+                // priority (2) and weight (2) are not needed
+                port: read_u16(rdata_start + 4)?,
+                target: decode_name(msg, rdata_start + 6)?.0,
+            },
+            TYPE_TXT => RecordData::Txt(decode_txt(rdata)),
+            other => RecordData::Other(other),
+        };
+
+        records.push(Record { name, data });
+        pos = rdata_start + rdata_len;
+    }
+
+    Some(Message {
+        id,
+        is_response: flags & FLAG_RESPONSE != 0,
+        records,
+    })
+}
+
+fn encode_name(name: &str, buf: &mut Vec<u8>) {
+    for label in name.trim_end_matches('.').split('.') {
+        // labels are limited to 63 bytes by the protocol
+        let label = &label.as_bytes()[..label.len().min(63)];
+        buf.push(label.len() as u8);
+        buf.extend_from_slice(label);
+    }
+    buf.push(0);
+}
+
+// Decodes the (possibly compressed) name at pos, returning it along with the
+// position just past the name in the original message
+fn decode_name(msg: &[u8], mut pos: usize) -> Option<(String, usize)> {
+    let mut labels: Vec<String> = Vec::new();
+    let mut end = None;
+    let mut jumps = 0;
+
+    loop {
+        let len = *msg.get(pos)?;
+
+        match len {
+            0 => {
+                end.get_or_insert(pos + 1);
+                break;
+            }
+            // compression pointer to an earlier name
+            l if l & 0xc0 == 0xc0 => {
+                jumps += 1;
+                if jumps > MAX_POINTER_JUMPS {
+                    return None;
+                }
+                let offset = usize::from(u16::from_be_bytes([
+                    l & 0x3f,
+                    *msg.get(pos + 1)?,
+                ]));
+                end.get_or_insert(pos + 2);
+                pos = offset;
+            }
+            l => {
+                let start = pos + 1;
+                let label = msg.get(start..start + usize::from(l))?;
+                labels.push(String::from_utf8_lossy(label).into_owned());
+                pos = start + usize::from(l);
+            }
+        }
+    }
+
+    Some((labels.join("."), end?))
+}
+
+fn decode_txt(rdata: &[u8]) -> Vec<String> {
+    let mut strings = Vec::new();
+    let mut pos = 0;
+
+    while let Some(len) = rdata.get(pos) {
+        let start = pos + 1;
+        let Some(s) = rdata.get(start..start + usize::from(*len)) else {
+            break;
+        };
+        if !s.is_empty() {
+            strings.push(String::from_utf8_lossy(s).into_owned());
+        }
+        pos = start + usize::from(*len);
+    }
+
+    strings
+}
+
+#[cfg(test)]
+#[path = "./dns_tests.rs"]
+mod tests;
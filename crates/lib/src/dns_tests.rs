@@ -0,0 +1,99 @@
+use super::*;
+
+// Appends a resource record with the given owner name bytes and rdata
+fn push_record(msg: &mut Vec<u8>, name: &[u8], rtype: u16, rdata: &[u8]) {
+    msg.extend_from_slice(name);
+    msg.extend_from_slice(&rtype.to_be_bytes());
+    msg.extend_from_slice(&CLASS_IN.to_be_bytes());
+    msg.extend_from_slice(&120_u32.to_be_bytes());
+    msg.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+    msg.extend_from_slice(rdata);
+}
+
+fn response_header(records: u16) -> Vec<u8> {
+    let mut msg = vec![0, 0, 0x84, 0, 0, 0];
+    msg.extend_from_slice(&records.to_be_bytes());
+    msg.extend_from_slice(&[0; 4]);
+    msg
+}
+
+#[test]
+fn builds_query() {
+    let query = build_query(7, &[("_ssh._tcp.local", TYPE_PTR)], true);
+
+    assert_eq!(&query[0..2], &[0, 7]);
+    assert_eq!(&query[4..6], &[0, 1]);
+    assert_eq!(&query[12..17], b"\x04_ssh");
+    // type PTR, class IN with the unicast response bit
+    assert_eq!(&query[query.len() - 4..], &[0, 12, 0x80, 1]);
+
+    let parsed = parse(&query).unwrap();
+    assert_eq!(parsed.id, 7);
+    assert!(!parsed.is_response);
+    assert!(parsed.records.is_empty());
+}
+
+#[test]
+fn parses_compressed_records() {
+    let mut msg = response_header(4);
+
+    // offset 12: _ssh._tcp.local
+    let service = b"\x04_ssh\x04_tcp\x05local\x00";
+    // offset 29: "nas" + pointer to _ssh._tcp.local
+    let mut instance = b"\x03nas".to_vec();
+    instance.extend_from_slice(&[0xc0, 12]);
+
+    let mut ptr_rdata = b"\x03nas".to_vec();
+    ptr_rdata.extend_from_slice(&[0xc0, 12]);
+    push_record(&mut msg, service, TYPE_PTR, &ptr_rdata);
+
+    let mut srv_rdata = vec![0, 0, 0, 0, 0, 22];
+    srv_rdata.extend_from_slice(b"\x03nas\x05local\x00");
+    let instance_offset = msg.len();
+    push_record(&mut msg, &instance, TYPE_SRV, &srv_rdata);
+
+    let pointer = [0xc0, instance_offset as u8];
+    push_record(&mut msg, &pointer, TYPE_TXT, b"\x05a=one\x00\x05b=two");
+    push_record(&mut msg, b"\x03nas\x05local\x00", TYPE_A, &[192, 168, 1, 9]);
+
+    let parsed = parse(&msg).unwrap();
+
+    assert!(parsed.is_response);
+    assert_eq!(
+        parsed.records,
+        vec![
+            Record {
+                name: "_ssh._tcp.local".into(),
+                data: RecordData::Ptr("nas._ssh._tcp.local".into()),
+            },
+            Record {
+                name: "nas._ssh._tcp.local".into(),
+                data: RecordData::Srv {
+                    port: 22,
+                    target: "nas.local".into(),
+                },
+            },
+            Record {
+                name: "nas._ssh._tcp.local".into(),
+                data: RecordData::Txt(vec!["a=one".into(), "b=two".into()]),
+            },
+            Record {
+                name: "nas.local".into(),
+                data: RecordData::A(Ipv4Addr::new(192, 168, 1, 9)),
+            },
+        ]
+    );
+}
+
+#[test]
+fn rejects_malformed_messages() {
+    assert!(parse(&[0; 4]).is_none());
+
+    // record count claims more than is present
+    assert!(parse(&response_header(1)).is_none());
+
+    // compression pointer that points at itself
+    let mut msg = response_header(1);
+    push_record(&mut msg, &[0xc0, 12], TYPE_A, &[10, 0, 0, 1]);
+    assert!(parse(&msg).is_none());
+}
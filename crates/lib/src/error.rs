@@ -14,11 +14,27 @@ use crate::{
     packet::{
         arp_packet::ArpPacketBuilderError,
         heartbeat_packet::HeartbeatPacketBuilderError,
-        rst_packet::RstPacketBuilderError, syn_packet::SynPacketBuilderError,
+        icmp_packet::IcmpEchoPacketBuilderError,
+        icmpv6_packet::Icmpv6EchoPacketBuilderError,
+        ndp_packet::NeighborSolicitPacketBuilderError,
+        rst_packet::RstPacketBuilderError, rst6_packet::Rst6PacketBuilderError,
+        syn_packet::SynPacketBuilderError, syn6_packet::Syn6PacketBuilderError,
+        tcp_packet::TcpPacketBuilderError, tcp6_packet::Tcp6PacketBuilderError,
+        udp_packet::UdpPacketBuilderError,
     },
     scanners::{
         ScanMessage, arp_scanner::ARPScannerBuilderError,
-        heartbeat::HeartBeatBuilderError, syn_scanner::SYNScannerBuilderError,
+        connect_scanner::ConnectScannerBuilderError,
+        full_scanner::FullScannerBuilderError,
+        heartbeat::HeartBeatBuilderError,
+        icmp_scanner::ICMPScannerBuilderError,
+        mdns_scanner::MDNSScannerBuilderError,
+        ndp_scanner::NDPScannerBuilderError,
+        passive_scanner::PassiveScannerBuilderError,
+        service_scanner::ServiceScannerBuilderError,
+        ssdp_scanner::SSDPScannerBuilderError,
+        syn_scanner::SYNScannerBuilderError,
+        udp_scanner::UDPScannerBuilderError,
     },
     wire::{Reader, Sender},
 };
@@ -30,6 +46,10 @@ pub enum RLanLibError {
     #[error("oui: {_0}")]
     Oui(String),
 
+    /// Error resulting from failures loading a services database
+    #[error("services: {_0}")]
+    Services(String),
+
     /// Error coming directly off the wire
     #[error("wire error: {_0}")]
     Wire(String),
@@ -50,6 +70,10 @@ pub enum RLanLibError {
     #[error("thread error: {_0}")]
     ThreadError(String),
 
+    /// Returned internally when a scan is cancelled through its handle
+    #[error("scan cancelled")]
+    Cancelled,
+
     /// Errors when consuming messages from channels
     #[error("failed to receive message from channel: {:#?}", _0)]
     ChannelReceive(#[from] RecvError),
@@ -62,22 +86,90 @@ pub enum RLanLibError {
     #[error("failed to build arp scanner: {_0}")]
     ArpScannerBuild(#[from] ARPScannerBuilderError),
 
+    /// Error resulting from failure to build connect scanner
+    #[error("failed to build connect scanner: {_0}")]
+    ConnectScannerBuild(#[from] ConnectScannerBuilderError),
+
+    /// Error resulting from failure to build full scanner
+    #[error("failed to build full scanner: {_0}")]
+    FullScannerBuild(#[from] FullScannerBuilderError),
+
+    /// Error resulting from failure to build ICMP scanner
+    #[error("failed to build icmp scanner: {_0}")]
+    IcmpScannerBuild(#[from] ICMPScannerBuilderError),
+
+    /// Error resulting from failure to build mDNS scanner
+    #[error("failed to build mdns scanner: {_0}")]
+    MdnsScannerBuild(#[from] MDNSScannerBuilderError),
+
+    /// Error resulting from failure to build NDP scanner
+    #[error("failed to build ndp scanner: {_0}")]
+    NdpScannerBuild(#[from] NDPScannerBuilderError),
+
+    /// Error resulting from failure to build passive scanner
+    #[error("failed to build passive scanner: {_0}")]
+    PassiveScannerBuild(#[from] PassiveScannerBuilderError),
+
+    /// Error resulting from failure to build service scanner
+    #[error("failed to build service scanner: {_0}")]
+    ServiceScannerBuild(#[from] ServiceScannerBuilderError),
+
+    /// Error resulting from failure to build SSDP scanner
+    #[error("failed to build ssdp scanner: {_0}")]
+    SsdpScannerBuild(#[from] SSDPScannerBuilderError),
+
     /// Error resulting from failure to build SYN scanner
     #[error("failed to build syn scanner: {_0}")]
     SynScannerBuild(#[from] SYNScannerBuilderError),
 
+    /// Error resulting from failure to build UDP scanner
+    #[error("failed to build udp scanner: {_0}")]
+    UdpScannerBuild(#[from] UDPScannerBuilderError),
+
     /// Error resulting from failure to build Heartbeat
     #[error("failed to build heartbeat: {_0}")]
     HeartBeatBuild(#[from] HeartBeatBuilderError),
 
+    /// Error generated during ICMP echo packet construction
+    #[error("failed to build ICMP echo packet: {_0}")]
+    IcmpEchoPacketBuild(#[from] IcmpEchoPacketBuilderError),
+
+    /// Error generated during ICMPv6 echo packet construction
+    #[error("failed to build ICMPv6 echo packet: {_0}")]
+    Icmpv6EchoPacketBuild(#[from] Icmpv6EchoPacketBuilderError),
+
+    /// Error generated during neighbor solicitation packet construction
+    #[error("failed to build neighbor solicitation packet: {_0}")]
+    NeighborSolicitPacketBuild(#[from] NeighborSolicitPacketBuilderError),
+
     /// Error generated during RST packet construction
     #[error("failed to build RST packet: {_0}")]
     RstPacketBuild(#[from] RstPacketBuilderError),
 
+    /// Error generated during IPv6 RST packet construction
+    #[error("failed to build IPv6 RST packet: {_0}")]
+    Rst6PacketBuild(#[from] Rst6PacketBuilderError),
+
     /// Error generated during SYN packet construction
     #[error("failed to build SYN packet: {_0}")]
     SynPacketBuild(#[from] SynPacketBuilderError),
 
+    /// Error generated during IPv6 SYN packet construction
+    #[error("failed to build IPv6 SYN packet: {_0}")]
+    Syn6PacketBuild(#[from] Syn6PacketBuilderError),
+
+    /// Error generated during TCP packet construction
+    #[error("failed to build TCP packet: {_0}")]
+    TcpPacketBuild(#[from] TcpPacketBuilderError),
+
+    /// Error generated during IPv6 TCP packet construction
+    #[error("failed to build IPv6 TCP packet: {_0}")]
+    Tcp6PacketBuild(#[from] Tcp6PacketBuilderError),
+
+    /// Error generated during UDP packet construction
+    #[error("failed to build UDP packet: {_0}")]
+    UdpPacketBuild(#[from] UdpPacketBuilderError),
+
     /// Error generated during heartbeat packet construction
     #[error("failed to build heartbeat packet: {_0}")]
     HeartbeatPacketBuild(#[from] HeartbeatPacketBuilderError),
@@ -86,6 +178,15 @@ pub enum RLanLibError {
     #[error("network interface error: {_0}")]
     NetworkInterface(String),
 
+    /// Error resulting from an entry in a target list that cannot be parsed
+    #[error("invalid target {target:?}: {reason}")]
+    InvalidTarget {
+        /// The entry that failed to parse
+        target: String,
+        /// Why the entry failed to parse
+        reason: TargetError,
+    },
+
     /// Wrapping errors related to scanning
     #[error("scanning error: {error} - ip: {:#?}, port: {:#?}", ip, port)]
     Scan {
@@ -98,6 +199,50 @@ pub enum RLanLibError {
     },
 }
 
+/// Reasons an entry in a target list cannot be parsed
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum TargetError {
+    /// Not a valid IPv4 address
+    #[error("not a valid IPv4 address")]
+    InvalidIp,
+
+    /// Not a valid range of IPv4 addresses, or its start is after its end
+    #[error("not a valid IP range")]
+    InvalidRange,
+
+    /// Not a valid octet range such as 192.168.1-3.1-254
+    #[error("not a valid octet range")]
+    InvalidOctetRange,
+
+    /// Not a valid CIDR block
+    #[error("not a valid CIDR block")]
+    InvalidCidr,
+
+    /// Not a valid hostname
+    #[error("not a valid hostname")]
+    InvalidHostname,
+
+    /// The hostname did not resolve to an IPv4 address
+    #[error("hostname did not resolve to an IPv4 address")]
+    UnresolvedHostname,
+
+    /// Not a valid port
+    #[error("not a valid port")]
+    InvalidPort,
+
+    /// Not a valid range of ports, or its start is after its end
+    #[error("not a valid port range")]
+    InvalidPortRange,
+
+    /// A shard index that is not less than the number of shards
+    #[error("shard index must be less than the number of shards")]
+    InvalidShard,
+
+    /// A file of targets could not be read
+    #[error("failed to read targets: {_0}")]
+    File(String),
+}
+
 impl From<Box<dyn Any + Send>> for RLanLibError {
     fn from(value: Box<dyn Any + Send>) -> Self {
         if let Some(s) = value.downcast_ref::<&'static str>() {
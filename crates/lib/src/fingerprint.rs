@@ -0,0 +1,25 @@
+//! Provides operating system and device type fingerprinting
+//!
+//! Each submodule matches a different kind of observed traffic against known
+//! signatures, producing an [`OsGuess`] with a confidence score.
+
+use crate::scanners::OsGuess;
+
+pub mod dhcp;
+pub mod tcp;
+
+/// Returns the more confident of two guesses, keeping the current one on
+/// ties
+pub fn most_confident(
+    current: Option<OsGuess>,
+    candidate: Option<OsGuess>,
+) -> Option<OsGuess> {
+    match (current, candidate) {
+        (Some(current), Some(candidate))
+            if candidate.confidence > current.confidence =>
+        {
+            Some(candidate)
+        }
+        (current, candidate) => current.or(candidate),
+    }
+}
@@ -0,0 +1,178 @@
+//! Fingerprints DHCP clients from their DISCOVER and REQUEST messages
+//!
+//! Every DHCP client implementation requests its own set of parameters
+//! (option 55) in its own order, and many announce a vendor class (option
+//! 60). Together with the client's hostname (option 12) this identifies the
+//! operating system far more reliably than the TTL of its packets.
+
+use crate::scanners::OsGuess;
+
+/// Option carrying the client's hostname
+pub const OPTION_HOSTNAME: u8 = 12;
+
+/// Option carrying the client's parameter request list
+pub const OPTION_PARAMETER_REQUEST_LIST: u8 = 55;
+
+/// Option carrying the client's vendor class identifier
+pub const OPTION_VENDOR_CLASS: u8 = 60;
+
+const WINDOWS: (&str, &str) = ("Windows", "computer");
+const MACOS: (&str, &str) = ("macOS", "computer");
+const IOS: (&str, &str) = ("iOS", "phone");
+const ANDROID: (&str, &str) = ("Android", "phone");
+const LINUX: (&str, &str) = ("Linux", "computer");
+const EMBEDDED_LINUX: (&str, &str) = ("Linux", "embedded");
+
+// vendor class prefixes, checked in order
+const VENDOR_CLASSES: &[(&str, (&str, &str), u8)] = &[
+    ("MSFT 5.0", WINDOWS, 90),
+    ("MSFT", WINDOWS, 85),
+    ("android-dhcp", ANDROID, 90),
+    ("udhcp", EMBEDDED_LINUX, 75),
+];
+
+// parameter request lists of common clients, in the order they are sent
+const PARAMETER_LISTS: &[(&[u8], (&str, &str), u8)] = &[
+    // Windows 10 / 11
+    (
+        &[1, 3, 6, 15, 31, 33, 43, 44, 46, 47, 119, 121, 249, 252],
+        WINDOWS,
+        80,
+    ),
+    // Windows 7 / 8
+    (
+        &[1, 15, 3, 6, 44, 46, 47, 31, 33, 121, 249, 43],
+        WINDOWS,
+        80,
+    ),
+    (
+        &[1, 121, 3, 6, 15, 108, 114, 119, 252, 95, 44, 46],
+        MACOS,
+        80,
+    ),
+    (&[1, 121, 3, 6, 15, 119, 252, 95, 44, 46], MACOS, 80),
+    (&[1, 121, 3, 6, 15, 108, 114, 119, 252], IOS, 75),
+    (&[1, 121, 3, 6, 15, 119, 252], IOS, 75),
+    (
+        &[1, 3, 6, 15, 26, 28, 51, 58, 59, 43, 114, 108],
+        ANDROID,
+        75,
+    ),
+    (&[1, 3, 6, 15, 26, 28, 51, 58, 59, 43], ANDROID, 75),
+    // ISC dhclient
+    (
+        &[1, 28, 2, 3, 15, 6, 119, 12, 44, 47, 26, 121, 42],
+        LINUX,
+        70,
+    ),
+    // busybox udhcpc defaults
+    (&[1, 3, 6, 12, 15, 28, 42], EMBEDDED_LINUX, 60),
+];
+
+// lowercase hostname fragments, checked in order
+const HOSTNAMES: &[(&str, (&str, &str), u8)] = &[
+    ("iphone", IOS, 65),
+    ("ipad", ("iPadOS", "tablet"), 65),
+    ("macbook", MACOS, 60),
+    ("imac", MACOS, 60),
+    ("galaxy", ANDROID, 60),
+    ("pixel", ANDROID, 55),
+    ("android", ANDROID, 60),
+    ("desktop-", WINDOWS, 55),
+    ("laptop-", WINDOWS, 55),
+    ("raspberrypi", LINUX, 60),
+];
+
+/// Options a DHCP client sent in a DISCOVER or REQUEST
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DhcpFingerprint {
+    /// Parameter request list (option 55) in the order requested
+    pub parameter_request_list: Vec<u8>,
+    /// Vendor class identifier (option 60)
+    pub vendor_class: Option<String>,
+    /// Client hostname (option 12)
+    pub hostname: Option<String>,
+}
+
+impl DhcpFingerprint {
+    /// Returns the best guess at the client's operating system and device
+    /// type, or None if nothing matches
+    pub fn guess(&self) -> Option<OsGuess> {
+        let mut guess = self
+            .vendor_class_guess()
+            .or_else(|| self.parameter_list_guess())
+            .or_else(|| self.hostname_guess())?;
+
+        // a matching hostname confirms the guess. Apple platforms share
+        // parameter lists so the hostname tells them apart i.e. "Bobs-iPad"
+        if let Some(hint) = self.hostname_guess() {
+            if hint.os == guess.os {
+                guess.confidence = guess.confidence.saturating_add(5).min(100);
+            } else if is_apple(&guess.os) && is_apple(&hint.os) {
+                guess.os = hint.os;
+                guess.device_type = hint.device_type;
+            }
+        }
+
+        Some(guess)
+    }
+
+    fn vendor_class_guess(&self) -> Option<OsGuess> {
+        let vendor = self.vendor_class.as_deref()?;
+
+        if vendor.starts_with("dhcpcd") {
+            // i.e. "dhcpcd-9.4.1:Linux-6.1.21-v8+:aarch64:BCM2835"
+            return vendor.contains(":Linux").then(|| os_guess(LINUX, 80));
+        }
+
+        VENDOR_CLASSES
+            .iter()
+            .find(|(prefix, _, _)| vendor.starts_with(prefix))
+            .map(|(_, os, confidence)| os_guess(*os, *confidence))
+    }
+
+    fn parameter_list_guess(&self) -> Option<OsGuess> {
+        let list = self.parameter_request_list.as_slice();
+
+        if list.is_empty() {
+            return None;
+        }
+
+        if let Some((_, os, confidence)) =
+            PARAMETER_LISTS.iter().find(|(known, _, _)| *known == list)
+        {
+            return Some(os_guess(*os, *confidence));
+        }
+
+        // newer releases usually append options to an existing list
+        PARAMETER_LISTS
+            .iter()
+            .find(|(known, _, _)| list.starts_with(known))
+            .map(|(_, os, confidence)| os_guess(*os, confidence - 20))
+    }
+
+    fn hostname_guess(&self) -> Option<OsGuess> {
+        let hostname = self.hostname.as_deref()?.to_lowercase();
+
+        HOSTNAMES
+            .iter()
+            .find(|(fragment, _, _)| hostname.contains(fragment))
+            .map(|(_, os, confidence)| os_guess(*os, *confidence))
+    }
+}
+
+fn is_apple(os: &str) -> bool {
+    matches!(os, "macOS" | "iOS" | "iPadOS")
+}
+
+fn os_guess((os, device_type): (&str, &str), confidence: u8) -> OsGuess {
+    OsGuess {
+        os: os.to_string(),
+        device_type: device_type.to_string(),
+        confidence,
+    }
+}
+
+#[cfg(test)]
+#[path = "./dhcp_tests.rs"]
+mod tests;
@@ -0,0 +1,79 @@
+use super::*;
+
+const WINDOWS_11: &[u8] =
+    &[1, 3, 6, 15, 31, 33, 43, 44, 46, 47, 119, 121, 249, 252];
+
+#[test]
+fn prefers_vendor_class() {
+    let fingerprint = DhcpFingerprint {
+        parameter_request_list: vec![1, 3, 6, 15, 26, 28, 51, 58, 59, 43],
+        vendor_class: Some("MSFT 5.0".into()),
+        hostname: None,
+    };
+
+    assert_eq!(
+        fingerprint.guess(),
+        Some(OsGuess {
+            os: "Windows".into(),
+            device_type: "computer".into(),
+            confidence: 90,
+        })
+    );
+
+    let fingerprint = DhcpFingerprint {
+        vendor_class: Some("dhcpcd-9.4.1:Linux-6.1.21-v8+:aarch64".into()),
+        ..DhcpFingerprint::default()
+    };
+
+    assert_eq!(fingerprint.guess().unwrap().os, "Linux");
+}
+
+#[test]
+fn matches_parameter_request_list() {
+    let exact = DhcpFingerprint {
+        parameter_request_list: WINDOWS_11.to_vec(),
+        ..DhcpFingerprint::default()
+    };
+
+    let exact_guess = exact.guess().unwrap();
+    assert_eq!(exact_guess.os, "Windows");
+
+    let mut extended = WINDOWS_11.to_vec();
+    extended.push(100);
+
+    let prefix = DhcpFingerprint {
+        parameter_request_list: extended,
+        ..DhcpFingerprint::default()
+    };
+
+    let prefix_guess = prefix.guess().unwrap();
+    assert_eq!(prefix_guess.os, "Windows");
+    assert!(prefix_guess.confidence < exact_guess.confidence);
+
+    let unknown = DhcpFingerprint {
+        parameter_request_list: vec![3, 1],
+        ..DhcpFingerprint::default()
+    };
+
+    assert_eq!(unknown.guess(), None);
+}
+
+#[test]
+fn hostname_refines_guess() {
+    let ipad = DhcpFingerprint {
+        parameter_request_list: vec![1, 121, 3, 6, 15, 119, 252],
+        hostname: Some("Bobs-iPad".into()),
+        ..DhcpFingerprint::default()
+    };
+
+    let guess = ipad.guess().unwrap();
+    assert_eq!(guess.os, "iPadOS");
+    assert_eq!(guess.device_type, "tablet");
+
+    let galaxy = DhcpFingerprint {
+        hostname: Some("Galaxy-S23".into()),
+        ..DhcpFingerprint::default()
+    };
+
+    assert_eq!(galaxy.guess().unwrap().os, "Android");
+}
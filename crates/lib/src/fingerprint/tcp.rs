@@ -0,0 +1,268 @@
+//! Fingerprints TCP/IP stacks from their SYN-ACK responses
+//!
+//! Each stack answers a SYN with its own window size, MSS, window scale and
+//! TCP option layout, and starts from its own initial TTL with or without the
+//! don't fragment bit. Matching these against known stacks gives a much finer
+//! guess than the TTL alone.
+
+use pnet::packet::tcp;
+
+use crate::scanners::OsGuess;
+
+const OPTION_EOL: u8 = 0;
+const OPTION_NOP: u8 = 1;
+const OPTION_MSS: u8 = 2;
+const OPTION_WSCALE: u8 = 3;
+const OPTION_SACK_PERMITTED: u8 = 4;
+const OPTION_TIMESTAMPS: u8 = 8;
+
+const M: u8 = OPTION_MSS;
+const N: u8 = OPTION_NOP;
+const W: u8 = OPTION_WSCALE;
+const S: u8 = OPTION_SACK_PERMITTED;
+const T: u8 = OPTION_TIMESTAMPS;
+const E: u8 = OPTION_EOL;
+
+// confidence of a guess made from the initial TTL alone
+const TTL_ONLY_CONFIDENCE: u8 = 30;
+
+// SYN-ACK characteristics of a known stack, in reply to the options offered
+// by our SYN packets
+struct Signature {
+    os: &'static str,
+    device_type: &'static str,
+    initial_ttl: u8,
+    dont_fragment: bool,
+    options: &'static [u8],
+    // common window sizes, empty if they vary too much to be useful
+    windows: &'static [u16],
+    window_scale: Option<u8>,
+}
+
+const SIGNATURES: &[Signature] = &[
+    Signature {
+        os: "Linux",
+        device_type: "",
+        initial_ttl: 64,
+        dont_fragment: true,
+        options: &[M, S, T, N, W],
+        windows: &[65160, 64240, 43440, 28960, 14480],
+        window_scale: Some(7),
+    },
+    Signature {
+        os: "Windows",
+        device_type: "computer",
+        initial_ttl: 128,
+        dont_fragment: true,
+        options: &[M, N, W, S, T],
+        windows: &[65535, 64240, 8192],
+        window_scale: Some(8),
+    },
+    // Windows only answers with timestamps when enabled
+    Signature {
+        os: "Windows",
+        device_type: "computer",
+        initial_ttl: 128,
+        dont_fragment: true,
+        options: &[M, N, W, N, N, S],
+        windows: &[65535, 64240, 8192],
+        window_scale: Some(8),
+    },
+    Signature {
+        os: "macOS/iOS",
+        device_type: "",
+        initial_ttl: 64,
+        dont_fragment: true,
+        options: &[M, N, W, N, N, T, S, E],
+        windows: &[65535],
+        window_scale: Some(6),
+    },
+    Signature {
+        os: "FreeBSD",
+        device_type: "",
+        initial_ttl: 64,
+        dont_fragment: true,
+        options: &[M, N, W, S, T],
+        windows: &[65535],
+        window_scale: Some(6),
+    },
+    Signature {
+        os: "Cisco IOS",
+        device_type: "network",
+        initial_ttl: 255,
+        dont_fragment: false,
+        options: &[M],
+        windows: &[4128],
+        window_scale: None,
+    },
+    // lwIP and similar stacks on printers, cameras and IoT devices
+    Signature {
+        os: "Embedded",
+        device_type: "embedded",
+        initial_ttl: 64,
+        dont_fragment: false,
+        options: &[M],
+        windows: &[],
+        window_scale: None,
+    },
+    Signature {
+        os: "Embedded",
+        device_type: "embedded",
+        initial_ttl: 255,
+        dont_fragment: false,
+        options: &[M],
+        windows: &[],
+        window_scale: None,
+    },
+];
+
+/// Characteristics of a SYN-ACK identifying the responder's TCP/IP stack
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TcpFingerprint {
+    /// TTL or hop limit the responder most likely started from
+    pub initial_ttl: u8,
+    /// Whether the don't fragment bit was set, None over IPv6
+    pub dont_fragment: Option<bool>,
+    /// Advertised receive window
+    pub window_size: u16,
+    /// Maximum segment size option value
+    pub mss: Option<u16>,
+    /// Window scale option value
+    pub window_scale: Option<u8>,
+    /// TCP option kinds in the order they were sent
+    pub options: Vec<u8>,
+}
+
+impl TcpFingerprint {
+    /// Records the fingerprint of a SYN-ACK received with the given TTL
+    pub fn new(
+        ttl: u8,
+        dont_fragment: Option<bool>,
+        packet: &tcp::TcpPacket,
+    ) -> Self {
+        let mut fingerprint = Self {
+            initial_ttl: initial_ttl(ttl),
+            dont_fragment,
+            window_size: packet.get_window(),
+            ..Self::default()
+        };
+
+        let mut options = packet.get_options_raw();
+
+        while let [kind, rest @ ..] = options {
+            fingerprint.options.push(*kind);
+
+            match *kind {
+                OPTION_EOL => break,
+                OPTION_NOP => options = rest,
+                _ => {
+                    let Some(len) = rest.first().map(|l| usize::from(*l))
+                    else {
+                        break;
+                    };
+
+                    let Some(value) = options.get(2..len) else {
+                        break;
+                    };
+
+                    match (*kind, value) {
+                        (OPTION_MSS, [a, b]) => {
+                            fingerprint.mss = Some(u16::from_be_bytes([*a, *b]))
+                        }
+                        (OPTION_WSCALE, [scale]) => {
+                            fingerprint.window_scale = Some(*scale)
+                        }
+                        _ => {}
+                    }
+
+                    options = &options[len..];
+                }
+            }
+        }
+
+        fingerprint
+    }
+
+    /// Returns the best guess at the responder's operating system. Falls
+    /// back to a low confidence guess from the initial TTL when no known
+    /// stack matches
+    pub fn guess(&self) -> Option<OsGuess> {
+        SIGNATURES
+            .iter()
+            .filter_map(|s| Some((s, self.score(s)?)))
+            .max_by_key(|(_, score)| *score)
+            .map(|(s, confidence)| OsGuess {
+                os: s.os.to_string(),
+                device_type: s.device_type.to_string(),
+                confidence,
+            })
+            .or_else(|| self.ttl_guess())
+    }
+
+    // Scores how well the fingerprint matches the signature, None if only
+    // the TTL matches
+    fn score(&self, signature: &Signature) -> Option<u8> {
+        if signature.initial_ttl != self.initial_ttl {
+            return None;
+        }
+
+        let options = signature.options == self.options;
+        let window = signature.windows.contains(&self.window_size);
+
+        if !options && !window {
+            return None;
+        }
+
+        let mut score = 40;
+
+        if options {
+            score += 30;
+        }
+
+        if window || signature.windows.is_empty() {
+            score += 15;
+        }
+
+        if signature.window_scale == self.window_scale {
+            score += 10;
+        }
+
+        if self
+            .dont_fragment
+            .is_none_or(|df| df == signature.dont_fragment)
+        {
+            score += 5;
+        }
+
+        Some(score)
+    }
+
+    fn ttl_guess(&self) -> Option<OsGuess> {
+        let (os, device_type) = match self.initial_ttl {
+            64 => ("Linux/macOS", ""),
+            128 => ("Windows", "computer"),
+            255 => ("Network", "network"),
+            _ => return None,
+        };
+
+        Some(OsGuess {
+            os: os.to_string(),
+            device_type: device_type.to_string(),
+            confidence: TTL_ONLY_CONFIDENCE,
+        })
+    }
+}
+
+/// Returns the initial TTL a host most likely started from given the TTL
+/// observed after crossing some number of hops
+pub fn initial_ttl(observed: u8) -> u8 {
+    match observed {
+        0..=64 => 64,
+        65..=128 => 128,
+        _ => 255,
+    }
+}
+
+#[cfg(test)]
+#[path = "./tcp_tests.rs"]
+mod tests;
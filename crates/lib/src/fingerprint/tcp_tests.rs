@@ -0,0 +1,89 @@
+use super::*;
+
+// Builds a SYN-ACK header carrying the given window and raw options
+fn syn_ack(window: u16, options: &[u8]) -> Vec<u8> {
+    let mut buf = vec![0u8; tcp::TcpPacket::minimum_packet_size()];
+    buf.extend_from_slice(options);
+
+    let mut packet = tcp::MutableTcpPacket::new(&mut buf).unwrap();
+    packet.set_flags(tcp::TcpFlags::SYN | tcp::TcpFlags::ACK);
+    packet.set_window(window);
+    packet.set_data_offset((5 + options.len() / 4) as u8);
+
+    buf
+}
+
+const LINUX_OPTIONS: [u8; 20] = [
+    2, 4, 0x05, 0xb4, 4, 2, 8, 10, 0, 0, 0, 1, 0, 0, 0, 0, 1, 3, 3, 7,
+];
+
+#[test]
+fn records_syn_ack_characteristics() {
+    let buf = syn_ack(65160, &LINUX_OPTIONS);
+    let packet = tcp::TcpPacket::new(&buf).unwrap();
+
+    let fingerprint = TcpFingerprint::new(61, Some(true), &packet);
+
+    assert_eq!(
+        fingerprint,
+        TcpFingerprint {
+            initial_ttl: 64,
+            dont_fragment: Some(true),
+            window_size: 65160,
+            mss: Some(1460),
+            window_scale: Some(7),
+            options: vec![M, S, T, N, W],
+        }
+    );
+
+    assert_eq!(
+        fingerprint.guess(),
+        Some(OsGuess {
+            os: "Linux".into(),
+            device_type: "".into(),
+            confidence: 100,
+        })
+    );
+}
+
+#[test]
+fn matches_windows_without_timestamps() {
+    let options = [2, 4, 0x05, 0xb4, 1, 3, 3, 8, 1, 1, 4, 2];
+    let buf = syn_ack(64240, &options);
+    let packet = tcp::TcpPacket::new(&buf).unwrap();
+
+    let guess = TcpFingerprint::new(127, Some(true), &packet)
+        .guess()
+        .unwrap();
+
+    assert_eq!(guess.os, "Windows");
+    assert_eq!(guess.confidence, 100);
+}
+
+#[test]
+fn falls_back_to_initial_ttl() {
+    let buf = syn_ack(1024, &[]);
+    let packet = tcp::TcpPacket::new(&buf).unwrap();
+
+    let guess = TcpFingerprint::new(250, None, &packet).guess().unwrap();
+
+    assert_eq!(guess.os, "Network");
+    assert_eq!(guess.confidence, TTL_ONLY_CONFIDENCE);
+
+    assert_eq!(initial_ttl(1), 64);
+    assert_eq!(initial_ttl(100), 128);
+    assert_eq!(initial_ttl(200), 255);
+}
+
+#[test]
+fn stops_at_malformed_options() {
+    // window scale claims more bytes than remain
+    let buf = syn_ack(512, &[2, 4, 0x02, 0x18, 3, 9, 0, 0]);
+    let packet = tcp::TcpPacket::new(&buf).unwrap();
+
+    let fingerprint = TcpFingerprint::new(64, Some(false), &packet);
+
+    assert_eq!(fingerprint.mss, Some(536));
+    assert_eq!(fingerprint.window_scale, None);
+    assert_eq!(fingerprint.options, vec![M, W]);
+}
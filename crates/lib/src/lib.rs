@@ -31,11 +31,16 @@
 #![deny(missing_docs)]
 
 use pnet::util::MacAddr as PnetMacAddr;
+pub mod dns;
 pub mod error;
+pub mod fingerprint;
+pub mod names;
 pub mod network;
 pub mod oui;
 pub mod packet;
+pub mod probes;
 pub mod scanners;
+pub mod services;
 pub mod targets;
 pub mod wire;
 
@@ -0,0 +1,243 @@
+//! Provides hostname resolution for hosts without PTR records
+//!
+//! Windows machines rarely have reverse DNS entries, but they answer
+//! NetBIOS Node Status requests (UDP 137) with their computer name,
+//! workgroup and MAC, and LLMNR reverse queries (UDP 5355) with their host
+//! name. Both queries are sent at once and the NetBIOS answer is preferred
+//! as it carries the most information. Unlike reverse DNS these go straight
+//! to the host, so they are only sent when asked for explicitly.
+
+use pnet::util::MacAddr;
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket},
+    time::{Duration, Instant},
+};
+
+use crate::dns::{self, RecordData, TYPE_PTR};
+
+/// Default time to wait for NetBIOS and LLMNR replies
+pub const DEFAULT_NAME_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Port NetBIOS name services listen on
+pub const NETBIOS_PORT: u16 = 137;
+
+/// Port LLMNR responders listen on
+pub const LLMNR_PORT: u16 = 5355;
+
+// NetBIOS encoding of the "*" wildcard name, which every node answers
+const NETBIOS_WILDCARD: &str = "CKAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA";
+
+const NETBIOS_TYPE_NBSTAT: u16 = 0x21;
+const NETBIOS_NAME_SIZE: usize = 15;
+const NETBIOS_ENTRY_SIZE: usize = 18;
+const NETBIOS_GROUP_FLAG: u16 = 0x8000;
+// suffix of workstation and domain / workgroup names
+const NETBIOS_SUFFIX_WORKSTATION: u8 = 0x00;
+
+/// Name details reported by a host
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct HostName {
+    /// Computer or host name
+    pub name: String,
+    /// Workgroup or domain, only reported over NetBIOS
+    pub workgroup: Option<String>,
+    /// MAC address, only reported over NetBIOS
+    pub mac: Option<MacAddr>,
+}
+
+/// Returns the hostname of the ip from reverse DNS, or an empty string if
+/// it has none
+pub fn lookup_hostname(ip: Ipv4Addr) -> String {
+    dns_lookup::lookup_addr(&ip.into()).unwrap_or_default()
+}
+
+/// Returns the hostname of the ip from reverse DNS, falling back to NetBIOS
+/// and LLMNR queries sent to the host when DNS returns nothing
+pub fn lookup_hostname_with_fallback(ip: Ipv4Addr) -> String {
+    match lookup_hostname(ip) {
+        name if !name.is_empty() => name,
+        _ => lookup(ip, DEFAULT_NAME_TIMEOUT)
+            .map(|n| n.name)
+            .unwrap_or_default(),
+    }
+}
+
+/// Queries the host over NetBIOS and LLMNR returning its name if either
+/// protocol answers within the timeout
+pub fn lookup(ip: Ipv4Addr, timeout: Duration) -> Option<HostName> {
+    query(
+        ip,
+        SocketAddr::new(IpAddr::V4(ip), NETBIOS_PORT),
+        SocketAddr::new(IpAddr::V4(ip), LLMNR_PORT),
+        timeout,
+    )
+}
+
+fn query(
+    ip: Ipv4Addr,
+    netbios: SocketAddr,
+    llmnr: SocketAddr,
+    timeout: Duration,
+) -> Option<HostName> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).ok()?;
+
+    let id = std::process::id() as u16;
+
+    let node_status =
+        dns::build_query(id, &[(NETBIOS_WILDCARD, NETBIOS_TYPE_NBSTAT)], false);
+    let reverse = dns::build_query(id, &[(&reverse_name(ip), TYPE_PTR)], false);
+
+    // either may fail i.e. if the host is unreachable, but one is enough
+    let netbios_sent = socket.send_to(&node_status, netbios).is_ok();
+    let llmnr_sent = socket.send_to(&reverse, llmnr).is_ok();
+
+    if !netbios_sent && !llmnr_sent {
+        return None;
+    }
+
+    let deadline = Instant::now() + timeout;
+    let mut llmnr_name = None;
+    let mut buf = [0u8; 1500];
+
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+
+        if remaining.is_zero() {
+            break;
+        }
+
+        socket.set_read_timeout(Some(remaining)).ok()?;
+
+        let Ok((n, from)) = socket.recv_from(&mut buf) else {
+            break;
+        };
+
+        if from == netbios
+            && let Some(found) = parse_node_status(&buf[..n])
+        {
+            return Some(found);
+        }
+
+        if from == llmnr
+            && let Some(name) = parse_reverse(&buf[..n])
+        {
+            log::debug!("llmnr resolved {} to {}", ip, name);
+            llmnr_name = Some(name);
+
+            // give NetBIOS a chance to answer with more details
+            if !netbios_sent {
+                break;
+            }
+        }
+    }
+
+    llmnr_name.map(|name| HostName {
+        name,
+        ..HostName::default()
+    })
+}
+
+fn reverse_name(ip: Ipv4Addr) -> String {
+    let [a, b, c, d] = ip.octets();
+    format!("{d}.{c}.{b}.{a}.in-addr.arpa")
+}
+
+fn parse_reverse(msg: &[u8]) -> Option<String> {
+    let msg = dns::parse(msg)?;
+
+    if !msg.is_response {
+        return None;
+    }
+
+    msg.records.into_iter().find_map(|r| match r.data {
+        RecordData::Ptr(name) if !name.is_empty() => Some(name),
+        _ => None,
+    })
+}
+
+// Parses a Node Status response. NBSTAT shares its type code with SRV so
+// this can't go through the generic DNS parser
+fn parse_node_status(msg: &[u8]) -> Option<HostName> {
+    let read_u16 = |pos: usize| -> Option<u16> {
+        Some(u16::from_be_bytes(msg.get(pos..pos + 2)?.try_into().ok()?))
+    };
+
+    let flags = read_u16(2)?;
+    let questions = read_u16(4)?;
+    let answers = read_u16(6)?;
+
+    if flags & 0x8000 == 0 || answers == 0 {
+        return None;
+    }
+
+    let mut pos = 12;
+
+    for _ in 0..questions {
+        pos = skip_name(msg, pos)? + 4;
+    }
+
+    pos = skip_name(msg, pos)?;
+
+    if read_u16(pos)? != NETBIOS_TYPE_NBSTAT {
+        return None;
+    }
+
+    // class (2) and ttl (4) are not needed
+    let rdata_len = usize::from(read_u16(pos + 8)?);
+    let rdata = msg.get(pos + 10..pos + 10 + rdata_len)?;
+
+    let (count, entries) = rdata.split_first()?;
+    let names_len = usize::from(*count) * NETBIOS_ENTRY_SIZE;
+    let entries = entries.get(..names_len)?;
+
+    let mut found = HostName::default();
+
+    for entry in entries.as_chunks::<NETBIOS_ENTRY_SIZE>().0 {
+        if entry[NETBIOS_NAME_SIZE] != NETBIOS_SUFFIX_WORKSTATION {
+            continue;
+        }
+
+        let name = String::from_utf8_lossy(&entry[..NETBIOS_NAME_SIZE])
+            .trim_end_matches([' ', '\0'])
+            .to_string();
+        let is_group = u16::from_be_bytes([entry[16], entry[17]])
+            & NETBIOS_GROUP_FLAG
+            != 0;
+
+        if is_group {
+            found.workgroup.get_or_insert(name);
+        } else if found.name.is_empty() {
+            found.name = name;
+        }
+    }
+
+    // the unit id following the names is the adapter MAC. Samba reports
+    // all zeros
+    found.mac = rdata
+        .get(1 + names_len..1 + names_len + 6)
+        .map(|m| MacAddr::new(m[0], m[1], m[2], m[3], m[4], m[5]))
+        .filter(|m| !m.is_zero());
+
+    if found.name.is_empty() {
+        return None;
+    }
+
+    Some(found)
+}
+
+// Returns the position just past the name at pos
+fn skip_name(msg: &[u8], mut pos: usize) -> Option<usize> {
+    loop {
+        let len = *msg.get(pos)?;
+
+        match len {
+            0 => return Some(pos + 1),
+            l if l & 0xc0 == 0xc0 => return Some(pos + 2),
+            l => pos += 1 + usize::from(l),
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "./names_tests.rs"]
+mod tests;
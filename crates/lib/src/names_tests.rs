@@ -0,0 +1,142 @@
+use super::*;
+use std::thread;
+
+// Builds a node status response listing the given (name, suffix, group)
+// entries followed by the unit id
+fn node_status_response(entries: &[(&str, u8, bool)], mac: [u8; 6]) -> Vec<u8> {
+    let mut rdata = vec![entries.len() as u8];
+    for (name, suffix, group) in entries {
+        let mut padded = format!("{name:<15}").into_bytes();
+        padded.push(*suffix);
+        rdata.extend_from_slice(&padded);
+        let flags: u16 = if *group { NETBIOS_GROUP_FLAG } else { 0 };
+        rdata.extend_from_slice(&flags.to_be_bytes());
+    }
+    rdata.extend_from_slice(&mac);
+
+    let mut msg = vec![0, 1, 0x84, 0, 0, 0, 0, 1, 0, 0, 0, 0];
+    msg.push(32);
+    msg.extend_from_slice(NETBIOS_WILDCARD.as_bytes());
+    msg.push(0);
+    msg.extend_from_slice(&NETBIOS_TYPE_NBSTAT.to_be_bytes());
+    msg.extend_from_slice(&[0, 1, 0, 0, 0, 0]);
+    msg.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+    msg.extend_from_slice(&rdata);
+    msg
+}
+
+fn llmnr_response(ip: Ipv4Addr, name: &str) -> Vec<u8> {
+    let mut msg = vec![0, 1, 0x80, 0, 0, 0, 0, 1, 0, 0, 0, 0];
+    let encode = |n: &str| {
+        let mut buf = Vec::new();
+        for label in n.split('.') {
+            buf.push(label.len() as u8);
+            buf.extend_from_slice(label.as_bytes());
+        }
+        buf.push(0);
+        buf
+    };
+    let owner = encode(&reverse_name(ip));
+    let target = encode(name);
+    msg.extend_from_slice(&owner);
+    msg.extend_from_slice(&TYPE_PTR.to_be_bytes());
+    msg.extend_from_slice(&[0, 1, 0, 0, 0, 30]);
+    msg.extend_from_slice(&(target.len() as u16).to_be_bytes());
+    msg.extend_from_slice(&target);
+    msg
+}
+
+// Replies to the first datagram received with the given response
+fn spawn_responder(response: Vec<u8>) -> SocketAddr {
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let addr = socket.local_addr().unwrap();
+
+    thread::spawn(move || {
+        let mut buf = [0u8; 1500];
+        if let Ok((_, from)) = socket.recv_from(&mut buf) {
+            let _ = socket.send_to(&response, from);
+        }
+    });
+
+    addr
+}
+
+// An address nothing listens on
+fn silent_addr() -> SocketAddr {
+    UdpSocket::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+}
+
+#[test]
+fn parses_node_status() {
+    let response = node_status_response(
+        &[
+            ("DESKTOP-42", 0x00, false),
+            ("WORKGROUP", 0x00, true),
+            ("DESKTOP-42", 0x20, false),
+        ],
+        [0x02, 0, 0, 0, 0, 0x42],
+    );
+
+    assert_eq!(
+        parse_node_status(&response),
+        Some(HostName {
+            name: "DESKTOP-42".into(),
+            workgroup: Some("WORKGROUP".into()),
+            mac: Some(MacAddr::new(0x02, 0, 0, 0, 0, 0x42)),
+        })
+    );
+}
+
+#[test]
+fn ignores_zero_unit_id_and_malformed_status() {
+    let response =
+        node_status_response(&[("NAS", 0x00, false)], [0, 0, 0, 0, 0, 0]);
+
+    let found = parse_node_status(&response).unwrap();
+    assert_eq!(found.name, "NAS");
+    assert_eq!(found.mac, None);
+
+    assert!(parse_node_status(&response[..response.len() - 20]).is_none());
+    assert!(parse_node_status(&[0; 4]).is_none());
+}
+
+#[test]
+fn prefers_netbios_name() {
+    let ip = Ipv4Addr::new(192, 168, 1, 42);
+
+    let netbios = spawn_responder(node_status_response(
+        &[("DESKTOP-42", 0x00, false)],
+        [0x02, 0, 0, 0, 0, 0x42],
+    ));
+    let llmnr = spawn_responder(llmnr_response(ip, "desktop-42"));
+
+    let found = query(ip, netbios, llmnr, Duration::from_secs(2)).unwrap();
+
+    assert_eq!(found.name, "DESKTOP-42");
+}
+
+#[test]
+fn falls_back_to_llmnr_name() {
+    let ip = Ipv4Addr::new(192, 168, 1, 43);
+
+    let llmnr = spawn_responder(llmnr_response(ip, "laptop"));
+
+    let found =
+        query(ip, silent_addr(), llmnr, Duration::from_millis(300)).unwrap();
+
+    assert_eq!(
+        found,
+        HostName {
+            name: "laptop".into(),
+            ..HostName::default()
+        }
+    );
+
+    assert!(
+        query(ip, silent_addr(), silent_addr(), Duration::from_millis(100))
+            .is_none()
+    );
+}
@@ -8,7 +8,7 @@ use pnet::{
     util::MacAddr,
 };
 use std::{
-    net::{Ipv4Addr, TcpListener},
+    net::{Ipv4Addr, Ipv6Addr, TcpListener},
     process::Command,
     str::FromStr,
 };
@@ -25,6 +25,9 @@ pub struct NetworkInterface {
     pub cidr: String,
     /// The assigned IPV4 address on the interface
     pub ipv4: Ipv4Addr,
+    /// The assigned IPV6 address on the interface if any. Link-local
+    /// addresses are preferred as they are used for neighbor discovery
+    pub ipv6: Option<Ipv6Addr>,
     /// The IpNetwork of the interface
     pub ips: Vec<IpNetwork>,
     /// The MAC address of the interface
@@ -52,6 +55,7 @@ impl TryFrom<PNetNetworkInterface> for NetworkInterface {
                 "failed to parse interface ip address '{ip}': {e}"
             ))
         })?;
+        let ipv6 = get_interface_ipv6(&value);
 
         Ok(Self {
             name: value.name,
@@ -62,6 +66,7 @@ impl TryFrom<PNetNetworkInterface> for NetworkInterface {
             ips: value.ips,
             cidr,
             ipv4,
+            ipv6,
         })
     }
 }
@@ -135,6 +140,22 @@ fn get_interface_ipv4_and_cidr(
     Some((host_ip, cidr))
 }
 
+fn get_interface_ipv6(interface: &PNetNetworkInterface) -> Option<Ipv6Addr> {
+    let ips = interface
+        .ips
+        .iter()
+        .filter_map(|i| match i {
+            IpNetwork::V6(net) => Some(net.ip()),
+            IpNetwork::V4(_) => None,
+        })
+        .collect::<Vec<Ipv6Addr>>();
+
+    ips.iter()
+        .find(|ip| ip.is_unicast_link_local())
+        .or(ips.first())
+        .copied()
+}
+
 /// Returns the default gateway IPv4 address by parsing the system routing
 /// table. Works on macOS (`netstat -rn`).
 /// Returns `None` if the gateway cannot be determined.
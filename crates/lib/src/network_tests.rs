@@ -52,3 +52,21 @@ fn get_ip_and_cidr_from_interface() {
     assert!(!ip.is_empty());
     assert!(!cidr.is_empty());
 }
+
+#[test]
+fn prefers_link_local_ipv6_for_interface() {
+    let iface = PNetNetworkInterface {
+        name: "test".to_string(),
+        description: String::new(),
+        index: 0,
+        mac: Some(MacAddr::default()),
+        ips: vec![
+            IpNetwork::from_str("192.168.1.2/24").unwrap(),
+            IpNetwork::from_str("2001:db8::2/64").unwrap(),
+            IpNetwork::from_str("fe80::2/64").unwrap(),
+        ],
+        flags: 0,
+    };
+    let ip = get_interface_ipv6(&iface).unwrap();
+    assert_eq!(ip, Ipv6Addr::from_str("fe80::2").unwrap());
+}
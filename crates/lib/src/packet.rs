@@ -2,5 +2,13 @@
 
 pub mod arp_packet;
 pub mod heartbeat_packet;
+pub mod icmp_packet;
+pub mod icmpv6_packet;
+pub mod ndp_packet;
+pub mod rst6_packet;
 pub mod rst_packet;
+pub mod syn6_packet;
 pub mod syn_packet;
+pub mod tcp6_packet;
+pub mod tcp_packet;
+pub mod udp_packet;
@@ -4,7 +4,7 @@ use std::net;
 
 use derive_builder::Builder;
 use pnet::{
-    packet::{MutablePacket, ethernet, ip, ipv4, tcp},
+    packet::{MutablePacket, Packet, ethernet, ip, ipv4, tcp},
     util,
 };
 
@@ -79,6 +79,37 @@ impl HeartbeatPacket {
     }
 }
 
+/// Returns true if the raw packet is a heartbeat sent from `source_mac` and
+/// `source_port`
+pub fn is_heartbeat(
+    pkt: &[u8],
+    source_mac: util::MacAddr,
+    source_port: u16,
+) -> bool {
+    let Some(eth) = ethernet::EthernetPacket::new(pkt) else {
+        return false;
+    };
+
+    if eth.get_source() != source_mac
+        || eth.get_destination() != source_mac
+        || eth.get_ethertype() != ethernet::EtherTypes::Ipv4
+    {
+        return false;
+    }
+
+    let Some(header) = ipv4::Ipv4Packet::new(eth.payload()) else {
+        return false;
+    };
+
+    if header.get_next_level_protocol() != ip::IpNextHeaderProtocols::Tcp {
+        return false;
+    }
+
+    tcp::TcpPacket::new(header.payload()).is_some_and(|tcp| {
+        tcp.get_source() == source_port && tcp.get_destination() == source_port
+    })
+}
+
 #[cfg(test)]
 #[path = "./heartbeat_packet_tests.rs"]
 mod tests;
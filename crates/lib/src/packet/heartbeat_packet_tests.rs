@@ -18,3 +18,21 @@ fn creates_heartbeat_packet() {
     let packet = heartbeat_packet.to_raw();
     assert!(!packet.is_empty());
 }
+
+#[test]
+fn recognizes_heartbeat_packets() {
+    let source_ip = net::Ipv4Addr::from_str("192.168.68.1").unwrap();
+    let source_mac = MacAddr::from_str("00:11:22:33:44:55").unwrap();
+    let packet = HeartbeatPacketBuilder::default()
+        .source_ip(source_ip)
+        .source_mac(source_mac)
+        .source_port(54321_u16)
+        .build()
+        .unwrap()
+        .to_raw();
+
+    assert!(is_heartbeat(&packet, source_mac, 54321));
+    assert!(!is_heartbeat(&packet, source_mac, 54322));
+    assert!(!is_heartbeat(&packet, MacAddr::zero(), 54321));
+    assert!(!is_heartbeat(&packet[..20], source_mac, 54321));
+}
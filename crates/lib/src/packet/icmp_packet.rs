@@ -0,0 +1,149 @@
+//! Provides helpers for creating ICMP echo request packets
+
+use std::net;
+
+use derive_builder::Builder;
+use pnet::{
+    packet::{
+        MutablePacket, ethernet,
+        icmp::{self, echo_request},
+        ip, ipv4,
+    },
+    util,
+};
+
+const PKT_ETH_SIZE: usize = ethernet::EthernetPacket::minimum_packet_size();
+const PKT_IP4_SIZE: usize = ipv4::Ipv4Packet::minimum_packet_size();
+const PKT_ICMP_SIZE: usize =
+    echo_request::EchoRequestPacket::minimum_packet_size();
+const PKT_PAYLOAD_SIZE: usize = 16;
+const PKT_TOTAL_SIZE: usize =
+    PKT_ETH_SIZE + PKT_IP4_SIZE + PKT_ICMP_SIZE + PKT_PAYLOAD_SIZE;
+
+/// Represents a generator for raw ICMP echo request packets
+#[derive(Debug, Builder)]
+#[builder(setter(into))]
+pub struct IcmpEchoPacket {
+    /// IP address of the host machine performing scanning
+    source_ip: net::Ipv4Addr,
+    /// MAC address of host machine performing scanning
+    source_mac: util::MacAddr,
+    /// Target destination IP for the packet
+    dest_ip: net::Ipv4Addr,
+    /// MAC address of the next hop. For off-link targets this is the MAC
+    /// of the gateway that will route the packet
+    dest_mac: util::MacAddr,
+    /// Identifier used to match echo replies to this scanner
+    identifier: u16,
+    /// Sequence number used to match echo replies to this request
+    sequence: u16,
+}
+
+impl IcmpEchoPacket {
+    /// Builds a new ICMP echo request packet using the provided information
+    pub fn to_raw(&self) -> [u8; PKT_TOTAL_SIZE] {
+        let mut pkt_buf = [0u8; PKT_TOTAL_SIZE];
+
+        let mut eth_header = ethernet::MutableEthernetPacket::new(&mut pkt_buf)
+            .expect("failed to generate ethernet header");
+        eth_header.set_ethertype(ethernet::EtherTypes::Ipv4);
+        eth_header.set_source(self.source_mac);
+        eth_header.set_destination(self.dest_mac);
+
+        // set ip header
+        let mut ip_buffer =
+            [0u8; PKT_IP4_SIZE + PKT_ICMP_SIZE + PKT_PAYLOAD_SIZE];
+
+        let mut ip_header = ipv4::MutableIpv4Packet::new(&mut ip_buffer)
+            .expect("failed to generate ip header");
+
+        ip_header.set_next_level_protocol(ip::IpNextHeaderProtocols::Icmp);
+        ip_header.set_source(self.source_ip);
+        ip_header.set_destination(self.dest_ip);
+        ip_header.set_version(4);
+        ip_header.set_ttl(64);
+        ip_header.set_identification(0);
+        ip_header.set_header_length(5);
+        ip_header.set_total_length(
+            (PKT_IP4_SIZE + PKT_ICMP_SIZE + PKT_PAYLOAD_SIZE) as u16,
+        );
+        ip_header.set_checksum(ipv4::checksum(&ip_header.to_immutable()));
+
+        // set icmp header
+        let mut icmp_buffer = [0u8; PKT_ICMP_SIZE + PKT_PAYLOAD_SIZE];
+
+        let mut icmp_header =
+            echo_request::MutableEchoRequestPacket::new(&mut icmp_buffer)
+                .expect("failed to generate icmp header");
+
+        icmp_header.set_icmp_type(icmp::IcmpTypes::EchoRequest);
+        icmp_header.set_icmp_code(echo_request::IcmpCodes::NoCode);
+        icmp_header.set_identifier(self.identifier);
+        icmp_header.set_sequence_number(self.sequence);
+        let checksum = icmp::checksum(
+            &icmp::IcmpPacket::new(icmp_header.packet_mut())
+                .expect("failed to generate icmp packet"),
+        );
+        icmp_header.set_checksum(checksum);
+
+        ip_header.set_payload(icmp_header.packet_mut());
+        eth_header.set_payload(ip_header.packet_mut());
+
+        pkt_buf
+    }
+}
+
+#[cfg(test)]
+#[allow(warnings)]
+#[doc(hidden)]
+// only used in tests
+pub fn create_icmp_echo_reply(
+    from_mac: util::MacAddr,
+    from_ip: net::Ipv4Addr,
+    to_mac: util::MacAddr,
+    to_ip: net::Ipv4Addr,
+    identifier: u16,
+    sequence: u16,
+    packet: &'static mut [u8; PKT_TOTAL_SIZE],
+) {
+    use pnet::packet::icmp::echo_reply;
+
+    let mut eth_header = ethernet::MutableEthernetPacket::new(packet)
+        .expect("failed to generate ethernet header");
+    eth_header.set_ethertype(ethernet::EtherTypes::Ipv4);
+    eth_header.set_source(from_mac);
+    eth_header.set_destination(to_mac);
+
+    let mut ip_buffer = [0u8; PKT_IP4_SIZE + PKT_ICMP_SIZE + PKT_PAYLOAD_SIZE];
+
+    let mut ip_header = ipv4::MutableIpv4Packet::new(&mut ip_buffer)
+        .expect("failed to generate ip header");
+
+    ip_header.set_next_level_protocol(ip::IpNextHeaderProtocols::Icmp);
+    ip_header.set_source(from_ip);
+    ip_header.set_destination(to_ip);
+    ip_header.set_version(4);
+    ip_header.set_ttl(63);
+    ip_header.set_header_length(5);
+    ip_header.set_total_length(
+        (PKT_IP4_SIZE + PKT_ICMP_SIZE + PKT_PAYLOAD_SIZE) as u16,
+    );
+    ip_header.set_checksum(ipv4::checksum(&ip_header.to_immutable()));
+
+    let mut icmp_buffer = [0u8; PKT_ICMP_SIZE + PKT_PAYLOAD_SIZE];
+
+    let mut icmp_header =
+        echo_reply::MutableEchoReplyPacket::new(&mut icmp_buffer)
+            .expect("failed to generate icmp header");
+
+    icmp_header.set_icmp_type(icmp::IcmpTypes::EchoReply);
+    icmp_header.set_identifier(identifier);
+    icmp_header.set_sequence_number(sequence);
+
+    ip_header.set_payload(icmp_header.packet_mut());
+    eth_header.set_payload(ip_header.packet_mut());
+}
+
+#[cfg(test)]
+#[path = "./icmp_packet_tests.rs"]
+mod tests;
@@ -0,0 +1,38 @@
+use super::*;
+
+use pnet::packet::Packet;
+use pnet::util::MacAddr;
+
+use std::str::FromStr;
+
+#[test]
+fn creates_icmp_echo_packet() {
+    let source_ip = net::Ipv4Addr::from_str("192.168.68.1").unwrap();
+    let source_mac = MacAddr::from_str("00:00:00:00:00:00").unwrap();
+    let target_ip = net::Ipv4Addr::from_str("10.0.5.2").unwrap();
+    let gateway_mac = MacAddr::from_str("00:00:00:00:00:01").unwrap();
+    let icmp_packet = IcmpEchoPacketBuilder::default()
+        .source_ip(source_ip)
+        .source_mac(source_mac)
+        .dest_ip(target_ip)
+        .dest_mac(gateway_mac)
+        .identifier(54321_u16)
+        .sequence(7_u16)
+        .build()
+        .unwrap();
+    let packet = icmp_packet.to_raw();
+
+    let eth = ethernet::EthernetPacket::new(&packet).unwrap();
+    let ip = ipv4::Ipv4Packet::new(eth.payload()).unwrap();
+    let echo = echo_request::EchoRequestPacket::new(ip.payload()).unwrap();
+
+    assert_eq!(eth.get_destination(), gateway_mac);
+    assert_eq!(ip.get_destination(), target_ip);
+    assert_eq!(echo.get_icmp_type(), icmp::IcmpTypes::EchoRequest);
+    assert_eq!(echo.get_identifier(), 54321);
+    assert_eq!(echo.get_sequence_number(), 7);
+    assert_eq!(
+        icmp::checksum(&icmp::IcmpPacket::new(ip.payload()).unwrap()),
+        echo.get_checksum()
+    );
+}
@@ -0,0 +1,142 @@
+//! Provides helpers for creating ICMPv6 echo request packets
+
+use std::net;
+
+use derive_builder::Builder;
+use pnet::{
+    packet::{
+        MutablePacket, ethernet,
+        icmpv6::{self, echo_request},
+        ip, ipv6,
+    },
+    util,
+};
+
+const PKT_ETH_SIZE: usize = ethernet::EthernetPacket::minimum_packet_size();
+const PKT_IP6_SIZE: usize = ipv6::Ipv6Packet::minimum_packet_size();
+const PKT_ICMP_SIZE: usize =
+    echo_request::EchoRequestPacket::minimum_packet_size();
+const PKT_PAYLOAD_SIZE: usize = 16;
+const PKT_TOTAL_SIZE: usize =
+    PKT_ETH_SIZE + PKT_IP6_SIZE + PKT_ICMP_SIZE + PKT_PAYLOAD_SIZE;
+
+/// Represents a generator for raw ICMPv6 echo request packets
+#[derive(Debug, Builder)]
+#[builder(setter(into))]
+pub struct Icmpv6EchoPacket {
+    /// IPv6 address of the host machine performing scanning
+    source_ip: net::Ipv6Addr,
+    /// MAC address of host machine performing scanning
+    source_mac: util::MacAddr,
+    /// Target destination IPv6 for the packet i.e. ff02::1
+    dest_ip: net::Ipv6Addr,
+    /// Target destination MAC address for the packet
+    dest_mac: util::MacAddr,
+    /// Identifier used to match echo replies to this scanner
+    identifier: u16,
+    /// Sequence number used to match echo replies to this request
+    sequence: u16,
+}
+
+impl Icmpv6EchoPacket {
+    /// Builds a new ICMPv6 echo request packet using the provided information
+    pub fn to_raw(&self) -> [u8; PKT_TOTAL_SIZE] {
+        let mut pkt_buf = [0u8; PKT_TOTAL_SIZE];
+
+        let mut eth_header = ethernet::MutableEthernetPacket::new(&mut pkt_buf)
+            .expect("failed to generate ethernet header");
+        eth_header.set_ethertype(ethernet::EtherTypes::Ipv6);
+        eth_header.set_source(self.source_mac);
+        eth_header.set_destination(self.dest_mac);
+
+        // set ip header
+        let mut ip_buffer =
+            [0u8; PKT_IP6_SIZE + PKT_ICMP_SIZE + PKT_PAYLOAD_SIZE];
+
+        let mut ip_header = ipv6::MutableIpv6Packet::new(&mut ip_buffer)
+            .expect("failed to generate ip header");
+
+        ip_header.set_version(6);
+        ip_header.set_next_header(ip::IpNextHeaderProtocols::Icmpv6);
+        ip_header.set_payload_length((PKT_ICMP_SIZE + PKT_PAYLOAD_SIZE) as u16);
+        ip_header.set_hop_limit(64);
+        ip_header.set_source(self.source_ip);
+        ip_header.set_destination(self.dest_ip);
+
+        // set icmpv6 header
+        let mut icmp_buffer = [0u8; PKT_ICMP_SIZE + PKT_PAYLOAD_SIZE];
+
+        let mut icmp_header =
+            echo_request::MutableEchoRequestPacket::new(&mut icmp_buffer)
+                .expect("failed to generate icmpv6 header");
+
+        icmp_header.set_icmpv6_type(icmpv6::Icmpv6Types::EchoRequest);
+        icmp_header.set_icmpv6_code(echo_request::Icmpv6Codes::NoCode);
+        icmp_header.set_identifier(self.identifier);
+        icmp_header.set_sequence_number(self.sequence);
+
+        let checksum = icmpv6::checksum(
+            &icmpv6::Icmpv6Packet::new(icmp_header.packet_mut())
+                .expect("failed to generate icmpv6 packet"),
+            &self.source_ip,
+            &self.dest_ip,
+        );
+        icmp_header.set_checksum(checksum);
+
+        ip_header.set_payload(icmp_header.packet_mut());
+        eth_header.set_payload(ip_header.packet_mut());
+
+        pkt_buf
+    }
+}
+
+#[cfg(test)]
+#[allow(warnings)]
+#[doc(hidden)]
+// only used in tests
+pub fn create_icmpv6_echo_reply(
+    from_mac: util::MacAddr,
+    from_ip: net::Ipv6Addr,
+    to_mac: util::MacAddr,
+    to_ip: net::Ipv6Addr,
+    identifier: u16,
+    sequence: u16,
+    packet: &'static mut [u8; PKT_TOTAL_SIZE],
+) {
+    use pnet::packet::icmpv6::echo_reply;
+
+    let mut eth_header = ethernet::MutableEthernetPacket::new(packet)
+        .expect("failed to generate ethernet header");
+    eth_header.set_ethertype(ethernet::EtherTypes::Ipv6);
+    eth_header.set_source(from_mac);
+    eth_header.set_destination(to_mac);
+
+    let mut ip_buffer = [0u8; PKT_IP6_SIZE + PKT_ICMP_SIZE + PKT_PAYLOAD_SIZE];
+
+    let mut ip_header = ipv6::MutableIpv6Packet::new(&mut ip_buffer)
+        .expect("failed to generate ip header");
+
+    ip_header.set_version(6);
+    ip_header.set_next_header(ip::IpNextHeaderProtocols::Icmpv6);
+    ip_header.set_payload_length((PKT_ICMP_SIZE + PKT_PAYLOAD_SIZE) as u16);
+    ip_header.set_hop_limit(64);
+    ip_header.set_source(from_ip);
+    ip_header.set_destination(to_ip);
+
+    let mut icmp_buffer = [0u8; PKT_ICMP_SIZE + PKT_PAYLOAD_SIZE];
+
+    let mut icmp_header =
+        echo_reply::MutableEchoReplyPacket::new(&mut icmp_buffer)
+            .expect("failed to generate icmpv6 header");
+
+    icmp_header.set_icmpv6_type(icmpv6::Icmpv6Types::EchoReply);
+    icmp_header.set_identifier(identifier);
+    icmp_header.set_sequence_number(sequence);
+
+    ip_header.set_payload(icmp_header.packet_mut());
+    eth_header.set_payload(ip_header.packet_mut());
+}
+
+#[cfg(test)]
+#[path = "./icmpv6_packet_tests.rs"]
+mod tests;
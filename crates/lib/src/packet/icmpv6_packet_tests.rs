@@ -0,0 +1,43 @@
+use super::*;
+
+use pnet::packet::Packet;
+use pnet::util::MacAddr;
+
+use std::str::FromStr;
+
+#[test]
+fn creates_icmpv6_echo_packet() {
+    let source_ip = net::Ipv6Addr::from_str("fe80::1").unwrap();
+    let source_mac = MacAddr::from_str("00:00:00:00:00:01").unwrap();
+    let dest_ip = net::Ipv6Addr::from_str("ff02::1").unwrap();
+    let dest_mac = MacAddr::from_str("33:33:00:00:00:01").unwrap();
+    let echo_packet = Icmpv6EchoPacketBuilder::default()
+        .source_ip(source_ip)
+        .source_mac(source_mac)
+        .dest_ip(dest_ip)
+        .dest_mac(dest_mac)
+        .identifier(54321_u16)
+        .sequence(1_u16)
+        .build()
+        .unwrap();
+    let packet = echo_packet.to_raw();
+
+    let eth = ethernet::EthernetPacket::new(&packet).unwrap();
+    let ip = ipv6::Ipv6Packet::new(eth.payload()).unwrap();
+    let echo = echo_request::EchoRequestPacket::new(ip.payload()).unwrap();
+
+    assert_eq!(eth.get_ethertype(), ethernet::EtherTypes::Ipv6);
+    assert_eq!(eth.get_destination(), dest_mac);
+    assert_eq!(ip.get_destination(), dest_ip);
+    assert_eq!(echo.get_icmpv6_type(), icmpv6::Icmpv6Types::EchoRequest);
+    assert_eq!(echo.get_identifier(), 54321);
+    assert_eq!(echo.get_sequence_number(), 1);
+    assert_eq!(
+        icmpv6::checksum(
+            &icmpv6::Icmpv6Packet::new(ip.payload()).unwrap(),
+            &source_ip,
+            &dest_ip,
+        ),
+        echo.get_checksum()
+    );
+}
@@ -0,0 +1,168 @@
+//! Provides helpers for creating ICMPv6 neighbor solicitation packets
+
+use std::net;
+
+use derive_builder::Builder;
+use pnet::{
+    packet::{
+        MutablePacket, ethernet,
+        icmpv6::{self, ndp},
+        ip, ipv6,
+    },
+    util,
+};
+
+const PKT_ETH_SIZE: usize = ethernet::EthernetPacket::minimum_packet_size();
+const PKT_IP6_SIZE: usize = ipv6::Ipv6Packet::minimum_packet_size();
+const PKT_NS_SIZE: usize = ndp::NeighborSolicitPacket::minimum_packet_size();
+// source link-layer address option: type, length and 6 byte MAC
+const PKT_OPT_SIZE: usize = 8;
+const PKT_TOTAL_SIZE: usize =
+    PKT_ETH_SIZE + PKT_IP6_SIZE + PKT_NS_SIZE + PKT_OPT_SIZE;
+
+/// Returns the solicited-node multicast address (ff02::1:ffXX:XXXX) for the
+/// provided IPv6 address
+pub fn solicited_node_multicast(ip: net::Ipv6Addr) -> net::Ipv6Addr {
+    let o = ip.octets();
+    net::Ipv6Addr::new(
+        0xff02,
+        0,
+        0,
+        0,
+        0,
+        1,
+        0xff00 | o[13] as u16,
+        ((o[14] as u16) << 8) | o[15] as u16,
+    )
+}
+
+/// Returns the ethernet multicast MAC (33:33:XX:XX:XX:XX) that maps to the
+/// provided IPv6 multicast address
+pub fn multicast_mac(ip: net::Ipv6Addr) -> util::MacAddr {
+    let o = ip.octets();
+    util::MacAddr::new(0x33, 0x33, o[12], o[13], o[14], o[15])
+}
+
+/// Represents a generator for raw ICMPv6 neighbor solicitation packets
+#[derive(Debug, Builder)]
+#[builder(setter(into))]
+pub struct NeighborSolicitPacket {
+    /// IPv6 address of the host machine performing scanning
+    source_ip: net::Ipv6Addr,
+    /// MAC address of host machine performing scanning
+    source_mac: util::MacAddr,
+    /// IPv6 address being solicited
+    target_ip: net::Ipv6Addr,
+}
+
+impl NeighborSolicitPacket {
+    /// Builds a new neighbor solicitation addressed to the target's
+    /// solicited-node multicast group
+    pub fn to_raw(&self) -> [u8; PKT_TOTAL_SIZE] {
+        let dest_ip = solicited_node_multicast(self.target_ip);
+
+        let mut pkt_buf = [0u8; PKT_TOTAL_SIZE];
+
+        let mut eth_header = ethernet::MutableEthernetPacket::new(&mut pkt_buf)
+            .expect("failed to generate ethernet header");
+        eth_header.set_ethertype(ethernet::EtherTypes::Ipv6);
+        eth_header.set_source(self.source_mac);
+        eth_header.set_destination(multicast_mac(dest_ip));
+
+        // set ip header
+        let mut ip_buffer = [0u8; PKT_IP6_SIZE + PKT_NS_SIZE + PKT_OPT_SIZE];
+
+        let mut ip_header = ipv6::MutableIpv6Packet::new(&mut ip_buffer)
+            .expect("failed to generate ip header");
+
+        ip_header.set_version(6);
+        ip_header.set_next_header(ip::IpNextHeaderProtocols::Icmpv6);
+        ip_header.set_payload_length((PKT_NS_SIZE + PKT_OPT_SIZE) as u16);
+        // RFC 4861 requires a hop limit of 255 for neighbor discovery
+        ip_header.set_hop_limit(255);
+        ip_header.set_source(self.source_ip);
+        ip_header.set_destination(dest_ip);
+
+        // set neighbor solicitation header
+        let mut ns_buffer = [0u8; PKT_NS_SIZE + PKT_OPT_SIZE];
+
+        let mut ns_header =
+            ndp::MutableNeighborSolicitPacket::new(&mut ns_buffer)
+                .expect("failed to generate neighbor solicitation header");
+
+        ns_header.set_icmpv6_type(icmpv6::Icmpv6Types::NeighborSolicit);
+        ns_header.set_icmpv6_code(ndp::Icmpv6Codes::NoCode);
+        ns_header.set_target_addr(self.target_ip);
+        ns_header.set_options(&[ndp::NdpOption {
+            option_type: ndp::NdpOptionTypes::SourceLLAddr,
+            length: 1,
+            data: self.source_mac.octets().to_vec(),
+        }]);
+
+        let checksum = icmpv6::checksum(
+            &icmpv6::Icmpv6Packet::new(ns_header.packet_mut())
+                .expect("failed to generate icmpv6 packet"),
+            &self.source_ip,
+            &dest_ip,
+        );
+        ns_header.set_checksum(checksum);
+
+        ip_header.set_payload(ns_header.packet_mut());
+        eth_header.set_payload(ip_header.packet_mut());
+
+        pkt_buf
+    }
+}
+
+#[cfg(test)]
+#[allow(warnings)]
+#[doc(hidden)]
+// only used in tests
+pub fn create_neighbor_advert(
+    from_mac: util::MacAddr,
+    from_ip: net::Ipv6Addr,
+    to_mac: util::MacAddr,
+    to_ip: net::Ipv6Addr,
+    packet: &'static mut [u8; PKT_TOTAL_SIZE],
+) {
+    let mut eth_header = ethernet::MutableEthernetPacket::new(packet)
+        .expect("failed to generate ethernet header");
+    eth_header.set_ethertype(ethernet::EtherTypes::Ipv6);
+    eth_header.set_source(from_mac);
+    eth_header.set_destination(to_mac);
+
+    let mut ip_buffer = [0u8; PKT_IP6_SIZE + PKT_NS_SIZE + PKT_OPT_SIZE];
+
+    let mut ip_header = ipv6::MutableIpv6Packet::new(&mut ip_buffer)
+        .expect("failed to generate ip header");
+
+    ip_header.set_version(6);
+    ip_header.set_next_header(ip::IpNextHeaderProtocols::Icmpv6);
+    ip_header.set_payload_length((PKT_NS_SIZE + PKT_OPT_SIZE) as u16);
+    ip_header.set_hop_limit(255);
+    ip_header.set_source(from_ip);
+    ip_header.set_destination(to_ip);
+
+    // neighbor advertisements share the neighbor solicitation layout
+    let mut na_buffer = [0u8; PKT_NS_SIZE + PKT_OPT_SIZE];
+
+    let mut na_header = ndp::MutableNeighborAdvertPacket::new(&mut na_buffer)
+        .expect("failed to generate neighbor advert header");
+
+    na_header.set_icmpv6_type(icmpv6::Icmpv6Types::NeighborAdvert);
+    na_header.set_icmpv6_code(ndp::Icmpv6Codes::NoCode);
+    na_header.set_flags(ndp::NeighborAdvertFlags::Solicited);
+    na_header.set_target_addr(from_ip);
+    na_header.set_options(&[ndp::NdpOption {
+        option_type: ndp::NdpOptionTypes::TargetLLAddr,
+        length: 1,
+        data: from_mac.octets().to_vec(),
+    }]);
+
+    ip_header.set_payload(na_header.packet_mut());
+    eth_header.set_payload(ip_header.packet_mut());
+}
+
+#[cfg(test)]
+#[path = "./ndp_packet_tests.rs"]
+mod tests;
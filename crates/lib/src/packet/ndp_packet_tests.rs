@@ -0,0 +1,53 @@
+use super::*;
+
+use pnet::packet::Packet;
+use pnet::util::MacAddr;
+
+use std::str::FromStr;
+
+#[test]
+fn creates_neighbor_solicit_packet() {
+    let source_ip = net::Ipv6Addr::from_str("fe80::1").unwrap();
+    let source_mac = MacAddr::from_str("00:00:00:00:00:01").unwrap();
+    let target_ip = net::Ipv6Addr::from_str("fe80::aabb:ccdd:eeff").unwrap();
+    let ns_packet = NeighborSolicitPacketBuilder::default()
+        .source_ip(source_ip)
+        .source_mac(source_mac)
+        .target_ip(target_ip)
+        .build()
+        .unwrap();
+    let packet = ns_packet.to_raw();
+
+    let eth = ethernet::EthernetPacket::new(&packet).unwrap();
+    let ip = ipv6::Ipv6Packet::new(eth.payload()).unwrap();
+    let ns = ndp::NeighborSolicitPacket::new(ip.payload()).unwrap();
+    let options = ns.get_options();
+
+    let multicast = net::Ipv6Addr::from_str("ff02::1:ffdd:eeff").unwrap();
+
+    assert_eq!(eth.get_destination(), multicast_mac(multicast));
+    assert_eq!(ip.get_destination(), multicast);
+    assert_eq!(ip.get_hop_limit(), 255);
+    assert_eq!(ns.get_icmpv6_type(), icmpv6::Icmpv6Types::NeighborSolicit);
+    assert_eq!(ns.get_target_addr(), target_ip);
+    assert_eq!(options.len(), 1);
+    assert_eq!(options[0].option_type, ndp::NdpOptionTypes::SourceLLAddr);
+    assert_eq!(options[0].data, source_mac.octets().to_vec());
+    assert_eq!(
+        icmpv6::checksum(
+            &icmpv6::Icmpv6Packet::new(ip.payload()).unwrap(),
+            &source_ip,
+            &multicast,
+        ),
+        ns.get_checksum()
+    );
+}
+
+#[test]
+fn maps_multicast_addresses_to_mac() {
+    let all_nodes = net::Ipv6Addr::from_str("ff02::1").unwrap();
+    assert_eq!(
+        multicast_mac(all_nodes),
+        MacAddr::new(0x33, 0x33, 0, 0, 0, 1)
+    );
+}
@@ -0,0 +1,87 @@
+//! Provides helpers for creating RST packets over IPv6
+
+use std::net;
+
+use derive_builder::Builder;
+use pnet::{
+    packet::{MutablePacket, ethernet, ip, ipv6, tcp},
+    util,
+};
+
+const PKT_ETH_SIZE: usize = ethernet::EthernetPacket::minimum_packet_size();
+const PKT_IP6_SIZE: usize = ipv6::Ipv6Packet::minimum_packet_size();
+const PKT_TCP_SIZE: usize = tcp::TcpPacket::minimum_packet_size();
+const PKT_TOTAL_SIZE: usize = PKT_ETH_SIZE + PKT_IP6_SIZE + PKT_TCP_SIZE;
+
+/// Represents a generator for raw IPv6 RST packets
+#[derive(Debug, Builder)]
+#[builder(setter(into))]
+pub struct Rst6Packet {
+    /// IPv6 address of the host machine performing scanning
+    source_ip: net::Ipv6Addr,
+    /// MAC address of host machine performing scanning
+    source_mac: util::MacAddr,
+    /// Source port on which host machine is listening for packets
+    source_port: u16,
+    /// Target destination IPv6 for the packet
+    dest_ip: net::Ipv6Addr,
+    /// Target destination MAC address for the packet
+    dest_mac: util::MacAddr,
+    /// Target destination port for the packet
+    dest_port: u16,
+    /// The sequence number for this packet
+    sequence_number: u32,
+}
+
+impl Rst6Packet {
+    /// Builds a new IPv6 RST packet based on provided info. See
+    /// [`crate::packet::rst_packet::RstPacket`] for why this is sent
+    pub fn to_raw(&self) -> [u8; PKT_TOTAL_SIZE] {
+        let mut pkt_buf = [0u8; PKT_TOTAL_SIZE];
+
+        let mut eth_header = ethernet::MutableEthernetPacket::new(&mut pkt_buf)
+            .expect("failed to generate ethernet header");
+        eth_header.set_ethertype(ethernet::EtherTypes::Ipv6);
+        eth_header.set_source(self.source_mac);
+        eth_header.set_destination(self.dest_mac);
+
+        // set ip header
+        let mut ip_buffer = [0u8; PKT_IP6_SIZE + PKT_TCP_SIZE];
+
+        let mut ip_header = ipv6::MutableIpv6Packet::new(&mut ip_buffer)
+            .expect("failed to generate ip header");
+
+        ip_header.set_version(6);
+        ip_header.set_next_header(ip::IpNextHeaderProtocols::Tcp);
+        ip_header.set_payload_length(PKT_TCP_SIZE as u16);
+        ip_header.set_hop_limit(64);
+        ip_header.set_source(self.source_ip);
+        ip_header.set_destination(self.dest_ip);
+
+        // set tcp header
+        let mut tcp_buffer = [0u8; PKT_TCP_SIZE];
+
+        let mut tcp_header = tcp::MutableTcpPacket::new(&mut tcp_buffer)
+            .expect("failed to generate tcp header");
+
+        tcp_header.set_source(self.source_port);
+        tcp_header.set_destination(self.dest_port);
+        tcp_header.set_flags(tcp::TcpFlags::RST);
+        tcp_header.set_data_offset(5);
+        tcp_header.set_sequence(self.sequence_number);
+        tcp_header.set_checksum(tcp::ipv6_checksum(
+            &tcp_header.to_immutable(),
+            &self.source_ip,
+            &self.dest_ip,
+        ));
+
+        ip_header.set_payload(tcp_header.packet_mut());
+        eth_header.set_payload(ip_header.packet_mut());
+
+        pkt_buf
+    }
+}
+
+#[cfg(test)]
+#[path = "./rst6_packet_tests.rs"]
+mod tests;
@@ -0,0 +1,35 @@
+use std::str::FromStr;
+
+use pnet::packet::Packet;
+use util::MacAddr;
+
+use super::*;
+
+#[test]
+fn creates_rst6_packet() {
+    let source_ip = net::Ipv6Addr::from_str("fe80::1").unwrap();
+    let source_mac = MacAddr::from_str("00:00:00:00:00:00").unwrap();
+    let source_port: u16 = 54321;
+    let target_ip = net::Ipv6Addr::from_str("fe80::2").unwrap();
+    let target_mac = MacAddr::from_str("00:00:00:00:00:01").unwrap();
+    let target_port: u16 = 22;
+    let sequence_number: u32 = 1;
+    let rst_packet = Rst6PacketBuilder::default()
+        .source_ip(source_ip)
+        .source_mac(source_mac)
+        .source_port(source_port)
+        .dest_ip(target_ip)
+        .dest_mac(target_mac)
+        .dest_port(target_port)
+        .sequence_number(sequence_number)
+        .build()
+        .unwrap();
+    let packet = rst_packet.to_raw();
+
+    let eth = ethernet::EthernetPacket::new(&packet).unwrap();
+    let ip = ipv6::Ipv6Packet::new(eth.payload()).unwrap();
+    let tcp = tcp::TcpPacket::new(ip.payload()).unwrap();
+
+    assert_eq!(tcp.get_flags(), tcp::TcpFlags::RST);
+    assert_eq!(tcp.get_sequence(), sequence_number);
+}
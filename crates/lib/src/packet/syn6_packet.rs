@@ -0,0 +1,223 @@
+//! Provides helpers for creating SYN packets over IPv6
+
+use std::net;
+
+use derive_builder::Builder;
+use pnet::{
+    packet::{MutablePacket, ethernet, ip, ipv6, tcp},
+    util,
+};
+
+use super::syn_packet::SYN_OPTIONS;
+
+const PKT_ETH_SIZE: usize = ethernet::EthernetPacket::minimum_packet_size();
+const PKT_IP6_SIZE: usize = ipv6::Ipv6Packet::minimum_packet_size();
+const PKT_TCP_SIZE: usize = tcp::TcpPacket::minimum_packet_size();
+// size of the replies built in tests, which carry no options
+#[cfg(test)]
+const PKT_TOTAL_SIZE: usize = PKT_ETH_SIZE + PKT_IP6_SIZE + PKT_TCP_SIZE;
+const PKT_SYN_TCP_SIZE: usize = PKT_TCP_SIZE + SYN_OPTIONS.len();
+const PKT_SYN_SIZE: usize = PKT_ETH_SIZE + PKT_IP6_SIZE + PKT_SYN_TCP_SIZE;
+
+/// Represents a generator for raw IPv6 SYN packets
+#[derive(Debug, Builder)]
+#[builder(setter(into))]
+pub struct Syn6Packet {
+    /// IPv6 address of the host machine performing scanning
+    source_ip: net::Ipv6Addr,
+    /// MAC address of host machine performing scanning
+    source_mac: util::MacAddr,
+    /// Source port on which host machine is listening for packets
+    source_port: u16,
+    /// Target destination IPv6 for the packet
+    dest_ip: net::Ipv6Addr,
+    /// Target destination MAC address for the packet
+    dest_mac: util::MacAddr,
+    /// Target destination port for the packet
+    dest_port: u16,
+    /// The initial sequence number for this packet
+    #[builder(default)]
+    sequence_number: u32,
+}
+
+impl Syn6Packet {
+    /// Builds a new IPv6 SYN request packet using the provided information
+    pub fn to_raw(&self) -> [u8; PKT_SYN_SIZE] {
+        let mut pkt_buf = [0u8; PKT_SYN_SIZE];
+
+        let mut eth_header = ethernet::MutableEthernetPacket::new(&mut pkt_buf)
+            .expect("failed to generate ethernet header");
+        eth_header.set_ethertype(ethernet::EtherTypes::Ipv6);
+        eth_header.set_source(self.source_mac);
+        eth_header.set_destination(self.dest_mac);
+
+        // set ip header
+        let mut ip_buffer = [0u8; PKT_IP6_SIZE + PKT_SYN_TCP_SIZE];
+
+        let mut ip_header = ipv6::MutableIpv6Packet::new(&mut ip_buffer)
+            .expect("failed to generate ip header");
+
+        ip_header.set_version(6);
+        ip_header.set_next_header(ip::IpNextHeaderProtocols::Tcp);
+        ip_header.set_payload_length(PKT_SYN_TCP_SIZE as u16);
+        ip_header.set_hop_limit(64);
+        ip_header.set_source(self.source_ip);
+        ip_header.set_destination(self.dest_ip);
+
+        // set tcp header
+        let mut tcp_buffer = [0u8; PKT_SYN_TCP_SIZE];
+        tcp_buffer[PKT_TCP_SIZE..].copy_from_slice(&SYN_OPTIONS);
+
+        let mut tcp_header = tcp::MutableTcpPacket::new(&mut tcp_buffer)
+            .expect("failed to generate tcp header");
+
+        tcp_header.set_source(self.source_port);
+        tcp_header.set_destination(self.dest_port);
+        tcp_header.set_flags(tcp::TcpFlags::SYN);
+        tcp_header.set_data_offset((PKT_SYN_TCP_SIZE / 4) as u8);
+        tcp_header.set_sequence(self.sequence_number);
+        tcp_header.set_checksum(tcp::ipv6_checksum(
+            &tcp_header.to_immutable(),
+            &self.source_ip,
+            &self.dest_ip,
+        ));
+
+        ip_header.set_payload(tcp_header.packet_mut());
+        eth_header.set_payload(ip_header.packet_mut());
+
+        pkt_buf
+    }
+}
+
+#[cfg(test)]
+#[allow(warnings)]
+#[doc(hidden)]
+// only used in tests
+pub fn create_syn6_reply(
+    from_mac: util::MacAddr,
+    from_ip: net::Ipv6Addr,
+    from_port: u16,
+    to_mac: util::MacAddr,
+    to_ip: net::Ipv6Addr,
+    to_port: u16,
+    acknowledgement: u32,
+    packet: &'static mut [u8; PKT_TOTAL_SIZE],
+) {
+    let mut eth_header = ethernet::MutableEthernetPacket::new(packet)
+        .expect("failed to generate ethernet header");
+    eth_header.set_ethertype(ethernet::EtherTypes::Ipv6);
+    eth_header.set_source(from_mac);
+    eth_header.set_destination(to_mac);
+
+    // set ip header
+    let mut ip_buffer = [0u8; PKT_IP6_SIZE + PKT_TCP_SIZE];
+
+    let mut ip_header = ipv6::MutableIpv6Packet::new(&mut ip_buffer)
+        .expect("failed to generate ip header");
+
+    ip_header.set_version(6);
+    ip_header.set_next_header(ip::IpNextHeaderProtocols::Tcp);
+    ip_header.set_payload_length(PKT_TCP_SIZE as u16);
+    ip_header.set_hop_limit(64);
+    ip_header.set_source(from_ip);
+    ip_header.set_destination(to_ip);
+
+    // set tcp header
+    let mut tcp_buffer = [0u8; PKT_TCP_SIZE];
+
+    let mut tcp_header = tcp::MutableTcpPacket::new(&mut tcp_buffer)
+        .expect("failed to generate tcp header");
+
+    tcp_header.set_source(from_port);
+    tcp_header.set_destination(to_port);
+    tcp_header.set_flags(tcp::TcpFlags::SYN | tcp::TcpFlags::ACK);
+    tcp_header.set_data_offset(5);
+    tcp_header.set_sequence(11111);
+    tcp_header.set_acknowledgement(acknowledgement);
+    tcp_header.set_checksum(tcp::ipv6_checksum(
+        &tcp_header.to_immutable(),
+        &from_ip,
+        &to_ip,
+    ));
+
+    ip_header.set_payload(tcp_header.packet_mut());
+    eth_header.set_payload(ip_header.packet_mut());
+}
+
+#[cfg(test)]
+#[allow(warnings)]
+#[doc(hidden)]
+// only used in tests
+pub fn create_syn6_icmp_unreachable_reply(
+    from_mac: util::MacAddr,
+    from_ip: net::Ipv6Addr,
+    to_mac: util::MacAddr,
+    to_ip: net::Ipv6Addr,
+    to_port: u16,
+    unreachable_port: u16,
+    sequence: u32,
+    code: pnet::packet::icmpv6::Icmpv6Code,
+    packet: &'static mut [u8; PKT_ETH_SIZE
+                     + PKT_IP6_SIZE
+                     + 8
+                     + PKT_IP6_SIZE
+                     + 8],
+) {
+    use pnet::packet::icmpv6;
+
+    // the original SYN quoted back to us inside the ICMPv6 error, cut to its
+    // IP header and the first 8 bytes of its TCP header
+    let quoted = Syn6PacketBuilder::default()
+        .source_ip(to_ip)
+        .source_mac(to_mac)
+        .source_port(to_port)
+        .dest_ip(from_ip)
+        .dest_mac(from_mac)
+        .dest_port(unreachable_port)
+        .sequence_number(sequence)
+        .build()
+        .unwrap()
+        .to_raw();
+
+    let mut eth_header = ethernet::MutableEthernetPacket::new(packet)
+        .expect("failed to generate ethernet header");
+    eth_header.set_ethertype(ethernet::EtherTypes::Ipv6);
+    eth_header.set_source(from_mac);
+    eth_header.set_destination(to_mac);
+
+    let mut ip_buffer = [0u8; PKT_IP6_SIZE + 8 + PKT_IP6_SIZE + 8];
+
+    let mut ip_header = ipv6::MutableIpv6Packet::new(&mut ip_buffer)
+        .expect("failed to generate ip header");
+
+    ip_header.set_version(6);
+    ip_header.set_next_header(ip::IpNextHeaderProtocols::Icmpv6);
+    ip_header.set_payload_length((8 + PKT_IP6_SIZE + 8) as u16);
+    ip_header.set_hop_limit(64);
+    ip_header.set_source(from_ip);
+    ip_header.set_destination(to_ip);
+
+    // type, code and checksum followed by 4 unused bytes, then the quote
+    let mut icmp_buffer = [0u8; 8 + PKT_IP6_SIZE + 8];
+    icmp_buffer[8..].copy_from_slice(
+        &quoted[PKT_ETH_SIZE..PKT_ETH_SIZE + PKT_IP6_SIZE + 8],
+    );
+
+    let mut icmp_header = icmpv6::MutableIcmpv6Packet::new(&mut icmp_buffer)
+        .expect("failed to generate icmpv6 header");
+
+    icmp_header.set_icmpv6_type(icmpv6::Icmpv6Types::DestinationUnreachable);
+    icmp_header.set_icmpv6_code(code);
+    icmp_header.set_checksum(icmpv6::checksum(
+        &icmp_header.to_immutable(),
+        &from_ip,
+        &to_ip,
+    ));
+
+    ip_header.set_payload(icmp_header.packet_mut());
+    eth_header.set_payload(ip_header.packet_mut());
+}
+
+#[cfg(test)]
+#[path = "./syn6_packet_tests.rs"]
+mod tests;
@@ -0,0 +1,42 @@
+use super::*;
+
+use pnet::packet::Packet;
+use pnet::util::MacAddr;
+
+use std::str::FromStr;
+
+#[test]
+fn creates_syn6_packet() {
+    let source_ip = net::Ipv6Addr::from_str("fe80::1").unwrap();
+    let source_mac = MacAddr::from_str("00:00:00:00:00:00").unwrap();
+    let source_port: u16 = 54321;
+    let target_ip = net::Ipv6Addr::from_str("fe80::2").unwrap();
+    let target_mac = MacAddr::from_str("00:00:00:00:00:01").unwrap();
+    let target_port: u16 = 22;
+    let syn_packet = Syn6PacketBuilder::default()
+        .source_ip(source_ip)
+        .source_mac(source_mac)
+        .source_port(source_port)
+        .dest_ip(target_ip)
+        .dest_mac(target_mac)
+        .dest_port(target_port)
+        .sequence_number(7_u32)
+        .build()
+        .unwrap();
+    let packet = syn_packet.to_raw();
+
+    let eth = ethernet::EthernetPacket::new(&packet).unwrap();
+    let ip = ipv6::Ipv6Packet::new(eth.payload()).unwrap();
+    let tcp = tcp::TcpPacket::new(ip.payload()).unwrap();
+
+    assert_eq!(eth.get_ethertype(), ethernet::EtherTypes::Ipv6);
+    assert_eq!(ip.get_destination(), target_ip);
+    assert_eq!(tcp.get_flags(), tcp::TcpFlags::SYN);
+    assert_eq!(tcp.get_destination(), target_port);
+    assert_eq!(tcp.get_options_raw(), SYN_OPTIONS);
+    assert_eq!(tcp.get_sequence(), 7);
+    assert_eq!(
+        tcp::ipv6_checksum(&tcp, &source_ip, &target_ip),
+        tcp.get_checksum()
+    );
+}
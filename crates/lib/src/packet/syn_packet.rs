@@ -11,7 +11,19 @@ use pnet::{
 const PKT_ETH_SIZE: usize = ethernet::EthernetPacket::minimum_packet_size();
 const PKT_IP4_SIZE: usize = ipv4::Ipv4Packet::minimum_packet_size();
 const PKT_TCP_SIZE: usize = tcp::TcpPacket::minimum_packet_size();
+// size of the replies built in tests, which carry no options
+#[cfg(test)]
 const PKT_TOTAL_SIZE: usize = PKT_ETH_SIZE + PKT_IP4_SIZE + PKT_TCP_SIZE;
+const PKT_SYN_TCP_SIZE: usize = PKT_TCP_SIZE + SYN_OPTIONS.len();
+const PKT_SYN_SIZE: usize = PKT_ETH_SIZE + PKT_IP4_SIZE + PKT_SYN_TCP_SIZE;
+
+// Options offered in every SYN, laid out as a Linux SYN: MSS 1460, SACK
+// permitted, timestamp, NOP and window scale 7. Responders only include the
+// options offered to them, and the values and order they choose identify
+// their TCP stack.
+pub(crate) const SYN_OPTIONS: [u8; 20] = [
+    2, 4, 0x05, 0xb4, 4, 2, 8, 10, 0, 0, 0, 1, 0, 0, 0, 0, 1, 3, 3, 7,
+];
 
 /// Represents a generator for raw SYN packets
 #[derive(Debug, Builder)]
@@ -29,12 +41,15 @@ pub struct SynPacket {
     dest_mac: util::MacAddr,
     /// Target destination port for the packet
     dest_port: u16,
+    /// The initial sequence number for this packet
+    #[builder(default)]
+    sequence_number: u32,
 }
 
 impl SynPacket {
     /// Builds a new SYN request packet using the provided information
-    pub fn to_raw(&self) -> [u8; PKT_TOTAL_SIZE] {
-        let mut pkt_buf = [0u8; PKT_TOTAL_SIZE];
+    pub fn to_raw(&self) -> [u8; PKT_SYN_SIZE] {
+        let mut pkt_buf = [0u8; PKT_SYN_SIZE];
 
         let mut eth_header = ethernet::MutableEthernetPacket::new(&mut pkt_buf)
             .expect("failed to generate ethernet header");
@@ -43,7 +58,7 @@ impl SynPacket {
         eth_header.set_destination(self.dest_mac);
 
         // set ip header
-        let mut ip_buffer = [0u8; PKT_IP4_SIZE + PKT_TCP_SIZE];
+        let mut ip_buffer = [0u8; PKT_IP4_SIZE + PKT_SYN_TCP_SIZE];
 
         let mut ip_header = ipv4::MutableIpv4Packet::new(&mut ip_buffer)
             .expect("failed to generate ip header");
@@ -55,11 +70,12 @@ impl SynPacket {
         ip_header.set_ttl(64);
         ip_header.set_identification(0);
         ip_header.set_header_length(5);
-        ip_header.set_total_length(40);
+        ip_header.set_total_length((PKT_IP4_SIZE + PKT_SYN_TCP_SIZE) as u16);
         ip_header.set_checksum(ipv4::checksum(&ip_header.to_immutable()));
 
         // set tcp header
-        let mut tcp_buffer = [0u8; PKT_TCP_SIZE];
+        let mut tcp_buffer = [0u8; PKT_SYN_TCP_SIZE];
+        tcp_buffer[PKT_TCP_SIZE..].copy_from_slice(&SYN_OPTIONS);
 
         let mut tcp_header = tcp::MutableTcpPacket::new(&mut tcp_buffer)
             .expect("failed to generate tcp header");
@@ -67,8 +83,8 @@ impl SynPacket {
         tcp_header.set_source(self.source_port);
         tcp_header.set_destination(self.dest_port);
         tcp_header.set_flags(tcp::TcpFlags::SYN);
-        tcp_header.set_data_offset(5);
-        tcp_header.set_sequence(0);
+        tcp_header.set_data_offset((PKT_SYN_TCP_SIZE / 4) as u8);
+        tcp_header.set_sequence(self.sequence_number);
         tcp_header.set_checksum(tcp::ipv4_checksum(
             &tcp_header.to_immutable(),
             &self.source_ip,
@@ -93,6 +109,7 @@ pub fn create_syn_reply(
     to_mac: util::MacAddr,
     to_ip: net::Ipv4Addr,
     to_port: u16,
+    acknowledgement: u32,
     packet: &'static mut [u8; PKT_TOTAL_SIZE],
 ) {
     let mut eth_header = ethernet::MutableEthernetPacket::new(packet)
@@ -128,6 +145,7 @@ pub fn create_syn_reply(
     tcp_header.set_flags(tcp::TcpFlags::SYN | tcp::TcpFlags::ACK);
     tcp_header.set_data_offset(5);
     tcp_header.set_sequence(11111);
+    tcp_header.set_acknowledgement(acknowledgement);
     tcp_header.set_checksum(tcp::ipv4_checksum(
         &tcp_header.to_immutable(),
         &from_ip,
@@ -138,6 +156,78 @@ pub fn create_syn_reply(
     eth_header.set_payload(ip_header.packet_mut());
 }
 
+#[cfg(test)]
+#[allow(warnings)]
+#[doc(hidden)]
+// only used in tests
+pub fn create_syn_icmp_unreachable_reply(
+    from_mac: util::MacAddr,
+    from_ip: net::Ipv4Addr,
+    to_mac: util::MacAddr,
+    to_ip: net::Ipv4Addr,
+    to_port: u16,
+    unreachable_port: u16,
+    sequence: u32,
+    code: pnet::packet::icmp::IcmpCode,
+    packet: &'static mut [u8; PKT_ETH_SIZE
+                     + PKT_IP4_SIZE
+                     + 8
+                     + PKT_IP4_SIZE
+                     + 8],
+) {
+    use pnet::packet::icmp;
+
+    // the original SYN quoted back to us inside the ICMP error, cut to its
+    // IP header and the first 8 bytes of its TCP header
+    let quoted = SynPacketBuilder::default()
+        .source_ip(to_ip)
+        .source_mac(to_mac)
+        .source_port(to_port)
+        .dest_ip(from_ip)
+        .dest_mac(from_mac)
+        .dest_port(unreachable_port)
+        .sequence_number(sequence)
+        .build()
+        .unwrap()
+        .to_raw();
+
+    let mut eth_header = ethernet::MutableEthernetPacket::new(packet)
+        .expect("failed to generate ethernet header");
+    eth_header.set_ethertype(ethernet::EtherTypes::Ipv4);
+    eth_header.set_source(from_mac);
+    eth_header.set_destination(to_mac);
+
+    let mut ip_buffer = [0u8; PKT_IP4_SIZE + 8 + PKT_IP4_SIZE + 8];
+
+    let mut ip_header = ipv4::MutableIpv4Packet::new(&mut ip_buffer)
+        .expect("failed to generate ip header");
+
+    ip_header.set_next_level_protocol(ip::IpNextHeaderProtocols::Icmp);
+    ip_header.set_source(from_ip);
+    ip_header.set_destination(to_ip);
+    ip_header.set_version(4);
+    ip_header.set_ttl(64);
+    ip_header.set_header_length(5);
+    ip_header.set_total_length((PKT_IP4_SIZE + 8 + PKT_IP4_SIZE + 8) as u16);
+    ip_header.set_checksum(ipv4::checksum(&ip_header.to_immutable()));
+
+    let mut icmp_buffer = [0u8; 8 + PKT_IP4_SIZE + 8];
+
+    let mut icmp_header =
+        icmp::destination_unreachable::MutableDestinationUnreachablePacket::new(
+            &mut icmp_buffer,
+        )
+        .expect("failed to generate icmp header");
+
+    icmp_header.set_icmp_type(icmp::IcmpTypes::DestinationUnreachable);
+    icmp_header.set_icmp_code(code);
+    icmp_header
+        .set_payload(&quoted[PKT_ETH_SIZE..PKT_ETH_SIZE + PKT_IP4_SIZE + 8]);
+
+    ip_header.set_payload(icmp_header.packet_mut());
+    eth_header.set_payload(ip_header.packet_mut());
+}
+
 #[cfg(test)]
 #[path = "./syn_packet_tests.rs"]
 mod tests;
@@ -1,6 +1,6 @@
 use super::*;
 
-use pnet::util::MacAddr;
+use pnet::{packet::Packet, util::MacAddr};
 
 use std::str::FromStr;
 
@@ -19,8 +19,24 @@ fn creates_syn_packet() {
         .dest_ip(target_ip)
         .dest_mac(target_mac)
         .dest_port(target_port)
+        .sequence_number(7_u32)
         .build()
         .unwrap();
     let packet = syn_packet.to_raw();
     assert!(!packet.is_empty());
+
+    let eth = ethernet::EthernetPacket::new(&packet).unwrap();
+    let ip = ipv4::Ipv4Packet::new(eth.payload()).unwrap();
+    let tcp = tcp::TcpPacket::new(ip.payload()).unwrap();
+
+    assert_eq!(
+        usize::from(ip.get_total_length()),
+        packet.len() - PKT_ETH_SIZE
+    );
+    assert_eq!(tcp.get_options_raw(), SYN_OPTIONS);
+    assert_eq!(tcp.get_sequence(), 7);
+    assert_eq!(
+        tcp.get_checksum(),
+        tcp::ipv4_checksum(&tcp, &source_ip, &target_ip)
+    );
 }
@@ -0,0 +1,36 @@
+use super::*;
+
+use pnet::packet::Packet;
+use pnet::util::MacAddr;
+
+use std::str::FromStr;
+
+#[test]
+fn creates_tcp6_packet_with_flags() {
+    let source_ip = net::Ipv6Addr::from_str("fe80::1").unwrap();
+    let target_ip = net::Ipv6Addr::from_str("fe80::2").unwrap();
+    let tcp_packet = Tcp6PacketBuilder::default()
+        .source_ip(source_ip)
+        .source_mac(MacAddr::from_str("00:00:00:00:00:00").unwrap())
+        .source_port(54321_u16)
+        .dest_ip(target_ip)
+        .dest_mac(MacAddr::from_str("00:00:00:00:00:01").unwrap())
+        .dest_port(22_u16)
+        .flags(tcp::TcpFlags::ACK)
+        .acknowledgement_number(7_u32)
+        .build()
+        .unwrap();
+    let packet = tcp_packet.to_raw();
+
+    let eth = ethernet::EthernetPacket::new(&packet).unwrap();
+    let ip = ipv6::Ipv6Packet::new(eth.payload()).unwrap();
+    let tcp = tcp::TcpPacket::new(ip.payload()).unwrap();
+
+    assert_eq!(eth.get_ethertype(), ethernet::EtherTypes::Ipv6);
+    assert_eq!(tcp.get_flags(), tcp::TcpFlags::ACK);
+    assert_eq!(tcp.get_acknowledgement(), 7);
+    assert_eq!(
+        tcp::ipv6_checksum(&tcp, &source_ip, &target_ip),
+        tcp.get_checksum()
+    );
+}
@@ -0,0 +1,36 @@
+use super::*;
+
+use pnet::packet::Packet;
+use pnet::util::MacAddr;
+
+use std::str::FromStr;
+
+#[test]
+fn creates_tcp_packet_with_flags() {
+    let source_ip = net::Ipv4Addr::from_str("192.168.68.1").unwrap();
+    let target_ip = net::Ipv4Addr::from_str("192.168.68.2").unwrap();
+    let flags = tcp::TcpFlags::FIN | tcp::TcpFlags::PSH | tcp::TcpFlags::URG;
+    let tcp_packet = TcpPacketBuilder::default()
+        .source_ip(source_ip)
+        .source_mac(MacAddr::from_str("00:00:00:00:00:00").unwrap())
+        .source_port(54321_u16)
+        .dest_ip(target_ip)
+        .dest_mac(MacAddr::from_str("00:00:00:00:00:01").unwrap())
+        .dest_port(22_u16)
+        .flags(flags)
+        .build()
+        .unwrap();
+    let packet = tcp_packet.to_raw();
+
+    let eth = ethernet::EthernetPacket::new(&packet).unwrap();
+    let ip = ipv4::Ipv4Packet::new(eth.payload()).unwrap();
+    let tcp = tcp::TcpPacket::new(ip.payload()).unwrap();
+
+    assert_eq!(ip.get_destination(), target_ip);
+    assert_eq!(tcp.get_flags(), flags);
+    assert_eq!(tcp.get_destination(), 22);
+    assert_eq!(
+        tcp::ipv4_checksum(&tcp, &source_ip, &target_ip),
+        tcp.get_checksum()
+    );
+}
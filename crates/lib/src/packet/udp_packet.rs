@@ -0,0 +1,190 @@
+//! Provides helpers for creating UDP packets
+
+use std::net;
+
+use derive_builder::Builder;
+use pnet::{
+    packet::{MutablePacket, ethernet, ip, ipv4, udp},
+    util,
+};
+
+const PKT_ETH_SIZE: usize = ethernet::EthernetPacket::minimum_packet_size();
+const PKT_IP4_SIZE: usize = ipv4::Ipv4Packet::minimum_packet_size();
+const PKT_UDP_SIZE: usize = udp::UdpPacket::minimum_packet_size();
+
+/// Represents a generator for raw UDP packets
+#[derive(Debug, Builder)]
+#[builder(setter(into))]
+pub struct UdpPacket {
+    /// IP address of the host machine performing scanning
+    source_ip: net::Ipv4Addr,
+    /// MAC address of host machine performing scanning
+    source_mac: util::MacAddr,
+    /// Source port on which host machine is listening for packets
+    source_port: u16,
+    /// Target destination IP for the packet
+    dest_ip: net::Ipv4Addr,
+    /// Target destination MAC address for the packet
+    dest_mac: util::MacAddr,
+    /// Target destination port for the packet
+    dest_port: u16,
+    /// Application payload carried by the datagram
+    #[builder(default)]
+    payload: Vec<u8>,
+}
+
+impl UdpPacket {
+    /// Builds a new UDP datagram using the provided information. Unlike the
+    /// TCP packets the size of a UDP packet depends on its payload so the
+    /// raw packet is returned as a Vec
+    pub fn to_raw(&self) -> Vec<u8> {
+        let udp_len = PKT_UDP_SIZE + self.payload.len();
+        let ip_len = PKT_IP4_SIZE + udp_len;
+
+        let mut pkt_buf = vec![0u8; PKT_ETH_SIZE + ip_len];
+
+        let mut eth_header = ethernet::MutableEthernetPacket::new(&mut pkt_buf)
+            .expect("failed to generate ethernet header");
+        eth_header.set_ethertype(ethernet::EtherTypes::Ipv4);
+        eth_header.set_source(self.source_mac);
+        eth_header.set_destination(self.dest_mac);
+
+        // set ip header
+        let mut ip_buffer = vec![0u8; ip_len];
+
+        let mut ip_header = ipv4::MutableIpv4Packet::new(&mut ip_buffer)
+            .expect("failed to generate ip header");
+
+        ip_header.set_next_level_protocol(ip::IpNextHeaderProtocols::Udp);
+        ip_header.set_source(self.source_ip);
+        ip_header.set_destination(self.dest_ip);
+        ip_header.set_version(4);
+        ip_header.set_ttl(64);
+        ip_header.set_identification(0);
+        ip_header.set_header_length(5);
+        ip_header.set_total_length(ip_len as u16);
+        ip_header.set_checksum(ipv4::checksum(&ip_header.to_immutable()));
+
+        // set udp header
+        let mut udp_buffer = vec![0u8; udp_len];
+
+        let mut udp_header = udp::MutableUdpPacket::new(&mut udp_buffer)
+            .expect("failed to generate udp header");
+
+        udp_header.set_source(self.source_port);
+        udp_header.set_destination(self.dest_port);
+        udp_header.set_length(udp_len as u16);
+        udp_header.set_payload(&self.payload);
+        udp_header.set_checksum(udp::ipv4_checksum(
+            &udp_header.to_immutable(),
+            &self.source_ip,
+            &self.dest_ip,
+        ));
+
+        ip_header.set_payload(udp_header.packet_mut());
+        eth_header.set_payload(ip_header.packet_mut());
+
+        pkt_buf
+    }
+}
+
+#[cfg(test)]
+#[allow(warnings)]
+#[doc(hidden)]
+// only used in tests
+pub fn create_udp_reply(
+    from_mac: util::MacAddr,
+    from_ip: net::Ipv4Addr,
+    from_port: u16,
+    to_mac: util::MacAddr,
+    to_ip: net::Ipv4Addr,
+    to_port: u16,
+    packet: &'static mut [u8; PKT_ETH_SIZE + PKT_IP4_SIZE + PKT_UDP_SIZE],
+) {
+    let raw = UdpPacketBuilder::default()
+        .source_ip(from_ip)
+        .source_mac(from_mac)
+        .source_port(from_port)
+        .dest_ip(to_ip)
+        .dest_mac(to_mac)
+        .dest_port(to_port)
+        .build()
+        .unwrap()
+        .to_raw();
+
+    packet.copy_from_slice(&raw);
+}
+
+#[cfg(test)]
+#[allow(warnings)]
+#[doc(hidden)]
+// only used in tests
+pub fn create_icmp_unreachable_reply(
+    from_mac: util::MacAddr,
+    from_ip: net::Ipv4Addr,
+    to_mac: util::MacAddr,
+    to_ip: net::Ipv4Addr,
+    to_port: u16,
+    unreachable_port: u16,
+    code: pnet::packet::icmp::IcmpCode,
+    packet: &'static mut [u8; PKT_ETH_SIZE
+                     + PKT_IP4_SIZE
+                     + 8
+                     + PKT_IP4_SIZE
+                     + PKT_UDP_SIZE],
+) {
+    use pnet::packet::icmp;
+
+    // the original datagram quoted back to us inside the ICMP error
+    let quoted = UdpPacketBuilder::default()
+        .source_ip(to_ip)
+        .source_mac(to_mac)
+        .source_port(to_port)
+        .dest_ip(from_ip)
+        .dest_mac(from_mac)
+        .dest_port(unreachable_port)
+        .build()
+        .unwrap()
+        .to_raw();
+
+    let mut eth_header = ethernet::MutableEthernetPacket::new(packet)
+        .expect("failed to generate ethernet header");
+    eth_header.set_ethertype(ethernet::EtherTypes::Ipv4);
+    eth_header.set_source(from_mac);
+    eth_header.set_destination(to_mac);
+
+    let mut ip_buffer = [0u8; PKT_IP4_SIZE + 8 + PKT_IP4_SIZE + PKT_UDP_SIZE];
+
+    let mut ip_header = ipv4::MutableIpv4Packet::new(&mut ip_buffer)
+        .expect("failed to generate ip header");
+
+    ip_header.set_next_level_protocol(ip::IpNextHeaderProtocols::Icmp);
+    ip_header.set_source(from_ip);
+    ip_header.set_destination(to_ip);
+    ip_header.set_version(4);
+    ip_header.set_ttl(64);
+    ip_header.set_header_length(5);
+    ip_header.set_total_length(
+        (PKT_IP4_SIZE + 8 + PKT_IP4_SIZE + PKT_UDP_SIZE) as u16,
+    );
+    ip_header.set_checksum(ipv4::checksum(&ip_header.to_immutable()));
+
+    let mut icmp_buffer = [0u8; 8 + PKT_IP4_SIZE + PKT_UDP_SIZE];
+
+    let mut icmp_header =
+        icmp::destination_unreachable::MutableDestinationUnreachablePacket::new(
+            &mut icmp_buffer,
+        )
+        .expect("failed to generate icmp header");
+
+    icmp_header.set_icmp_type(icmp::IcmpTypes::DestinationUnreachable);
+    icmp_header.set_icmp_code(code);
+    icmp_header.set_payload(&quoted[PKT_ETH_SIZE..]);
+
+    ip_header.set_payload(icmp_header.packet_mut());
+    eth_header.set_payload(ip_header.packet_mut());
+}
+
+#[cfg(test)]
+#[path = "./udp_packet_tests.rs"]
+mod tests;
@@ -0,0 +1,37 @@
+use super::*;
+
+use pnet::packet::Packet;
+use pnet::util::MacAddr;
+
+use std::str::FromStr;
+
+#[test]
+fn creates_udp_packet() {
+    let source_ip = net::Ipv4Addr::from_str("192.168.68.1").unwrap();
+    let source_mac = MacAddr::from_str("00:00:00:00:00:00").unwrap();
+    let source_port: u16 = 54321;
+    let target_ip = net::Ipv4Addr::from_str("192.168.68.2").unwrap();
+    let target_mac = MacAddr::from_str("00:00:00:00:00:01").unwrap();
+    let target_port: u16 = 53;
+    let udp_packet = UdpPacketBuilder::default()
+        .source_ip(source_ip)
+        .source_mac(source_mac)
+        .source_port(source_port)
+        .dest_ip(target_ip)
+        .dest_mac(target_mac)
+        .dest_port(target_port)
+        .payload(vec![1, 2, 3])
+        .build()
+        .unwrap();
+    let packet = udp_packet.to_raw();
+
+    let eth = ethernet::EthernetPacket::new(&packet).unwrap();
+    let ip = ipv4::Ipv4Packet::new(eth.payload()).unwrap();
+    let udp = udp::UdpPacket::new(ip.payload()).unwrap();
+
+    assert_eq!(ip.get_destination(), target_ip);
+    assert_eq!(udp.get_source(), source_port);
+    assert_eq!(udp.get_destination(), target_port);
+    assert_eq!(udp.get_length(), 11);
+    assert_eq!(udp.payload(), &[1, 2, 3]);
+}
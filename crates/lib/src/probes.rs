@@ -0,0 +1,494 @@
+//! Provides protocol probes for identifying services running on open ports
+//!
+//! Services that greet clients as soon as they connect (SSH, SMTP, FTP,
+//! MySQL) are identified from their banner. Everything else is identified
+//! by sending protocol specific probes (HTTP, Redis, MongoDB, PostgreSQL)
+//! one at a time until one of them gets a recognizable response. The probe
+//! associated with the port's well known service is always tried first.
+
+use std::{
+    io::{Read, Write},
+    net::{SocketAddr, TcpStream},
+    time::Duration,
+};
+
+// Maximum number of bytes read from a single response
+const MAX_RESPONSE_SIZE: usize = 8192;
+
+// Time to wait for the remainder of a response once the first bytes arrive
+const TRAILING_READ_TIMEOUT: Duration = Duration::from_millis(100);
+
+// PostgreSQL SSLRequest message; answered with a single 'S' or 'N' byte
+const POSTGRES_SSL_REQUEST: [u8; 8] = [0, 0, 0, 8, 0x04, 0xd2, 0x16, 0x2f];
+
+// MongoDB OP_MSG opcode
+const MONGO_OP_MSG: i32 = 2013;
+
+/// Service details identified by a probe
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ServiceMatch {
+    /// The service name i.e. ssh, http, redis
+    pub service: String,
+    /// The product implementing the service i.e. OpenSSH, nginx
+    pub product: Option<String>,
+    /// The version of the product if advertised
+    pub version: Option<String>,
+}
+
+impl ServiceMatch {
+    fn new(
+        service: &str,
+        product: Option<String>,
+        version: Option<String>,
+    ) -> Self {
+        Self {
+            service: service.to_string(),
+            product,
+            version,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Probe {
+    // sends nothing and waits for the server to speak first
+    Null,
+    Http,
+    Redis,
+    MongoDb,
+    PostgreSql,
+}
+
+const PROBES: [Probe; 5] = [
+    Probe::Null,
+    Probe::Http,
+    Probe::Redis,
+    Probe::MongoDb,
+    Probe::PostgreSql,
+];
+
+impl Probe {
+    // Ports on which this probe is tried before any other
+    fn ports(&self) -> &'static [u16] {
+        match self {
+            Self::Null => &[21, 22, 25, 110, 143, 587, 3306],
+            Self::Http => &[80, 8000, 8008, 8080, 8888],
+            Self::Redis => &[6379],
+            Self::MongoDb => &[27017],
+            Self::PostgreSql => &[5432],
+        }
+    }
+
+    fn request(&self) -> Vec<u8> {
+        match self {
+            Self::Null => Vec::new(),
+            Self::Http => b"GET / HTTP/1.0\r\n\r\n".to_vec(),
+            Self::Redis => b"PING\r\n".to_vec(),
+            Self::MongoDb => mongo_command("hello"),
+            Self::PostgreSql => POSTGRES_SSL_REQUEST.to_vec(),
+        }
+    }
+
+    fn matches(&self, response: &[u8]) -> Option<ServiceMatch> {
+        if let Some(m) = match_banner(response) {
+            return Some(m);
+        }
+
+        match self {
+            Self::Null => None,
+            Self::Http => match_http(response),
+            Self::Redis => match_redis(response),
+            Self::MongoDb => match_mongo(response),
+            Self::PostgreSql => match_postgres(response),
+        }
+    }
+
+    // Some protocols only advertise their version in response to a second
+    // request sent over the same connection
+    fn follow_up(&self, stream: &mut TcpStream, found: &mut ServiceMatch) {
+        let request = match (self, found.service.as_str()) {
+            (Self::Redis, "redis") => b"INFO server\r\n".to_vec(),
+            (Self::MongoDb, "mongodb") => mongo_command("buildInfo"),
+            _ => return,
+        };
+
+        let Some(response) = exchange(stream, &request) else {
+            return;
+        };
+
+        found.version = match self {
+            Self::Redis => redis_version(&response),
+            _ => mongo_version(&response),
+        };
+    }
+}
+
+/// Connects to the address and tries each probe in turn until the service
+/// is identified. Returns None if the service could not be identified
+pub fn identify(addr: SocketAddr, timeout: Duration) -> Option<ServiceMatch> {
+    let mut probes = PROBES.to_vec();
+
+    // stable sort keeps the default order for everything else
+    probes.sort_by_key(|p| !p.ports().contains(&addr.port()));
+
+    for probe in probes {
+        let mut stream = match TcpStream::connect_timeout(&addr, timeout) {
+            Ok(stream) => stream,
+            Err(e) => {
+                log::debug!("{} probe connect failed: {}", addr, e);
+                return None;
+            }
+        };
+
+        if stream.set_read_timeout(Some(timeout)).is_err()
+            || stream.set_write_timeout(Some(timeout)).is_err()
+        {
+            return None;
+        }
+
+        let Some(response) = exchange(&mut stream, &probe.request()) else {
+            continue;
+        };
+
+        if let Some(mut found) = probe.matches(&response) {
+            probe.follow_up(&mut stream, &mut found);
+            log::debug!("{} identified as {:?}", addr, found);
+            return Some(found);
+        }
+
+        // a server that greets with an unknown banner won't answer any of
+        // the other probes in a meaningful way
+        if probe == Probe::Null {
+            return None;
+        }
+    }
+
+    None
+}
+
+// Writes the request, if any, and returns whatever the server responds with
+fn exchange(stream: &mut TcpStream, request: &[u8]) -> Option<Vec<u8>> {
+    if !request.is_empty() {
+        stream.write_all(request).ok()?;
+    }
+
+    let read_timeout = stream.read_timeout().ok()?;
+    let mut response = Vec::new();
+    let mut buf = [0u8; 1024];
+
+    loop {
+        match stream.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => {
+                response.extend_from_slice(&buf[..n]);
+
+                if response.len() >= MAX_RESPONSE_SIZE {
+                    break;
+                }
+
+                // the first bytes arrived so only wait a little longer for
+                // the rest of the response
+                stream.set_read_timeout(Some(TRAILING_READ_TIMEOUT)).ok()?;
+            }
+            // timed out waiting for more data or the connection was reset
+            Err(_) => break,
+        }
+    }
+
+    stream.set_read_timeout(read_timeout).ok()?;
+
+    if response.is_empty() {
+        None
+    } else {
+        Some(response)
+    }
+}
+
+// Returns the first line of the response if it is valid UTF-8
+fn first_line(response: &[u8]) -> Option<&str> {
+    let text = std::str::from_utf8(response).ok()?;
+    text.lines().next().map(|l| l.trim())
+}
+
+// Splits a product token like "OpenSSH_9.6p1" or "nginx/1.25.3" into its
+// product and version parts
+fn split_product(token: &str, separator: char) -> (String, Option<String>) {
+    match token.split_once(separator) {
+        Some((product, version)) if !version.is_empty() => {
+            (product.to_string(), Some(version.to_string()))
+        }
+        _ => (token.to_string(), None),
+    }
+}
+
+// Returns the next word if it looks like a version number
+fn version_after<'a>(
+    words: &mut impl Iterator<Item = &'a str>,
+) -> Option<String> {
+    words
+        .next()
+        .filter(|w| w.starts_with(|c: char| c.is_ascii_digit()))
+        .map(|w| w.trim_end_matches([',', ')']).to_string())
+}
+
+fn match_banner(response: &[u8]) -> Option<ServiceMatch> {
+    match_ssh(response)
+        .or_else(|| match_smtp(response))
+        .or_else(|| match_ftp(response))
+        .or_else(|| match_mysql(response))
+}
+
+// SSH-2.0-OpenSSH_9.6p1 Ubuntu-3ubuntu13
+fn match_ssh(response: &[u8]) -> Option<ServiceMatch> {
+    let line = first_line(response)?;
+    let rest = line.strip_prefix("SSH-")?;
+    let software = rest.split_once('-')?.1.split_whitespace().next()?;
+    let (product, version) = split_product(software, '_');
+    Some(ServiceMatch::new("ssh", Some(product), version))
+}
+
+// 220 mail.example.com ESMTP Postfix (Ubuntu)
+fn match_smtp(response: &[u8]) -> Option<ServiceMatch> {
+    let line = first_line(response)?;
+
+    if !line.starts_with("220") {
+        return None;
+    }
+
+    let mut words = line.split_whitespace();
+    words.find(|w| w.eq_ignore_ascii_case("esmtp") || *w == "SMTP")?;
+
+    let product = words
+        .next()
+        .filter(|w| w.starts_with(|c: char| c.is_ascii_alphabetic()))
+        .map(|w| w.to_string());
+
+    let version = product.as_ref().and_then(|_| version_after(&mut words));
+
+    Some(ServiceMatch::new("smtp", product, version))
+}
+
+// 220 (vsFTPd 3.0.5)
+fn match_ftp(response: &[u8]) -> Option<ServiceMatch> {
+    let line = first_line(response)?;
+
+    if !line.starts_with("220") || !line.to_uppercase().contains("FTP") {
+        return None;
+    }
+
+    let mut words = line
+        .split_whitespace()
+        .map(|w| w.trim_matches(|c| c == '(' || c == '['));
+
+    let product = words
+        .find(|w| w.to_uppercase().contains("FTP"))
+        .map(|w| w.trim_end_matches([')', ']']).to_string());
+
+    let version = product.as_ref().and_then(|_| version_after(&mut words));
+
+    Some(ServiceMatch::new("ftp", product, version))
+}
+
+// MySQL initial handshake packet: 3 byte length, sequence id 0, protocol
+// version 10 followed by a null terminated server version
+fn match_mysql(response: &[u8]) -> Option<ServiceMatch> {
+    if response.len() < 6 || response[3] != 0 || response[4] != 10 {
+        return None;
+    }
+
+    let end = response[5..].iter().position(|b| *b == 0)? + 5;
+    let server_version = std::str::from_utf8(&response[5..end]).ok()?;
+
+    if !server_version.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+
+    // MariaDB prefixes its version with 5.5.5- for compatibility
+    if let Some(idx) = server_version.find("-MariaDB") {
+        let version = server_version[..idx].trim_start_matches("5.5.5-");
+        return Some(ServiceMatch::new(
+            "mysql",
+            Some("MariaDB".into()),
+            Some(version.into()),
+        ));
+    }
+
+    let version = server_version.split('-').next().map(|v| v.to_string());
+
+    Some(ServiceMatch::new("mysql", Some("MySQL".into()), version))
+}
+
+// HTTP/1.1 200 OK ... Server: nginx/1.25.3
+fn match_http(response: &[u8]) -> Option<ServiceMatch> {
+    let text = String::from_utf8_lossy(response);
+
+    if !text.starts_with("HTTP/") {
+        return None;
+    }
+
+    let server = text
+        .lines()
+        .take_while(|l| !l.is_empty())
+        .find_map(|l| {
+            let (name, value) = l.split_once(':')?;
+            name.eq_ignore_ascii_case("server").then(|| value.trim())
+        })
+        .and_then(|v| v.split_whitespace().next());
+
+    let (product, version) = match server {
+        Some(s) => {
+            let (product, version) = split_product(s, '/');
+            (Some(product), version)
+        }
+        None => (None, None),
+    };
+
+    Some(ServiceMatch::new("http", product, version))
+}
+
+fn match_redis(response: &[u8]) -> Option<ServiceMatch> {
+    if response.starts_with(b"+PONG") || response.starts_with(b"-NOAUTH") {
+        return Some(ServiceMatch::new("redis", Some("Redis".into()), None));
+    }
+
+    None
+}
+
+// redis_version:7.2.4
+fn redis_version(response: &[u8]) -> Option<String> {
+    let text = std::str::from_utf8(response).ok()?;
+
+    text.lines()
+        .find_map(|l| l.strip_prefix("redis_version:"))
+        .map(|v| v.trim().to_string())
+}
+
+fn match_postgres(response: &[u8]) -> Option<ServiceMatch> {
+    if response == b"S" || response == b"N" {
+        return Some(ServiceMatch::new(
+            "postgresql",
+            Some("PostgreSQL".into()),
+            None,
+        ));
+    }
+
+    None
+}
+
+// Builds an OP_MSG running the given command against the admin database
+fn mongo_command(command: &str) -> Vec<u8> {
+    let mut doc = Vec::new();
+
+    // {<command>: 1, $db: "admin"}
+    doc.push(0x10);
+    doc.extend_from_slice(command.as_bytes());
+    doc.push(0);
+    doc.extend_from_slice(&1_i32.to_le_bytes());
+    doc.push(0x02);
+    doc.extend_from_slice(b"$db\0");
+    doc.extend_from_slice(&6_i32.to_le_bytes());
+    doc.extend_from_slice(b"admin\0");
+    doc.push(0);
+
+    let doc_len = (doc.len() + 4) as i32;
+
+    // header + flag bits + section kind + document
+    let msg_len = 16 + 4 + 1 + doc_len;
+
+    let mut msg = Vec::with_capacity(msg_len as usize);
+    msg.extend_from_slice(&msg_len.to_le_bytes());
+    msg.extend_from_slice(&1_i32.to_le_bytes());
+    msg.extend_from_slice(&0_i32.to_le_bytes());
+    msg.extend_from_slice(&MONGO_OP_MSG.to_le_bytes());
+    msg.extend_from_slice(&0_u32.to_le_bytes());
+    msg.push(0);
+    msg.extend_from_slice(&doc_len.to_le_bytes());
+    msg.extend_from_slice(&doc);
+    msg
+}
+
+// Returns the BSON body document of an OP_MSG reply
+fn mongo_body(response: &[u8]) -> Option<&[u8]> {
+    if response.len() < 21 {
+        return None;
+    }
+
+    let op_code = i32::from_le_bytes(response[12..16].try_into().ok()?);
+
+    // only single body sections (kind 0) are sent in replies to commands
+    if op_code != MONGO_OP_MSG || response[20] != 0 {
+        return None;
+    }
+
+    Some(&response[21..])
+}
+
+fn match_mongo(response: &[u8]) -> Option<ServiceMatch> {
+    mongo_body(response)?;
+    Some(ServiceMatch::new("mongodb", Some("MongoDB".into()), None))
+}
+
+fn mongo_version(response: &[u8]) -> Option<String> {
+    bson_string(mongo_body(response)?, "version")
+}
+
+// Finds a top level string field in a BSON document
+fn bson_string(doc: &[u8], key: &str) -> Option<String> {
+    let read_i32 = |pos: usize| -> Option<usize> {
+        let bytes = doc.get(pos..pos + 4)?.try_into().ok()?;
+        usize::try_from(i32::from_le_bytes(bytes)).ok()
+    };
+
+    let doc_len = read_i32(0)?.min(doc.len());
+    let mut pos = 4;
+
+    while pos < doc_len {
+        let element_type = doc[pos];
+
+        if element_type == 0 {
+            break;
+        }
+
+        let name_end = doc[pos + 1..].iter().position(|b| *b == 0)? + pos + 1;
+        let name = &doc[pos + 1..name_end];
+        pos = name_end + 1;
+
+        let value_len = match element_type {
+            // double, datetime, timestamp, int64
+            0x01 | 0x09 | 0x11 | 0x12 => 8,
+            // string
+            0x02 => {
+                let len = read_i32(pos)?;
+
+                if name == key.as_bytes() {
+                    let value = doc.get(pos + 4..pos + 4 + len)?;
+                    let value = value.strip_suffix(&[0]).unwrap_or(value);
+                    return String::from_utf8(value.to_vec()).ok();
+                }
+
+                4 + len
+            }
+            // embedded document, array
+            0x03 | 0x04 => read_i32(pos)?,
+            // binary
+            0x05 => 4 + 1 + read_i32(pos)?,
+            // object id
+            0x07 => 12,
+            // bool
+            0x08 => 1,
+            // null
+            0x0a => 0,
+            // int32
+            0x10 => 4,
+            _ => return None,
+        };
+
+        pos += value_len;
+    }
+
+    None
+}
+
+#[cfg(test)]
+#[path = "./probes_tests.rs"]
+mod tests;
@@ -0,0 +1,146 @@
+use std::{
+    net::{Ipv4Addr, TcpListener},
+    thread,
+};
+
+use super::*;
+
+fn serve_once(response: &'static [u8], wait_for_request: bool) -> SocketAddr {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+
+            if wait_for_request {
+                let mut buf = [0u8; 1024];
+                if stream.read(&mut buf).unwrap_or(0) == 0 {
+                    continue;
+                }
+            }
+
+            let _ = stream.write_all(response);
+            break;
+        }
+    });
+
+    addr
+}
+
+#[test]
+fn matches_ssh_banner() {
+    let found =
+        match_banner(b"SSH-2.0-OpenSSH_9.6p1 Ubuntu-3ubuntu13\r\n").unwrap();
+
+    assert_eq!(found.service, "ssh");
+    assert_eq!(found.product, Some("OpenSSH".into()));
+    assert_eq!(found.version, Some("9.6p1".into()));
+}
+
+#[test]
+fn matches_smtp_and_ftp_banners() {
+    let smtp =
+        match_banner(b"220 mail.example.com ESMTP Exim 4.96 Mon, 1 Jan\r\n")
+            .unwrap();
+
+    assert_eq!(smtp.service, "smtp");
+    assert_eq!(smtp.product, Some("Exim".into()));
+    assert_eq!(smtp.version, Some("4.96".into()));
+
+    let ftp = match_banner(b"220 (vsFTPd 3.0.5)\r\n").unwrap();
+
+    assert_eq!(ftp.service, "ftp");
+    assert_eq!(ftp.product, Some("vsFTPd".into()));
+    assert_eq!(ftp.version, Some("3.0.5".into()));
+}
+
+#[test]
+fn matches_mysql_and_mariadb_handshakes() {
+    let mysql = match_banner(b"\x4a\x00\x00\x00\x0a8.0.36\x00rest").unwrap();
+
+    assert_eq!(mysql.service, "mysql");
+    assert_eq!(mysql.product, Some("MySQL".into()));
+    assert_eq!(mysql.version, Some("8.0.36".into()));
+
+    let mariadb = match_banner(
+        b"\x4a\x00\x00\x00\x0a5.5.5-10.11.6-MariaDB-0+deb12u1\x00rest",
+    )
+    .unwrap();
+
+    assert_eq!(mariadb.product, Some("MariaDB".into()));
+    assert_eq!(mariadb.version, Some("10.11.6".into()));
+}
+
+#[test]
+fn matches_http_server_header() {
+    let found = match_http(
+        b"HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nServer: nginx/1.25.3\r\n\r\n<html>",
+    )
+    .unwrap();
+
+    assert_eq!(found.service, "http");
+    assert_eq!(found.product, Some("nginx".into()));
+    assert_eq!(found.version, Some("1.25.3".into()));
+
+    assert!(match_http(b"+PONG\r\n").is_none());
+}
+
+#[test]
+fn matches_redis_and_postgres_replies() {
+    assert_eq!(match_redis(b"+PONG\r\n").unwrap().service, "redis");
+    assert_eq!(
+        redis_version(b"$100\r\n# Server\r\nredis_version:7.2.4\r\n"),
+        Some("7.2.4".into())
+    );
+    assert_eq!(match_postgres(b"N").unwrap().service, "postgresql");
+    assert!(match_postgres(b"NO").is_none());
+}
+
+#[test]
+fn reads_version_from_mongo_build_info_reply() {
+    // a reply is shaped like a request so reuse the builder, then swap the
+    // document for one carrying a version
+    let mut reply = mongo_command("buildInfo");
+    reply.truncate(21);
+
+    let mut doc = Vec::new();
+    doc.push(0x10);
+    doc.extend_from_slice(b"ok\0");
+    doc.extend_from_slice(&1_i32.to_le_bytes());
+    doc.push(0x02);
+    doc.extend_from_slice(b"version\0");
+    doc.extend_from_slice(&6_i32.to_le_bytes());
+    doc.extend_from_slice(b"7.0.5\0");
+    doc.push(0);
+
+    reply.extend_from_slice(&((doc.len() + 4) as i32).to_le_bytes());
+    reply.extend_from_slice(&doc);
+
+    assert_eq!(match_mongo(&reply).unwrap().service, "mongodb");
+    assert_eq!(mongo_version(&reply), Some("7.0.5".into()));
+}
+
+#[test]
+fn identifies_service_from_banner() {
+    let addr = serve_once(b"SSH-2.0-OpenSSH_9.6p1\r\n", false);
+
+    let found = identify(addr, Duration::from_millis(500)).unwrap();
+
+    assert_eq!(found.service, "ssh");
+    assert_eq!(found.version, Some("9.6p1".into()));
+}
+
+#[test]
+fn identifies_service_from_probe() {
+    let addr = serve_once(
+        b"HTTP/1.0 200 OK\r\nServer: Apache/2.4.57 (Debian)\r\n\r\n",
+        true,
+    );
+
+    let found = identify(addr, Duration::from_millis(200)).unwrap();
+
+    assert_eq!(found.service, "http");
+    assert_eq!(found.product, Some("Apache".into()));
+    assert_eq!(found.version, Some("2.4.57".into()));
+}
@@ -2,7 +2,15 @@
 //!
 //! This includes:
 //! - ARP Scanning
+//! - TCP Connect Scanning (unprivileged)
+//! - ICMP Echo Scanning
+//! - IPv6 Neighbor Discovery Scanning
+//! - mDNS / DNS-SD Service Discovery
+//! - Passive Discovery (listen only)
 //! - SYN Scanning
+//! - Service Detection (banner grabbing and protocol probes)
+//! - SSDP / UPnP Discovery
+//! - UDP Scanning
 //! - Full Scanning (ARP + SYN)
 
 use itertools::Itertools;
@@ -15,34 +23,105 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::fmt::Display;
 use std::hash::Hash;
-use std::net::Ipv4Addr;
-use std::thread::JoinHandle;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::time::Duration;
 
-use crate::error::Result;
+use crate::{
+    error::Result,
+    scanners::handle::ScanHandle,
+    targets::{permutation::Permutation, ports::PortTargets},
+};
 
 pub mod arp_scanner;
+pub mod connect_scanner;
+pub mod cookie;
 pub mod full_scanner;
+pub mod handle;
 pub mod heartbeat;
+pub mod icmp_scanner;
+pub mod mdns_scanner;
+pub mod ndp_scanner;
+pub mod notifier;
+pub mod passive_scanner;
+pub mod progress;
+pub mod rate;
+pub mod service_scanner;
+pub mod ssdp_scanner;
+#[cfg(feature = "async")]
+pub mod stream;
 pub mod syn_scanner;
+pub mod udp_scanner;
 
 /// The default idle timeout for a scanner
 pub const IDLE_TIMEOUT: u16 = 10000;
 
-#[derive(Debug, Clone, Eq, Serialize, Deserialize)]
+/// The default time to wait for replies before unanswered targets are
+/// probed again
+pub const DEFAULT_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize,
+)]
+/// The state of a port as determined by the response to a probe
+pub enum PortState {
+    /// A service responded on the port
+    #[default]
+    Open,
+    /// No response was received so the port is either open or filtered
+    OpenFiltered,
+    /// The host responded that nothing is listening on the port
+    Closed,
+    /// A firewall or router rejected the probe
+    Filtered,
+    /// The port is reachable through any firewall but whether it is open or
+    /// closed is unknown
+    Unfiltered,
+}
+
+impl Display for PortState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Open => write!(f, "open"),
+            Self::OpenFiltered => write!(f, "open|filtered"),
+            Self::Closed => write!(f, "closed"),
+            Self::Filtered => write!(f, "filtered"),
+            Self::Unfiltered => write!(f, "unfiltered"),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Eq, Serialize, Deserialize)]
 /// Data structure representing a port
 pub struct Port {
     /// The ID of the port i.e. 22, 80, 443 etc.
     pub id: u16,
     /// The associated service name for the port if known
     pub service: String,
+    /// The state of the port as determined by the scanner
+    #[serde(default)]
+    pub state: PortState,
+    /// The product running on the port if detected i.e. OpenSSH, nginx
+    #[serde(default)]
+    pub product: Option<String>,
+    /// The version of the product running on the port if detected
+    #[serde(default)]
+    pub version: Option<String>,
 }
 
 impl Display for Port {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         if self.service.is_empty() {
-            write!(f, "{}", self.id)
+            write!(f, "{}", self.id)?;
         } else {
-            write!(f, "{}:{}", self.id, self.service)
+            write!(f, "{}:{}", self.id, self.service)?;
+        }
+
+        match (&self.product, &self.version) {
+            (Some(product), Some(version)) => {
+                write!(f, " ({} {})", product, version)
+            }
+            (Some(product), None) => write!(f, " ({})", product),
+            _ => Ok(()),
         }
     }
 }
@@ -115,6 +194,65 @@ where
     s.parse::<T>().map_err(serde::de::Error::custom)
 }
 
+#[derive(
+    Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize,
+)]
+/// Data structure representing a service advertised over mDNS / DNS-SD
+pub struct AdvertisedService {
+    /// Service instance name i.e. "Office Printer"
+    pub instance: String,
+    /// Service type i.e. _ipp._tcp
+    pub service_type: String,
+    /// Port the service is advertised on
+    pub port: u16,
+    /// TXT record key=value strings
+    pub txt: Vec<String>,
+}
+
+impl Display for AdvertisedService {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.service_type, self.port)
+    }
+}
+
+#[derive(
+    Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize,
+)]
+/// Data structure representing a UPnP device description
+pub struct UpnpDescription {
+    /// Human readable name i.e. "Living Room TV"
+    pub friendly_name: String,
+    /// Manufacturer name
+    pub manufacturer: String,
+    /// Model name
+    pub model_name: String,
+    /// Serial number
+    pub serial_number: String,
+}
+
+#[derive(
+    Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize,
+)]
+/// Data structure representing a guess at a device's operating system
+pub struct OsGuess {
+    /// Operating system i.e. Windows, macOS, Android
+    pub os: String,
+    /// Kind of device i.e. computer, phone, printer, if known
+    pub device_type: String,
+    /// Confidence in the guess from 0 to 100
+    pub confidence: u8,
+}
+
+impl Display for OsGuess {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.device_type.is_empty() {
+            write!(f, "{} ({}%)", self.os, self.confidence)
+        } else {
+            write!(f, "{} {} ({}%)", self.os, self.device_type, self.confidence)
+        }
+    }
+}
+
 // ARP Result from a single device
 #[derive(Debug, Clone, Eq, Serialize, Deserialize)]
 /// Data structure representing a device on the network
@@ -123,6 +261,9 @@ pub struct Device {
     pub hostname: String,
     /// IPv4 of the device
     pub ip: Ipv4Addr,
+    /// IPv6 addresses (link-local and global) discovered for the device
+    #[serde(default)]
+    pub ipv6: Vec<Ipv6Addr>,
     /// MAC address of the device
     #[serde(
         serialize_with = "serialize_to_string",
@@ -141,6 +282,15 @@ pub struct Device {
     pub latency_ms: Option<u128>,
     /// TTL value from the SYN-ACK response, if observed
     pub response_ttl: Option<u8>,
+    /// Services advertised by the device over mDNS / DNS-SD
+    #[serde(default)]
+    pub services: Vec<AdvertisedService>,
+    /// UPnP description fetched from the device's SSDP location
+    #[serde(default)]
+    pub upnp: Option<UpnpDescription>,
+    /// Best guess at the operating system and kind of device
+    #[serde(default)]
+    pub os_guess: Option<OsGuess>,
 }
 
 impl Default for Device {
@@ -148,12 +298,16 @@ impl Default for Device {
         Self {
             hostname: "".into(),
             ip: Ipv4Addr::UNSPECIFIED,
+            ipv6: Vec::new(),
             is_current_host: false,
             is_gateway: false,
             latency_ms: None,
             response_ttl: None,
             mac: MacAddr::default(),
             open_ports: PortSet::new(),
+            services: Vec::new(),
+            upnp: None,
+            os_guess: None,
             vendor: "".into(),
         }
     }
@@ -187,29 +341,106 @@ impl PartialOrd for Device {
 #[derive(Debug)]
 /// Data structure representing a message that a device is being scanned
 pub struct Scanning {
-    /// IPv4 of the device
-    pub ip: Ipv4Addr,
+    /// IP of the device, IPv6 for devices only reachable over IPv6
+    pub ip: IpAddr,
     /// Port being scanned
     pub port: Option<u16>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Data structure summarizing how a scanner sent its probes
+pub struct ScanStats {
+    /// Number of probes sent
+    pub sent: usize,
+    /// Rate in probes per second the scan ended at, None if unthrottled
+    pub rate: Option<u32>,
+    /// Number of times adaptive rate control slowed down
+    pub backoffs: usize,
+    /// Number of replies rejected as they didn't answer a probe
+    pub rejected: usize,
+}
+
 #[derive(Debug)]
 /// Generic enum representing the various kinds of scanning messages over the
 /// mcsp channel
 pub enum ScanMessage {
     /// Indicates that scanning has completed
     Done,
-    /// Send to inform that a device is about to be scanned
+    /// Send to inform that a device is about to be scanned. Only sent by
+    /// scanners built with `include_info` as it is sent for every probe
     Info(Scanning),
-    /// Sent whenever an ARP response is received from a device
+    /// Sent periodically while a scanner sends its probes, and once more
+    /// when sending ends
+    Progress {
+        /// Number of probes sent so far
+        sent: usize,
+        /// Total number of probes the scanner will send
+        total: usize,
+        /// Time spent sending so far
+        elapsed: Duration,
+        /// Estimated time left to send the remaining probes, unknown until
+        /// the first probe was sent
+        eta: Option<Duration>,
+    },
+    /// Sent once by scanners that send probes, right before Done
+    Stats(ScanStats),
+    /// Sent whenever an ARP response is received from a device, or a new
+    /// IP / MAC pair is observed by the passive scanner
     ARPScanDevice(Device),
-    /// Sent whenever a SYN response is received from a device
+    /// Sent whenever an ICMP echo reply is received from a device
+    ICMPScanDevice(Device),
+    /// Sent whenever an IPv6 neighbor is discovered. The Device carries the
+    /// IPv4 of a previously known device with the same MAC, otherwise its
+    /// ip is unspecified
+    NDPScanDevice(Device),
+    /// Sent whenever a device answers an mDNS / DNS-SD query. The Device
+    /// carries the .local hostname and advertised services, but its mac is
+    /// unknown so consumers should merge by ip
+    MDNSScanDevice(Device),
+    /// Sent whenever a UPnP description is fetched for a device that
+    /// answered an SSDP search. Like mDNS the mac is unknown so consumers
+    /// should merge by ip
+    SSDPScanDevice(Device),
+    /// Sent whenever a SYN response is received from a device, or a
+    /// connection is established by the connect scanner
     SYNScanDevice(Device),
+    /// Sent whenever the state of a UDP port is determined for a device. The
+    /// Device's open_ports contains the classified ports and their states
+    UDPScanDevice(Device),
+    /// Sent whenever the service running on an open port is identified. The
+    /// Device's open_ports contains only the identified port
+    ServiceScanDevice(Device),
 }
 
 #[cfg_attr(test, automock)]
 /// Trait used by all scanners
 pub trait Scanner: Sync + Send {
-    /// Performs network scanning
-    fn scan(&self) -> Result<JoinHandle<Result<()>>>;
+    /// Starts scanning in the background, returning a handle to control and
+    /// join the scan
+    fn scan(&self) -> Result<ScanHandle>;
+}
+
+// Visits every device and port pair, port by port in list order or
+// interleaved in a random order when seeded
+pub(crate) fn for_each_probe<F: FnMut(&Device, u16) -> Result<()>>(
+    devices: &[Device],
+    ports: &PortTargets,
+    seed: Option<u64>,
+    mut cb: F,
+) -> Result<()> {
+    let Some(seed) = seed else {
+        return ports.lazy_loop(|port| {
+            devices.iter().try_for_each(|device| cb(device, port))
+        });
+    };
+
+    let count = devices.len();
+
+    for index in Permutation::new(count * ports.len(), seed) {
+        if let Some(port) = ports.nth(index / count) {
+            cb(&devices[index % count], port)?;
+        }
+    }
+
+    Ok(())
 }
@@ -3,25 +3,31 @@
 use derive_builder::Builder;
 use pnet::packet::{Packet, arp, ethernet};
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     net::Ipv4Addr,
-    sync::{self, Arc, Mutex},
+    sync::{self, Arc, Mutex, mpsc::TryRecvError},
     thread::{self, JoinHandle},
     time::{Duration, SystemTime},
 };
 use threadpool::ThreadPool;
 
 use crate::{
-    error::{RLanLibError, Result},
+    error::Result,
+    names,
     network::NetworkInterface,
     oui::traits::Oui,
-    packet::arp_packet::ArpPacketBuilder,
-    scanners::{Device, PortSet, Scanning},
+    packet::{arp_packet::ArpPacketBuilder, heartbeat_packet::is_heartbeat},
+    scanners::{
+        DEFAULT_RETRY_DELAY, Device, PortSet, Scanning,
+        handle::{ScanControl, ScanHandle, loop_error},
+        progress::{DEFAULT_PROGRESS_INTERVAL, Progress},
+        rate::{RateBounds, RateController},
+    },
     targets::ips::IPTargets,
     wire::{DEFAULT_PACKET_SEND_TIMING, PacketMetadata, Wire},
 };
 
-use super::{ScanMessage, Scanner, heartbeat::HeartBeat};
+use super::{ScanMessage, Scanner, heartbeat::HeartBeat, notifier::Notifier};
 
 /// Data structure representing an ARP scanner
 #[derive(Clone, Builder)]
@@ -37,16 +43,40 @@ pub struct ARPScanner {
     source_port: u16,
     /// Whether to include vendor lookups for discovered devices
     include_vendor: bool,
-    /// Whether to include hostname lookups for discovered devices
+    /// Whether to include reverse DNS lookups for discovered devices
     include_host_names: bool,
+    /// Whether hostname lookups fall back to NetBIOS and LLMNR queries sent
+    /// to devices reverse DNS has no name for
+    #[builder(default)]
+    include_netbios_names: bool,
     /// Duration to wait for responses after scanning completes
     idle_timeout: Duration,
     /// Channel for sending scan results and status messages
-    notifier: sync::mpsc::Sender<ScanMessage>,
+    notifier: Notifier,
     /// Throttles speed at which packets are sent. Higher throttles result
     /// in more accurate scans
     #[builder(default = DEFAULT_PACKET_SEND_TIMING)]
     throttle: Duration,
+    /// Enables adaptive rate control within the given bounds, replacing
+    /// the fixed throttle
+    #[builder(default)]
+    adaptive_rate: Option<RateBounds>,
+    /// Seeds a random order in which targets are probed. Targets are
+    /// probed in list order when None
+    #[builder(default)]
+    seed: Option<u64>,
+    /// Whether to send a ScanMessage::Info for every target
+    #[builder(default)]
+    include_info: bool,
+    /// Interval between ScanMessage::Progress reports
+    #[builder(default = DEFAULT_PROGRESS_INTERVAL)]
+    progress_interval: Duration,
+    /// Number of times targets that have not answered are probed again
+    #[builder(default)]
+    retries: u8,
+    /// Time to wait for replies before unanswered targets are probed again
+    #[builder(default = DEFAULT_RETRY_DELAY)]
+    retry_delay: Duration,
     /// Default gateway IP, used to mark the gateway device in scan results
     #[builder(default)]
     gateway: Option<Ipv4Addr>,
@@ -58,6 +88,13 @@ pub struct ARPScanner {
     /// the reply (metadata.timestamp).
     #[builder(default = "Arc::new(Mutex::new(HashMap::new()))")]
     send_times: Arc<Mutex<HashMap<Ipv4Addr, SystemTime>>>,
+    /// Tracks every IP that answered so retries skip it and repeated
+    /// replies are only reported once
+    #[builder(setter(skip), default = "Arc::new(Mutex::new(HashSet::new()))")]
+    responded: Arc<Mutex<HashSet<Ipv4Addr>>>,
+    /// Paces probes and collects the statistics reported when done
+    #[builder(setter(skip))]
+    rate: RateController,
 }
 
 impl ARPScanner {
@@ -68,7 +105,7 @@ impl ARPScanner {
 
     fn process_target(&self, target: Ipv4Addr) -> Result<()> {
         // throttle packet sending to prevent packet loss
-        thread::sleep(self.throttle);
+        thread::sleep(self.rate.delay());
 
         log::debug!("scanning ARP target: {}", target);
 
@@ -76,21 +113,27 @@ impl ARPScanner {
         // device entry immediately rather than waiting for a reply that will
         // never arrive.
         if target == self.interface.ipv4 {
-            self.notifier
-                .send(ScanMessage::ARPScanDevice(Device {
-                    hostname: String::new(),
-                    ip: self.interface.ipv4,
-                    mac: self.interface.mac,
-                    vendor: String::new(),
-                    is_current_host: true,
-                    is_gateway: self
-                        .gateway
-                        .is_some_and(|gw| gw == self.interface.ipv4),
-                    open_ports: PortSet::new(),
-                    latency_ms: Some(0),
-                    response_ttl: None,
-                }))
-                .map_err(RLanLibError::from_channel_send_error)?;
+            if !self.mark_responded(target) {
+                return Ok(());
+            }
+
+            self.notifier.send(ScanMessage::ARPScanDevice(Device {
+                hostname: String::new(),
+                ip: self.interface.ipv4,
+                ipv6: Vec::new(),
+                mac: self.interface.mac,
+                vendor: String::new(),
+                is_current_host: true,
+                is_gateway: self
+                    .gateway
+                    .is_some_and(|gw| gw == self.interface.ipv4),
+                open_ports: PortSet::new(),
+                latency_ms: Some(0),
+                response_ttl: None,
+                services: Vec::new(),
+                upnp: None,
+                os_guess: None,
+            }))?;
             return Ok(());
         }
 
@@ -102,13 +145,13 @@ impl ARPScanner {
 
         let pkt_buf = arp_packet.to_raw();
 
-        // inform consumer we are scanning this target (ignore error on failure to notify)
-        self.notifier
-            .send(ScanMessage::Info(Scanning {
-                ip: target,
+        // inform consumer we are scanning this target
+        if self.include_info {
+            self.notifier.send(ScanMessage::Info(Scanning {
+                ip: target.into(),
                 port: None,
-            }))
-            .map_err(RLanLibError::from_channel_send_error)?;
+            }))?;
+        }
 
         let send_time = {
             let mut pkt_sender = self.wire.0.lock()?;
@@ -119,6 +162,8 @@ impl ARPScanner {
             t
         };
 
+        self.rate.sent();
+
         if let Ok(mut times) = self.send_times.lock() {
             times.insert(target, send_time);
         }
@@ -126,6 +171,83 @@ impl ARPScanner {
         Ok(())
     }
 
+    // Records that the IP answered, returning false if it already had
+    fn mark_responded(&self, ip: Ipv4Addr) -> bool {
+        self.responded
+            .lock()
+            .map(|mut responded| responded.insert(ip))
+            .unwrap_or(true)
+    }
+
+    fn has_responded(&self, ip: Ipv4Addr) -> bool {
+        self.responded
+            .lock()
+            .map(|responded| responded.contains(&ip))
+            .unwrap_or(false)
+    }
+
+    // Visits targets in list order, or in a random order when seeded
+    fn for_each_target<F: FnMut(Ipv4Addr) -> Result<()>>(
+        &self,
+        cb: F,
+    ) -> Result<()> {
+        match self.seed {
+            Some(seed) => self.targets.lazy_loop_shuffled(seed, cb),
+            None => self.targets.lazy_loop(cb),
+        }
+    }
+
+    fn send_probes(&self, control: &ScanControl) -> Result<()> {
+        let mut progress = Progress::start(
+            self.notifier.clone(),
+            self.targets.len(),
+            self.progress_interval,
+        )?;
+
+        self.for_each_target(|t| {
+            control.checkpoint()?;
+            self.process_target(t)?;
+            progress.sent()
+        })?;
+
+        for retry in 1..=self.retries {
+            control.sleep(self.retry_delay);
+
+            let mut unanswered = 0;
+
+            self.targets.lazy_loop(|t| {
+                if !self.has_responded(t) {
+                    unanswered += 1;
+                }
+                Ok(())
+            })?;
+
+            if unanswered == 0 {
+                break;
+            }
+
+            log::debug!(
+                "ARP retry {}: probing {} unanswered targets",
+                retry,
+                unanswered
+            );
+
+            progress.add(unanswered);
+
+            self.for_each_target(|t| {
+                if self.has_responded(t) {
+                    return Ok(());
+                }
+
+                control.checkpoint()?;
+                self.process_target(t)?;
+                progress.sent()
+            })?;
+        }
+
+        progress.finish()
+    }
+
     fn process_incoming_packet(
         &self,
         pkt: &[u8],
@@ -149,6 +271,11 @@ impl ARPScanner {
         let ip4 = header.get_sender_proto_addr();
         let mac = eth.get_source();
 
+        // retried targets may answer every probe
+        if !self.mark_responded(ip4) {
+            return Ok(());
+        }
+
         // RTT = kernel capture time of reply − SystemTime recorded just
         // before the send. Both are fixed points so mutex contention during
         // this lookup cannot inflate the measurement.
@@ -168,6 +295,7 @@ impl ARPScanner {
         let notification_sender = self.notifier.clone();
         let interface = Arc::clone(&self.interface);
         let include_host_names = self.include_host_names;
+        let include_netbios_names = self.include_netbios_names;
         let include_vendor = self.include_vendor;
         let gateway = self.gateway;
         let oui = self.oui.as_ref().map(Arc::clone);
@@ -175,9 +303,12 @@ impl ARPScanner {
         // use a thread pool here so we don't slow down packet
         // processing while limiting concurrent threads
         pool.execute(move || {
-            let hostname = if include_host_names {
+            let hostname = if include_host_names && include_netbios_names {
                 log::debug!("looking up hostname for {}", ip4);
-                dns_lookup::lookup_addr(&ip4.into()).unwrap_or_default()
+                names::lookup_hostname_with_fallback(ip4)
+            } else if include_host_names {
+                log::debug!("looking up hostname for {}", ip4);
+                names::lookup_hostname(ip4)
             } else {
                 String::new()
             };
@@ -194,6 +325,7 @@ impl ARPScanner {
                 notification_sender.send(ScanMessage::ARPScanDevice(Device {
                     hostname,
                     ip: ip4,
+                    ipv6: Vec::new(),
                     mac,
                     vendor,
                     is_current_host: ip4 == interface.ipv4,
@@ -201,6 +333,9 @@ impl ARPScanner {
                     open_ports: PortSet::new(),
                     latency_ms,
                     response_ttl: None,
+                    services: Vec::new(),
+                    upnp: None,
+                    os_guess: None,
                 }));
         });
 
@@ -212,6 +347,7 @@ impl ARPScanner {
     fn read_packets(
         &self,
         done: sync::mpsc::Receiver<()>,
+        control: ScanControl,
     ) -> Result<JoinHandle<Result<()>>> {
         let (heartbeat_tx, heartbeat_rx) = sync::mpsc::channel::<()>();
 
@@ -220,6 +356,8 @@ impl ARPScanner {
             .source_ipv4(self.interface.ipv4)
             .source_port(self.source_port)
             .packet_sender(Arc::clone(&self.wire.0))
+            .control(control.clone())
+            .rate(self.rate.clone())
             .build()?;
 
         heartbeat.start_in_thread(heartbeat_rx)?;
@@ -233,7 +371,10 @@ impl ARPScanner {
             let lookup_pool = ThreadPool::new(8);
 
             loop {
-                if done.try_recv().is_ok() {
+                // also stop if the scan thread went away without signalling
+                if control.is_cancelled()
+                    || !matches!(done.try_recv(), Err(TryRecvError::Empty))
+                {
                     log::debug!("exiting arp packet reader");
                     if let Err(e) = heartbeat_tx.send(()) {
                         log::error!("failed to stop heartbeat: {}", e);
@@ -243,6 +384,15 @@ impl ARPScanner {
 
                 let (pkt, metadata) = reader.next_packet_with_metadata()?;
 
+                if is_heartbeat(
+                    pkt,
+                    self_clone.interface.mac,
+                    self_clone.source_port,
+                ) {
+                    self_clone.rate.heartbeat_seen();
+                    continue;
+                }
+
                 self_clone.process_incoming_packet(
                     pkt,
                     metadata,
@@ -250,48 +400,59 @@ impl ARPScanner {
                 )?;
             }
 
+            // devices are reported once their lookups finish, which must
+            // happen before the scan reports Done
+            lookup_pool.join();
+
             Ok(())
         }))
     }
-}
 
-// Implements the Scanner trait for ARPScanner
-impl Scanner for ARPScanner {
-    fn scan(&self) -> Result<JoinHandle<Result<()>>> {
+    // Starts the scan under the given control, shared with the FullScanner
+    pub(crate) fn start(
+        &self,
+        control: ScanControl,
+    ) -> Result<JoinHandle<Result<()>>> {
         log::debug!("performing ARP scan on targets: {:?}", self.targets);
         log::debug!("include_vendor: {}", self.include_vendor);
         log::debug!("include_host_names: {}", self.include_host_names);
+        log::debug!("include_netbios_names: {}", self.include_netbios_names);
         log::debug!("starting arp packet reader");
 
+        if let Ok(mut responded) = self.responded.lock() {
+            responded.clear();
+        }
+
+        // devices discovered by ARP are not known to be up beforehand so
+        // unanswered probes say nothing about loss
+        self.rate.reset(self.throttle, self.adaptive_rate, false);
+        self.rate.watch_drops(&self.interface.name);
+
         let self_clone = self.clone();
         let (done_tx, done_rx) = sync::mpsc::channel::<()>();
 
-        let read_handle = self.read_packets(done_rx)?;
+        let read_handle = self.read_packets(done_rx, control.clone())?;
 
         // prevent blocking thread so messages can be freely sent to consumer
         let scan_handle = thread::spawn(move || -> Result<()> {
-            let mut scan_error: Option<RLanLibError> = None;
-
-            if let Err(err) = self_clone
-                .targets
-                .lazy_loop(|t| self_clone.process_target(t))
-            {
-                scan_error = Some(err);
-            }
+            let scan_error = loop_error(self_clone.send_probes(&control));
 
-            thread::sleep(self_clone.idle_timeout);
-
-            self_clone
-                .notifier
-                .send(ScanMessage::Done)
-                .map_err(RLanLibError::from_channel_send_error)?;
+            control.sleep(self_clone.idle_timeout);
 
             // ignore errors here as the thread may already be dead due to error
             // we'll catch any errors from that thread below and report
             let _ = done_tx.send(());
 
+            // the reader waits for pending lookups so every device is sent
+            // before Done
             let read_result = read_handle.join()?;
 
+            self_clone
+                .notifier
+                .send(ScanMessage::Stats(self_clone.rate.stats()))?;
+
+            self_clone.notifier.send(ScanMessage::Done)?;
+
             if let Some(err) = scan_error {
                 return Err(err);
             }
@@ -303,6 +464,15 @@ impl Scanner for ARPScanner {
     }
 }
 
+// Implements the Scanner trait for ARPScanner
+impl Scanner for ARPScanner {
+    fn scan(&self) -> Result<ScanHandle> {
+        let control = ScanControl::new();
+        let handle = self.start(control.clone())?;
+        Ok(ScanHandle::new(handle, control))
+    }
+}
+
 #[cfg(test)]
 #[path = "./arp_scanner_tests.rs"]
 mod tests;
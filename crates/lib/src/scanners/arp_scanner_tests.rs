@@ -5,11 +5,13 @@ use pnet::{
 };
 use std::sync::mpsc::channel;
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::{net::Ipv4Addr, str::FromStr};
 
 use crate::{
+    error::RLanLibError,
     network,
+    oui::traits::mocks::MockOuiDb,
     packet::{arp_packet::create_arp_reply, syn_packet::create_syn_reply},
     wire::{
         PacketMetadata, Reader, Sender,
@@ -109,6 +111,7 @@ fn sends_and_reads_packets() {
     let mut detected_device = Device {
         hostname: "".to_string(),
         ip: Ipv4Addr::new(10, 10, 10, 10),
+        ipv6: Vec::new(),
         is_current_host: false,
         is_gateway: false,
         mac: MacAddr::default(),
@@ -116,6 +119,9 @@ fn sends_and_reads_packets() {
         open_ports: PortSet::new(),
         latency_ms: None,
         response_ttl: None,
+        services: Vec::new(),
+        upnp: None,
+        os_guess: None,
     };
 
     loop {
@@ -232,6 +238,7 @@ fn ignores_unrelated_packets() {
             interface.mac,
             interface.ipv4,
             54321,
+            0,
             #[allow(static_mut_refs)]
             unsafe {
                 &mut PACKET
@@ -267,7 +274,7 @@ fn ignores_unrelated_packets() {
 
     let (done_tx, done_rx) = channel();
 
-    scanner.read_packets(done_rx);
+    scanner.read_packets(done_rx, ScanControl::new());
 
     let mut detected_devices: Vec<Device> = Vec::new();
 
@@ -338,7 +345,7 @@ fn reports_error_on_packet_reader_lock() {
 
     let (_done_tx, done_rx) = channel();
 
-    let handle = scanner.read_packets(done_rx).unwrap();
+    let handle = scanner.read_packets(done_rx, ScanControl::new()).unwrap();
 
     let result = handle.join().unwrap();
 
@@ -379,7 +386,7 @@ fn reports_error_on_packet_read_error() {
 
     let (_done_tx, done_rx) = channel();
 
-    let handle = scanner.read_packets(done_rx).unwrap();
+    let handle = scanner.read_packets(done_rx, ScanControl::new()).unwrap();
 
     let result = handle.join().unwrap();
 
@@ -633,3 +640,313 @@ fn reports_errors_from_read_handle() {
 
     assert!(result.is_err());
 }
+
+// Builds a scanner over a /24 that would take well over a minute to finish
+// on its own. Every packet read is unrelated to the scan.
+fn slow_scanner(notifier: sync::mpsc::Sender<ScanMessage>) -> ARPScanner {
+    let interface = Arc::new(network::get_default_interface().unwrap());
+
+    let mut receiver = MockPacketReader::new();
+    let mut sender = MockPacketSender::new();
+
+    receiver.expect_next_packet_with_metadata().returning(|| {
+        thread::sleep(Duration::from_millis(10));
+        Ok((&[], PacketMetadata { timestamp: None }))
+    });
+
+    sender.expect_send().returning(|_| Ok(()));
+
+    let sender: Arc<Mutex<dyn Sender>> = Arc::new(Mutex::new(sender));
+    let receiver: Arc<Mutex<dyn Reader>> = Arc::new(Mutex::new(receiver));
+
+    ARPScanner::builder()
+        .interface(interface)
+        .wire(Wire(sender, receiver))
+        .targets(IPTargets::new(vec!["10.99.0.0/24".to_string()]).unwrap())
+        .source_port(54321_u16)
+        .include_vendor(false)
+        .include_host_names(false)
+        .idle_timeout(Duration::from_secs(60))
+        .throttle(Duration::from_millis(50))
+        .include_info(true)
+        .notifier(notifier)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn cancels_promptly() {
+    let (tx, rx) = channel();
+    let scanner = slow_scanner(tx);
+
+    let started = Instant::now();
+    let handle = scanner.scan().unwrap();
+
+    let mut scanned = 0;
+
+    loop {
+        match rx.recv().unwrap() {
+            ScanMessage::Done => break,
+            ScanMessage::Info(_) => {
+                scanned += 1;
+                if scanned == 2 {
+                    handle.cancel();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    assert!(handle.join().unwrap().is_ok());
+    assert!(scanned < 10);
+    assert!(started.elapsed() < Duration::from_secs(10));
+}
+
+#[test]
+fn pauses_and_resumes_sending() {
+    let (tx, rx) = channel();
+    let scanner = slow_scanner(tx);
+
+    let handle = scanner.scan().unwrap();
+
+    // skip the progress reported when sending starts
+    assert!(matches!(rx.recv().unwrap(), ScanMessage::Progress { .. }));
+    assert!(matches!(rx.recv().unwrap(), ScanMessage::Info(_)));
+    handle.pause();
+
+    // drain the target that may have been in flight when pausing
+    while rx.recv_timeout(Duration::from_millis(200)).is_ok() {}
+
+    assert!(rx.recv_timeout(Duration::from_millis(300)).is_err());
+
+    handle.resume();
+    assert!(matches!(
+        rx.recv_timeout(Duration::from_secs(2)).unwrap(),
+        ScanMessage::Info(_)
+    ));
+
+    handle.cancel();
+    assert!(handle.join().unwrap().is_ok());
+}
+
+#[test]
+fn reports_progress_instead_of_info_by_default() {
+    let interface = Arc::new(network::get_default_interface().unwrap());
+
+    let mut receiver = MockPacketReader::new();
+    let mut sender = MockPacketSender::new();
+
+    receiver.expect_next_packet_with_metadata().returning(|| {
+        thread::sleep(Duration::from_millis(10));
+        Ok((&[], PacketMetadata { timestamp: None }))
+    });
+
+    sender.expect_send().returning(|_| Ok(()));
+
+    let sender: Arc<Mutex<dyn Sender>> = Arc::new(Mutex::new(sender));
+    let receiver: Arc<Mutex<dyn Reader>> = Arc::new(Mutex::new(receiver));
+
+    let (tx, rx) = channel();
+
+    let scanner = ARPScanner::builder()
+        .interface(interface)
+        .wire(Wire(sender, receiver))
+        .targets(
+            IPTargets::new(vec!["10.99.0.1-10.99.0.8".to_string()]).unwrap(),
+        )
+        .source_port(54321_u16)
+        .include_vendor(false)
+        .include_host_names(false)
+        .idle_timeout(Duration::from_millis(50))
+        .progress_interval(Duration::ZERO)
+        .notifier(tx)
+        .build()
+        .unwrap();
+
+    let handle = scanner.scan().unwrap();
+
+    let mut reports = Vec::new();
+
+    loop {
+        match rx.recv().unwrap() {
+            ScanMessage::Done => break,
+            ScanMessage::Info(_) => panic!("info messages are opt-in"),
+            ScanMessage::Progress { sent, total, .. } => {
+                assert_eq!(total, 8);
+                reports.push(sent);
+            }
+            _ => {}
+        }
+    }
+
+    assert!(handle.join().unwrap().is_ok());
+    assert_eq!(reports.first(), Some(&0));
+    assert_eq!(reports.last(), Some(&8));
+}
+
+#[test]
+fn retries_unanswered_targets_and_reports_devices_once() {
+    static mut PACKET: [u8; PKT_TOTAL_ARP_SIZE] = [0u8; PKT_TOTAL_ARP_SIZE];
+
+    let interface = Arc::new(network::get_default_interface().unwrap());
+    let answering_ip = Ipv4Addr::new(10, 99, 0, 1);
+
+    create_arp_reply(
+        util::MacAddr::default(),
+        answering_ip,
+        interface.mac,
+        interface.ipv4,
+        #[allow(static_mut_refs)]
+        unsafe {
+            &mut PACKET
+        },
+    );
+
+    let mut receiver = MockPacketReader::new();
+    let mut sender = MockPacketSender::new();
+
+    // the device answers every probe
+    #[allow(static_mut_refs)]
+    receiver.expect_next_packet_with_metadata().returning(|| {
+        thread::sleep(Duration::from_millis(1));
+        Ok((unsafe { &PACKET }, PacketMetadata { timestamp: None }))
+    });
+
+    let probes: Arc<Mutex<HashMap<Ipv4Addr, usize>>> = Arc::default();
+    let sent = Arc::clone(&probes);
+
+    sender.expect_send().returning(move |pkt| {
+        if let Some(eth) = ethernet::EthernetPacket::new(pkt)
+            && eth.get_ethertype() == ethernet::EtherTypes::Arp
+            && let Some(req) = arp::ArpPacket::new(eth.payload())
+        {
+            *sent
+                .lock()
+                .unwrap()
+                .entry(req.get_target_proto_addr())
+                .or_default() += 1;
+        }
+        Ok(())
+    });
+
+    let sender: Arc<Mutex<dyn Sender>> = Arc::new(Mutex::new(sender));
+    let receiver: Arc<Mutex<dyn Reader>> = Arc::new(Mutex::new(receiver));
+    let (tx, rx) = channel();
+
+    let scanner = ARPScanner::builder()
+        .interface(interface)
+        .wire(Wire(sender, receiver))
+        .targets(
+            IPTargets::new(vec!["10.99.0.1-10.99.0.3".to_string()]).unwrap(),
+        )
+        .source_port(54321_u16)
+        .include_vendor(false)
+        .include_host_names(false)
+        .idle_timeout(Duration::from_millis(50))
+        .retries(2)
+        .retry_delay(Duration::from_millis(100))
+        .notifier(tx)
+        .build()
+        .unwrap();
+
+    let handle = scanner.scan().unwrap();
+
+    let mut devices = 0;
+
+    loop {
+        match rx.recv().unwrap() {
+            ScanMessage::Done => break,
+            ScanMessage::ARPScanDevice(_) => devices += 1,
+            _ => {}
+        }
+    }
+
+    assert!(handle.join().unwrap().is_ok());
+    assert_eq!(devices, 1);
+
+    let probes = probes.lock().unwrap();
+    assert_eq!(probes.get(&answering_ip), Some(&1));
+    assert_eq!(probes.get(&Ipv4Addr::new(10, 99, 0, 2)), Some(&3));
+    assert_eq!(probes.get(&Ipv4Addr::new(10, 99, 0, 3)), Some(&3));
+}
+
+#[test]
+#[allow(static_mut_refs)]
+fn reports_devices_from_slow_lookups_before_done() {
+    static mut PACKETS: [[u8; PKT_TOTAL_ARP_SIZE]; 3] =
+        [[0u8; PKT_TOTAL_ARP_SIZE]; 3];
+
+    let interface = Arc::new(network::get_default_interface().unwrap());
+    let ips: Vec<Ipv4Addr> =
+        (1..=3).map(|i| Ipv4Addr::new(10, 98, 0, i)).collect();
+
+    for (i, ip) in ips.iter().enumerate() {
+        create_arp_reply(
+            util::MacAddr::default(),
+            *ip,
+            interface.mac,
+            interface.ipv4,
+            unsafe { &mut PACKETS[i] },
+        );
+    }
+
+    let mut receiver = MockPacketReader::new();
+    let mut sender = MockPacketSender::new();
+    let mut next = 0;
+
+    receiver
+        .expect_next_packet_with_metadata()
+        .returning(move || {
+            thread::sleep(Duration::from_millis(1));
+            next += 1;
+            Ok((
+                unsafe { &PACKETS[next % 3] },
+                PacketMetadata { timestamp: None },
+            ))
+        });
+
+    sender.expect_send().returning(|_| Ok(()));
+
+    // lookups finish well after the idle timeout
+    let mut oui = MockOuiDb::new();
+    oui.expect_lookup().returning(|_| {
+        thread::sleep(Duration::from_millis(300));
+        None
+    });
+
+    let sender: Arc<Mutex<dyn Sender>> = Arc::new(Mutex::new(sender));
+    let receiver: Arc<Mutex<dyn Reader>> = Arc::new(Mutex::new(receiver));
+    let (tx, rx) = channel();
+
+    let scanner = ARPScanner::builder()
+        .interface(interface)
+        .wire(Wire(sender, receiver))
+        .targets(
+            IPTargets::new(vec!["10.98.0.1-10.98.0.3".to_string()]).unwrap(),
+        )
+        .source_port(54321_u16)
+        .include_vendor(true)
+        .include_host_names(false)
+        .oui(Arc::new(oui) as Arc<dyn Oui>)
+        .idle_timeout(Duration::from_millis(20))
+        .notifier(tx)
+        .build()
+        .unwrap();
+
+    let handle = scanner.scan().unwrap();
+
+    let mut devices = HashSet::new();
+
+    loop {
+        match rx.recv().unwrap() {
+            ScanMessage::Done => break,
+            ScanMessage::ARPScanDevice(device) => {
+                devices.insert(device.ip);
+            }
+            _ => {}
+        }
+    }
+
+    assert!(handle.join().unwrap().is_ok());
+    assert_eq!(devices, ips.into_iter().collect());
+}
@@ -0,0 +1,469 @@
+//! Provides Scanner implementation for unprivileged TCP connect scanning
+//!
+//! Unlike the other scanners this does not require a [`crate::wire::Wire`]
+//! or elevated privileges. Each probe is a full TCP handshake performed by
+//! the OS, so it is slower and noisier than SYN scanning but works anywhere
+//! an ordinary socket can be opened.
+//!
+//! Connections are non-blocking and multiplexed on the scanning thread, so
+//! up to `concurrency` handshakes are in flight without a thread each.
+//!
+//! Without raw sockets there is no ARP discovery, so the scanner can probe
+//! every IP in an [`IPTargets`] list as a potential device. Devices are
+//! created as each IP is probed, so large lists are never materialized.
+
+use derive_builder::Builder;
+use mio::{Events, Interest, Poll, Token, net::TcpStream};
+use std::{
+    collections::HashMap,
+    io::{self, ErrorKind},
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    error::{RLanLibError, Result},
+    network::NetworkInterface,
+    scanners::{
+        PortSet, PortState, Scanning,
+        handle::{ScanControl, ScanHandle, loop_error},
+        progress::{DEFAULT_PROGRESS_INTERVAL, Progress},
+    },
+    services::{
+        self,
+        traits::{Protocol, Services},
+    },
+    targets::{ips::IPTargets, permutation::Permutation, ports::PortTargets},
+};
+
+use super::{
+    Device, Port, ScanMessage, Scanner, for_each_probe, notifier::Notifier,
+};
+
+/// Default time to wait for each connection to be established
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_millis(1000);
+
+/// Default number of connections allowed to be in flight at once
+pub const DEFAULT_CONNECT_CONCURRENCY: usize = 128;
+
+/// Hosts probed by a [`ConnectScanner`]
+#[derive(Debug, Clone)]
+pub enum ConnectTargets {
+    /// Devices already discovered, i.e. by another scanner
+    Devices(Vec<Device>),
+    /// Every IP in the list, each treated as a potential device
+    Ips(Arc<IPTargets>),
+}
+
+impl From<Vec<Device>> for ConnectTargets {
+    fn from(devices: Vec<Device>) -> Self {
+        Self::Devices(devices)
+    }
+}
+
+impl From<Arc<IPTargets>> for ConnectTargets {
+    fn from(ips: Arc<IPTargets>) -> Self {
+        Self::Ips(ips)
+    }
+}
+
+/// Data structure representing a TCP connect scanner
+#[derive(Clone, Builder)]
+#[builder(setter(into), pattern = "owned")]
+pub struct ConnectScanner {
+    /// Devices or IPs to scan for open ports
+    targets: ConnectTargets,
+    /// Port targets to scan on each device
+    ports: Arc<PortTargets>,
+    /// Time to wait for each connection before giving up on the port
+    #[builder(default = DEFAULT_CONNECT_TIMEOUT)]
+    timeout: Duration,
+    /// Maximum number of connections in flight at once, each holding a
+    /// socket open
+    #[builder(default = DEFAULT_CONNECT_CONCURRENCY)]
+    concurrency: usize,
+    /// Channel for sending scan results and status messages
+    notifier: Notifier,
+    /// Whether to send a ScanMessage::Info for every connection
+    #[builder(default)]
+    include_info: bool,
+    /// Interval between ScanMessage::Progress reports
+    #[builder(default = DEFAULT_PROGRESS_INTERVAL)]
+    progress_interval: Duration,
+    /// Seeds a random order in which device and port pairs are probed,
+    /// interleaving hosts and ports. Each port is probed on every device
+    /// before moving to the next when None
+    #[builder(default)]
+    seed: Option<u64>,
+    /// Used to lookup the names of services on open ports
+    #[builder(default = "services::default()")]
+    services: Arc<dyn Services>,
+    /// Network interface used to mark the current host among IP targets
+    #[builder(default)]
+    interface: Option<Arc<NetworkInterface>>,
+    /// Default gateway IP, used to mark the gateway among IP targets
+    #[builder(default)]
+    gateway: Option<Ipv4Addr>,
+}
+
+// A connection whose handshake has not completed yet
+struct Pending {
+    stream: TcpStream,
+    device: Device,
+    port: u16,
+    deadline: Instant,
+}
+
+// Non-blocking connections in flight, polled together on one thread
+struct Connections {
+    poll: Poll,
+    events: Events,
+    pending: HashMap<Token, Pending>,
+    next_token: usize,
+}
+
+impl Connections {
+    fn new(capacity: usize) -> Result<Self> {
+        Ok(Self {
+            poll: Poll::new().map_err(poll_error)?,
+            events: Events::with_capacity(capacity),
+            pending: HashMap::with_capacity(capacity),
+            next_token: 0,
+        })
+    }
+
+    fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    // Starts connecting to the port without waiting for the handshake
+    fn connect(
+        &mut self,
+        device: &Device,
+        addr: SocketAddr,
+        timeout: Duration,
+    ) -> Result<()> {
+        let mut stream = match TcpStream::connect(addr) {
+            Ok(stream) => stream,
+            Err(e) => {
+                log::debug!("{} filtered: {}", addr, e);
+                return Ok(());
+            }
+        };
+
+        let token = Token(self.next_token);
+        self.next_token = self.next_token.wrapping_add(1);
+
+        self.poll
+            .registry()
+            .register(&mut stream, token, Interest::WRITABLE)
+            .map_err(poll_error)?;
+
+        self.pending.insert(
+            token,
+            Pending {
+                stream,
+                device: device.clone(),
+                port: addr.port(),
+                deadline: Instant::now() + timeout,
+            },
+        );
+
+        Ok(())
+    }
+
+    // Waits until at least one connection completes or the earliest one
+    // times out, returning the outcome of every connection that finished
+    fn wait(&mut self) -> Result<Vec<(Pending, io::Result<()>)>> {
+        let now = Instant::now();
+        let timeout = self
+            .pending
+            .values()
+            .map(|p| p.deadline.saturating_duration_since(now))
+            .min();
+
+        match self.poll.poll(&mut self.events, timeout) {
+            Err(e) if e.kind() != ErrorKind::Interrupted => {
+                return Err(poll_error(e));
+            }
+            _ => {}
+        }
+
+        let mut finished = Vec::new();
+
+        let ready: Vec<Token> = self.events.iter().map(|e| e.token()).collect();
+
+        for token in ready {
+            let Some(result) =
+                self.pending.get(&token).and_then(|p| outcome(&p.stream))
+            else {
+                continue;
+            };
+
+            if let Some(pending) = self.remove(token) {
+                finished.push((pending, result));
+            }
+        }
+
+        let now = Instant::now();
+
+        let expired: Vec<Token> = self
+            .pending
+            .iter()
+            .filter(|(_, p)| p.deadline <= now)
+            .map(|(token, _)| *token)
+            .collect();
+
+        for token in expired {
+            if let Some(pending) = self.remove(token) {
+                finished.push((pending, Err(ErrorKind::TimedOut.into())));
+            }
+        }
+
+        Ok(finished)
+    }
+
+    fn remove(&mut self, token: Token) -> Option<Pending> {
+        let mut pending = self.pending.remove(&token)?;
+        // the stream is closed when dropped so failing to deregister it
+        // leaves nothing behind
+        let _ = self.poll.registry().deregister(&mut pending.stream);
+        Some(pending)
+    }
+}
+
+// Returns the outcome of a connection that became writable, None if its
+// handshake is still in progress
+fn outcome(stream: &TcpStream) -> Option<io::Result<()>> {
+    match stream.take_error() {
+        Ok(Some(e)) | Err(e) => return Some(Err(e)),
+        Ok(None) => {}
+    }
+
+    match stream.peer_addr() {
+        Ok(_) => Some(Ok(())),
+        Err(e) if e.kind() == ErrorKind::NotConnected => None,
+        Err(e) => Some(Err(e)),
+    }
+}
+
+fn poll_error(e: io::Error) -> RLanLibError {
+    RLanLibError::Scan {
+        error: e.to_string(),
+        ip: None,
+        port: None,
+    }
+}
+
+impl ConnectScanner {
+    /// Returns a builder for ConnectScanner
+    pub fn builder() -> ConnectScannerBuilder {
+        ConnectScannerBuilder::default()
+    }
+
+    // Returns the potential device at an IP target
+    fn device(&self, ip: Ipv4Addr) -> Device {
+        Device {
+            ip,
+            is_current_host: self
+                .interface
+                .as_ref()
+                .is_some_and(|i| i.ipv4 == ip),
+            is_gateway: self.gateway.is_some_and(|gw| gw == ip),
+            ..Device::default()
+        }
+    }
+
+    // Visits every device and port pair like for_each_probe, creating
+    // devices for IP targets as they are visited
+    fn for_each_target<F: FnMut(&Device, u16) -> Result<()>>(
+        &self,
+        mut cb: F,
+    ) -> Result<()> {
+        let ips = match &self.targets {
+            ConnectTargets::Devices(devices) => {
+                return for_each_probe(devices, &self.ports, self.seed, cb);
+            }
+            ConnectTargets::Ips(ips) => ips,
+        };
+
+        let Some(seed) = self.seed else {
+            return self.ports.lazy_loop(|port| {
+                ips.lazy_loop(|ip| cb(&self.device(ip), port))
+            });
+        };
+
+        let count = ips.len();
+
+        for index in Permutation::new(count * self.ports.len(), seed) {
+            if let (Some(ip), Some(port)) =
+                (ips.nth(index % count), self.ports.nth(index / count))
+            {
+                cb(&self.device(ip), port)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Returns the IP address at which the device should be scanned. Link
+    // local IPv6 addresses require a scope id so only global addresses are
+    // used for IPv6-only devices
+    pub(crate) fn target_ip(device: &Device) -> Option<IpAddr> {
+        if !device.ip.is_unspecified() {
+            return Some(IpAddr::V4(device.ip));
+        }
+
+        device
+            .ipv6
+            .iter()
+            .find(|ip| !ip.is_unicast_link_local())
+            .map(|ip| IpAddr::V6(*ip))
+    }
+
+    fn send_probes(
+        &self,
+        connections: &mut Connections,
+        control: &ScanControl,
+    ) -> Result<()> {
+        let devices = match &self.targets {
+            ConnectTargets::Devices(devices) => devices
+                .iter()
+                .filter(|d| Self::target_ip(d).is_some())
+                .count(),
+            ConnectTargets::Ips(ips) => ips.len(),
+        };
+
+        let mut progress = Progress::start(
+            self.notifier.clone(),
+            devices * self.ports.len(),
+            self.progress_interval,
+        )?;
+
+        self.for_each_target(|d, p| {
+            self.process_probe(d, p, connections, control, &mut progress)
+        })?;
+
+        progress.finish()?;
+
+        // wait for in flight connections to finish before reporting done
+        while connections.len() > 0 {
+            control.checkpoint()?;
+            self.process_finished(connections)?;
+        }
+
+        Ok(())
+    }
+
+    fn process_probe(
+        &self,
+        device: &Device,
+        port: u16,
+        connections: &mut Connections,
+        control: &ScanControl,
+        progress: &mut Progress,
+    ) -> Result<()> {
+        let Some(target_ip) = Self::target_ip(device) else {
+            return Ok(());
+        };
+
+        control.checkpoint()?;
+
+        // wait for a free slot so open sockets stay bounded no matter how
+        // many targets and ports are being scanned
+        while connections.len() >= self.concurrency.max(1) {
+            self.process_finished(connections)?;
+        }
+
+        log::debug!("scanning connect target: {}:{}", target_ip, port);
+
+        if self.include_info {
+            self.notifier.send(ScanMessage::Info(Scanning {
+                ip: target_ip,
+                port: Some(port),
+            }))?;
+        }
+
+        connections.connect(
+            device,
+            SocketAddr::new(target_ip, port),
+            self.timeout,
+        )?;
+
+        progress.sent()
+    }
+
+    // Waits for connections to finish, reporting those that were accepted
+    fn process_finished(&self, connections: &mut Connections) -> Result<()> {
+        for (pending, result) in connections.wait()? {
+            let port = pending.port;
+
+            match result {
+                Ok(()) => {
+                    let mut ports = PortSet::new();
+                    ports.0.insert(Port {
+                        id: port,
+                        service: self
+                            .services
+                            .lookup(port, Protocol::Tcp)
+                            .unwrap_or_default(),
+                        state: PortState::Open,
+                        ..Port::default()
+                    });
+
+                    self.notifier.send(ScanMessage::SYNScanDevice(Device {
+                        open_ports: ports,
+                        ..pending.device
+                    }))?;
+                }
+                Err(e) if e.kind() == ErrorKind::ConnectionRefused => {
+                    log::debug!("{}:{} closed", pending.device.ip, port);
+                }
+                Err(e) => {
+                    log::debug!(
+                        "{}:{} filtered: {}",
+                        pending.device.ip,
+                        port,
+                        e
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Implements the Scanner trait for ConnectScanner
+impl Scanner for ConnectScanner {
+    fn scan(&self) -> Result<ScanHandle> {
+        let control = ScanControl::new();
+        log::debug!("performing connect scan on targets: {:?}", self.targets);
+
+        let self_clone = self.clone();
+        let scan_control = control.clone();
+
+        // prevent blocking thread so messages can be freely sent to consumer
+        let handle = thread::spawn(move || -> Result<()> {
+            let scan_error = loop_error(
+                Connections::new(self_clone.concurrency.max(1)).and_then(
+                    |mut connections| {
+                        self_clone.send_probes(&mut connections, &scan_control)
+                    },
+                ),
+            );
+
+            self_clone.notifier.send(ScanMessage::Done)?;
+
+            scan_error.map_or(Ok(()), Err)
+        });
+
+        Ok(ScanHandle::new(handle, control))
+    }
+}
+
+#[cfg(test)]
+#[path = "./connect_scanner_tests.rs"]
+mod tests;
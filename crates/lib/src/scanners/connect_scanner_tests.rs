@@ -0,0 +1,303 @@
+use super::*;
+use std::net::{Ipv4Addr, Ipv6Addr, TcpListener};
+use std::str::FromStr;
+use std::sync::mpsc::{self, channel};
+
+use crate::services::traits::mocks::MockServicesDb;
+
+fn collect(rx: mpsc::Receiver<ScanMessage>) -> Vec<Device> {
+    let mut detected: Vec<Device> = Vec::new();
+
+    loop {
+        if let Ok(msg) = rx.recv() {
+            match msg {
+                ScanMessage::Done => {
+                    break;
+                }
+                ScanMessage::SYNScanDevice(device) => {
+                    detected.push(device);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    detected
+}
+
+#[test]
+fn new() {
+    let devices: Vec<Device> = Vec::new();
+    let ports = PortTargets::new(vec!["2000-8000".to_string()]).unwrap();
+    let (tx, _) = channel();
+
+    let scanner = ConnectScanner::builder()
+        .targets(devices.clone())
+        .ports(ports)
+        .notifier(tx)
+        .build()
+        .unwrap();
+
+    assert!(
+        matches!(scanner.targets, ConnectTargets::Devices(d) if d == devices)
+    );
+    assert_eq!(scanner.timeout, DEFAULT_CONNECT_TIMEOUT);
+    assert_eq!(scanner.concurrency, DEFAULT_CONNECT_CONCURRENCY);
+}
+
+#[test]
+fn reports_open_ports_only() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let open_port = listener.local_addr().unwrap().port();
+
+    // bind and immediately release a port so nothing is listening on it
+    let closed_port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+
+    let device = Device {
+        ip: Ipv4Addr::LOCALHOST,
+        ..Device::default()
+    };
+
+    let ports =
+        PortTargets::new(vec![open_port.to_string(), closed_port.to_string()])
+            .unwrap();
+    let (tx, rx) = channel();
+
+    let scanner = ConnectScanner::builder()
+        .targets(vec![device.clone()])
+        .ports(ports)
+        .timeout(Duration::from_millis(500))
+        .concurrency(1_usize)
+        .notifier(tx)
+        .build()
+        .unwrap();
+
+    let handle = scanner.scan().unwrap();
+    let detected = collect(rx);
+
+    assert!(handle.join().unwrap().is_ok());
+    assert_eq!(detected.len(), 1);
+    assert_eq!(detected[0].ip, device.ip);
+    assert_eq!(
+        detected[0].open_ports.to_sorted_vec()[0],
+        Port {
+            id: open_port,
+            // ephemeral ports may be registered to a service
+            service: services::default()
+                .lookup(open_port, Protocol::Tcp)
+                .unwrap_or_default(),
+            ..Port::default()
+        }
+    );
+}
+
+#[test]
+fn names_services_from_custom_database() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let open_port = listener.local_addr().unwrap().port();
+
+    let mut services = MockServicesDb::new();
+    services
+        .expect_lookup()
+        .withf(move |port, protocol| {
+            *port == open_port && *protocol == Protocol::Tcp
+        })
+        .returning(|_, _| Some("billing".to_string()));
+
+    let device = Device {
+        ip: Ipv4Addr::LOCALHOST,
+        ..Device::default()
+    };
+
+    let ports = PortTargets::new(vec![open_port.to_string()]).unwrap();
+    let (tx, rx) = channel();
+
+    let scanner = ConnectScanner::builder()
+        .targets(vec![device])
+        .ports(ports)
+        .timeout(Duration::from_millis(500))
+        .notifier(tx)
+        .services(Arc::new(services) as Arc<dyn Services>)
+        .build()
+        .unwrap();
+
+    let handle = scanner.scan().unwrap();
+    let detected = collect(rx);
+
+    assert!(handle.join().unwrap().is_ok());
+    assert_eq!(detected[0].open_ports.to_sorted_vec()[0].service, "billing");
+}
+
+#[test]
+fn skips_devices_with_only_link_local_ipv6() {
+    let device = Device {
+        ipv6: vec![Ipv6Addr::from_str("fe80::1").unwrap()],
+        ..Device::default()
+    };
+
+    let ports = PortTargets::new(vec!["22".to_string()]).unwrap();
+    let (tx, rx) = channel();
+
+    let scanner = ConnectScanner::builder()
+        .targets(vec![device])
+        .ports(ports)
+        .notifier(tx)
+        .build()
+        .unwrap();
+
+    let handle = scanner.scan().unwrap();
+
+    // nothing to scan so only empty progress is reported before Done
+    loop {
+        match rx.recv().unwrap() {
+            ScanMessage::Done => break,
+            ScanMessage::Progress { sent, total, .. } => {
+                assert_eq!((sent, total), (0, 0));
+            }
+            m => panic!("unexpected message: {:?}", m),
+        }
+    }
+    assert!(handle.join().unwrap().is_ok());
+}
+
+#[test]
+fn reports_open_ports_among_many_in_flight() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let open_port = listener.local_addr().unwrap().port();
+
+    // release a batch of ports so nothing is listening on them
+    let closed: Vec<TcpListener> = (0..32)
+        .map(|_| TcpListener::bind("127.0.0.1:0").unwrap())
+        .collect();
+    let mut ports: Vec<String> = closed
+        .iter()
+        .map(|l| l.local_addr().unwrap().port().to_string())
+        .collect();
+    drop(closed);
+    ports.push(open_port.to_string());
+
+    let device = Device {
+        ip: Ipv4Addr::LOCALHOST,
+        ..Device::default()
+    };
+
+    let (tx, rx) = channel();
+
+    let scanner = ConnectScanner::builder()
+        .targets(vec![device])
+        .ports(PortTargets::new(ports).unwrap())
+        .timeout(Duration::from_millis(500))
+        .concurrency(8_usize)
+        .notifier(tx)
+        .build()
+        .unwrap();
+
+    let handle = scanner.scan().unwrap();
+    let detected = collect(rx);
+
+    assert!(handle.join().unwrap().is_ok());
+    assert_eq!(detected.len(), 1);
+    assert_eq!(detected[0].open_ports.to_sorted_vec()[0].id, open_port);
+}
+
+#[test]
+fn reports_open_ports_on_ip_targets() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let open_port = listener.local_addr().unwrap().port();
+    let interface = NetworkInterface {
+        ipv4: Ipv4Addr::LOCALHOST,
+        ..crate::network::get_default_interface().unwrap()
+    };
+    let (tx, rx) = channel();
+
+    let scanner = ConnectScanner::builder()
+        .targets(IPTargets::new(vec!["127.0.0.1".to_string()]).unwrap())
+        .ports(PortTargets::new(vec![open_port.to_string()]).unwrap())
+        .timeout(Duration::from_millis(500))
+        .interface(Arc::new(interface))
+        .notifier(tx)
+        .build()
+        .unwrap();
+
+    let handle = scanner.scan().unwrap();
+    let detected = collect(rx);
+
+    assert!(handle.join().unwrap().is_ok());
+    assert_eq!(detected.len(), 1);
+    assert_eq!(detected[0].ip, Ipv4Addr::LOCALHOST);
+    assert!(detected[0].is_current_host);
+    assert_eq!(detected[0].open_ports.to_sorted_vec()[0].id, open_port);
+}
+
+#[test]
+fn creates_devices_for_ip_targets_as_probed() {
+    let gateway = Ipv4Addr::new(10, 0, 0, 1);
+    let (tx, _) = channel();
+
+    for seed in [None, Some(7)] {
+        let scanner = ConnectScanner::builder()
+            .targets(IPTargets::new(vec!["10.0.0.0/8".to_string()]).unwrap())
+            .ports(PortTargets::new(vec!["22".to_string()]).unwrap())
+            .gateway(gateway)
+            .seed(seed)
+            .notifier(tx.clone())
+            .build()
+            .unwrap();
+
+        let mut probed = Vec::new();
+
+        // stop early, the /8 is never collected into devices
+        let result = scanner.for_each_target(|device, port| {
+            probed.push((device.clone(), port));
+
+            if probed.len() == 3 {
+                return Err(RLanLibError::Wire("stop".to_string()));
+            }
+
+            Ok(())
+        });
+
+        assert!(result.is_err());
+        assert_eq!(probed.len(), 3);
+        assert!(probed.iter().all(|(_, port)| *port == 22));
+
+        if seed.is_none() {
+            assert_eq!(probed[0].0.ip, gateway);
+            assert!(probed[0].0.is_gateway);
+            assert!(!probed[1].0.is_gateway);
+        }
+    }
+}
+
+#[test]
+fn probes_every_ip_and_port_once_when_seeded() {
+    let (tx, _) = channel();
+
+    let scanner = ConnectScanner::builder()
+        .targets(IPTargets::new(vec!["10.0.0.1-10.0.0.5".to_string()]).unwrap())
+        .ports(PortTargets::new(vec!["22-24".to_string()]).unwrap())
+        .seed(Some(42))
+        .notifier(tx)
+        .build()
+        .unwrap();
+
+    let mut probed = Vec::new();
+
+    scanner
+        .for_each_target(|device, port| {
+            probed.push((device.ip, port));
+            Ok(())
+        })
+        .unwrap();
+
+    let mut sorted = probed.clone();
+    sorted.sort();
+    sorted.dedup();
+
+    assert_eq!(sorted.len(), 15);
+    assert_ne!(probed, sorted);
+}
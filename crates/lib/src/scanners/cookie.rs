@@ -0,0 +1,92 @@
+//! Provides keyed sequence numbers that let scanners validate replies
+//! without remembering the probes they sent
+//!
+//! Like masscan, the initial sequence number of each probe is a SipHash-1-3
+//! of the destination ip, destination port and source port keyed with a
+//! secret 128 bit key. A genuine SYN-ACK acknowledges that number plus one,
+//! which stray or spoofed packets can't predict without the key.
+
+use siphasher::sip::SipHasher13;
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    net::IpAddr,
+};
+
+use crate::targets::permutation::SplitMix64;
+
+/// Derives the sequence numbers of probes from a secret key
+///
+/// # Examples
+///
+/// ```
+/// # use std::net::{IpAddr, Ipv4Addr};
+/// # use r_lanlib::scanners::cookie::SequenceCookie;
+/// let cookie = SequenceCookie::new(42);
+/// let ip = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 2));
+/// let sequence = cookie.sequence(ip, 22, 54321);
+/// assert!(cookie.acknowledges_syn(ip, 22, 54321, sequence.wrapping_add(1)));
+/// assert!(!cookie.acknowledges_syn(ip, 23, 54321, sequence.wrapping_add(1)));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SequenceCookie {
+    k0: u64,
+    k1: u64,
+}
+
+impl SequenceCookie {
+    /// Returns a cookie whose SipHash keys are both derived from `key`
+    pub fn new(key: u64) -> Self {
+        let mut rng = SplitMix64(key);
+
+        Self {
+            k0: rng.next(),
+            k1: rng.next(),
+        }
+    }
+
+    /// Returns a cookie with random keys
+    pub fn random() -> Self {
+        Self {
+            k0: RandomState::new().build_hasher().finish(),
+            k1: RandomState::new().build_hasher().finish(),
+        }
+    }
+
+    /// Returns the sequence number of a probe sent from `source_port` to
+    /// `ip`:`port`
+    pub fn sequence(&self, ip: IpAddr, port: u16, source_port: u16) -> u32 {
+        let mut hasher = SipHasher13::new_with_keys(self.k0, self.k1);
+
+        match ip {
+            IpAddr::V4(ip) => hasher.write(&ip.octets()),
+            IpAddr::V6(ip) => hasher.write(&ip.octets()),
+        }
+
+        hasher.write_u16(port);
+        hasher.write_u16(source_port);
+        hasher.finish() as u32
+    }
+
+    /// Returns whether `acknowledgement` answers the SYN sent from
+    /// `source_port` to `ip`:`port`, which consumed one sequence number
+    pub fn acknowledges_syn(
+        &self,
+        ip: IpAddr,
+        port: u16,
+        source_port: u16,
+        acknowledgement: u32,
+    ) -> bool {
+        acknowledgement == self.sequence(ip, port, source_port).wrapping_add(1)
+    }
+}
+
+impl Default for SequenceCookie {
+    fn default() -> Self {
+        Self::random()
+    }
+}
+
+#[cfg(test)]
+#[path = "./cookie_tests.rs"]
+mod tests;
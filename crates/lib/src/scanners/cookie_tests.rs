@@ -0,0 +1,57 @@
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use super::*;
+
+#[test]
+fn derives_the_same_sequence_for_the_same_key() {
+    let ip = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 2));
+
+    assert_eq!(
+        SequenceCookie::new(7).sequence(ip, 80, 54321),
+        SequenceCookie::new(7).sequence(ip, 80, 54321)
+    );
+}
+
+#[test]
+fn derives_different_sequences_per_key_and_probe() {
+    let ip = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 2));
+    let cookie = SequenceCookie::new(7);
+    let sequence = cookie.sequence(ip, 80, 54321);
+
+    assert_ne!(SequenceCookie::new(8).sequence(ip, 80, 54321), sequence);
+    assert_ne!(cookie.sequence(ip, 81, 54321), sequence);
+    assert_ne!(cookie.sequence(ip, 80, 54322), sequence);
+    assert_ne!(
+        cookie.sequence(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 3)), 80, 54321),
+        sequence
+    );
+    assert_ne!(
+        cookie.sequence(IpAddr::V6(Ipv6Addr::LOCALHOST), 80, 54321),
+        sequence
+    );
+}
+
+#[test]
+fn only_accepts_the_acknowledgement_of_the_syn() {
+    let ip = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 2));
+    let cookie = SequenceCookie::new(7);
+    let sequence = cookie.sequence(ip, 80, 54321);
+
+    assert!(cookie.acknowledges_syn(ip, 80, 54321, sequence.wrapping_add(1)));
+    assert!(!cookie.acknowledges_syn(ip, 80, 54321, sequence));
+    assert!(!cookie.acknowledges_syn(ip, 80, 54321, 0));
+}
+
+#[test]
+fn random_cookies_use_different_keys() {
+    assert_ne!(SequenceCookie::random(), SequenceCookie::random());
+}
+
+#[test]
+fn derives_both_keys_from_the_seed() {
+    let cookie = SequenceCookie::new(7);
+
+    assert_eq!(cookie, SequenceCookie::new(7));
+    assert_ne!(cookie.k0, cookie.k1);
+    assert_ne!(cookie.k0, 7);
+}
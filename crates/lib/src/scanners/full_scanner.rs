@@ -3,7 +3,7 @@
 use derive_builder::Builder;
 use std::{
     sync::{Arc, mpsc},
-    thread::JoinHandle,
+    thread,
     time::Duration,
 };
 
@@ -11,18 +11,25 @@ use crate::{
     error::Result,
     network::NetworkInterface,
     oui::traits::Oui,
+    services::{self, traits::Services},
     targets::{ips::IPTargets, ports::PortTargets},
     wire::{DEFAULT_PACKET_SEND_TIMING, Wire},
 };
 
 use super::{
-    Device, ScanMessage, Scanner, arp_scanner::ARPScanner,
-    syn_scanner::SYNScanner,
+    DEFAULT_RETRY_DELAY, Device, ScanMessage, Scanner,
+    arp_scanner::ARPScanner,
+    cookie::SequenceCookie,
+    handle::{ScanControl, ScanHandle},
+    notifier::Notifier,
+    progress::DEFAULT_PROGRESS_INTERVAL,
+    rate::RateBounds,
+    syn_scanner::{SYNScanner, TcpScanType},
 };
 
 /// Data structure representing a Full scanner (ARP + SYN)
-#[derive(Builder)]
-#[builder(setter(into))]
+#[derive(Clone, Builder)]
+#[builder(setter(into), pattern = "owned")]
 pub struct FullScanner {
     /// Network interface to use for scanning
     interface: Arc<NetworkInterface>,
@@ -36,19 +43,57 @@ pub struct FullScanner {
     vendor: bool,
     /// Whether to include hostname lookups for discovered devices
     host: bool,
+    /// Whether hostname lookups fall back to NetBIOS and LLMNR
+    #[builder(default)]
+    netbios: bool,
     /// Duration to wait for responses after scanning completes
     idle_timeout: Duration,
     /// Throttles speed at which packets are sent. Higher throttles result
     /// in more accurate scans
     #[builder(default = DEFAULT_PACKET_SEND_TIMING)]
     throttle: Duration,
+    /// Enables adaptive rate control within the given bounds, replacing
+    /// the fixed throttle in both phases
+    #[builder(default)]
+    adaptive_rate: Option<RateBounds>,
+    /// Seeds a random probe order in both phases, interleaving hosts and
+    /// ports in the SYN phase. Probes are sent in list order when None
+    #[builder(default)]
+    seed: Option<u64>,
     /// Channel for sending scan results and status messages
-    notifier: mpsc::Sender<ScanMessage>,
+    notifier: Notifier,
+    /// Whether to send a ScanMessage::Info for every probe
+    #[builder(default)]
+    include_info: bool,
+    /// Interval between ScanMessage::Progress reports. Each phase reports
+    /// its own progress and statistics
+    #[builder(default = DEFAULT_PROGRESS_INTERVAL)]
+    progress_interval: Duration,
+    /// Whether the SYN phase also reports closed and filtered ports
+    #[builder(default)]
+    report_non_open: bool,
+    /// Flags probes are sent with in the port scanning phase
+    #[builder(default)]
+    scan_type: TcpScanType,
+    /// Derives the sequence numbers of probes that replies must answer.
+    /// Keyed randomly unless set
+    #[builder(default)]
+    cookie: SequenceCookie,
+    /// Number of times targets that have not answered are probed again, in
+    /// both phases
+    #[builder(default)]
+    retries: u8,
+    /// Time to wait for replies before unanswered targets are probed again
+    #[builder(default = DEFAULT_RETRY_DELAY)]
+    retry_delay: Duration,
     /// Source port for packet listener and incoming packet identification
     source_port: u16,
     /// Used to lookup vendor info for mac addresses
     #[builder(default)]
     oui: Option<Arc<dyn Oui>>,
+    /// Used to lookup the names of services on open ports
+    #[builder(default = "services::default()")]
+    services: Arc<dyn Services>,
 }
 
 impl FullScanner {
@@ -57,7 +102,10 @@ impl FullScanner {
         FullScannerBuilder::default()
     }
 
-    fn get_syn_targets_from_arp_scan(&self) -> Result<Vec<Device>> {
+    fn get_syn_targets_from_arp_scan(
+        &self,
+        control: &ScanControl,
+    ) -> Result<Vec<Device>> {
         let (tx, rx) = mpsc::channel::<ScanMessage>();
 
         let mut syn_targets: Vec<Device> = Vec::new();
@@ -69,8 +117,15 @@ impl FullScanner {
             .source_port(self.source_port)
             .include_vendor(self.vendor)
             .include_host_names(self.host)
+            .include_netbios_names(self.netbios)
             .idle_timeout(self.idle_timeout)
             .throttle(self.throttle)
+            .adaptive_rate(self.adaptive_rate)
+            .seed(self.seed)
+            .include_info(self.include_info)
+            .progress_interval(self.progress_interval)
+            .retries(self.retries)
+            .retry_delay(self.retry_delay)
             .notifier(tx.clone());
 
         if let Some(oui) = self.oui.as_ref() {
@@ -79,7 +134,7 @@ impl FullScanner {
 
         let arp = arp_builder.build()?;
 
-        arp.scan()?;
+        let arp_handle = arp.start(control.clone())?;
 
         loop {
             if let Ok(msg) = rx.recv() {
@@ -91,32 +146,63 @@ impl FullScanner {
                     ScanMessage::ARPScanDevice(device) => {
                         syn_targets.push(device.to_owned());
                     }
+                    msg @ (ScanMessage::Info(_)
+                    | ScanMessage::Progress { .. }
+                    | ScanMessage::Stats(_)) => {
+                        self.notifier.send(msg)?;
+                    }
                     _ => {}
                 }
             }
         }
 
+        arp_handle.join()??;
+
         Ok(syn_targets)
     }
 }
 
 // Implements the Scanner trait for FullScanner
 impl Scanner for FullScanner {
-    fn scan(&self) -> Result<JoinHandle<Result<()>>> {
-        let syn_targets = self.get_syn_targets_from_arp_scan()?;
-
-        let syn = SYNScanner::builder()
-            .interface(Arc::clone(&self.interface))
-            .wire(self.wire.clone())
-            .targets(syn_targets)
-            .ports(Arc::clone(&self.ports))
-            .source_port(self.source_port)
-            .idle_timeout(self.idle_timeout)
-            .throttle(self.throttle)
-            .notifier(self.notifier.clone())
-            .build()?;
+    fn scan(&self) -> Result<ScanHandle> {
+        let control = ScanControl::new();
+        let scan_control = control.clone();
+        let self_clone = self.clone();
+
+        // both phases share the control so the handle covers the whole scan
+        let handle = thread::spawn(move || -> Result<()> {
+            let syn_targets =
+                self_clone.get_syn_targets_from_arp_scan(&scan_control)?;
+
+            if scan_control.is_cancelled() {
+                return self_clone.notifier.send(ScanMessage::Done);
+            }
 
-        syn.scan()
+            let syn = SYNScanner::builder()
+                .interface(Arc::clone(&self_clone.interface))
+                .wire(self_clone.wire.clone())
+                .targets(syn_targets)
+                .ports(Arc::clone(&self_clone.ports))
+                .source_port(self_clone.source_port)
+                .idle_timeout(self_clone.idle_timeout)
+                .throttle(self_clone.throttle)
+                .adaptive_rate(self_clone.adaptive_rate)
+                .seed(self_clone.seed)
+                .include_info(self_clone.include_info)
+                .progress_interval(self_clone.progress_interval)
+                .retries(self_clone.retries)
+                .retry_delay(self_clone.retry_delay)
+                .report_non_open(self_clone.report_non_open)
+                .scan_type(self_clone.scan_type)
+                .cookie(self_clone.cookie)
+                .notifier(self_clone.notifier.clone())
+                .services(Arc::clone(&self_clone.services))
+                .build()?;
+
+            syn.start(scan_control)?.join()?
+        });
+
+        Ok(ScanHandle::new(handle, control))
     }
 }
 
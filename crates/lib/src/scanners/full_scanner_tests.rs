@@ -91,6 +91,9 @@ fn sends_and_reads_packets() {
         interface.mac,
         interface.ipv4,
         54321,
+        SequenceCookie::new(42)
+            .sequence(net::IpAddr::V4(device_ip), device_port, 54321)
+            .wrapping_add(1),
         #[allow(static_mut_refs)]
         unsafe {
             &mut SYN_PACKET
@@ -148,6 +151,7 @@ fn sends_and_reads_packets() {
         .idle_timeout(idle_timeout)
         .notifier(tx)
         .source_port(54321_u16)
+        .cookie(SequenceCookie::new(42))
         .oui(arc_oui)
         .build()
         .unwrap();
@@ -159,6 +163,7 @@ fn sends_and_reads_packets() {
     let expected_open_port = Port {
         id: device_port,
         service: "".to_string(),
+        ..Port::default()
     };
 
     loop {
@@ -0,0 +1,182 @@
+//! Provides handles for controlling running scans
+//!
+//! Every call to [`Scanner::scan`](super::Scanner::scan) returns a
+//! [`ScanHandle`] sharing a [`ScanControl`] with the scan's threads. The send
+//! loop, packet reader and heartbeat check it so cancelling or pausing takes
+//! effect promptly rather than at the end of the scan.
+
+use std::{
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crate::error::{RLanLibError, Result};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum ScanState {
+    #[default]
+    Running,
+    Paused,
+    Cancelled,
+}
+
+/// Shared run state of a scan
+#[derive(Debug, Default, Clone)]
+pub struct ScanControl(Arc<(Mutex<ScanState>, Condvar)>);
+
+impl ScanControl {
+    /// Returns a control for a new running scan
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stops the scan. A cancelled scan skips its remaining targets and idle
+    /// timeout, reports [`ScanMessage::Done`](super::ScanMessage::Done) and
+    /// finishes successfully
+    pub fn cancel(&self) {
+        self.set(ScanState::Cancelled);
+    }
+
+    /// Stops sending probes until resumed. Replies to probes already sent
+    /// are still processed
+    pub fn pause(&self) {
+        let mut state = self.state();
+        if *state == ScanState::Running {
+            *state = ScanState::Paused;
+        }
+    }
+
+    /// Continues a paused scan
+    pub fn resume(&self) {
+        let mut state = self.state();
+        if *state == ScanState::Paused {
+            *state = ScanState::Running;
+            self.0.1.notify_all();
+        }
+    }
+
+    /// Returns true once the scan has been cancelled
+    pub fn is_cancelled(&self) -> bool {
+        *self.state() == ScanState::Cancelled
+    }
+
+    /// Returns true while the scan is paused
+    pub fn is_paused(&self) -> bool {
+        *self.state() == ScanState::Paused
+    }
+
+    /// Blocks while the scan is paused. Returns [`RLanLibError::Cancelled`]
+    /// once the scan is cancelled so send loops can bail out with `?`
+    pub fn checkpoint(&self) -> Result<()> {
+        let state = self
+            .0
+            .1
+            .wait_while(self.state(), |s| *s == ScanState::Paused)
+            .unwrap_or_else(|e| e.into_inner());
+
+        if *state == ScanState::Cancelled {
+            return Err(RLanLibError::Cancelled);
+        }
+
+        Ok(())
+    }
+
+    /// Sleeps for the given duration, not counting time spent paused.
+    /// Returns early if the scan is cancelled
+    pub fn sleep(&self, duration: Duration) {
+        let mut remaining = duration;
+        let mut state = self.state();
+
+        loop {
+            match *state {
+                ScanState::Cancelled => return,
+                ScanState::Paused => {
+                    state =
+                        self.0.1.wait(state).unwrap_or_else(|e| e.into_inner());
+                }
+                ScanState::Running => {
+                    if remaining.is_zero() {
+                        return;
+                    }
+
+                    let started = Instant::now();
+
+                    state = self
+                        .0
+                        .1
+                        .wait_timeout(state, remaining)
+                        .unwrap_or_else(|e| e.into_inner())
+                        .0;
+
+                    remaining = remaining.saturating_sub(started.elapsed());
+                }
+            }
+        }
+    }
+
+    fn set(&self, new: ScanState) {
+        *self.state() = new;
+        self.0.1.notify_all();
+    }
+
+    fn state(&self) -> MutexGuard<'_, ScanState> {
+        // the state is a plain enum so a poisoned lock is still usable
+        self.0.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+// Returns the error that ended a send loop, if it wasn't cancelled
+pub(crate) fn loop_error(result: Result<()>) -> Option<RLanLibError> {
+    result
+        .err()
+        .filter(|e| !matches!(e, RLanLibError::Cancelled))
+}
+
+/// Handle to a running scan
+#[derive(Debug)]
+pub struct ScanHandle {
+    handle: JoinHandle<Result<()>>,
+    control: ScanControl,
+}
+
+impl ScanHandle {
+    /// Wraps the scan's thread and the control it checks
+    pub fn new(handle: JoinHandle<Result<()>>, control: ScanControl) -> Self {
+        Self { handle, control }
+    }
+
+    /// Stops the scan, see [`ScanControl::cancel`]
+    pub fn cancel(&self) {
+        self.control.cancel();
+    }
+
+    /// Pauses the scan, see [`ScanControl::pause`]
+    pub fn pause(&self) {
+        self.control.pause();
+    }
+
+    /// Resumes a paused scan
+    pub fn resume(&self) {
+        self.control.resume();
+    }
+
+    /// Returns the control shared with the scan's threads
+    pub fn control(&self) -> &ScanControl {
+        &self.control
+    }
+
+    /// Returns true once the scan's thread has finished
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// Waits for the scan to finish, see [`JoinHandle::join`]
+    pub fn join(self) -> thread::Result<Result<()>> {
+        self.handle.join()
+    }
+}
+
+#[cfg(test)]
+#[path = "./handle_tests.rs"]
+mod tests;
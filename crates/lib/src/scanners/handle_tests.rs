@@ -0,0 +1,96 @@
+use super::*;
+
+#[test]
+fn checkpoint_blocks_while_paused() {
+    let control = ScanControl::new();
+    control.pause();
+
+    let waiting = control.clone();
+    let handle = thread::spawn(move || waiting.checkpoint());
+
+    thread::sleep(Duration::from_millis(50));
+    assert!(!handle.is_finished());
+
+    control.resume();
+
+    assert!(handle.join().unwrap().is_ok());
+}
+
+#[test]
+fn checkpoint_fails_once_cancelled() {
+    let control = ScanControl::new();
+    control.pause();
+
+    let waiting = control.clone();
+    let handle = thread::spawn(move || waiting.checkpoint());
+
+    control.cancel();
+
+    assert!(matches!(
+        handle.join().unwrap(),
+        Err(RLanLibError::Cancelled)
+    ));
+    assert!(control.is_cancelled());
+
+    // a cancelled scan can't be resumed
+    control.resume();
+    assert!(control.is_cancelled());
+}
+
+#[test]
+fn sleep_returns_early_when_cancelled() {
+    let control = ScanControl::new();
+
+    let sleeping = control.clone();
+    let handle = thread::spawn(move || {
+        let started = Instant::now();
+        sleeping.sleep(Duration::from_secs(10));
+        started.elapsed()
+    });
+
+    thread::sleep(Duration::from_millis(50));
+    control.cancel();
+
+    assert!(handle.join().unwrap() < Duration::from_secs(1));
+}
+
+#[test]
+fn sleep_does_not_count_paused_time() {
+    let control = ScanControl::new();
+    control.pause();
+
+    let sleeping = control.clone();
+    let handle = thread::spawn(move || {
+        let started = Instant::now();
+        sleeping.sleep(Duration::from_millis(50));
+        started.elapsed()
+    });
+
+    thread::sleep(Duration::from_millis(100));
+    control.resume();
+
+    assert!(handle.join().unwrap() >= Duration::from_millis(150));
+}
+
+#[test]
+fn handle_controls_scan_thread() {
+    let control = ScanControl::new();
+    let scan_control = control.clone();
+
+    let handle = ScanHandle::new(
+        thread::spawn(move || {
+            while scan_control.checkpoint().is_ok() {
+                thread::sleep(Duration::from_millis(1));
+            }
+            Ok(())
+        }),
+        control,
+    );
+
+    handle.pause();
+    assert!(handle.control().is_paused());
+    handle.resume();
+    handle.cancel();
+
+    assert!(handle.join().unwrap().is_ok());
+}
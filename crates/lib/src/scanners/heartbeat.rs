@@ -7,13 +7,18 @@ use derive_builder::Builder;
 use pnet::util::MacAddr;
 use std::{
     net::Ipv4Addr,
-    sync::{Arc, Mutex, mpsc},
+    sync::{
+        Arc, Mutex,
+        mpsc::{self, TryRecvError},
+    },
     thread::{self, JoinHandle},
     time::Duration,
 };
 
 use crate::{
-    error::Result, packet::heartbeat_packet::HeartbeatPacketBuilder,
+    error::Result,
+    packet::heartbeat_packet::HeartbeatPacketBuilder,
+    scanners::{handle::ScanControl, rate::RateController},
     wire::Sender,
 };
 
@@ -29,6 +34,14 @@ pub struct HeartBeat {
     source_port: u16,
     /// Packet sender for transmitting heartbeat packets
     packet_sender: Arc<Mutex<dyn Sender>>,
+    /// Control of the scan being kept alive. Beats stop while it is paused
+    /// and a final beat wakes the reader once it is cancelled
+    #[builder(default)]
+    control: ScanControl,
+    /// Rate controller of the scan, told when each beat is sent so it can
+    /// time the beat's round trip through the wire
+    #[builder(default)]
+    rate: RateController,
 }
 
 impl HeartBeat {
@@ -48,6 +61,7 @@ impl HeartBeat {
         let packet = heartbeat_packet.to_raw();
 
         let mut sender = self.packet_sender.lock()?;
+        self.rate.heartbeat_sent();
         sender.send(&packet)?;
         Ok(())
     }
@@ -72,6 +86,8 @@ impl HeartBeat {
             .source_port(source_port)
             .build()?;
         let packet = heartbeat_packet.to_raw();
+        let control = self.control.clone();
+        let rate = self.rate.clone();
 
         Ok(thread::spawn(move || -> Result<()> {
             log::debug!("starting heartbeat thread");
@@ -86,7 +102,8 @@ impl HeartBeat {
                 {
                     return Err(err);
                 }
-                if done.try_recv().is_ok() {
+                // also stop if the scan thread went away without signalling
+                if !matches!(done.try_recv(), Err(TryRecvError::Empty)) {
                     log::debug!("stopping heartbeat");
                     return Ok(());
                 }
@@ -94,6 +111,7 @@ impl HeartBeat {
                 {
                     // scoped to drop lock before end of loop
                     let mut sender = packet_sender.lock()?;
+                    rate.heartbeat_sent();
                     if let Err(err) = sender.send(&packet) {
                         misses += 1;
                         send_err = Some(err);
@@ -102,7 +120,11 @@ impl HeartBeat {
                         send_err = None;
                     }
                 }
-                thread::sleep(interval);
+                if control.is_cancelled() {
+                    log::debug!("scan cancelled: stopping heartbeat");
+                    return Ok(());
+                }
+                control.sleep(interval);
             }
         }))
     }
@@ -103,3 +103,32 @@ fn sends_heartbeat_packets_in_thread() {
 
     handle.join().unwrap().unwrap();
 }
+
+#[test]
+fn stops_promptly_when_scan_cancelled() {
+    let mut packet_sender = MockPacketSender::new();
+    packet_sender.expect_send().returning(|_| Ok(()));
+
+    let control = ScanControl::new();
+
+    let heart_beat = HeartBeat::builder()
+        .source_mac(MacAddr::default())
+        .source_ipv4(Ipv4Addr::from_str("192.168.1.1").unwrap())
+        .source_port(54321)
+        .packet_sender(Arc::new(Mutex::new(packet_sender)))
+        .control(control.clone())
+        .build()
+        .unwrap();
+
+    // keep the done channel open so only the cancel can stop it
+    let (_stop_tx, stop_rx) = mpsc::channel();
+
+    let handle = heart_beat.start_in_thread(stop_rx).unwrap();
+
+    thread::sleep(Duration::from_millis(100));
+    control.cancel();
+    thread::sleep(Duration::from_millis(200));
+
+    assert!(handle.is_finished());
+    handle.join().unwrap().unwrap();
+}
@@ -0,0 +1,357 @@
+//! Provides Scanner implementation for ICMP echo (ping) sweeps
+//!
+//! Unlike ARP, ICMP echo requests can be routed, so this scanner is able to
+//! discover hosts on subnets that sit on the other side of the default
+//! gateway. Every request is addressed at layer 2 to the gateway's MAC and
+//! the router takes care of delivering it.
+
+use derive_builder::Builder;
+use pnet::{
+    packet::{Packet, ethernet, icmp, ip, ipv4},
+    util::MacAddr,
+};
+use std::{
+    collections::HashMap,
+    net::Ipv4Addr,
+    sync::{self, Arc, Mutex, mpsc::TryRecvError},
+    thread::{self, JoinHandle},
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    error::{RLanLibError, Result},
+    network::NetworkInterface,
+    packet::{
+        heartbeat_packet::is_heartbeat, icmp_packet::IcmpEchoPacketBuilder,
+    },
+    scanners::{
+        Device, PortSet, Scanning,
+        handle::{ScanControl, ScanHandle, loop_error},
+        progress::{DEFAULT_PROGRESS_INTERVAL, Progress},
+        rate::{RateBounds, RateController},
+    },
+    targets::ips::IPTargets,
+    wire::{DEFAULT_PACKET_SEND_TIMING, PacketMetadata, Wire},
+};
+
+use super::{ScanMessage, Scanner, heartbeat::HeartBeat, notifier::Notifier};
+
+/// Data structure representing an ICMP echo scanner
+#[derive(Clone, Builder)]
+#[builder(setter(into), pattern = "owned")]
+pub struct ICMPScanner {
+    /// Network interface to use for scanning
+    interface: Arc<NetworkInterface>,
+    /// Wire for reading and sending packets on the wire
+    wire: Wire,
+    /// IP targets to scan
+    targets: Arc<IPTargets>,
+    /// MAC address of the gateway through which echo requests are routed
+    gateway_mac: MacAddr,
+    /// Source port for the heartbeat listener. Also used as the ICMP
+    /// identifier so replies can be matched to this scanner
+    source_port: u16,
+    /// Duration to wait for responses after scanning completes
+    idle_timeout: Duration,
+    /// Channel for sending scan results and status messages
+    notifier: Notifier,
+    /// Throttles speed at which packets are sent. Higher throttles result
+    /// in more accurate scans
+    #[builder(default = DEFAULT_PACKET_SEND_TIMING)]
+    throttle: Duration,
+    /// Enables adaptive rate control within the given bounds, replacing
+    /// the fixed throttle
+    #[builder(default)]
+    adaptive_rate: Option<RateBounds>,
+    /// Seeds a random order in which targets are probed. Targets are
+    /// probed in list order when None
+    #[builder(default)]
+    seed: Option<u64>,
+    /// Whether to send a ScanMessage::Info for every target
+    #[builder(default)]
+    include_info: bool,
+    /// Interval between ScanMessage::Progress reports
+    #[builder(default = DEFAULT_PROGRESS_INTERVAL)]
+    progress_interval: Duration,
+    /// Default gateway IP, used to mark the gateway device in scan results
+    #[builder(default)]
+    gateway: Option<Ipv4Addr>,
+    /// Tracks the sequence number and SystemTime at which each echo request
+    /// was sent, keyed by target IP. Used to match replies and compute RTT
+    #[builder(setter(skip), default = "Arc::new(Mutex::new(HashMap::new()))")]
+    send_times: Arc<Mutex<HashMap<Ipv4Addr, (u16, SystemTime)>>>,
+    /// Paces probes and collects the statistics reported when done
+    #[builder(setter(skip))]
+    rate: RateController,
+}
+
+impl ICMPScanner {
+    /// Returns builder for ICMPScanner
+    pub fn builder() -> ICMPScannerBuilder {
+        ICMPScannerBuilder::default()
+    }
+
+    fn process_target(&self, target: Ipv4Addr, sequence: u16) -> Result<()> {
+        // throttle packet sending to prevent packet loss
+        thread::sleep(self.rate.delay());
+
+        log::debug!("scanning ICMP target: {}", target);
+
+        // Our own address would only be reflected back by the gateway, so
+        // synthesize the device entry immediately like the ARP scanner does
+        if target == self.interface.ipv4 {
+            self.notifier.send(ScanMessage::ICMPScanDevice(Device {
+                ip: self.interface.ipv4,
+                mac: self.interface.mac,
+                is_current_host: true,
+                is_gateway: self
+                    .gateway
+                    .is_some_and(|gw| gw == self.interface.ipv4),
+                latency_ms: Some(0),
+                ..Device::default()
+            }))?;
+            return Ok(());
+        }
+
+        let echo_packet = IcmpEchoPacketBuilder::default()
+            .source_ip(self.interface.ipv4)
+            .source_mac(self.interface.mac)
+            .dest_ip(target)
+            .dest_mac(self.gateway_mac)
+            .identifier(self.source_port)
+            .sequence(sequence)
+            .build()?;
+
+        let pkt_buf = echo_packet.to_raw();
+
+        // inform consumer we are scanning this target
+        if self.include_info {
+            self.notifier.send(ScanMessage::Info(Scanning {
+                ip: target.into(),
+                port: None,
+            }))?;
+        }
+
+        // record before sending so a fast reply can never beat the entry
+        if let Ok(mut times) = self.send_times.lock() {
+            times.insert(target, (sequence, SystemTime::now()));
+        }
+
+        let mut pkt_sender = self.wire.0.lock()?;
+        pkt_sender.send(&pkt_buf).map_err(|e| RLanLibError::Scan {
+            ip: Some(target.to_string()),
+            port: None,
+            error: e.to_string(),
+        })?;
+
+        self.rate.sent();
+
+        Ok(())
+    }
+
+    // Visits targets in list order, or in a random order when seeded
+    fn for_each_target<F: FnMut(Ipv4Addr) -> Result<()>>(
+        &self,
+        cb: F,
+    ) -> Result<()> {
+        match self.seed {
+            Some(seed) => self.targets.lazy_loop_shuffled(seed, cb),
+            None => self.targets.lazy_loop(cb),
+        }
+    }
+
+    fn send_probes(&self, control: &ScanControl) -> Result<()> {
+        let mut progress = Progress::start(
+            self.notifier.clone(),
+            self.targets.len(),
+            self.progress_interval,
+        )?;
+        let mut sequence: u16 = 0;
+
+        self.for_each_target(|t| {
+            control.checkpoint()?;
+            sequence = sequence.wrapping_add(1);
+            self.process_target(t, sequence)?;
+            progress.sent()
+        })?;
+
+        progress.finish()
+    }
+
+    fn process_incoming_packet(
+        &self,
+        pkt: &[u8],
+        metadata: PacketMetadata,
+    ) -> Result<()> {
+        let Some(eth) = ethernet::EthernetPacket::new(pkt) else {
+            return Ok(());
+        };
+
+        let Some(header) = ipv4::Ipv4Packet::new(eth.payload()) else {
+            return Ok(());
+        };
+
+        if header.get_next_level_protocol() != ip::IpNextHeaderProtocols::Icmp {
+            return Ok(());
+        }
+
+        let Some(reply) =
+            icmp::echo_reply::EchoReplyPacket::new(header.payload())
+        else {
+            return Ok(());
+        };
+
+        if reply.get_icmp_type() != icmp::IcmpTypes::EchoReply
+            || reply.get_identifier() != self.source_port
+        {
+            return Ok(());
+        }
+
+        let ip4 = header.get_source();
+        let sequence = reply.get_sequence_number();
+
+        // Only accept a reply whose sequence matches the request we sent to
+        // that host. Removing the entry also de-duplicates repeated replies
+        let send_time = self.send_times.lock().ok().and_then(|mut times| {
+            match times.get(&ip4) {
+                Some((seq, _)) if *seq == sequence => {
+                    times.remove(&ip4).map(|(_, t)| t)
+                }
+                _ => None,
+            }
+        });
+
+        let Some(sent) = send_time else {
+            return Ok(());
+        };
+
+        let recv = metadata.timestamp.unwrap_or_else(SystemTime::now);
+        let latency_ms = recv.duration_since(sent).map(|d| d.as_millis()).ok();
+
+        // For routed hosts the layer 2 source is the router that forwarded
+        // the reply, which is also the next hop to use when scanning them
+        self.notifier.send(ScanMessage::ICMPScanDevice(Device {
+            hostname: String::new(),
+            ip: ip4,
+            ipv6: Vec::new(),
+            mac: eth.get_source(),
+            vendor: String::new(),
+            is_current_host: ip4 == self.interface.ipv4,
+            is_gateway: self.gateway.is_some_and(|gw| gw == ip4),
+            open_ports: PortSet::new(),
+            latency_ms,
+            response_ttl: Some(header.get_ttl()),
+            services: Vec::new(),
+            upnp: None,
+            os_guess: None,
+        }))?;
+
+        Ok(())
+    }
+
+    // Implements packet reading in a separate thread so we can send and
+    // receive packets simultaneously
+    fn read_packets(
+        &self,
+        done: sync::mpsc::Receiver<()>,
+        control: ScanControl,
+    ) -> Result<JoinHandle<Result<()>>> {
+        let (heartbeat_tx, heartbeat_rx) = sync::mpsc::channel::<()>();
+
+        let heartbeat = HeartBeat::builder()
+            .source_mac(self.interface.mac)
+            .source_ipv4(self.interface.ipv4)
+            .source_port(self.source_port)
+            .packet_sender(Arc::clone(&self.wire.0))
+            .control(control.clone())
+            .rate(self.rate.clone())
+            .build()?;
+
+        heartbeat.start_in_thread(heartbeat_rx)?;
+
+        let self_clone = self.clone();
+
+        Ok(thread::spawn(move || -> Result<()> {
+            let mut reader = self_clone.wire.1.lock()?;
+
+            loop {
+                // also stop if the scan thread went away without signalling
+                if control.is_cancelled()
+                    || !matches!(done.try_recv(), Err(TryRecvError::Empty))
+                {
+                    log::debug!("exiting icmp packet reader");
+                    if let Err(e) = heartbeat_tx.send(()) {
+                        log::error!("failed to stop heartbeat: {}", e);
+                    }
+                    break;
+                }
+
+                let (pkt, metadata) = reader.next_packet_with_metadata()?;
+
+                if is_heartbeat(
+                    pkt,
+                    self_clone.interface.mac,
+                    self_clone.source_port,
+                ) {
+                    self_clone.rate.heartbeat_seen();
+                    continue;
+                }
+
+                self_clone.process_incoming_packet(pkt, metadata)?;
+            }
+
+            Ok(())
+        }))
+    }
+}
+
+// Implements the Scanner trait for ICMPScanner
+impl Scanner for ICMPScanner {
+    fn scan(&self) -> Result<ScanHandle> {
+        let control = ScanControl::new();
+        log::debug!("performing ICMP scan on targets: {:?}", self.targets);
+        log::debug!("routing echo requests via: {}", self.gateway_mac);
+        log::debug!("starting icmp packet reader");
+
+        // like ARP, sweeps don't know which targets are up
+        self.rate.reset(self.throttle, self.adaptive_rate, false);
+        self.rate.watch_drops(&self.interface.name);
+
+        let self_clone = self.clone();
+        let (done_tx, done_rx) = sync::mpsc::channel::<()>();
+
+        let read_handle = self.read_packets(done_rx, control.clone())?;
+        let scan_control = control.clone();
+
+        // prevent blocking thread so messages can be freely sent to consumer
+        let scan_handle = thread::spawn(move || -> Result<()> {
+            let scan_error = loop_error(self_clone.send_probes(&scan_control));
+
+            scan_control.sleep(self_clone.idle_timeout);
+
+            self_clone
+                .notifier
+                .send(ScanMessage::Stats(self_clone.rate.stats()))?;
+
+            self_clone.notifier.send(ScanMessage::Done)?;
+
+            // ignore errors here as the thread may already be dead due to error
+            // we'll catch any errors from that thread below and report
+            let _ = done_tx.send(());
+
+            let read_result = read_handle.join()?;
+
+            if let Some(err) = scan_error {
+                return Err(err);
+            }
+
+            read_result
+        });
+
+        Ok(ScanHandle::new(scan_handle, control))
+    }
+}
+
+#[cfg(test)]
+#[path = "./icmp_scanner_tests.rs"]
+mod tests;
@@ -0,0 +1,202 @@
+use super::*;
+use pnet::packet::{ethernet, icmp::echo_request, ipv4};
+use std::str::FromStr;
+use std::sync::mpsc::channel;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::{
+    network,
+    packet::icmp_packet::create_icmp_echo_reply,
+    wire::{
+        PacketMetadata, Reader, Sender,
+        mocks::{MockPacketReader, MockPacketSender},
+    },
+};
+
+const PKT_ETH_SIZE: usize = ethernet::EthernetPacket::minimum_packet_size();
+const PKT_IP4_SIZE: usize = ipv4::Ipv4Packet::minimum_packet_size();
+const PKT_ICMP_SIZE: usize =
+    echo_request::EchoRequestPacket::minimum_packet_size();
+const PKT_TOTAL_ICMP_SIZE: usize =
+    PKT_ETH_SIZE + PKT_IP4_SIZE + PKT_ICMP_SIZE + 16;
+
+#[test]
+fn new() {
+    let interface = Arc::new(network::get_default_interface().unwrap());
+    let sender: Arc<Mutex<dyn Sender>> =
+        Arc::new(Mutex::new(MockPacketSender::new()));
+    let receiver: Arc<Mutex<dyn Reader>> =
+        Arc::new(Mutex::new(MockPacketReader::new()));
+    let wire = Wire(sender, receiver);
+    let idle_timeout = Duration::from_secs(2);
+    let gateway_mac = MacAddr::from_str("00:00:00:00:00:01").unwrap();
+    let targets = IPTargets::new(vec!["10.0.5.0/24".to_string()]).unwrap();
+    let (tx, _) = channel();
+
+    let scanner = ICMPScanner::builder()
+        .interface(interface)
+        .wire(wire)
+        .targets(targets)
+        .gateway_mac(gateway_mac)
+        .source_port(54321_u16)
+        .idle_timeout(idle_timeout)
+        .notifier(tx)
+        .build()
+        .unwrap();
+
+    assert_eq!(scanner.gateway_mac, gateway_mac);
+    assert_eq!(scanner.idle_timeout, idle_timeout);
+    assert_eq!(scanner.source_port, 54321);
+}
+
+#[test]
+#[allow(static_mut_refs)]
+fn sends_and_reads_packets() {
+    static mut PACKET: [u8; PKT_TOTAL_ICMP_SIZE] = [0u8; PKT_TOTAL_ICMP_SIZE];
+    let interface = Arc::new(network::get_default_interface().unwrap());
+    let device_ip = Ipv4Addr::from_str("10.0.5.2").unwrap();
+    let gateway_mac = MacAddr::from_str("00:00:00:00:00:01").unwrap();
+
+    create_icmp_echo_reply(
+        gateway_mac,
+        device_ip,
+        interface.mac,
+        interface.ipv4,
+        54321,
+        1,
+        #[allow(static_mut_refs)]
+        unsafe {
+            &mut PACKET
+        },
+    );
+
+    let mut receiver = MockPacketReader::new();
+    let mut sender = MockPacketSender::new();
+
+    #[allow(static_mut_refs)]
+    receiver.expect_next_packet_with_metadata().returning(|| {
+        Ok((unsafe { &PACKET }, PacketMetadata { timestamp: None }))
+    });
+
+    sender.expect_send().returning(|_| Ok(()));
+
+    let targets = IPTargets::new(vec![device_ip.to_string()]).unwrap();
+    let (tx, rx) = channel();
+
+    let sender: Arc<Mutex<dyn Sender>> = Arc::new(Mutex::new(sender));
+    let receiver: Arc<Mutex<dyn Reader>> = Arc::new(Mutex::new(receiver));
+    let wire = Wire(sender, receiver);
+
+    let scanner = ICMPScanner::builder()
+        .interface(interface)
+        .wire(wire)
+        .targets(targets)
+        .gateway_mac(gateway_mac)
+        .source_port(54321_u16)
+        .idle_timeout(Duration::from_secs(2))
+        .notifier(tx)
+        .build()
+        .unwrap();
+
+    let handle = scanner.scan().unwrap();
+
+    let mut detected: Vec<Device> = Vec::new();
+
+    loop {
+        if let Ok(msg) = rx.recv() {
+            match msg {
+                ScanMessage::Done => {
+                    break;
+                }
+                ScanMessage::ICMPScanDevice(device) => {
+                    detected.push(device);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let result = handle.join().unwrap();
+    assert!(result.is_ok());
+    // repeated replies are only reported once
+    assert_eq!(detected.len(), 1);
+    assert_eq!(detected[0].ip, device_ip);
+    assert_eq!(detected[0].mac, gateway_mac);
+    assert_eq!(detected[0].response_ttl, Some(63));
+    assert!(detected[0].latency_ms.is_some());
+}
+
+#[test]
+#[allow(static_mut_refs)]
+fn ignores_replies_with_mismatched_identifier_or_sequence() {
+    static mut WRONG_ID: [u8; PKT_TOTAL_ICMP_SIZE] = [0u8; PKT_TOTAL_ICMP_SIZE];
+    static mut WRONG_SEQ: [u8; PKT_TOTAL_ICMP_SIZE] =
+        [0u8; PKT_TOTAL_ICMP_SIZE];
+    let interface = Arc::new(network::get_default_interface().unwrap());
+    let device_ip = Ipv4Addr::from_str("10.0.5.2").unwrap();
+    let gateway_mac = MacAddr::from_str("00:00:00:00:00:01").unwrap();
+
+    create_icmp_echo_reply(
+        gateway_mac,
+        device_ip,
+        interface.mac,
+        interface.ipv4,
+        12345,
+        1,
+        #[allow(static_mut_refs)]
+        unsafe {
+            &mut WRONG_ID
+        },
+    );
+
+    create_icmp_echo_reply(
+        gateway_mac,
+        device_ip,
+        interface.mac,
+        interface.ipv4,
+        54321,
+        99,
+        #[allow(static_mut_refs)]
+        unsafe {
+            &mut WRONG_SEQ
+        },
+    );
+
+    let (tx, rx) = channel();
+
+    let sender: Arc<Mutex<dyn Sender>> =
+        Arc::new(Mutex::new(MockPacketSender::new()));
+    let receiver: Arc<Mutex<dyn Reader>> =
+        Arc::new(Mutex::new(MockPacketReader::new()));
+    let wire = Wire(sender, receiver);
+
+    let scanner = ICMPScanner::builder()
+        .interface(interface)
+        .wire(wire)
+        .targets(IPTargets::new(vec![device_ip.to_string()]).unwrap())
+        .gateway_mac(gateway_mac)
+        .source_port(54321_u16)
+        .idle_timeout(Duration::from_secs(2))
+        .notifier(tx)
+        .build()
+        .unwrap();
+
+    scanner
+        .send_times
+        .lock()
+        .unwrap()
+        .insert(device_ip, (1, SystemTime::now()));
+
+    let metadata = || PacketMetadata { timestamp: None };
+
+    scanner
+        .process_incoming_packet(unsafe { &WRONG_ID }, metadata())
+        .unwrap();
+    scanner
+        .process_incoming_packet(unsafe { &WRONG_SEQ }, metadata())
+        .unwrap();
+
+    assert!(rx.try_recv().is_err());
+    assert!(scanner.send_times.lock().unwrap().contains_key(&device_ip));
+}
@@ -0,0 +1,339 @@
+//! Provides Scanner implementation for mDNS / DNS-SD service discovery
+//!
+//! Browses `_services._dns-sd._udp.local` for advertised service types, then
+//! resolves the PTR, SRV, TXT and A records of every instance to attach
+//! `.local` hostnames and advertised services to devices. Queries are sent as
+//! legacy unicast queries from an ephemeral port using an ordinary OS socket
+//! so responders answer directly and no elevated privileges are required.
+
+use derive_builder::Builder;
+use ipnet::Ipv4Net;
+use std::{
+    collections::{HashMap, HashSet},
+    io,
+    net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket},
+    str::FromStr,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    dns::{self, RecordData, TYPE_A, TYPE_PTR, TYPE_SRV, TYPE_TXT},
+    error::{RLanLibError, Result},
+    network::NetworkInterface,
+    scanners::{
+        AdvertisedService, Device,
+        handle::{ScanControl, ScanHandle},
+    },
+};
+
+use super::{ScanMessage, Scanner, notifier::Notifier};
+
+/// Default destination for mDNS queries
+pub const MDNS_ADDR: SocketAddr =
+    SocketAddr::new(IpAddr::V4(Ipv4Addr::new(224, 0, 0, 251)), 5353);
+
+/// Default time to wait for responses after the last query or answer
+pub const DEFAULT_MDNS_IDLE_TIMEOUT: Duration = Duration::from_millis(2000);
+
+/// Query used to enumerate every advertised service type
+pub const SERVICES_QUERY: &str = "_services._dns-sd._udp.local";
+
+// mDNS requires multicast queries to be sent with a TTL of 255
+const MULTICAST_TTL: u32 = 255;
+
+// how often the receive loop wakes to send follow up queries
+const READ_TIMEOUT: Duration = Duration::from_millis(100);
+
+// keeps queries well under the minimum mDNS message size
+const MAX_QUESTIONS_PER_QUERY: usize = 16;
+
+/// Data structure representing an mDNS / DNS-SD scanner
+#[derive(Clone, Builder)]
+#[builder(setter(into), pattern = "owned")]
+pub struct MDNSScanner {
+    /// Network interface whose subnet devices are reported for
+    interface: Arc<NetworkInterface>,
+    /// Duration to wait for responses after the last query or answer
+    #[builder(default = DEFAULT_MDNS_IDLE_TIMEOUT)]
+    idle_timeout: Duration,
+    /// Channel for sending scan results and status messages
+    notifier: Notifier,
+    /// Address queries are sent to, defaults to the mDNS multicast group
+    #[builder(default = MDNS_ADDR)]
+    destination: SocketAddr,
+}
+
+impl MDNSScanner {
+    /// Returns builder for MDNSScanner
+    pub fn builder() -> MDNSScannerBuilder {
+        MDNSScannerBuilder::default()
+    }
+
+    fn send_questions(
+        &self,
+        socket: &UdpSocket,
+        id: u16,
+        questions: &[(String, u16)],
+    ) -> Result<()> {
+        for chunk in questions.chunks(MAX_QUESTIONS_PER_QUERY) {
+            let chunk: Vec<(&str, u16)> =
+                chunk.iter().map(|(n, t)| (n.as_str(), *t)).collect();
+            log::debug!("sending mDNS query: {:?}", chunk);
+            socket
+                .send_to(&dns::build_query(id, &chunk, true), self.destination)
+                .map_err(socket_error)?;
+        }
+
+        Ok(())
+    }
+
+    // Sends queries and follow up queries until no new answers arrive for
+    // the idle timeout, returning everything learned
+    fn browse(&self, control: &ScanControl) -> Result<Answers> {
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+            .map_err(socket_error)?;
+        socket
+            .set_multicast_ttl_v4(MULTICAST_TTL)
+            .map_err(socket_error)?;
+        socket
+            .set_read_timeout(Some(READ_TIMEOUT))
+            .map_err(socket_error)?;
+
+        let mut answers = Answers::default();
+        let mut asked = HashSet::from([(SERVICES_QUERY.to_string(), TYPE_PTR)]);
+        let mut questions = vec![(SERVICES_QUERY.to_string(), TYPE_PTR)];
+        let mut last_activity = Instant::now();
+        let mut id: u16 = 0;
+        let mut buf = [0u8; 9000];
+
+        loop {
+            // report whatever was collected once cancelled
+            if control.checkpoint().is_err() {
+                return Ok(answers);
+            }
+
+            if !questions.is_empty() {
+                id = id.wrapping_add(1);
+                self.send_questions(&socket, id, &questions)?;
+                last_activity = Instant::now();
+            }
+
+            if last_activity.elapsed() >= self.idle_timeout {
+                return Ok(answers);
+            }
+
+            match socket.recv_from(&mut buf) {
+                Ok((n, from)) => {
+                    if let Some(msg) = dns::parse(&buf[..n])
+                        && msg.is_response
+                    {
+                        answers.record(msg, from.ip());
+                        last_activity = Instant::now();
+                    }
+                }
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                    ) => {}
+                Err(e) => return Err(socket_error(e)),
+            }
+
+            questions = answers.next_questions(&mut asked);
+        }
+    }
+}
+
+fn socket_error(e: io::Error) -> RLanLibError {
+    RLanLibError::Scan {
+        error: format!("mdns socket error: {e}"),
+        ip: None,
+        port: None,
+    }
+}
+
+// Records collected from all responses received during a scan
+#[derive(Default)]
+struct Answers {
+    // PTR owner name -> pointed to names
+    pointers: HashMap<String, HashSet<String>>,
+    // service instance -> (port, target host)
+    locations: HashMap<String, (u16, String)>,
+    // service instance -> TXT strings
+    txt: HashMap<String, Vec<String>>,
+    // host -> IPv4 from A records
+    addresses: HashMap<String, Ipv4Addr>,
+    // host -> IPv4 of the responder that advertised it, used when no A
+    // record was returned
+    responders: HashMap<String, Ipv4Addr>,
+}
+
+impl Answers {
+    fn record(&mut self, msg: dns::Message, from: IpAddr) {
+        for record in msg.records {
+            match record.data {
+                RecordData::Ptr(target) => {
+                    self.pointers
+                        .entry(record.name)
+                        .or_default()
+                        .insert(target);
+                }
+                RecordData::Srv { port, target } => {
+                    if let IpAddr::V4(ip) = from {
+                        self.responders.insert(target.clone(), ip);
+                    }
+                    self.locations.insert(record.name, (port, target));
+                }
+                RecordData::Txt(strings) => {
+                    self.txt.insert(record.name, strings);
+                }
+                RecordData::A(ip) => {
+                    self.addresses.insert(record.name, ip);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // Returns the service types advertised along with their instances
+    fn service_types(
+        &self,
+    ) -> impl Iterator<Item = (&String, &HashSet<String>)> {
+        self.pointers.iter().filter(|(owner, _)| {
+            owner.as_str() != SERVICES_QUERY
+                && (owner.ends_with("._tcp.local")
+                    || owner.ends_with("._udp.local"))
+        })
+    }
+
+    // Returns the questions needed to resolve what has been learned so far
+    // that have not already been asked
+    fn next_questions(
+        &self,
+        asked: &mut HashSet<(String, u16)>,
+    ) -> Vec<(String, u16)> {
+        let types = self
+            .pointers
+            .get(SERVICES_QUERY)
+            .into_iter()
+            .flatten()
+            .map(|t| (t.clone(), TYPE_PTR));
+
+        let instances = self.service_types().flat_map(|(_, instances)| {
+            instances
+                .iter()
+                .flat_map(|i| [(i.clone(), TYPE_SRV), (i.clone(), TYPE_TXT)])
+        });
+
+        let hosts = self
+            .locations
+            .values()
+            .filter(|(_, target)| !self.addresses.contains_key(target))
+            .map(|(_, target)| (target.clone(), TYPE_A));
+
+        types
+            .chain(instances)
+            .chain(hosts)
+            .filter(|q| asked.insert(q.clone()))
+            .collect()
+    }
+
+    // Correlates records into one device per IP
+    fn devices(&self) -> HashMap<Ipv4Addr, Device> {
+        let mut devices: HashMap<Ipv4Addr, Device> = HashMap::new();
+
+        for (host, ip) in self.addresses.iter() {
+            if host.ends_with(".local") {
+                devices.entry(*ip).or_default().hostname = host.clone();
+            }
+        }
+
+        for (service_type, instances) in self.service_types() {
+            for instance in instances {
+                let Some((port, target)) = self.locations.get(instance) else {
+                    continue;
+                };
+
+                let Some(ip) = self
+                    .addresses
+                    .get(target)
+                    .or_else(|| self.responders.get(target))
+                else {
+                    continue;
+                };
+
+                let device = devices.entry(*ip).or_default();
+
+                if device.hostname.is_empty() {
+                    device.hostname = target.clone();
+                }
+
+                device.services.push(AdvertisedService {
+                    instance: instance
+                        .strip_suffix(&format!(".{service_type}"))
+                        .unwrap_or(instance)
+                        .to_string(),
+                    service_type: service_type
+                        .trim_end_matches(".local")
+                        .to_string(),
+                    port: *port,
+                    txt: self.txt.get(instance).cloned().unwrap_or_default(),
+                });
+            }
+        }
+
+        for (ip, device) in devices.iter_mut() {
+            device.ip = *ip;
+            device.services.sort_by(|a, b| {
+                (&a.service_type, &a.instance)
+                    .cmp(&(&b.service_type, &b.instance))
+            });
+        }
+
+        devices
+    }
+}
+
+// Implements the Scanner trait for MDNSScanner
+impl Scanner for MDNSScanner {
+    fn scan(&self) -> Result<ScanHandle> {
+        let control = ScanControl::new();
+        log::debug!("performing mDNS scan via {}", self.destination);
+
+        let self_clone = self.clone();
+        let scan_control = control.clone();
+
+        // prevent blocking thread so messages can be freely sent to consumer
+        let handle = thread::spawn(move || -> Result<()> {
+            let answers = self_clone.browse(&scan_control)?;
+            let subnet = Ipv4Net::from_str(&self_clone.interface.cidr).ok();
+
+            for (ip, device) in answers.devices() {
+                // responders on other interfaces may advertise addresses
+                // that are not reachable on this network
+                if subnet.is_some_and(|net| !net.contains(&ip)) {
+                    continue;
+                }
+
+                log::debug!("mDNS discovered {} at {}", device.hostname, ip);
+
+                self_clone.notifier.send(ScanMessage::MDNSScanDevice(
+                    Device {
+                        is_current_host: ip == self_clone.interface.ipv4,
+                        ..device
+                    },
+                ))?;
+            }
+
+            self_clone.notifier.send(ScanMessage::Done)
+        });
+
+        Ok(ScanHandle::new(handle, control))
+    }
+}
+
+#[cfg(test)]
+#[path = "./mdns_scanner_tests.rs"]
+mod tests;
@@ -0,0 +1,149 @@
+use super::*;
+use std::sync::mpsc::channel;
+
+use crate::network;
+
+fn encode(name: &str) -> Vec<u8> {
+    let mut buf = Vec::new();
+    for label in name.split('.') {
+        buf.push(label.len() as u8);
+        buf.extend_from_slice(label.as_bytes());
+    }
+    buf.push(0);
+    buf
+}
+
+fn record(name: &str, rtype: u16, rdata: &[u8]) -> Vec<u8> {
+    let mut buf = encode(name);
+    buf.extend_from_slice(&rtype.to_be_bytes());
+    buf.extend_from_slice(&[0, 1, 0, 0, 0, 120]);
+    buf.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+    buf.extend_from_slice(rdata);
+    buf
+}
+
+fn response(id: [u8; 2], records: Vec<Vec<u8>>) -> Vec<u8> {
+    let mut msg = id.to_vec();
+    msg.extend_from_slice(&[0x84, 0, 0, 0]);
+    msg.extend_from_slice(&(records.len() as u16).to_be_bytes());
+    msg.extend_from_slice(&[0; 4]);
+    for r in records {
+        msg.extend_from_slice(&r);
+    }
+    msg
+}
+
+// Answers mDNS queries for a single NAS advertising ssh
+fn spawn_responder(nas_ip: Ipv4Addr) -> SocketAddr {
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    socket
+        .set_read_timeout(Some(Duration::from_secs(3)))
+        .unwrap();
+    let addr = socket.local_addr().unwrap();
+
+    let contains = |q: &[u8], name: &str| {
+        let name = encode(name);
+        q.windows(name.len()).any(|w| w == name.as_slice())
+    };
+
+    thread::spawn(move || {
+        let mut buf = [0u8; 1500];
+
+        while let Ok((n, from)) = socket.recv_from(&mut buf) {
+            let q = &buf[..n];
+
+            let records = if contains(q, SERVICES_QUERY) {
+                vec![record(
+                    SERVICES_QUERY,
+                    TYPE_PTR,
+                    &encode("_ssh._tcp.local"),
+                )]
+            } else if contains(q, "nas._ssh._tcp.local") {
+                let mut srv = vec![0, 0, 0, 0, 0, 22];
+                srv.extend_from_slice(&encode("nas.local"));
+                vec![
+                    record("nas._ssh._tcp.local", TYPE_SRV, &srv),
+                    record("nas._ssh._tcp.local", TYPE_TXT, b"\x08vendor=x"),
+                ]
+            } else if contains(q, "_ssh._tcp.local") {
+                vec![record(
+                    "_ssh._tcp.local",
+                    TYPE_PTR,
+                    &encode("nas._ssh._tcp.local"),
+                )]
+            } else if contains(q, "nas.local") {
+                vec![record("nas.local", TYPE_A, &nas_ip.octets())]
+            } else {
+                continue;
+            };
+
+            let _ = socket.send_to(&response([q[0], q[1]], records), from);
+        }
+    });
+
+    addr
+}
+
+#[test]
+fn new() {
+    let interface = Arc::new(network::get_default_interface().unwrap());
+    let (tx, _) = channel();
+
+    let scanner = MDNSScanner::builder()
+        .interface(interface)
+        .notifier(tx)
+        .build()
+        .unwrap();
+
+    assert_eq!(scanner.destination, MDNS_ADDR);
+    assert_eq!(scanner.idle_timeout, DEFAULT_MDNS_IDLE_TIMEOUT);
+}
+
+#[test]
+fn discovers_hostnames_and_services() {
+    let interface = Arc::new(network::get_default_interface().unwrap());
+    let nas_ip = Ipv4Net::from_str(&interface.cidr)
+        .unwrap()
+        .hosts()
+        .nth(5)
+        .unwrap();
+
+    let destination = spawn_responder(nas_ip);
+    let (tx, rx) = channel();
+
+    let scanner = MDNSScanner::builder()
+        .interface(interface)
+        .idle_timeout(Duration::from_millis(500))
+        .destination(destination)
+        .notifier(tx)
+        .build()
+        .unwrap();
+
+    let handle = scanner.scan().unwrap();
+
+    let mut detected = Vec::new();
+
+    loop {
+        match rx.recv().unwrap() {
+            ScanMessage::Done => break,
+            ScanMessage::MDNSScanDevice(d) => detected.push(d),
+            _ => {}
+        }
+    }
+
+    assert!(handle.join().unwrap().is_ok());
+    assert_eq!(detected.len(), 1);
+
+    let device = &detected[0];
+    assert_eq!(device.ip, nas_ip);
+    assert_eq!(device.hostname, "nas.local");
+    assert_eq!(
+        device.services,
+        vec![AdvertisedService {
+            instance: "nas".into(),
+            service_type: "_ssh._tcp".into(),
+            port: 22,
+            txt: vec!["vendor=x".into()],
+        }]
+    );
+}
@@ -0,0 +1,368 @@
+//! Provides Scanner implementation for IPv6 neighbor discovery
+//!
+//! Sends an ICMPv6 echo request to the all-nodes multicast group (ff02::1)
+//! along with neighbor solicitations for any explicit IPv6 targets. Echo
+//! replies, neighbor advertisements and neighbor solicitations seen on the
+//! wire are used to map IPv6 addresses to MACs. Devices already known by
+//! IPv4 (i.e. from an ARP scan) are correlated by MAC address.
+
+use derive_builder::Builder;
+use pnet::{
+    packet::{
+        Packet, ethernet,
+        icmpv6::{self, echo_reply, ndp},
+        ip, ipv6,
+    },
+    util::MacAddr,
+};
+use std::{
+    collections::HashMap,
+    net::Ipv6Addr,
+    sync::{self, Arc, Mutex, mpsc::TryRecvError},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::{
+    error::{RLanLibError, Result},
+    network::NetworkInterface,
+    packet::{
+        icmpv6_packet::Icmpv6EchoPacketBuilder,
+        ndp_packet::{NeighborSolicitPacketBuilder, multicast_mac},
+    },
+    scanners::{
+        Device,
+        handle::{ScanControl, ScanHandle, loop_error},
+    },
+    wire::{DEFAULT_PACKET_SEND_TIMING, Wire},
+};
+
+use super::{ScanMessage, Scanner, heartbeat::HeartBeat, notifier::Notifier};
+
+/// The link-local all-nodes multicast address
+pub const ALL_NODES: Ipv6Addr = Ipv6Addr::new(0xff02, 0, 0, 0, 0, 0, 0, 1);
+
+/// Data structure representing an IPv6 neighbor discovery scanner
+#[derive(Clone, Builder)]
+#[builder(setter(into), pattern = "owned")]
+pub struct NDPScanner {
+    /// Network interface to use for scanning. Must have an IPv6 address
+    interface: Arc<NetworkInterface>,
+    /// Wire for reading and sending packets on the wire
+    wire: Wire,
+    /// Explicit IPv6 addresses to send neighbor solicitations to in addition
+    /// to the all-nodes echo request
+    #[builder(default)]
+    targets: Vec<Ipv6Addr>,
+    /// Devices already discovered by IPv4 used to correlate IPv6 addresses
+    /// by MAC
+    #[builder(default)]
+    known: Vec<Device>,
+    /// Source port for the heartbeat listener. Also used as the ICMPv6
+    /// identifier so echo replies can be matched to this scanner
+    source_port: u16,
+    /// Duration to wait for responses after scanning completes
+    idle_timeout: Duration,
+    /// Channel for sending scan results and status messages
+    notifier: Notifier,
+    /// Throttles speed at which packets are sent. Higher throttles result
+    /// in more accurate scans
+    #[builder(default = DEFAULT_PACKET_SEND_TIMING)]
+    throttle: Duration,
+    /// Devices discovered so far keyed by MAC
+    #[builder(setter(skip), default = "Arc::new(Mutex::new(HashMap::new()))")]
+    discovered: Arc<Mutex<HashMap<MacAddr, Device>>>,
+}
+
+impl NDPScanner {
+    /// Returns builder for NDPScanner
+    pub fn builder() -> NDPScannerBuilder {
+        NDPScannerBuilder::default()
+    }
+
+    fn source_ipv6(&self) -> Result<Ipv6Addr> {
+        self.interface
+            .ipv6
+            .ok_or(RLanLibError::NetworkInterface(format!(
+                "interface {} has no IPv6 address",
+                self.interface.name
+            )))
+    }
+
+    // Records the IPv6 address for the device with the given MAC and
+    // notifies the consumer if the address has not been seen before
+    fn record(&self, mac: MacAddr, ip: Ipv6Addr) -> Result<()> {
+        if ip.is_unspecified() || ip.is_multicast() {
+            return Ok(());
+        }
+
+        let device = {
+            let mut discovered = self.discovered.lock().map_err(|e| {
+                RLanLibError::ThreadError(format!(
+                    "failed to lock discovered devices: {e}"
+                ))
+            })?;
+
+            let device = discovered.entry(mac).or_insert_with(|| {
+                self.known
+                    .iter()
+                    .find(|d| d.mac == mac)
+                    .cloned()
+                    .unwrap_or_else(|| Device {
+                        mac,
+                        is_current_host: mac == self.interface.mac,
+                        ..Device::default()
+                    })
+            });
+
+            if device.ipv6.contains(&ip) {
+                return Ok(());
+            }
+
+            device.ipv6.push(ip);
+            device.ipv6.sort();
+            device.clone()
+        };
+
+        self.notifier.send(ScanMessage::NDPScanDevice(device))
+    }
+
+    fn send(&self, pkt: &[u8], target: Ipv6Addr) -> Result<()> {
+        let mut pkt_sender = self.wire.0.lock()?;
+        pkt_sender.send(pkt).map_err(|e| RLanLibError::Scan {
+            ip: Some(target.to_string()),
+            port: None,
+            error: e.to_string(),
+        })
+    }
+
+    fn process_targets(&self, control: &ScanControl) -> Result<()> {
+        let source_ip = self.source_ipv6()?;
+
+        // the OS never answers its own solicitations so report our own
+        // addresses immediately like the ARP scanner does
+        for net in self.interface.ips.iter().filter(|i| i.is_ipv6()) {
+            if let std::net::IpAddr::V6(ip) = net.ip() {
+                self.record(self.interface.mac, ip)?;
+            }
+        }
+
+        log::debug!("sending ICMPv6 echo request to {}", ALL_NODES);
+
+        let echo_packet = Icmpv6EchoPacketBuilder::default()
+            .source_ip(source_ip)
+            .source_mac(self.interface.mac)
+            .dest_ip(ALL_NODES)
+            .dest_mac(multicast_mac(ALL_NODES))
+            .identifier(self.source_port)
+            .sequence(1_u16)
+            .build()?;
+
+        self.send(&echo_packet.to_raw(), ALL_NODES)?;
+
+        for target in self.targets.iter() {
+            control.checkpoint()?;
+
+            // throttle packet sending to prevent packet loss
+            thread::sleep(self.throttle);
+
+            log::debug!("soliciting neighbor: {}", target);
+
+            let ns_packet = NeighborSolicitPacketBuilder::default()
+                .source_ip(source_ip)
+                .source_mac(self.interface.mac)
+                .target_ip(*target)
+                .build()?;
+
+            self.send(&ns_packet.to_raw(), *target)?;
+        }
+
+        Ok(())
+    }
+
+    fn process_incoming_packet(&self, pkt: &[u8]) -> Result<()> {
+        let Some(eth) = ethernet::EthernetPacket::new(pkt) else {
+            return Ok(());
+        };
+
+        if eth.get_ethertype() != ethernet::EtherTypes::Ipv6
+            || eth.get_source() == self.interface.mac
+        {
+            return Ok(());
+        }
+
+        let Some(header) = ipv6::Ipv6Packet::new(eth.payload()) else {
+            return Ok(());
+        };
+
+        if header.get_next_header() != ip::IpNextHeaderProtocols::Icmpv6 {
+            return Ok(());
+        }
+
+        let Some(icmp) = icmpv6::Icmpv6Packet::new(header.payload()) else {
+            return Ok(());
+        };
+
+        match icmp.get_icmpv6_type() {
+            icmpv6::Icmpv6Types::EchoReply => {
+                let Some(reply) =
+                    echo_reply::EchoReplyPacket::new(header.payload())
+                else {
+                    return Ok(());
+                };
+
+                if reply.get_identifier() != self.source_port {
+                    return Ok(());
+                }
+
+                self.record(eth.get_source(), header.get_source())
+            }
+            icmpv6::Icmpv6Types::NeighborAdvert => {
+                let Some(advert) =
+                    ndp::NeighborAdvertPacket::new(header.payload())
+                else {
+                    return Ok(());
+                };
+
+                let mac = link_layer_address(
+                    &advert.get_options(),
+                    ndp::NdpOptionTypes::TargetLLAddr,
+                )
+                .unwrap_or(eth.get_source());
+
+                self.record(mac, advert.get_target_addr())
+            }
+            icmpv6::Icmpv6Types::NeighborSolicit => {
+                // neighbors resolving our address while answering the echo
+                // request reveal themselves too
+                let Some(solicit) =
+                    ndp::NeighborSolicitPacket::new(header.payload())
+                else {
+                    return Ok(());
+                };
+
+                let mac = link_layer_address(
+                    &solicit.get_options(),
+                    ndp::NdpOptionTypes::SourceLLAddr,
+                )
+                .unwrap_or(eth.get_source());
+
+                self.record(mac, header.get_source())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // Implements packet reading in a separate thread so we can send and
+    // receive packets simultaneously
+    fn read_packets(
+        &self,
+        done: sync::mpsc::Receiver<()>,
+        control: ScanControl,
+    ) -> Result<JoinHandle<Result<()>>> {
+        let (heartbeat_tx, heartbeat_rx) = sync::mpsc::channel::<()>();
+
+        let heartbeat = HeartBeat::builder()
+            .source_mac(self.interface.mac)
+            .source_ipv4(self.interface.ipv4)
+            .source_port(self.source_port)
+            .packet_sender(Arc::clone(&self.wire.0))
+            .control(control.clone())
+            .build()?;
+
+        heartbeat.start_in_thread(heartbeat_rx)?;
+
+        let self_clone = self.clone();
+
+        Ok(thread::spawn(move || -> Result<()> {
+            let mut reader = self_clone.wire.1.lock()?;
+
+            loop {
+                // also stop if the scan thread went away without signalling
+                if control.is_cancelled()
+                    || !matches!(done.try_recv(), Err(TryRecvError::Empty))
+                {
+                    log::debug!("exiting ndp packet reader");
+                    if let Err(e) = heartbeat_tx.send(()) {
+                        log::error!("failed to stop heartbeat: {}", e);
+                    }
+                    break;
+                }
+
+                let pkt = reader.next_packet()?;
+
+                self_clone.process_incoming_packet(pkt)?;
+            }
+
+            Ok(())
+        }))
+    }
+}
+
+// Returns the MAC carried in the first NDP link-layer address option of the
+// given type
+fn link_layer_address(
+    options: &[ndp::NdpOption],
+    option_type: ndp::NdpOptionType,
+) -> Option<MacAddr> {
+    options
+        .iter()
+        .find(|o| o.option_type == option_type && o.data.len() >= 6)
+        .map(|o| {
+            MacAddr::new(
+                o.data[0], o.data[1], o.data[2], o.data[3], o.data[4],
+                o.data[5],
+            )
+        })
+}
+
+// Implements the Scanner trait for NDPScanner
+impl Scanner for NDPScanner {
+    fn scan(&self) -> Result<ScanHandle> {
+        let control = ScanControl::new();
+        log::debug!("performing NDP scan on targets: {:?}", self.targets);
+
+        // fail fast rather than starting threads that can never succeed
+        self.source_ipv6()?;
+
+        if let Ok(mut discovered) = self.discovered.lock() {
+            discovered.clear();
+        }
+
+        log::debug!("starting ndp packet reader");
+
+        let self_clone = self.clone();
+        let (done_tx, done_rx) = sync::mpsc::channel::<()>();
+
+        let read_handle = self.read_packets(done_rx, control.clone())?;
+        let scan_control = control.clone();
+
+        // prevent blocking thread so messages can be freely sent to consumer
+        let scan_handle = thread::spawn(move || -> Result<()> {
+            let scan_error =
+                loop_error(self_clone.process_targets(&scan_control));
+
+            scan_control.sleep(self_clone.idle_timeout);
+
+            self_clone.notifier.send(ScanMessage::Done)?;
+
+            // ignore errors here as the thread may already be dead due to error
+            // we'll catch any errors from that thread below and report
+            let _ = done_tx.send(());
+
+            let read_result = read_handle.join()?;
+
+            if let Some(err) = scan_error {
+                return Err(err);
+            }
+
+            read_result
+        });
+
+        Ok(ScanHandle::new(scan_handle, control))
+    }
+}
+
+#[cfg(test)]
+#[path = "./ndp_scanner_tests.rs"]
+mod tests;
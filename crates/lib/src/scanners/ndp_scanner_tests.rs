@@ -0,0 +1,213 @@
+use super::*;
+use pnet::ipnetwork::IpNetwork;
+use std::net::Ipv4Addr;
+use std::str::FromStr;
+use std::sync::mpsc::channel;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::{
+    network,
+    packet::{
+        icmpv6_packet::create_icmpv6_echo_reply,
+        ndp_packet::create_neighbor_advert,
+    },
+    wire::{
+        Reader, Sender,
+        mocks::{MockPacketReader, MockPacketSender},
+    },
+};
+
+const PKT_ETH_SIZE: usize = ethernet::EthernetPacket::minimum_packet_size();
+const PKT_IP6_SIZE: usize = ipv6::Ipv6Packet::minimum_packet_size();
+const PKT_ECHO_SIZE: usize = PKT_ETH_SIZE + PKT_IP6_SIZE + 8 + 16;
+const PKT_NA_SIZE: usize = PKT_ETH_SIZE + PKT_IP6_SIZE + 24 + 8;
+
+fn ipv6_interface() -> Arc<NetworkInterface> {
+    let mut interface = network::get_default_interface().unwrap();
+    interface.ipv6 = Some(Ipv6Addr::from_str("fe80::2").unwrap());
+    interface.ips = vec![IpNetwork::from_str("fe80::2/64").unwrap()];
+    Arc::new(interface)
+}
+
+#[test]
+fn new() {
+    let interface = ipv6_interface();
+    let sender: Arc<Mutex<dyn Sender>> =
+        Arc::new(Mutex::new(MockPacketSender::new()));
+    let receiver: Arc<Mutex<dyn Reader>> =
+        Arc::new(Mutex::new(MockPacketReader::new()));
+    let wire = Wire(sender, receiver);
+    let idle_timeout = Duration::from_secs(2);
+    let (tx, _) = channel();
+
+    let scanner = NDPScanner::builder()
+        .interface(interface)
+        .wire(wire)
+        .source_port(54321_u16)
+        .idle_timeout(idle_timeout)
+        .notifier(tx)
+        .build()
+        .unwrap();
+
+    assert!(scanner.targets.is_empty());
+    assert!(scanner.known.is_empty());
+    assert_eq!(scanner.idle_timeout, idle_timeout);
+    assert_eq!(scanner.source_port, 54321);
+}
+
+#[test]
+fn returns_error_when_interface_has_no_ipv6() {
+    let mut interface = network::get_default_interface().unwrap();
+    interface.ipv6 = None;
+    let sender: Arc<Mutex<dyn Sender>> =
+        Arc::new(Mutex::new(MockPacketSender::new()));
+    let receiver: Arc<Mutex<dyn Reader>> =
+        Arc::new(Mutex::new(MockPacketReader::new()));
+    let (tx, _) = channel();
+
+    let scanner = NDPScanner::builder()
+        .interface(Arc::new(interface))
+        .wire(Wire(sender, receiver))
+        .source_port(54321_u16)
+        .idle_timeout(Duration::from_secs(2))
+        .notifier(tx)
+        .build()
+        .unwrap();
+
+    assert!(scanner.scan().is_err());
+}
+
+#[test]
+#[allow(static_mut_refs)]
+fn correlates_echo_replies_with_known_devices_by_mac() {
+    static mut PACKET: [u8; PKT_ECHO_SIZE] = [0u8; PKT_ECHO_SIZE];
+    let interface = ipv6_interface();
+    let device_mac = MacAddr::from_str("00:00:00:00:00:01").unwrap();
+    let device_ipv6 = Ipv6Addr::from_str("fe80::1").unwrap();
+    let known = Device {
+        hostname: "known".to_string(),
+        ip: Ipv4Addr::from_str("192.168.0.1").unwrap(),
+        mac: device_mac,
+        ..Device::default()
+    };
+
+    create_icmpv6_echo_reply(
+        device_mac,
+        device_ipv6,
+        interface.mac,
+        interface.ipv6.unwrap(),
+        54321,
+        1,
+        #[allow(static_mut_refs)]
+        unsafe {
+            &mut PACKET
+        },
+    );
+
+    let mut receiver = MockPacketReader::new();
+    let mut sender = MockPacketSender::new();
+
+    #[allow(static_mut_refs)]
+    receiver
+        .expect_next_packet()
+        .returning(|| Ok(unsafe { &PACKET }));
+
+    sender.expect_send().returning(|_| Ok(()));
+
+    let (tx, rx) = channel();
+
+    let sender: Arc<Mutex<dyn Sender>> = Arc::new(Mutex::new(sender));
+    let receiver: Arc<Mutex<dyn Reader>> = Arc::new(Mutex::new(receiver));
+    let wire = Wire(sender, receiver);
+
+    let scanner = NDPScanner::builder()
+        .interface(Arc::clone(&interface))
+        .wire(wire)
+        .known(vec![known.clone()])
+        .source_port(54321_u16)
+        .idle_timeout(Duration::from_secs(2))
+        .notifier(tx)
+        .build()
+        .unwrap();
+
+    let handle = scanner.scan().unwrap();
+
+    let mut detected: Vec<Device> = Vec::new();
+
+    loop {
+        if let Ok(msg) = rx.recv() {
+            match msg {
+                ScanMessage::Done => {
+                    break;
+                }
+                ScanMessage::NDPScanDevice(device) => {
+                    detected.push(device);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let result = handle.join().unwrap();
+    assert!(result.is_ok());
+    // current host plus one de-duplicated neighbor
+    assert_eq!(detected.len(), 2);
+
+    let host = detected.iter().find(|d| d.is_current_host).unwrap();
+    assert_eq!(host.ipv6, vec![interface.ipv6.unwrap()]);
+
+    let neighbor = detected.iter().find(|d| d.mac == device_mac).unwrap();
+    assert_eq!(neighbor.ip, known.ip);
+    assert_eq!(neighbor.hostname, known.hostname);
+    assert_eq!(neighbor.ipv6, vec![device_ipv6]);
+}
+
+#[test]
+#[allow(static_mut_refs)]
+fn reports_ipv6_only_neighbor_from_advert() {
+    static mut PACKET: [u8; PKT_NA_SIZE] = [0u8; PKT_NA_SIZE];
+    let interface = ipv6_interface();
+    let device_mac = MacAddr::from_str("00:00:00:00:00:03").unwrap();
+    let device_ipv6 = Ipv6Addr::from_str("2001:db8::3").unwrap();
+
+    create_neighbor_advert(
+        device_mac,
+        device_ipv6,
+        interface.mac,
+        interface.ipv6.unwrap(),
+        #[allow(static_mut_refs)]
+        unsafe {
+            &mut PACKET
+        },
+    );
+
+    let (tx, rx) = channel();
+
+    let sender: Arc<Mutex<dyn Sender>> =
+        Arc::new(Mutex::new(MockPacketSender::new()));
+    let receiver: Arc<Mutex<dyn Reader>> =
+        Arc::new(Mutex::new(MockPacketReader::new()));
+
+    let scanner = NDPScanner::builder()
+        .interface(interface)
+        .wire(Wire(sender, receiver))
+        .source_port(54321_u16)
+        .idle_timeout(Duration::from_secs(2))
+        .notifier(tx)
+        .build()
+        .unwrap();
+
+    scanner.process_incoming_packet(unsafe { &PACKET }).unwrap();
+    // repeated adverts for the same address are not reported again
+    scanner.process_incoming_packet(unsafe { &PACKET }).unwrap();
+
+    let ScanMessage::NDPScanDevice(device) = rx.try_recv().unwrap() else {
+        panic!("expected NDPScanDevice message");
+    };
+
+    assert!(rx.try_recv().is_err());
+    assert_eq!(device.ip, Ipv4Addr::UNSPECIFIED);
+    assert_eq!(device.mac, device_mac);
+    assert_eq!(device.ipv6, vec![device_ipv6]);
+}
@@ -0,0 +1,55 @@
+//! Provides the channel scanners report their messages on
+//!
+//! Scanners send every [`ScanMessage`] through a [`Notifier`]. It converts
+//! from a std [`mpsc::Sender`] and, with the `async` feature, from a tokio
+//! `UnboundedSender`, so either can be passed to a scanner's builder and
+//! async consumers receive messages without a thread forwarding them.
+
+use std::sync::mpsc;
+
+use crate::error::{RLanLibError, Result};
+
+use super::ScanMessage;
+
+/// Sending half of the channel a scanner reports on
+#[derive(Debug, Clone)]
+pub struct Notifier(Channel);
+
+#[derive(Debug, Clone)]
+enum Channel {
+    Std(mpsc::Sender<ScanMessage>),
+    #[cfg(feature = "async")]
+    Tokio(tokio::sync::mpsc::UnboundedSender<ScanMessage>),
+}
+
+impl Notifier {
+    /// Sends the message, failing if the receiving half was dropped
+    pub fn send(&self, message: ScanMessage) -> Result<()> {
+        let result = match &self.0 {
+            Channel::Std(tx) => tx.send(message),
+            #[cfg(feature = "async")]
+            Channel::Tokio(tx) => {
+                tx.send(message).map_err(|e| mpsc::SendError(e.0))
+            }
+        };
+
+        result.map_err(RLanLibError::from_channel_send_error)
+    }
+}
+
+impl From<mpsc::Sender<ScanMessage>> for Notifier {
+    fn from(tx: mpsc::Sender<ScanMessage>) -> Self {
+        Self(Channel::Std(tx))
+    }
+}
+
+#[cfg(feature = "async")]
+impl From<tokio::sync::mpsc::UnboundedSender<ScanMessage>> for Notifier {
+    fn from(tx: tokio::sync::mpsc::UnboundedSender<ScanMessage>) -> Self {
+        Self(Channel::Tokio(tx))
+    }
+}
+
+#[cfg(test)]
+#[path = "./notifier_tests.rs"]
+mod tests;
@@ -0,0 +1,26 @@
+use super::*;
+
+#[test]
+fn sends_over_std_channels() {
+    let (tx, rx) = mpsc::channel();
+    let notifier = Notifier::from(tx);
+
+    notifier.send(ScanMessage::Done).unwrap();
+    assert!(matches!(rx.recv().unwrap(), ScanMessage::Done));
+
+    drop(rx);
+    assert!(notifier.send(ScanMessage::Done).is_err());
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn sends_over_tokio_channels() {
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let notifier = Notifier::from(tx);
+
+    notifier.send(ScanMessage::Done).unwrap();
+    assert!(matches!(rx.try_recv().unwrap(), ScanMessage::Done));
+
+    drop(rx);
+    assert!(notifier.send(ScanMessage::Done).is_err());
+}
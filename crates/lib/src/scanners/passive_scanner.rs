@@ -0,0 +1,442 @@
+//! Provides Scanner implementation for passive device discovery
+//!
+//! The passive scanner never sends a packet. It only listens on the
+//! [`Wire`] reader and learns IP / MAC pairs from the traffic it observes:
+//! ARP requests and replies (including gratuitous ARP), DHCP acknowledgements
+//! and renewals, and any other IPv4 traffic (mDNS, broadcasts etc.) sourced
+//! from the local subnet. DHCP DISCOVER and REQUEST messages are also
+//! fingerprinted to guess the client's operating system and hostname, which
+//! is the only hostname reported as name lookups would send packets.
+
+use derive_builder::Builder;
+use ipnet::Ipv4Net;
+use pnet::{
+    packet::{Packet, arp, ethernet, ip, ipv4, udp},
+    util::MacAddr,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    net::Ipv4Addr,
+    str::FromStr,
+    sync::{self, Arc, Mutex, MutexGuard, mpsc::TryRecvError},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+use threadpool::ThreadPool;
+
+use crate::{
+    error::{RLanLibError, Result},
+    fingerprint::dhcp::{
+        DhcpFingerprint, OPTION_HOSTNAME, OPTION_PARAMETER_REQUEST_LIST,
+        OPTION_VENDOR_CLASS,
+    },
+    network::NetworkInterface,
+    oui::traits::Oui,
+    scanners::{
+        Device,
+        handle::{ScanControl, ScanHandle},
+    },
+    wire::Wire,
+};
+
+use super::{ScanMessage, Scanner, notifier::Notifier};
+
+const DHCP_SERVER_PORT: u16 = 67;
+const DHCP_CLIENT_PORT: u16 = 68;
+
+// fixed size BOOTP header preceding the DHCP magic cookie and options
+const BOOTP_HEADER_SIZE: usize = 236;
+const DHCP_MAGIC_COOKIE: [u8; 4] = [99, 130, 83, 99];
+const DHCP_OPTION_PAD: u8 = 0;
+const DHCP_OPTION_MESSAGE_TYPE: u8 = 53;
+const DHCP_OPTION_END: u8 = 255;
+const DHCP_DISCOVER: u8 = 1;
+const DHCP_REQUEST: u8 = 3;
+const DHCP_ACK: u8 = 5;
+
+/// Data structure representing a passive scanner
+#[derive(Clone, Builder)]
+#[builder(setter(into), pattern = "owned")]
+pub struct PassiveScanner {
+    /// Network interface to listen on
+    interface: Arc<NetworkInterface>,
+    /// Wire for reading packets. Nothing is ever sent on it, so its reader
+    /// must time out like the default wire's for the scan to stop on a
+    /// quiet network
+    wire: Wire,
+    /// How long to listen before reporting done
+    duration: Duration,
+    /// Channel for sending scan results and status messages
+    notifier: Notifier,
+    /// Whether to include vendor lookups for discovered devices
+    #[builder(default)]
+    include_vendor: bool,
+    /// Default gateway IP, used to mark the gateway device in scan results
+    #[builder(default)]
+    gateway: Option<Ipv4Addr>,
+    /// Used to lookup vendor info for mac addresses
+    #[builder(default)]
+    oui: Option<Arc<dyn Oui>>,
+    /// IP / MAC pairs already reported during this scan
+    #[builder(setter(skip), default = "Arc::new(Mutex::new(HashSet::new()))")]
+    seen: Arc<Mutex<HashSet<(Ipv4Addr, MacAddr)>>>,
+    /// Latest DHCP fingerprint of each client MAC
+    #[builder(setter(skip), default = "Arc::new(Mutex::new(HashMap::new()))")]
+    fingerprints: Arc<Mutex<HashMap<MacAddr, DhcpFingerprint>>>,
+}
+
+impl PassiveScanner {
+    /// Returns builder for PassiveScanner
+    pub fn builder() -> PassiveScannerBuilder {
+        PassiveScannerBuilder::default()
+    }
+
+    // Returns the IP / MAC pair learned from the packet if any
+    fn learn(
+        &self,
+        pkt: &[u8],
+        subnet: Option<&Ipv4Net>,
+    ) -> Result<Option<(Ipv4Addr, MacAddr)>> {
+        let Some(eth) = ethernet::EthernetPacket::new(pkt) else {
+            return Ok(None);
+        };
+
+        match eth.get_ethertype() {
+            ethernet::EtherTypes::Arp => {
+                Ok(arp::ArpPacket::new(eth.payload()).map(|header| {
+                    // covers requests, replies and gratuitous announcements
+                    (
+                        header.get_sender_proto_addr(),
+                        header.get_sender_hw_addr(),
+                    )
+                }))
+            }
+            ethernet::EtherTypes::Ipv4 => {
+                let Some(header) = ipv4::Ipv4Packet::new(eth.payload()) else {
+                    return Ok(None);
+                };
+
+                if let Some(msg) = dhcp_message(&header) {
+                    if let Some(fingerprint) = msg.fingerprint() {
+                        self.remember_fingerprint(msg.chaddr, fingerprint)?;
+                    }
+
+                    if let Some(lease) = msg.lease() {
+                        return Ok(Some(lease));
+                    }
+                }
+
+                let source = header.get_source();
+
+                // traffic routed in from other networks carries the MAC of
+                // the router, not of the host that sent it
+                if subnet.is_some_and(|net| !net.contains(&source)) {
+                    return Ok(None);
+                }
+
+                Ok(Some((source, eth.get_source())))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    // Stores the client's fingerprint, forgetting any pairs already reported
+    // for it when the fingerprint changed so they are reported again with it
+    fn remember_fingerprint(
+        &self,
+        mac: MacAddr,
+        fingerprint: DhcpFingerprint,
+    ) -> Result<()> {
+        let changed = lock(&self.fingerprints, "fingerprints")?
+            .insert(mac, fingerprint.clone())
+            .is_none_or(|previous| previous != fingerprint);
+
+        if changed {
+            log::debug!("fingerprinted DHCP client {}: {:?}", mac, fingerprint);
+            lock(&self.seen, "seen devices")?.retain(|(_, m)| *m != mac);
+        }
+
+        Ok(())
+    }
+
+    fn process_incoming_packet(
+        &self,
+        pkt: &[u8],
+        subnet: Option<&Ipv4Net>,
+        pool: &ThreadPool,
+    ) -> Result<()> {
+        let Some((ip4, mac)) = self.learn(pkt, subnet)? else {
+            return Ok(());
+        };
+
+        // skip probes for address conflicts (sender 0.0.0.0), broadcast
+        // sources and multicast MACs which never identify a single device
+        if ip4.is_unspecified()
+            || ip4.is_broadcast()
+            || ip4.is_multicast()
+            || mac.is_broadcast()
+            || mac.is_multicast()
+            || mac.is_zero()
+        {
+            return Ok(());
+        }
+
+        let is_new = lock(&self.seen, "seen devices")?.insert((ip4, mac));
+
+        if !is_new {
+            return Ok(());
+        }
+
+        log::debug!("passively discovered {} at {}", ip4, mac);
+
+        let fingerprint =
+            lock(&self.fingerprints, "fingerprints")?.get(&mac).cloned();
+
+        let notification_sender = self.notifier.clone();
+        let interface = Arc::clone(&self.interface);
+        let include_vendor = self.include_vendor;
+        let gateway = self.gateway;
+        let oui = self.oui.as_ref().map(Arc::clone);
+
+        // use a thread pool here so we don't slow down packet
+        // processing while limiting concurrent threads
+        pool.execute(move || {
+            // name lookups would send packets, so the only name known is
+            // the one the client gave the DHCP server
+            let hostname = fingerprint
+                .as_ref()
+                .and_then(|f| f.hostname.clone())
+                .unwrap_or_default();
+
+            let vendor = if include_vendor && let Some(db) = oui {
+                db.lookup(mac)
+                    .map(|v| v.organization().to_string())
+                    .unwrap_or_default()
+            } else {
+                String::new()
+            };
+
+            let _ =
+                notification_sender.send(ScanMessage::ARPScanDevice(Device {
+                    hostname,
+                    ip: ip4,
+                    mac,
+                    vendor,
+                    is_current_host: ip4 == interface.ipv4,
+                    is_gateway: gateway.is_some_and(|gw| gw == ip4),
+                    os_guess: fingerprint.and_then(|f| f.guess()),
+                    ..Device::default()
+                }));
+        });
+
+        Ok(())
+    }
+
+    // Implements packet reading in a separate thread so the scan thread can
+    // report done once the duration elapses
+    fn read_packets(
+        &self,
+        done: sync::mpsc::Receiver<()>,
+        control: ScanControl,
+    ) -> JoinHandle<Result<()>> {
+        let self_clone = self.clone();
+
+        thread::spawn(move || -> Result<()> {
+            let mut reader = self_clone.wire.1.lock()?;
+            let subnet = Ipv4Net::from_str(&self_clone.interface.cidr).ok();
+            // Use a bounded thread pool for vendor lookups to prevent
+            // spawning thousands of threads on large networks
+            let lookup_pool = ThreadPool::new(8);
+
+            loop {
+                // also stop if the scan thread went away without signalling
+                if control.is_cancelled()
+                    || !matches!(done.try_recv(), Err(TryRecvError::Empty))
+                {
+                    log::debug!("exiting passive packet reader");
+                    break;
+                }
+
+                let pkt = reader.next_packet()?;
+
+                self_clone.process_incoming_packet(
+                    pkt,
+                    subnet.as_ref(),
+                    &lookup_pool,
+                )?;
+            }
+
+            Ok(())
+        })
+    }
+}
+
+fn lock<'a, T>(mutex: &'a Mutex<T>, name: &str) -> Result<MutexGuard<'a, T>> {
+    mutex.lock().map_err(|e| {
+        RLanLibError::ThreadError(format!("failed to lock {name}: {e}"))
+    })
+}
+
+// Fields of a DHCP message used to learn devices and fingerprint clients
+struct DhcpMessage {
+    from_client: bool,
+    message_type: Option<u8>,
+    ciaddr: Ipv4Addr,
+    yiaddr: Ipv4Addr,
+    chaddr: MacAddr,
+    options: HashMap<u8, Vec<u8>>,
+}
+
+impl DhcpMessage {
+    // Returns the IP / MAC pair assigned by a DHCP ACK or renewed by a
+    // client
+    fn lease(&self) -> Option<(Ipv4Addr, MacAddr)> {
+        // the server confirms the address it assigned to the client
+        if self.message_type == Some(DHCP_ACK) && !self.yiaddr.is_unspecified()
+        {
+            return Some((self.yiaddr, self.chaddr));
+        }
+
+        // clients renewing a lease already own the address they send from
+        if !self.ciaddr.is_unspecified() {
+            return Some((self.ciaddr, self.chaddr));
+        }
+
+        None
+    }
+
+    // Returns the options identifying the client of a DISCOVER or REQUEST
+    fn fingerprint(&self) -> Option<DhcpFingerprint> {
+        if !self.from_client
+            || !matches!(self.message_type, Some(DHCP_DISCOVER | DHCP_REQUEST))
+        {
+            return None;
+        }
+
+        let text = |code: u8| {
+            self.options
+                .get(&code)
+                .map(|v| {
+                    String::from_utf8_lossy(v)
+                        .trim_end_matches('\0')
+                        .to_string()
+                })
+                .filter(|v| !v.is_empty())
+        };
+
+        let fingerprint = DhcpFingerprint {
+            parameter_request_list: self
+                .options
+                .get(&OPTION_PARAMETER_REQUEST_LIST)
+                .cloned()
+                .unwrap_or_default(),
+            vendor_class: text(OPTION_VENDOR_CLASS),
+            hostname: text(OPTION_HOSTNAME),
+        };
+
+        (fingerprint != DhcpFingerprint::default()).then_some(fingerprint)
+    }
+}
+
+fn dhcp_message(header: &ipv4::Ipv4Packet) -> Option<DhcpMessage> {
+    if header.get_next_level_protocol() != ip::IpNextHeaderProtocols::Udp {
+        return None;
+    }
+
+    let datagram = udp::UdpPacket::new(header.payload())?;
+    let ports = (datagram.get_source(), datagram.get_destination());
+    let from_client = ports == (DHCP_CLIENT_PORT, DHCP_SERVER_PORT);
+
+    if ports != (DHCP_SERVER_PORT, DHCP_CLIENT_PORT) && !from_client {
+        return None;
+    }
+
+    let bootp = datagram.payload();
+
+    if bootp.len() < BOOTP_HEADER_SIZE + DHCP_MAGIC_COOKIE.len()
+        || bootp[BOOTP_HEADER_SIZE..BOOTP_HEADER_SIZE + 4] != DHCP_MAGIC_COOKIE
+    {
+        return None;
+    }
+
+    let octets = |at: usize| -> [u8; 4] {
+        [bootp[at], bootp[at + 1], bootp[at + 2], bootp[at + 3]]
+    };
+
+    let mut msg = DhcpMessage {
+        from_client,
+        message_type: None,
+        ciaddr: Ipv4Addr::from(octets(12)),
+        yiaddr: Ipv4Addr::from(octets(16)),
+        chaddr: MacAddr::new(
+            bootp[28], bootp[29], bootp[30], bootp[31], bootp[32], bootp[33],
+        ),
+        options: HashMap::new(),
+    };
+
+    let mut options = &bootp[BOOTP_HEADER_SIZE + 4..];
+
+    while let [code, rest @ ..] = options {
+        match *code {
+            DHCP_OPTION_PAD => options = rest,
+            DHCP_OPTION_END => break,
+            _ => {
+                let [len, rest @ ..] = rest else { break };
+                let len = usize::from(*len);
+                if rest.len() < len {
+                    break;
+                }
+                if *code == DHCP_OPTION_MESSAGE_TYPE && len == 1 {
+                    msg.message_type = Some(rest[0]);
+                }
+                msg.options.insert(*code, rest[..len].to_vec());
+                options = &rest[len..];
+            }
+        }
+    }
+
+    Some(msg)
+}
+
+// Implements the Scanner trait for PassiveScanner
+impl Scanner for PassiveScanner {
+    fn scan(&self) -> Result<ScanHandle> {
+        let control = ScanControl::new();
+        log::debug!("performing passive scan for {:?}", self.duration);
+        log::debug!("include_vendor: {}", self.include_vendor);
+
+        let self_clone = self.clone();
+        let (done_tx, done_rx) = sync::mpsc::channel::<()>();
+
+        let read_handle = self.read_packets(done_rx, control.clone());
+        let scan_control = control.clone();
+
+        // prevent blocking thread so messages can be freely sent to consumer
+        let scan_handle = thread::spawn(move || -> Result<()> {
+            // time spent paused doesn't count towards the listen duration
+            scan_control.sleep(self_clone.duration);
+
+            // ignore errors here as the thread may already be dead due to error
+            // we'll catch any errors from that thread below and report
+            let _ = done_tx.send(());
+
+            // nothing is sent to wake the reader, but the wire's read
+            // timeout returns an empty packet on a quiet network so it still
+            // notices done
+            let read_result = read_handle
+                .join()
+                .map_err(RLanLibError::from)
+                .and_then(|r| r);
+
+            self_clone.notifier.send(ScanMessage::Done)?;
+
+            read_result
+        });
+
+        Ok(ScanHandle::new(scan_handle, control))
+    }
+}
+
+#[cfg(test)]
+#[path = "./passive_scanner_tests.rs"]
+mod tests;
@@ -0,0 +1,284 @@
+use super::*;
+use pnet::packet::{MutablePacket, ethernet::MutableEthernetPacket};
+use pnet::util::MacAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::channel;
+
+use crate::{
+    network,
+    packet::udp_packet::UdpPacketBuilder,
+    wire::{
+        Reader, Sender,
+        mocks::{MockPacketReader, MockPacketSender},
+    },
+};
+
+const ROUTER_MAC: MacAddr = MacAddr(0x02, 0, 0, 0, 0, 0x01);
+const DEVICE_MAC: MacAddr = MacAddr(0x02, 0, 0, 0, 0, 0x02);
+const DHCP_CLIENT_MAC: MacAddr = MacAddr(0x02, 0, 0, 0, 0, 0x03);
+
+fn local_ip(interface: &NetworkInterface, n: usize) -> Ipv4Addr {
+    Ipv4Net::from_str(&interface.cidr)
+        .unwrap()
+        .hosts()
+        .nth(n)
+        .unwrap()
+}
+
+fn udp(
+    from_mac: MacAddr,
+    from_ip: Ipv4Addr,
+    from_port: u16,
+    to_ip: Ipv4Addr,
+    to_port: u16,
+) -> Vec<u8> {
+    UdpPacketBuilder::default()
+        .source_ip(from_ip)
+        .source_mac(from_mac)
+        .source_port(from_port)
+        .dest_ip(to_ip)
+        .dest_mac(MacAddr::broadcast())
+        .dest_port(to_port)
+        .build()
+        .unwrap()
+        .to_raw()
+        .to_vec()
+}
+
+// Builds a BOOTP payload for DHCP_CLIENT_MAC with the given options
+fn bootp(op: u8, yiaddr: Ipv4Addr, options: &[u8]) -> Vec<u8> {
+    let mut bootp = vec![0u8; BOOTP_HEADER_SIZE];
+    bootp[0] = op;
+    bootp[16..20].copy_from_slice(&yiaddr.octets());
+    bootp[28..34].copy_from_slice(&[0x02, 0, 0, 0, 0, 0x03]);
+    bootp.extend_from_slice(&DHCP_MAGIC_COOKIE);
+    bootp.extend_from_slice(options);
+    bootp.push(DHCP_OPTION_END);
+    bootp
+}
+
+// Builds a DHCP ACK from the server assigning `assigned` to the client
+fn dhcp_ack(server_ip: Ipv4Addr, assigned: Ipv4Addr) -> Vec<u8> {
+    let bootp = bootp(2, assigned, &[DHCP_OPTION_MESSAGE_TYPE, 1, DHCP_ACK]);
+
+    let pkt = udp(
+        ROUTER_MAC,
+        server_ip,
+        DHCP_SERVER_PORT,
+        Ipv4Addr::BROADCAST,
+        DHCP_CLIENT_PORT,
+    );
+
+    with_payload(pkt, &bootp)
+}
+
+// Builds a DHCP DISCOVER broadcast by a Windows client
+fn dhcp_discover() -> Vec<u8> {
+    let mut options = vec![DHCP_OPTION_MESSAGE_TYPE, 1, DHCP_DISCOVER];
+    options.extend_from_slice(&[OPTION_HOSTNAME, 10]);
+    options.extend_from_slice(b"DESKTOP-42");
+    options.extend_from_slice(&[OPTION_VENDOR_CLASS, 8]);
+    options.extend_from_slice(b"MSFT 5.0");
+    options.extend_from_slice(&[OPTION_PARAMETER_REQUEST_LIST, 4, 1, 3, 6, 15]);
+
+    let bootp = bootp(1, Ipv4Addr::UNSPECIFIED, &options);
+
+    let pkt = udp(
+        DHCP_CLIENT_MAC,
+        Ipv4Addr::UNSPECIFIED,
+        DHCP_CLIENT_PORT,
+        Ipv4Addr::BROADCAST,
+        DHCP_SERVER_PORT,
+    );
+
+    with_payload(pkt, &bootp)
+}
+
+// Replaces the payload of a udp packet with the bootp message
+fn with_payload(mut pkt: Vec<u8>, bootp: &[u8]) -> Vec<u8> {
+    // grow the udp template to fit the bootp payload
+    let udp_start = ethernet::EthernetPacket::minimum_packet_size()
+        + ipv4::Ipv4Packet::minimum_packet_size();
+    pkt.truncate(udp_start + udp::UdpPacket::minimum_packet_size());
+    pkt.extend_from_slice(bootp);
+
+    let mut eth = MutableEthernetPacket::new(&mut pkt).unwrap();
+    let mut ip = ipv4::MutableIpv4Packet::new(eth.payload_mut()).unwrap();
+    ip.set_total_length(
+        (ipv4::Ipv4Packet::minimum_packet_size()
+            + udp::UdpPacket::minimum_packet_size()
+            + bootp.len()) as u16,
+    );
+    let mut datagram = udp::MutableUdpPacket::new(ip.payload_mut()).unwrap();
+    datagram.set_length(
+        (udp::UdpPacket::minimum_packet_size() + bootp.len()) as u16,
+    );
+
+    pkt
+}
+
+fn scanner_with_packets(
+    interface: Arc<NetworkInterface>,
+    packets: Vec<Vec<u8>>,
+    notifier: sync::mpsc::Sender<ScanMessage>,
+) -> PassiveScanner {
+    let packets: &'static [Vec<u8>] = Box::leak(packets.into_boxed_slice());
+    let next = AtomicUsize::new(0);
+
+    let mut receiver = MockPacketReader::new();
+    receiver.expect_next_packet().returning(move || {
+        let i = next.fetch_add(1, Ordering::Relaxed);
+        Ok(packets[i % packets.len()].as_slice())
+    });
+    receiver.expect_next_packet_with_metadata().never();
+
+    // the passive scanner must never send anything
+    let mut sender = MockPacketSender::new();
+    sender.expect_send().never();
+
+    let sender: Arc<Mutex<dyn Sender>> = Arc::new(Mutex::new(sender));
+    let receiver: Arc<Mutex<dyn Reader>> = Arc::new(Mutex::new(receiver));
+
+    PassiveScanner::builder()
+        .interface(interface)
+        .wire(Wire(sender, receiver))
+        .duration(Duration::from_millis(500))
+        .notifier(notifier)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn new() {
+    let interface = Arc::new(network::get_default_interface().unwrap());
+    let (tx, _) = channel();
+
+    let scanner = scanner_with_packets(interface, vec![vec![]], tx);
+
+    assert_eq!(scanner.duration, Duration::from_millis(500));
+    assert!(!scanner.include_vendor);
+    assert!(scanner.oui.is_none());
+}
+
+#[test]
+#[allow(static_mut_refs)]
+fn learns_devices_from_observed_traffic() {
+    static mut ARP: [u8; 42] = [0u8; 42];
+
+    let interface = Arc::new(network::get_default_interface().unwrap());
+    let router_ip = local_ip(&interface, 0);
+    let device_ip = local_ip(&interface, 1);
+    let leased_ip = local_ip(&interface, 2);
+
+    crate::packet::arp_packet::create_arp_reply(
+        ROUTER_MAC,
+        router_ip,
+        interface.mac,
+        interface.ipv4,
+        unsafe { &mut ARP },
+    );
+
+    let packets = vec![
+        unsafe { ARP.to_vec() },
+        // mDNS announcement from a device on the local subnet
+        udp(
+            DEVICE_MAC,
+            device_ip,
+            5353,
+            Ipv4Addr::new(224, 0, 0, 251),
+            5353,
+        ),
+        // traffic from another network is relayed by the router
+        udp(
+            ROUTER_MAC,
+            Ipv4Addr::new(8, 8, 8, 8),
+            53,
+            interface.ipv4,
+            5000,
+        ),
+        dhcp_ack(router_ip, leased_ip),
+    ];
+
+    let (tx, rx) = channel();
+    let scanner = scanner_with_packets(interface, packets, tx);
+
+    let handle = scanner.scan().unwrap();
+
+    let mut detected = Vec::new();
+
+    loop {
+        match rx.recv().unwrap() {
+            ScanMessage::Done => break,
+            ScanMessage::ARPScanDevice(d) => detected.push((d.ip, d.mac)),
+            _ => {}
+        }
+    }
+
+    assert!(handle.join().unwrap().is_ok());
+
+    detected.sort();
+
+    // each pair is reported once even though the packets repeat
+    assert_eq!(
+        detected,
+        vec![
+            (router_ip, ROUTER_MAC),
+            (device_ip, DEVICE_MAC),
+            (leased_ip, DHCP_CLIENT_MAC),
+        ]
+    );
+}
+
+#[test]
+fn fingerprints_dhcp_clients() {
+    let interface = Arc::new(network::get_default_interface().unwrap());
+    let router_ip = local_ip(&interface, 0);
+    let leased_ip = local_ip(&interface, 2);
+
+    let packets = vec![dhcp_discover(), dhcp_ack(router_ip, leased_ip)];
+
+    let (tx, rx) = channel();
+    let scanner = scanner_with_packets(interface, packets, tx);
+
+    let handle = scanner.scan().unwrap();
+
+    let mut detected = Vec::new();
+
+    loop {
+        match rx.recv().unwrap() {
+            ScanMessage::Done => break,
+            ScanMessage::ARPScanDevice(d) => detected.push(d),
+            _ => {}
+        }
+    }
+
+    assert!(handle.join().unwrap().is_ok());
+
+    let client = detected.iter().find(|d| d.mac == DHCP_CLIENT_MAC).unwrap();
+
+    assert_eq!(client.ip, leased_ip);
+    assert_eq!(client.hostname, "DESKTOP-42");
+    assert_eq!(client.os_guess.as_ref().unwrap().os, "Windows");
+}
+
+#[test]
+fn joins_reader_on_quiet_network() {
+    let interface = Arc::new(network::get_default_interface().unwrap());
+    let (tx, rx) = channel();
+
+    // empty packets are what the wire returns when its read times out
+    let scanner = scanner_with_packets(interface, vec![vec![]], tx);
+
+    let handle = scanner.scan().unwrap();
+
+    loop {
+        if let ScanMessage::Done = rx.recv().unwrap() {
+            break;
+        }
+    }
+
+    handle.join().unwrap().unwrap();
+
+    // the reader has exited and released the wire
+    assert!(scanner.wire.1.try_lock().is_ok());
+}
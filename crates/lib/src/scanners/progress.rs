@@ -0,0 +1,102 @@
+//! Provides periodic progress reporting for scans
+//!
+//! Rather than notifying consumers of every probe, scanners count the probes
+//! they send and report a [`ScanMessage::Progress`] at most once per
+//! interval, plus once when sending starts and once when it ends.
+
+use std::time::{Duration, Instant};
+
+use crate::error::Result;
+
+use super::{ScanMessage, notifier::Notifier};
+
+/// Default interval between [`ScanMessage::Progress`] reports
+pub const DEFAULT_PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Estimates the time remaining to send `total` probes given that `sent`
+/// took `elapsed`. Returns None until at least one probe was sent
+pub fn eta(sent: usize, total: usize, elapsed: Duration) -> Option<Duration> {
+    if sent == 0 {
+        return None;
+    }
+
+    let remaining = total.saturating_sub(sent);
+
+    Some(elapsed.mul_f64(remaining as f64 / sent as f64))
+}
+
+// Counts the probes sent by a scan and reports progress to the consumer
+pub(crate) struct Progress {
+    notifier: Notifier,
+    total: usize,
+    interval: Duration,
+    started: Instant,
+    reported: Instant,
+    sent: usize,
+}
+
+impl Progress {
+    // Starts tracking and reports that nothing was sent yet so consumers
+    // learn the total straight away
+    pub(crate) fn start(
+        notifier: Notifier,
+        total: usize,
+        interval: Duration,
+    ) -> Result<Self> {
+        let now = Instant::now();
+
+        let mut progress = Self {
+            notifier,
+            total,
+            interval,
+            started: now,
+            reported: now,
+            sent: 0,
+        };
+
+        progress.report()?;
+
+        Ok(progress)
+    }
+
+    // Records a sent probe, reporting if the interval has passed
+    pub(crate) fn sent(&mut self) -> Result<()> {
+        self.sent += 1;
+
+        if self.reported.elapsed() >= self.interval {
+            self.report()?;
+        }
+
+        Ok(())
+    }
+
+    // Adds probes to the total, e.g. when unanswered targets are retried
+    pub(crate) fn add(&mut self, probes: usize) {
+        self.total += probes;
+    }
+
+    // Reports the final count once sending has ended. Retried targets may
+    // answer before they are probed again so the total becomes what was
+    // actually sent
+    pub(crate) fn finish(&mut self) -> Result<()> {
+        self.total = self.sent;
+        self.report()
+    }
+
+    fn report(&mut self) -> Result<()> {
+        let elapsed = self.started.elapsed();
+
+        self.reported = Instant::now();
+
+        self.notifier.send(ScanMessage::Progress {
+            sent: self.sent,
+            total: self.total,
+            elapsed,
+            eta: eta(self.sent, self.total, elapsed),
+        })
+    }
+}
+
+#[cfg(test)]
+#[path = "./progress_tests.rs"]
+mod tests;
@@ -0,0 +1,98 @@
+use super::*;
+use std::sync::mpsc::channel;
+
+#[test]
+fn eta_is_unknown_until_a_probe_is_sent() {
+    assert_eq!(eta(0, 10, Duration::from_secs(1)), None);
+}
+
+#[test]
+fn eta_extrapolates_send_rate() {
+    assert_eq!(
+        eta(25, 100, Duration::from_secs(5)),
+        Some(Duration::from_secs(15))
+    );
+    assert_eq!(eta(100, 100, Duration::from_secs(5)), Some(Duration::ZERO));
+}
+
+#[test]
+fn reports_at_start_interval_and_finish() {
+    let (tx, rx) = channel();
+
+    let mut progress =
+        Progress::start(tx.into(), 3, Duration::from_secs(60)).unwrap();
+
+    assert!(matches!(
+        rx.try_recv().unwrap(),
+        ScanMessage::Progress {
+            sent: 0,
+            total: 3,
+            eta: None,
+            ..
+        }
+    ));
+
+    progress.sent().unwrap();
+    progress.sent().unwrap();
+
+    // interval hasn't passed yet
+    assert!(rx.try_recv().is_err());
+
+    progress.sent().unwrap();
+    progress.finish().unwrap();
+
+    assert!(matches!(
+        rx.try_recv().unwrap(),
+        ScanMessage::Progress {
+            sent: 3,
+            total: 3,
+            eta: Some(Duration::ZERO),
+            ..
+        }
+    ));
+}
+
+#[test]
+fn reports_every_probe_with_zero_interval() {
+    let (tx, rx) = channel();
+
+    let mut progress = Progress::start(tx.into(), 2, Duration::ZERO).unwrap();
+    progress.sent().unwrap();
+    progress.sent().unwrap();
+
+    let sent: Vec<usize> = rx
+        .try_iter()
+        .map(|m| match m {
+            ScanMessage::Progress { sent, .. } => sent,
+            _ => panic!("unexpected message"),
+        })
+        .collect();
+
+    assert_eq!(sent, vec![0, 1, 2]);
+}
+
+#[test]
+fn finish_settles_total_on_probes_sent() {
+    let (tx, rx) = channel();
+
+    let mut progress =
+        Progress::start(tx.into(), 2, Duration::from_secs(60)).unwrap();
+    progress.sent().unwrap();
+    progress.sent().unwrap();
+
+    // two retries were planned but one target answered in the meantime
+    progress.add(2);
+    progress.sent().unwrap();
+    progress.finish().unwrap();
+
+    let last = rx.try_iter().last().unwrap();
+
+    assert!(matches!(
+        last,
+        ScanMessage::Progress {
+            sent: 3,
+            total: 3,
+            ..
+        }
+    ));
+}
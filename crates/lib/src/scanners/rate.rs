@@ -0,0 +1,333 @@
+//! Provides adaptive control of the rate at which probes are sent
+//!
+//! Like nmap's congestion control the rate starts at its maximum, backs off
+//! multiplicatively when loss is suspected and recovers gradually while
+//! probes get through. Loss is suspected when:
+//!
+//! - heartbeat packets take much longer than usual to come back off the
+//!   wire, meaning a queue is building up between us and the interface
+//! - scanners probing devices known to be up (SYN and UDP) see the share of
+//!   answered probes collapse
+//! - the kernel's count of packets the interface dropped on receive rises,
+//!   meaning replies arrive faster than they are read
+//!
+//! pnet does not expose the socket's PACKET_STATISTICS, so drops are read
+//! from the interface's `rx_dropped` counter in sysfs. Platforms without it
+//! rely on the other signals.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use super::ScanStats;
+
+/// Default lowest rate adaptive rate control backs off to, in probes per
+/// second
+pub const DEFAULT_MIN_RATE: u32 = 100;
+
+/// Default highest rate adaptive rate control starts at, in probes per
+/// second
+pub const DEFAULT_MAX_RATE: u32 = 20_000;
+
+// Number of probes between evaluations of the answer ratio
+const WINDOW: u32 = 256;
+
+// A window answering less than this share of the recent best is lossy
+const LOSS_THRESHOLD: f64 = 0.5;
+
+// How quickly the recent best answer ratio is forgotten per window
+const BASELINE_DECAY: f64 = 0.95;
+
+// Delay multiplier applied when backing off
+const BACKOFF: f64 = 2.0;
+
+// Delay multiplier applied after every window without loss
+const SPEED_UP: f64 = 0.9;
+
+// A heartbeat round trip this many times the fastest seen means queueing
+const QUEUEING_FACTOR: u32 = 4;
+
+// Round trips below this are never treated as queueing
+const MIN_QUEUEING_DELAY: Duration = Duration::from_millis(2);
+
+/// Bounds within which adaptive rate control keeps the send rate, in
+/// probes per second
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateBounds {
+    /// Lowest rate to back off to
+    pub min: u32,
+    /// Highest rate, also the rate scanning starts at
+    pub max: u32,
+}
+
+impl Default for RateBounds {
+    fn default() -> Self {
+        Self {
+            min: DEFAULT_MIN_RATE,
+            max: DEFAULT_MAX_RATE,
+        }
+    }
+}
+
+// Kernel counter of packets the interface dropped on receive
+#[derive(Debug)]
+struct DropCounter {
+    path: PathBuf,
+    last: u64,
+}
+
+impl DropCounter {
+    fn new(path: PathBuf) -> Option<Self> {
+        let last = read_counter(&path)?;
+        Some(Self { path, last })
+    }
+
+    // Returns whether the counter rose since it was last checked
+    fn rose(&mut self) -> bool {
+        let Some(current) = read_counter(&self.path) else {
+            return false;
+        };
+
+        let rose = current > self.last;
+        self.last = current;
+        rose
+    }
+}
+
+fn read_counter(path: &Path) -> Option<u64> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+#[derive(Debug, Default)]
+struct RateState {
+    delay: Duration,
+    min_delay: Duration,
+    max_delay: Duration,
+    adaptive: bool,
+    loss_from_answers: bool,
+    window_sent: u32,
+    window_answered: u32,
+    baseline: Option<f64>,
+    heartbeat_sent: Option<Instant>,
+    fastest_heartbeat: Option<Duration>,
+    drops: Option<DropCounter>,
+    sent: usize,
+    backoffs: usize,
+    rejected: usize,
+}
+
+impl RateState {
+    fn back_off(&mut self) {
+        let delay = self.delay.mul_f64(BACKOFF).min(self.max_delay);
+
+        if delay != self.delay {
+            log::debug!("loss suspected: slowing to {:?} per probe", delay);
+            self.delay = delay;
+            self.backoffs += 1;
+        }
+    }
+
+    fn speed_up(&mut self) {
+        self.delay = self.delay.mul_f64(SPEED_UP).max(self.min_delay);
+    }
+
+    fn evaluate_window(&mut self) {
+        let ratio = self.window_answered.min(self.window_sent) as f64
+            / self.window_sent as f64;
+
+        let dropped = self.drops.as_mut().is_some_and(DropCounter::rose);
+
+        if dropped {
+            log::debug!("interface dropped packets during the last window");
+        }
+
+        let lossy = dropped
+            || self.loss_from_answers
+                && self.baseline.is_some_and(|b| ratio < b * LOSS_THRESHOLD);
+
+        if lossy {
+            self.back_off();
+        } else {
+            self.speed_up();
+        }
+
+        if self.loss_from_answers {
+            self.baseline = Some(
+                self.baseline
+                    .map_or(ratio, |b| (b * BASELINE_DECAY).max(ratio)),
+            );
+        }
+
+        self.window_sent = 0;
+        self.window_answered = 0;
+    }
+}
+
+/// Paces the probes of a scan, shared between its sending, reading and
+/// heartbeat threads
+#[derive(Debug, Default, Clone)]
+pub struct RateController(Arc<Mutex<RateState>>);
+
+impl RateController {
+    /// Returns a controller sending a probe every `throttle`
+    pub fn fixed(throttle: Duration) -> Self {
+        let controller = Self::default();
+        controller.reset(throttle, None, false);
+        controller
+    }
+
+    /// Returns a controller adapting the rate within `bounds`. With
+    /// `loss_from_answers` a drop in the share of answered probes is
+    /// treated as loss, which only holds when the targets are known to be
+    /// up
+    pub fn adaptive(bounds: RateBounds, loss_from_answers: bool) -> Self {
+        let controller = Self::default();
+        controller.reset(Duration::ZERO, Some(bounds), loss_from_answers);
+        controller
+    }
+
+    // Starts over for a new scan, adapting within bounds if provided or
+    // sending every throttle otherwise
+    pub(crate) fn reset(
+        &self,
+        throttle: Duration,
+        bounds: Option<RateBounds>,
+        loss_from_answers: bool,
+    ) {
+        let mut state = self.state();
+
+        *state = match bounds {
+            Some(bounds) => {
+                let min = bounds.min.max(1);
+                let max = bounds.max.max(min);
+                let min_delay = Duration::from_secs(1) / max;
+
+                RateState {
+                    delay: min_delay,
+                    min_delay,
+                    max_delay: Duration::from_secs(1) / min,
+                    adaptive: true,
+                    loss_from_answers,
+                    ..RateState::default()
+                }
+            }
+            None => RateState {
+                delay: throttle,
+                min_delay: throttle,
+                max_delay: throttle,
+                ..RateState::default()
+            },
+        };
+    }
+
+    // Watches the kernel drop counter of the interface as a loss signal
+    // while adapting, if the platform exposes one
+    pub(crate) fn watch_drops(&self, interface: &str) {
+        self.watch_drops_at(PathBuf::from(format!(
+            "/sys/class/net/{interface}/statistics/rx_dropped"
+        )));
+    }
+
+    fn watch_drops_at(&self, path: PathBuf) {
+        let mut state = self.state();
+
+        if state.adaptive {
+            state.drops = DropCounter::new(path);
+        }
+    }
+
+    /// Returns the current delay between probes
+    pub fn delay(&self) -> Duration {
+        self.state().delay
+    }
+
+    /// Returns the current rate in probes per second, None if unthrottled
+    pub fn rate(&self) -> Option<u32> {
+        let delay = self.delay();
+
+        if delay.is_zero() {
+            return None;
+        }
+
+        Some((1.0 / delay.as_secs_f64()).round() as u32)
+    }
+
+    /// Returns statistics for the scan so far
+    pub fn stats(&self) -> ScanStats {
+        let rate = self.rate();
+        let state = self.state();
+
+        ScanStats {
+            sent: state.sent,
+            rate,
+            backoffs: state.backoffs,
+            rejected: state.rejected,
+        }
+    }
+
+    // Records that a probe was sent
+    pub(crate) fn sent(&self) {
+        let mut state = self.state();
+
+        state.sent += 1;
+
+        if !state.adaptive {
+            return;
+        }
+
+        state.window_sent += 1;
+
+        if state.window_sent >= WINDOW {
+            state.evaluate_window();
+        }
+    }
+
+    // Records that a probe was answered
+    pub(crate) fn answered(&self) {
+        self.state().window_answered += 1;
+    }
+
+    // Records that a reply was rejected as it didn't answer a probe
+    pub(crate) fn rejected(&self) {
+        self.state().rejected += 1;
+    }
+
+    // Records that a heartbeat is about to be sent
+    pub(crate) fn heartbeat_sent(&self) {
+        self.state().heartbeat_sent = Some(Instant::now());
+    }
+
+    // Records that the last heartbeat was read back off the wire
+    pub(crate) fn heartbeat_seen(&self) {
+        let mut state = self.state();
+
+        let Some(sent) = state.heartbeat_sent.take() else {
+            return;
+        };
+
+        let rtt = sent.elapsed();
+        let fastest = state.fastest_heartbeat.map_or(rtt, |f| f.min(rtt));
+
+        state.fastest_heartbeat = Some(fastest);
+
+        if state.adaptive
+            && rtt > MIN_QUEUEING_DELAY
+            && rtt > fastest * QUEUEING_FACTOR
+        {
+            log::debug!("heartbeat took {:?}, fastest {:?}", rtt, fastest);
+            state.back_off();
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, RateState> {
+        // the state is plain data so a poisoned lock is still usable
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+#[path = "./rate_tests.rs"]
+mod tests;
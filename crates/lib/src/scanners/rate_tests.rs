@@ -0,0 +1,162 @@
+use super::*;
+use std::io::Write;
+
+fn send_window(controller: &RateController, answered: u32) {
+    for n in 0..WINDOW {
+        controller.sent();
+        if n < answered {
+            controller.answered();
+        }
+    }
+}
+
+#[test]
+fn fixed_rate_never_adapts() {
+    let controller = RateController::fixed(Duration::from_millis(1));
+
+    send_window(&controller, WINDOW);
+    send_window(&controller, 0);
+    controller.heartbeat_sent();
+    controller.heartbeat_seen();
+
+    assert_eq!(controller.delay(), Duration::from_millis(1));
+    assert_eq!(
+        controller.stats(),
+        ScanStats {
+            sent: WINDOW as usize * 2,
+            rate: Some(1_000),
+            backoffs: 0,
+            rejected: 0,
+        }
+    );
+}
+
+#[test]
+fn unthrottled_has_no_rate() {
+    let controller = RateController::fixed(Duration::ZERO);
+    assert_eq!(controller.rate(), None);
+}
+
+#[test]
+fn adaptive_rate_starts_at_max() {
+    let controller = RateController::adaptive(
+        RateBounds {
+            min: 10,
+            max: 1_000,
+        },
+        true,
+    );
+
+    assert_eq!(controller.rate(), Some(1_000));
+}
+
+#[test]
+fn backs_off_when_answers_collapse_and_recovers() {
+    let controller = RateController::adaptive(
+        RateBounds {
+            min: 10,
+            max: 1_000,
+        },
+        true,
+    );
+
+    send_window(&controller, WINDOW);
+    assert_eq!(controller.rate(), Some(1_000));
+
+    send_window(&controller, WINDOW / 10);
+    assert_eq!(controller.rate(), Some(500));
+    assert_eq!(controller.stats().backoffs, 1);
+
+    send_window(&controller, WINDOW);
+    assert_eq!(controller.rate(), Some(556));
+}
+
+#[test]
+fn never_backs_off_below_min() {
+    let controller = RateController::adaptive(
+        RateBounds {
+            min: 400,
+            max: 1_000,
+        },
+        true,
+    );
+
+    send_window(&controller, WINDOW);
+    send_window(&controller, 0);
+    send_window(&controller, 0);
+
+    assert_eq!(controller.rate(), Some(400));
+    assert_eq!(controller.stats().backoffs, 2);
+}
+
+#[test]
+fn ignores_answers_for_targets_not_known_to_be_up() {
+    let controller = RateController::adaptive(
+        RateBounds {
+            min: 10,
+            max: 1_000,
+        },
+        false,
+    );
+
+    send_window(&controller, WINDOW);
+    send_window(&controller, 0);
+
+    assert_eq!(controller.rate(), Some(1_000));
+}
+
+#[test]
+fn backs_off_when_heartbeats_queue() {
+    let controller = RateController::adaptive(
+        RateBounds {
+            min: 10,
+            max: 1_000,
+        },
+        false,
+    );
+
+    controller.heartbeat_sent();
+    controller.heartbeat_seen();
+
+    controller.heartbeat_sent();
+    std::thread::sleep(MIN_QUEUEING_DELAY * 2);
+    controller.heartbeat_seen();
+
+    assert_eq!(controller.rate(), Some(500));
+}
+
+#[test]
+fn backs_off_when_the_interface_drops_packets() {
+    let mut counter = tempfile::NamedTempFile::new().unwrap();
+    write!(counter, "10").unwrap();
+
+    let controller = RateController::adaptive(
+        RateBounds {
+            min: 10,
+            max: 1_000,
+        },
+        false,
+    );
+    controller.watch_drops_at(counter.path().to_path_buf());
+
+    send_window(&controller, 0);
+    assert_eq!(controller.rate(), Some(1_000));
+
+    fs::write(counter.path(), "12\n").unwrap();
+    send_window(&controller, 0);
+
+    assert_eq!(controller.rate(), Some(500));
+    assert_eq!(controller.stats().backoffs, 1);
+
+    // only a rise since the last window counts
+    send_window(&controller, 0);
+    assert!(controller.rate().unwrap() > 500);
+}
+
+#[test]
+fn fixed_rate_ignores_drops() {
+    let controller = RateController::fixed(Duration::from_millis(1));
+    controller.watch_drops("lo");
+
+    assert!(controller.state().drops.is_none());
+}
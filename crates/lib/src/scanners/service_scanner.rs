@@ -0,0 +1,136 @@
+//! Provides Scanner implementation for detecting services on open ports
+//!
+//! Connects to every open port on the target devices and identifies the
+//! service, product and version using the probes in [`crate::probes`]. Like
+//! the connect scanner this uses ordinary OS sockets and does not require
+//! elevated privileges.
+
+use derive_builder::Builder;
+use std::{net::SocketAddr, thread, time::Duration};
+use threadpool::ThreadPool;
+
+use crate::{
+    error::Result,
+    probes,
+    scanners::{
+        PortSet, PortState,
+        connect_scanner::ConnectScanner,
+        handle::{ScanControl, ScanHandle},
+    },
+};
+
+use super::{Device, Port, ScanMessage, Scanner, notifier::Notifier};
+
+/// Default time to wait when connecting to and reading from a port
+pub const DEFAULT_PROBE_TIMEOUT: Duration = Duration::from_millis(2000);
+
+/// Default number of ports probed at once
+pub const DEFAULT_PROBE_CONCURRENCY: usize = 32;
+
+/// Data structure representing a service detection scanner
+#[derive(Clone, Builder)]
+#[builder(setter(into), pattern = "owned")]
+pub struct ServiceScanner {
+    /// Devices whose open ports should be probed
+    targets: Vec<Device>,
+    /// Time to wait for each connection and response
+    #[builder(default = DEFAULT_PROBE_TIMEOUT)]
+    timeout: Duration,
+    /// Maximum number of ports probed at once
+    #[builder(default = DEFAULT_PROBE_CONCURRENCY)]
+    concurrency: usize,
+    /// Channel for sending scan results and status messages
+    notifier: Notifier,
+}
+
+impl ServiceScanner {
+    /// Returns a builder for ServiceScanner
+    pub fn builder() -> ServiceScannerBuilder {
+        ServiceScannerBuilder::default()
+    }
+
+    fn process_device(
+        &self,
+        device: &Device,
+        pool: &ThreadPool,
+        control: &ScanControl,
+    ) {
+        let Some(target_ip) = ConnectScanner::target_ip(device) else {
+            return;
+        };
+
+        let open = device
+            .open_ports
+            .0
+            .iter()
+            .filter(|p| p.state == PortState::Open);
+
+        for port in open {
+            let addr = SocketAddr::new(target_ip, port.id);
+            let device = device.clone();
+            let port = port.clone();
+            let timeout = self.timeout;
+            let notifier = self.notifier.clone();
+            let control = control.clone();
+
+            pool.execute(move || {
+                // skip queued probes once cancelled
+                if control.checkpoint().is_err() {
+                    return;
+                }
+
+                let Some(found) = probes::identify(addr, timeout) else {
+                    log::debug!("{} service not identified", addr);
+                    return;
+                };
+
+                let mut ports = PortSet::new();
+                ports.0.insert(Port {
+                    service: found.service,
+                    product: found.product,
+                    version: found.version,
+                    ..port
+                });
+
+                let _ = notifier.send(ScanMessage::ServiceScanDevice(Device {
+                    open_ports: ports,
+                    ..device
+                }));
+            });
+        }
+    }
+}
+
+// Implements the Scanner trait for ServiceScanner
+impl Scanner for ServiceScanner {
+    fn scan(&self) -> Result<ScanHandle> {
+        let control = ScanControl::new();
+        log::debug!(
+            "performing service detection on targets: {:?}",
+            self.targets
+        );
+
+        let self_clone = self.clone();
+        let concurrency = self.concurrency.max(1);
+        let scan_control = control.clone();
+
+        // prevent blocking thread so messages can be freely sent to consumer
+        let handle = thread::spawn(move || -> Result<()> {
+            let pool = ThreadPool::new(concurrency);
+
+            for device in self_clone.targets.iter() {
+                self_clone.process_device(device, &pool, &scan_control);
+            }
+
+            pool.join();
+
+            self_clone.notifier.send(ScanMessage::Done)
+        });
+
+        Ok(ScanHandle::new(handle, control))
+    }
+}
+
+#[cfg(test)]
+#[path = "./service_scanner_tests.rs"]
+mod tests;
@@ -0,0 +1,74 @@
+use super::*;
+use std::io::Write;
+use std::net::{Ipv4Addr, TcpListener};
+use std::sync::mpsc::channel;
+
+#[test]
+fn new() {
+    let devices: Vec<Device> = Vec::new();
+    let (tx, _) = channel();
+
+    let scanner = ServiceScanner::builder()
+        .targets(devices.clone())
+        .notifier(tx)
+        .build()
+        .unwrap();
+
+    assert_eq!(scanner.targets, devices);
+    assert_eq!(scanner.timeout, DEFAULT_PROBE_TIMEOUT);
+    assert_eq!(scanner.concurrency, DEFAULT_PROBE_CONCURRENCY);
+}
+
+#[test]
+fn identifies_services_on_open_ports() {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        stream.write_all(b"SSH-2.0-OpenSSH_9.6p1\r\n").unwrap();
+    });
+
+    let mut open_ports = PortSet::new();
+    open_ports.0.insert(Port {
+        id: port,
+        ..Port::default()
+    });
+
+    let device = Device {
+        ip: Ipv4Addr::LOCALHOST,
+        open_ports,
+        ..Device::default()
+    };
+
+    let (tx, rx) = channel();
+
+    let scanner = ServiceScanner::builder()
+        .targets(vec![device])
+        .timeout(Duration::from_millis(500))
+        .notifier(tx)
+        .build()
+        .unwrap();
+
+    let handle = scanner.scan().unwrap();
+
+    let mut detected = Vec::new();
+
+    loop {
+        match rx.recv().unwrap() {
+            ScanMessage::Done => break,
+            ScanMessage::ServiceScanDevice(d) => detected.push(d),
+            _ => {}
+        }
+    }
+
+    assert!(handle.join().unwrap().is_ok());
+    assert_eq!(detected.len(), 1);
+
+    let found = detected[0].open_ports.to_sorted_vec();
+
+    assert_eq!(found[0].id, port);
+    assert_eq!(found[0].service, "ssh");
+    assert_eq!(found[0].product, Some("OpenSSH".into()));
+    assert_eq!(found[0].version, Some("9.6p1".into()));
+}
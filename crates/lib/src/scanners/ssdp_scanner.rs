@@ -0,0 +1,314 @@
+//! Provides Scanner implementation for SSDP / UPnP discovery
+//!
+//! Multicasts an SSDP M-SEARCH, collects the LOCATION of every responder and
+//! fetches the UPnP device description XML from it. The friendly name,
+//! manufacturer, model and serial number are attached to the device. Smart
+//! TVs, NAS boxes and routers describe themselves far better this way than
+//! the OUI vendor of their MAC does. Descriptions are only fetched from the
+//! responder itself, and redirects are not followed, so a spoofed response
+//! can't point the scanner at other hosts. Uses ordinary OS sockets so no
+//! elevated privileges are required.
+
+use derive_builder::Builder;
+use std::{
+    collections::HashMap,
+    io,
+    net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket},
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+use threadpool::ThreadPool;
+
+use crate::{
+    error::{RLanLibError, Result},
+    network::NetworkInterface,
+    scanners::{
+        Device, UpnpDescription,
+        handle::{ScanControl, ScanHandle},
+    },
+};
+
+use super::{ScanMessage, Scanner, notifier::Notifier};
+
+/// Default destination for SSDP searches
+pub const SSDP_ADDR: SocketAddr =
+    SocketAddr::new(IpAddr::V4(Ipv4Addr::new(239, 255, 255, 250)), 1900);
+
+/// Default time to wait for responses after the last one received
+pub const DEFAULT_SSDP_IDLE_TIMEOUT: Duration = Duration::from_millis(3000);
+
+/// Default time allowed for fetching a single device description
+pub const DEFAULT_DESCRIPTION_TIMEOUT: Duration = Duration::from_millis(2000);
+
+// maximum seconds responders may wait before answering, spreading replies
+// out so they don't flood the network. Must be below the idle timeout
+const MX_SECONDS: u8 = 2;
+
+// UPnP recommends a multicast TTL of 2
+const MULTICAST_TTL: u32 = 2;
+
+const READ_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Data structure representing an SSDP / UPnP scanner
+#[derive(Clone, Builder)]
+#[builder(setter(into), pattern = "owned")]
+pub struct SSDPScanner {
+    /// Network interface used to identify the current host in results
+    interface: Arc<NetworkInterface>,
+    /// Duration to wait for responses after the last one received
+    #[builder(default = DEFAULT_SSDP_IDLE_TIMEOUT)]
+    idle_timeout: Duration,
+    /// Time allowed for fetching each device description
+    #[builder(default = DEFAULT_DESCRIPTION_TIMEOUT)]
+    fetch_timeout: Duration,
+    /// Channel for sending scan results and status messages
+    notifier: Notifier,
+    /// Address searches are sent to, defaults to the SSDP multicast group
+    #[builder(default = SSDP_ADDR)]
+    destination: SocketAddr,
+}
+
+impl SSDPScanner {
+    /// Returns builder for SSDPScanner
+    pub fn builder() -> SSDPScannerBuilder {
+        SSDPScannerBuilder::default()
+    }
+
+    // Sends the search and collects description locations per responder
+    // until no new responses arrive for the idle timeout
+    fn search(
+        &self,
+        control: &ScanControl,
+    ) -> Result<HashMap<Ipv4Addr, Vec<String>>> {
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+            .map_err(socket_error)?;
+        socket
+            .set_multicast_ttl_v4(MULTICAST_TTL)
+            .map_err(socket_error)?;
+        socket
+            .set_read_timeout(Some(READ_TIMEOUT))
+            .map_err(socket_error)?;
+
+        let request = format!(
+            "M-SEARCH * HTTP/1.1\r\n\
+             HOST: {SSDP_ADDR}\r\n\
+             MAN: \"ssdp:discover\"\r\n\
+             MX: {MX_SECONDS}\r\n\
+             ST: ssdp:all\r\n\r\n"
+        );
+
+        log::debug!("sending SSDP search to {}", self.destination);
+
+        socket
+            .send_to(request.as_bytes(), self.destination)
+            .map_err(socket_error)?;
+
+        let mut locations: HashMap<Ipv4Addr, Vec<String>> = HashMap::new();
+        let mut last_activity = Instant::now();
+        let mut buf = [0u8; 2048];
+
+        while last_activity.elapsed() < self.idle_timeout {
+            if control.checkpoint().is_err() {
+                break;
+            }
+
+            match socket.recv_from(&mut buf) {
+                Ok((n, SocketAddr::V4(from))) => {
+                    last_activity = Instant::now();
+
+                    let response = String::from_utf8_lossy(&buf[..n]);
+
+                    let Some(location) = parse_location(&response) else {
+                        continue;
+                    };
+
+                    let found = locations.entry(*from.ip()).or_default();
+
+                    // devices answer once per embedded device and service
+                    // but most share a single description
+                    if !found.contains(&location) {
+                        found.push(location);
+                    }
+                }
+                Ok(_) => {}
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                    ) => {}
+                Err(e) => return Err(socket_error(e)),
+            }
+        }
+
+        Ok(locations)
+    }
+}
+
+fn socket_error(e: io::Error) -> RLanLibError {
+    RLanLibError::Scan {
+        error: format!("ssdp socket error: {e}"),
+        ip: None,
+        port: None,
+    }
+}
+
+// Returns the LOCATION header of an SSDP response if it is an http url
+fn parse_location(response: &str) -> Option<String> {
+    response.lines().skip(1).find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        let value = value.trim();
+
+        (name.trim().eq_ignore_ascii_case("location")
+            && value.starts_with("http://"))
+        .then(|| value.to_string())
+    })
+}
+
+// Returns whether the location url points at the responder's own ip
+fn is_hosted_by(location: &str, ip: Ipv4Addr) -> bool {
+    location
+        .parse::<ureq::http::Uri>()
+        .ok()
+        .and_then(|uri| uri.host()?.parse::<Ipv4Addr>().ok())
+        == Some(ip)
+}
+
+fn fetch_description(
+    ip: Ipv4Addr,
+    location: &str,
+    timeout: Duration,
+) -> Option<UpnpDescription> {
+    if !is_hosted_by(location, ip) {
+        log::debug!("ignoring description {} not hosted by {}", location, ip);
+        return None;
+    }
+
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .timeout_global(Some(timeout))
+        // devices are on the local network and never reachable via a proxy
+        .proxy(None)
+        // a redirect could lead anywhere
+        .max_redirects(0)
+        .build()
+        .into();
+
+    let xml = agent
+        .get(location)
+        .call()
+        .and_then(|mut r| {
+            if !r.status().is_success() {
+                return Err(ureq::Error::StatusCode(r.status().as_u16()));
+            }
+
+            r.body_mut().read_to_string()
+        })
+        .inspect_err(|e| {
+            log::debug!("failed to fetch description {}: {}", location, e)
+        })
+        .ok()?;
+
+    parse_description(&xml)
+}
+
+// Extracts the root device's details from a description document. Only the
+// first occurrence of each element is used, which belongs to the root
+// device as embedded devices are listed after it.
+fn parse_description(xml: &str) -> Option<UpnpDescription> {
+    let device = &xml[xml.find("<device>")?..];
+
+    let description = UpnpDescription {
+        friendly_name: element_text(device, "friendlyName"),
+        manufacturer: element_text(device, "manufacturer"),
+        model_name: element_text(device, "modelName"),
+        serial_number: element_text(device, "serialNumber"),
+    };
+
+    if description == UpnpDescription::default() {
+        return None;
+    }
+
+    Some(description)
+}
+
+fn element_text(xml: &str, name: &str) -> String {
+    let open = format!("<{name}>");
+    let close = format!("</{name}>");
+
+    let Some(start) = xml.find(&open).map(|i| i + open.len()) else {
+        return String::new();
+    };
+
+    let Some(len) = xml[start..].find(&close) else {
+        return String::new();
+    };
+
+    xml[start..start + len]
+        .trim()
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+// Implements the Scanner trait for SSDPScanner
+impl Scanner for SSDPScanner {
+    fn scan(&self) -> Result<ScanHandle> {
+        let control = ScanControl::new();
+        log::debug!("performing SSDP scan via {}", self.destination);
+
+        let self_clone = self.clone();
+        let scan_control = control.clone();
+
+        // prevent blocking thread so messages can be freely sent to consumer
+        let handle = thread::spawn(move || -> Result<()> {
+            let locations = self_clone.search(&scan_control)?;
+
+            // fetch descriptions concurrently as slow devices can take
+            // the full timeout to answer
+            let pool = ThreadPool::new(8);
+
+            for (ip, urls) in locations {
+                let notifier = self_clone.notifier.clone();
+                let timeout = self_clone.fetch_timeout;
+                let current_ip = self_clone.interface.ipv4;
+                let control = scan_control.clone();
+
+                pool.execute(move || {
+                    // skip queued fetches once cancelled
+                    if control.checkpoint().is_err() {
+                        return;
+                    }
+
+                    let Some(upnp) = urls
+                        .iter()
+                        .find_map(|url| fetch_description(ip, url, timeout))
+                    else {
+                        return;
+                    };
+
+                    log::debug!("SSDP discovered {:?} at {}", upnp, ip);
+
+                    let _ =
+                        notifier.send(ScanMessage::SSDPScanDevice(Device {
+                            ip,
+                            is_current_host: ip == current_ip,
+                            upnp: Some(upnp),
+                            ..Device::default()
+                        }));
+                });
+            }
+
+            pool.join();
+
+            self_clone.notifier.send(ScanMessage::Done)
+        });
+
+        Ok(ScanHandle::new(handle, control))
+    }
+}
+
+#[cfg(test)]
+#[path = "./ssdp_scanner_tests.rs"]
+mod tests;
@@ -0,0 +1,196 @@
+use super::*;
+use std::{
+    io::{Read, Write},
+    net::TcpListener,
+    sync::mpsc::channel,
+};
+
+use crate::network;
+
+const DESCRIPTION: &str = r#"<?xml version="1.0"?>
+<root xmlns="urn:schemas-upnp-org:device-1-0">
+  <device>
+    <deviceType>urn:schemas-upnp-org:device:MediaRenderer:1</deviceType>
+    <friendlyName>Living Room TV</friendlyName>
+    <manufacturer>Acme &amp; Sons</manufacturer>
+    <modelName>AX-55</modelName>
+    <serialNumber>SN1234</serialNumber>
+    <deviceList>
+      <device>
+        <friendlyName>Embedded</friendlyName>
+      </device>
+    </deviceList>
+  </device>
+</root>"#;
+
+// Serves the description to a single request
+fn spawn_http_server() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    thread::spawn(move || {
+        if let Ok((mut stream, _)) = listener.accept() {
+            let mut buf = [0u8; 1024];
+            let _ = stream.read(&mut buf);
+            let response = format!(
+                "HTTP/1.1 200 OK\r\n\
+                 Content-Type: text/xml\r\n\
+                 Content-Length: {}\r\n\
+                 Connection: close\r\n\r\n{}",
+                DESCRIPTION.len(),
+                DESCRIPTION
+            );
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+
+    format!("http://{addr}/description.xml")
+}
+
+// Redirects a single request to the location
+fn spawn_redirect_server(location: &str) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let response = format!(
+        "HTTP/1.1 302 Found\r\n\
+         Location: {location}\r\n\
+         Content-Length: 0\r\n\
+         Connection: close\r\n\r\n"
+    );
+
+    thread::spawn(move || {
+        if let Ok((mut stream, _)) = listener.accept() {
+            let mut buf = [0u8; 1024];
+            let _ = stream.read(&mut buf);
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+
+    format!("http://{addr}/description.xml")
+}
+
+// Answers the M-SEARCH twice with the same location, as devices do for
+// each of their services
+fn spawn_responder(location: String) -> SocketAddr {
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let addr = socket.local_addr().unwrap();
+
+    thread::spawn(move || {
+        let mut buf = [0u8; 1024];
+        if let Ok((n, from)) = socket.recv_from(&mut buf) {
+            assert!(buf[..n].starts_with(b"M-SEARCH * HTTP/1.1"));
+            for st in ["upnp:rootdevice", "urn:dial-multiscreen-org:service"] {
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\n\
+                     CACHE-CONTROL: max-age=1800\r\n\
+                     Location: {location}\r\n\
+                     ST: {st}\r\n\r\n"
+                );
+                let _ = socket.send_to(response.as_bytes(), from);
+            }
+        }
+    });
+
+    addr
+}
+
+#[test]
+fn new() {
+    let interface = Arc::new(network::get_default_interface().unwrap());
+    let (tx, _) = channel();
+
+    let scanner = SSDPScanner::builder()
+        .interface(interface)
+        .notifier(tx)
+        .build()
+        .unwrap();
+
+    assert_eq!(scanner.destination, SSDP_ADDR);
+    assert_eq!(scanner.idle_timeout, DEFAULT_SSDP_IDLE_TIMEOUT);
+    assert_eq!(scanner.fetch_timeout, DEFAULT_DESCRIPTION_TIMEOUT);
+}
+
+#[test]
+fn parses_location_and_description() {
+    let response = "HTTP/1.1 200 OK\r\nLOCATION: http://10.0.0.2:80/d.xml\r\n";
+    assert_eq!(
+        parse_location(response),
+        Some("http://10.0.0.2:80/d.xml".to_string())
+    );
+    assert_eq!(parse_location("HTTP/1.1 200 OK\r\nST: x\r\n"), None);
+
+    assert!(parse_description("<root></root>").is_none());
+    assert!(parse_description("<root><device></device></root>").is_none());
+}
+
+#[test]
+fn fetches_descriptions_of_responders() {
+    let interface = Arc::new(network::get_default_interface().unwrap());
+    let destination = spawn_responder(spawn_http_server());
+    let (tx, rx) = channel();
+
+    let scanner = SSDPScanner::builder()
+        .interface(interface)
+        .idle_timeout(Duration::from_millis(500))
+        .destination(destination)
+        .notifier(tx)
+        .build()
+        .unwrap();
+
+    let handle = scanner.scan().unwrap();
+
+    let mut detected = Vec::new();
+
+    loop {
+        match rx.recv().unwrap() {
+            ScanMessage::Done => break,
+            ScanMessage::SSDPScanDevice(d) => detected.push(d),
+            _ => {}
+        }
+    }
+
+    assert!(handle.join().unwrap().is_ok());
+    assert_eq!(detected.len(), 1);
+    assert_eq!(detected[0].ip, Ipv4Addr::LOCALHOST);
+    assert_eq!(
+        detected[0].upnp,
+        Some(UpnpDescription {
+            friendly_name: "Living Room TV".into(),
+            manufacturer: "Acme & Sons".into(),
+            model_name: "AX-55".into(),
+            serial_number: "SN1234".into(),
+        })
+    );
+}
+
+#[test]
+fn only_fetches_descriptions_hosted_by_responder() {
+    let ip = Ipv4Addr::new(10, 0, 0, 2);
+
+    assert!(is_hosted_by("http://10.0.0.2:80/d.xml", ip));
+    assert!(is_hosted_by("http://10.0.0.2/d.xml", ip));
+    assert!(!is_hosted_by("http://10.0.0.3:80/d.xml", ip));
+    assert!(!is_hosted_by("http://router.local/d.xml", ip));
+    assert!(!is_hosted_by("http://10.0.0.2.example.com/d.xml", ip));
+
+    let location = spawn_http_server();
+    let timeout = Duration::from_secs(1);
+
+    assert!(fetch_description(ip, &location, timeout).is_none());
+}
+
+#[test]
+fn does_not_follow_redirects() {
+    let location = spawn_http_server();
+    let redirect = spawn_redirect_server(&location);
+    let timeout = Duration::from_secs(1);
+
+    assert!(
+        fetch_description(Ipv4Addr::LOCALHOST, &redirect, timeout).is_none()
+    );
+
+    // the single request the server answers was not used by the redirect
+    assert!(
+        fetch_description(Ipv4Addr::LOCALHOST, &location, timeout).is_some()
+    );
+}
@@ -0,0 +1,149 @@
+//! Provides an async API for scanners, enabled with the `async` feature
+//!
+//! Scanners run on their own threads. [`ScanStreamBuilder::scan_stream`]
+//! installs a tokio channel as the scanner's
+//! [`Notifier`](super::notifier::Notifier) so the scan reports straight into
+//! a [`ScanStream`], which is consumed as a [`Stream`] without blocking the
+//! runtime. Dropping the stream cancels the scan.
+
+use futures_core::Stream;
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio::sync::mpsc;
+
+use crate::error::{RLanLibError, Result};
+
+use super::{
+    ScanMessage, Scanner,
+    arp_scanner::ARPScannerBuilder,
+    connect_scanner::ConnectScannerBuilder,
+    full_scanner::FullScannerBuilder,
+    handle::{ScanControl, ScanHandle},
+    icmp_scanner::ICMPScannerBuilder,
+    mdns_scanner::MDNSScannerBuilder,
+    ndp_scanner::NDPScannerBuilder,
+    passive_scanner::PassiveScannerBuilder,
+    service_scanner::ServiceScannerBuilder,
+    ssdp_scanner::SSDPScannerBuilder,
+    syn_scanner::SYNScannerBuilder,
+    udp_scanner::UDPScannerBuilder,
+};
+
+/// Stream of the messages reported by a running scan
+///
+/// Ends once the scanner reports [`ScanMessage::Done`] or fails. Dropping it
+/// cancels the scan through its [`ScanHandle`].
+#[derive(Debug)]
+pub struct ScanStream {
+    messages: mpsc::UnboundedReceiver<ScanMessage>,
+    handle: Option<ScanHandle>,
+    done: bool,
+}
+
+impl ScanStream {
+    /// Starts the scanner, which must report to the sending half of
+    /// `messages`
+    pub fn start(
+        scanner: &dyn Scanner,
+        messages: mpsc::UnboundedReceiver<ScanMessage>,
+    ) -> Result<Self> {
+        Ok(Self {
+            messages,
+            handle: Some(scanner.scan()?),
+            done: false,
+        })
+    }
+
+    /// Returns the control of the running scan, i.e. to pause and resume it
+    pub fn control(&self) -> Option<&ScanControl> {
+        self.handle.as_ref().map(ScanHandle::control)
+    }
+
+    /// Waits for the scan to finish and returns its result. Messages not yet
+    /// consumed from the stream are discarded
+    pub async fn join(mut self) -> Result<()> {
+        // drain rather than drop the messages so the scan isn't cancelled.
+        // The channel only closes once the scan's threads have dropped their
+        // notifiers, so joining below doesn't block the runtime
+        while self.messages.recv().await.is_some() {}
+
+        let Some(handle) = self.handle.take() else {
+            return Ok(());
+        };
+
+        handle.join().map_err(RLanLibError::from)?
+    }
+}
+
+impl Stream for ScanStream {
+    type Item = ScanMessage;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        if self.done {
+            return Poll::Ready(None);
+        }
+
+        let message = self.messages.poll_recv(cx);
+
+        if let Poll::Ready(Some(ScanMessage::Done)) = message {
+            self.done = true;
+        }
+
+        message
+    }
+}
+
+impl Drop for ScanStream {
+    fn drop(&mut self) {
+        // harmless once the scan has finished
+        if let Some(handle) = &self.handle {
+            log::debug!("scan stream dropped: cancelling scan");
+            handle.cancel();
+        }
+    }
+}
+
+/// Extends scanner builders with an async entry point, ending a chain of
+/// setters in place of `build`
+pub trait ScanStreamBuilder {
+    /// Builds the scanner reporting to a new tokio channel in place of the
+    /// notifier and starts it, returning the stream of its messages
+    fn scan_stream(self) -> Result<ScanStream>;
+}
+
+macro_rules! impl_scan_stream_builder {
+    ($($builder:ident),+ $(,)?) => {
+        $(
+            impl ScanStreamBuilder for $builder {
+                fn scan_stream(self) -> Result<ScanStream> {
+                    let (notifier, messages) = mpsc::unbounded_channel();
+                    let scanner = self.notifier(notifier).build()?;
+                    ScanStream::start(&scanner, messages)
+                }
+            }
+        )+
+    };
+}
+
+impl_scan_stream_builder!(
+    ARPScannerBuilder,
+    ConnectScannerBuilder,
+    FullScannerBuilder,
+    ICMPScannerBuilder,
+    MDNSScannerBuilder,
+    NDPScannerBuilder,
+    PassiveScannerBuilder,
+    ServiceScannerBuilder,
+    SSDPScannerBuilder,
+    SYNScannerBuilder,
+    UDPScannerBuilder,
+);
+
+#[cfg(test)]
+#[path = "./stream_tests.rs"]
+mod tests;
@@ -0,0 +1,133 @@
+use super::*;
+use std::{
+    net::Ipv4Addr,
+    sync::{self, mpsc::channel},
+    thread,
+    time::Duration,
+};
+
+use crate::scanners::{
+    MockScanner, Scanning, notifier::Notifier, service_scanner::ServiceScanner,
+};
+
+fn info(n: u8) -> ScanMessage {
+    ScanMessage::Info(Scanning {
+        ip: Ipv4Addr::new(192, 168, 0, n).into(),
+        port: None,
+    })
+}
+
+// Mocks a scanner that keeps reporting until cancelled, then signals
+// `stopped`
+fn endless_scanner(
+    notifier: Notifier,
+    stopped: sync::mpsc::Sender<()>,
+) -> MockScanner {
+    let mut scanner = MockScanner::new();
+    scanner.expect_scan().return_once(move || {
+        let control = ScanControl::new();
+        let scan_control = control.clone();
+
+        let handle = thread::spawn(move || {
+            let mut n = 0u8;
+            while scan_control.checkpoint().is_ok() {
+                notifier.send(info(n))?;
+                n = n.wrapping_add(1);
+                thread::sleep(Duration::from_millis(10));
+            }
+            let _ = stopped.send(());
+            Ok(())
+        });
+
+        Ok(ScanHandle::new(handle, control))
+    });
+    scanner
+}
+
+async fn next(stream: &mut ScanStream) -> Option<ScanMessage> {
+    std::future::poll_fn(|cx| Pin::new(&mut *stream).poll_next(cx)).await
+}
+
+#[tokio::test]
+async fn streams_messages_until_done() {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let notifier = Notifier::from(tx);
+
+    let mut scanner = MockScanner::new();
+    scanner.expect_scan().return_once(move || {
+        let handle = thread::spawn(move || {
+            for n in 1..=3 {
+                notifier.send(info(n))?;
+            }
+            notifier.send(ScanMessage::Done)?;
+            // reported after Done so must not be streamed
+            notifier.send(info(4))
+        });
+
+        Ok(ScanHandle::new(handle, ScanControl::new()))
+    });
+
+    let mut stream = ScanStream::start(&scanner, rx).unwrap();
+    let mut received = Vec::new();
+
+    while let Some(message) = next(&mut stream).await {
+        received.push(message);
+    }
+
+    assert_eq!(received.len(), 4);
+    assert!(matches!(received.last(), Some(ScanMessage::Done)));
+    assert!(stream.join().await.is_ok());
+}
+
+#[tokio::test]
+async fn join_returns_scan_error() {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let notifier = Notifier::from(tx);
+
+    let mut scanner = MockScanner::new();
+    scanner.expect_scan().return_once(move || {
+        let handle = thread::spawn(move || {
+            notifier.send(info(1))?;
+            Err(RLanLibError::ThreadError("boom".into()))
+        });
+
+        Ok(ScanHandle::new(handle, ScanControl::new()))
+    });
+
+    let stream = ScanStream::start(&scanner, rx).unwrap();
+
+    assert!(matches!(
+        stream.join().await,
+        Err(RLanLibError::ThreadError(e)) if e == "boom"
+    ));
+}
+
+#[tokio::test]
+async fn dropping_stream_cancels_scan() {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let (stopped_tx, stopped_rx) = channel();
+
+    let scanner = endless_scanner(tx.into(), stopped_tx);
+    let mut stream = ScanStream::start(&scanner, rx).unwrap();
+
+    assert!(matches!(
+        next(&mut stream).await,
+        Some(ScanMessage::Info(_))
+    ));
+
+    drop(stream);
+
+    assert!(stopped_rx.recv_timeout(Duration::from_secs(2)).is_ok());
+}
+
+#[tokio::test]
+async fn streams_scans_started_from_builders() {
+    let mut stream = ServiceScanner::builder()
+        .targets(vec![])
+        .scan_stream()
+        .unwrap();
+
+    assert!(matches!(next(&mut stream).await, Some(ScanMessage::Done)));
+    assert!(next(&mut stream).await.is_none());
+    assert!(stream.join().await.is_ok());
+}
@@ -1,57 +1,185 @@
 //! Provides Scanner implementation for SYN scanning
+//!
+//! Ports are classified by the reply to each probe:
+//!
+//! - a SYN-ACK means the port is [`PortState::Open`]
+//! - a RST means the port is [`PortState::Closed`]
+//! - an ICMP or ICMPv6 unreachable means the port is [`PortState::Filtered`]
+//! - silence after every retry means the port is [`PortState::Filtered`]
+//!
+//! Only open ports are reported unless `report_non_open` is set.
+//!
+//! The scanner can also send the probes of another [`TcpScanType`], which
+//! map firewall rules rather than find open ports:
+//!
+//! - ACK: a RST means [`PortState::Unfiltered`], silence or an ICMP
+//!   unreachable means [`PortState::Filtered`]
+//! - FIN, NULL and Xmas: a RST means [`PortState::Closed`], silence means
+//!   [`PortState::OpenFiltered`] and an ICMP unreachable means
+//!   [`PortState::Filtered`]
+//!
+//! These scan types have no open state so every port is reported.
+//!
+//! The initial sequence number of each probe is derived from a
+//! [`SequenceCookie`], so a SYN-ACK or RST is only classified when it
+//! answers the probe sent to its ip and port, and an ICMP unreachable only
+//! when it quotes that probe. Others are counted as rejected in the scan's
+//! stats.
+//!
+//! Devices are scanned over IPv4 unless their IPv4 is unspecified and they
+//! carry IPv6 addresses (i.e. IPv6-only devices from the NDP scanner), in
+//! which case they are scanned over IPv6.
 
 use derive_builder::Builder;
-use pnet::packet::{Packet, ethernet, ip, ipv4, tcp};
+use pnet::packet::{
+    Packet, ethernet,
+    icmp::{IcmpTypes, destination_unreachable},
+    icmpv6::{self, Icmpv6Code, Icmpv6Types},
+    ip, ipv4, ipv6, tcp,
+};
 use std::{
-    collections::HashMap,
-    net::Ipv4Addr,
-    sync::{self, Arc, LazyLock, mpsc},
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    net::{IpAddr, Ipv6Addr},
+    sync::{
+        self, Arc, Mutex,
+        mpsc::{self, TryRecvError},
+    },
     thread::{self, JoinHandle},
     time::Duration,
 };
 
 use crate::{
     error::{RLanLibError, Result},
+    fingerprint::{self, tcp::TcpFingerprint},
     network::NetworkInterface,
-    packet::{rst_packet::RstPacketBuilder, syn_packet::SynPacketBuilder},
-    scanners::{PortSet, Scanning, heartbeat::HeartBeat},
+    packet::{
+        heartbeat_packet::is_heartbeat, rst_packet::RstPacketBuilder,
+        rst6_packet::Rst6PacketBuilder, syn_packet::SynPacketBuilder,
+        syn6_packet::Syn6PacketBuilder, tcp_packet::TcpPacketBuilder,
+        tcp6_packet::Tcp6PacketBuilder,
+    },
+    scanners::{
+        DEFAULT_RETRY_DELAY, PortSet, PortState, Scanning,
+        cookie::SequenceCookie,
+        handle::{ScanControl, ScanHandle, loop_error},
+        heartbeat::HeartBeat,
+        progress::{DEFAULT_PROGRESS_INTERVAL, Progress},
+        rate::{RateBounds, RateController},
+    },
+    services::{
+        self,
+        traits::{Protocol, Services},
+    },
     targets::ports::PortTargets,
     wire::{DEFAULT_PACKET_SEND_TIMING, Wire},
 };
 
-use super::{Device, Port, ScanMessage, Scanner};
-
-static SERVICES: LazyLock<HashMap<u16, &str>> = LazyLock::new(|| {
-    HashMap::from([
-        (20, "ftp-data"),
-        (21, "ftp"),
-        (22, "ssh"),
-        (23, "telnet"),
-        (25, "smtp"),
-        (53, "dns"),
-        (80, "http"),
-        (110, "pop3"),
-        (143, "imap"),
-        (443, "https"),
-        (445, "microsoft-ds"),
-        (587, "submission"),
-        (993, "imaps"),
-        (995, "pop3s"),
-        (1433, "mssql"),
-        (3306, "mysql"),
-        (3389, "rdp"),
-        (5432, "postgresql"),
-        (5900, "vnc"),
-        (6379, "redis"),
-        (8080, "http-alt"),
-        (8443, "https-alt"),
-        (27017, "mongodb"),
-    ])
-});
+use super::{
+    Device, Port, ScanMessage, Scanner, for_each_probe, notifier::Notifier,
+};
+
+// ICMP unreachable codes sent by hosts and firewalls that reject a probe
+const FILTERED_CODES: [pnet::packet::icmp::IcmpCode; 6] = [
+    destination_unreachable::IcmpCodes::DestinationHostUnreachable,
+    destination_unreachable::IcmpCodes::DestinationProtocolUnreachable,
+    destination_unreachable::IcmpCodes::DestinationPortUnreachable,
+    destination_unreachable::IcmpCodes::NetworkAdministrativelyProhibited,
+    destination_unreachable::IcmpCodes::HostAdministrativelyProhibited,
+    destination_unreachable::IcmpCodes::CommunicationAdministrativelyProhibited,
+];
+
+// ICMPv6 unreachable codes sent by hosts and firewalls that reject a probe:
+// administratively prohibited, address and port unreachable, failed
+// ingress/egress policy and reject route
+const FILTERED_CODES_V6: [Icmpv6Code; 5] = [
+    Icmpv6Code(1),
+    Icmpv6Code(3),
+    Icmpv6Code(4),
+    Icmpv6Code(5),
+    Icmpv6Code(6),
+];
+
+/// The TCP flags a [`SYNScanner`] probes ports with
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TcpScanType {
+    /// SYN probes find open ports without completing a connection
+    #[default]
+    Syn,
+    /// ACK probes find which ports a stateful firewall filters, as hosts
+    /// answer an unexpected ACK with a RST whether the port is open or not
+    Ack,
+    /// FIN probes slip past firewalls that only block SYNs. Closed ports
+    /// answer with a RST while open ports stay silent
+    Fin,
+    /// Probes with no flags set, answered like FIN probes
+    Null,
+    /// Probes with the FIN, PSH and URG flags set, answered like FIN probes
+    Xmas,
+}
+
+impl TcpScanType {
+    /// Returns the TCP flags set on probes of this scan type
+    pub fn flags(&self) -> u8 {
+        match self {
+            Self::Syn => tcp::TcpFlags::SYN,
+            Self::Ack => tcp::TcpFlags::ACK,
+            Self::Fin => tcp::TcpFlags::FIN,
+            Self::Null => 0,
+            Self::Xmas => {
+                tcp::TcpFlags::FIN | tcp::TcpFlags::PSH | tcp::TcpFlags::URG
+            }
+        }
+    }
+
+    // Returns whether the RST answers a probe of this type sent with the
+    // sequence number. Per RFC 793 a RST takes its sequence number from the
+    // acknowledgement of the probe if it had one, and otherwise
+    // acknowledges the probe's sequence number plus its SYN and FIN flags
+    fn answered_by(&self, sequence: u32, rst: &tcp::TcpPacket) -> bool {
+        if self.flags() & tcp::TcpFlags::ACK != 0 {
+            return rst.get_sequence() == sequence;
+        }
+
+        let length = (self.flags() & (tcp::TcpFlags::SYN | tcp::TcpFlags::FIN)
+            != 0) as u32;
+
+        rst.get_flags() & tcp::TcpFlags::ACK != 0
+            && rst.get_acknowledgement() == sequence.wrapping_add(length)
+    }
+
+    // The state of a port that answered with a RST
+    fn rst_state(&self) -> PortState {
+        match self {
+            Self::Ack => PortState::Unfiltered,
+            _ => PortState::Closed,
+        }
+    }
+
+    // The state of a port that never answered
+    fn silent_state(&self) -> PortState {
+        match self {
+            Self::Syn | Self::Ack => PortState::Filtered,
+            _ => PortState::OpenFiltered,
+        }
+    }
+}
+
+impl Display for TcpScanType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Syn => write!(f, "syn"),
+            Self::Ack => write!(f, "ack"),
+            Self::Fin => write!(f, "fin"),
+            Self::Null => write!(f, "null"),
+            Self::Xmas => write!(f, "xmas"),
+        }
+    }
+}
 
 /// Data structure representing a SYN scanner
 #[derive(Clone, Builder)]
-#[builder(setter(into))]
+#[builder(setter(into), pattern = "owned")]
 pub struct SYNScanner {
     /// Network interface to use for scanning
     interface: Arc<NetworkInterface>,
@@ -69,8 +197,52 @@ pub struct SYNScanner {
     /// in more accurate scans
     #[builder(default = DEFAULT_PACKET_SEND_TIMING)]
     throttle: Duration,
+    /// Enables adaptive rate control within the given bounds, replacing
+    /// the fixed throttle
+    #[builder(default)]
+    adaptive_rate: Option<RateBounds>,
+    /// Seeds a random order in which device and port pairs are probed,
+    /// interleaving hosts and ports. Each port is probed on every device
+    /// before moving to the next when None
+    #[builder(default)]
+    seed: Option<u64>,
     /// Channel for sending scan results and status messages
-    notifier: mpsc::Sender<ScanMessage>,
+    notifier: Notifier,
+    /// Whether to send a ScanMessage::Info for every probe
+    #[builder(default)]
+    include_info: bool,
+    /// Interval between ScanMessage::Progress reports
+    #[builder(default = DEFAULT_PROGRESS_INTERVAL)]
+    progress_interval: Duration,
+    /// Number of times ports that have not answered are probed again
+    #[builder(default)]
+    retries: u8,
+    /// Time to wait for replies before unanswered ports are probed again
+    #[builder(default = DEFAULT_RETRY_DELAY)]
+    retry_delay: Duration,
+    /// Used to lookup the names of services on open ports
+    #[builder(default = "services::default()")]
+    services: Arc<dyn Services>,
+    /// Whether to also report closed and filtered ports. Ports that never
+    /// answer are reported as filtered once scanning ends
+    #[builder(default)]
+    report_non_open: bool,
+    /// Flags probes are sent with. Scan types other than SYN have no open
+    /// state so every port they classify is reported
+    #[builder(default)]
+    scan_type: TcpScanType,
+    /// Derives the sequence numbers of probes that replies must answer.
+    /// Keyed randomly unless set
+    #[builder(default)]
+    cookie: SequenceCookie,
+    /// Tracks every (ip, port) pair that answered with a SYN-ACK, RST or
+    /// ICMP unreachable so retries skip it and repeated replies are only
+    /// reported once
+    #[builder(setter(skip), default = "Arc::new(Mutex::new(HashSet::new()))")]
+    responded: Arc<Mutex<HashSet<(IpAddr, u16)>>>,
+    /// Paces probes and collects the statistics reported when done
+    #[builder(setter(skip))]
+    rate: RateController,
 }
 
 impl SYNScanner {
@@ -79,44 +251,221 @@ impl SYNScanner {
         SYNScannerBuilder::default()
     }
 
-    fn process_port(&self, port: u16) -> Result<()> {
-        for device in self.targets.iter() {
-            // throttle packet sending to prevent packet loss
-            thread::sleep(self.throttle);
+    // Returns the IP address at which the device should be scanned
+    fn target_ip(&self, device: &Device) -> Option<IpAddr> {
+        if !device.ip.is_unspecified() {
+            return Some(IpAddr::V4(device.ip));
+        }
+
+        // link-local addresses are always reachable from our own link-local
+        // source address so prefer them over global addresses
+        device
+            .ipv6
+            .iter()
+            .find(|ip| ip.is_unicast_link_local())
+            .or(device.ipv6.first())
+            .map(|ip| IpAddr::V6(*ip))
+    }
 
-            log::debug!("scanning SYN target: {}:{}", device.ip, port);
+    fn reports_non_open(&self) -> bool {
+        self.report_non_open || self.scan_type != TcpScanType::Syn
+    }
 
-            let dest_ipv4 = device.ip;
-            let dest_mac = device.mac;
+    fn service_name(&self, port: u16) -> String {
+        self.services
+            .lookup(port, Protocol::Tcp)
+            .unwrap_or_default()
+    }
 
-            let syn_packet = SynPacketBuilder::default()
-                .source_ip(self.interface.ipv4)
+    fn source_ipv6(&self) -> Result<Ipv6Addr> {
+        self.interface
+            .ipv6
+            .ok_or(RLanLibError::NetworkInterface(format!(
+                "interface {} has no IPv6 address",
+                self.interface.name
+            )))
+    }
+
+    // Records that the port answered, returning false if it already had
+    fn mark_responded(&self, ip: IpAddr, port: u16) -> bool {
+        self.responded
+            .lock()
+            .map(|mut responded| responded.insert((ip, port)))
+            .unwrap_or(true)
+    }
+
+    fn has_responded(&self, ip: IpAddr, port: u16) -> bool {
+        self.responded
+            .lock()
+            .map(|responded| responded.contains(&(ip, port)))
+            .unwrap_or(false)
+    }
+
+    fn send_probes(&self, control: &ScanControl) -> Result<()> {
+        let target_ips: Vec<IpAddr> = self
+            .targets
+            .iter()
+            .filter_map(|d| self.target_ip(d))
+            .collect();
+
+        let mut progress = Progress::start(
+            self.notifier.clone(),
+            target_ips.len() * self.ports.len(),
+            self.progress_interval,
+        )?;
+
+        for_each_probe(&self.targets, &self.ports, self.seed, |d, p| {
+            self.process_probe(d, p, false, control, &mut progress)
+        })?;
+
+        for retry in 1..=self.retries {
+            control.sleep(self.retry_delay);
+
+            let mut unanswered = 0;
+
+            // order doesn't matter when counting
+            for_each_probe(&self.targets, &self.ports, None, |d, p| {
+                if let Some(ip) = self.target_ip(d)
+                    && !self.has_responded(ip, p)
+                {
+                    unanswered += 1;
+                }
+                Ok(())
+            })?;
+
+            if unanswered == 0 {
+                break;
+            }
+
+            log::debug!(
+                "SYN retry {}: probing {} unanswered ports",
+                retry,
+                unanswered
+            );
+
+            progress.add(unanswered);
+
+            for_each_probe(&self.targets, &self.ports, self.seed, |d, p| {
+                self.process_probe(d, p, true, control, &mut progress)
+            })?;
+        }
+
+        progress.finish()
+    }
+
+    // Probes the port on the device, unless it already answered when
+    // retrying
+    fn process_probe(
+        &self,
+        device: &Device,
+        port: u16,
+        retry: bool,
+        control: &ScanControl,
+        progress: &mut Progress,
+    ) -> Result<()> {
+        let Some(target_ip) = self.target_ip(device) else {
+            return Ok(());
+        };
+
+        if retry && self.has_responded(target_ip, port) {
+            return Ok(());
+        }
+
+        control.checkpoint()?;
+
+        // throttle packet sending to prevent packet loss
+        thread::sleep(self.rate.delay());
+
+        log::debug!("scanning SYN target: {}:{}", target_ip, port);
+
+        // send info message to consumer
+        if self.include_info {
+            self.notifier.send(ScanMessage::Info(Scanning {
+                ip: target_ip,
+                port: Some(port),
+            }))?;
+        }
+
+        let dest_mac = device.mac;
+        let sequence = self.cookie.sequence(target_ip, port, self.source_port);
+        // hosts reset unexpected ACKs with the sequence number they
+        // acknowledge, so ACK probes carry the cookie there too
+        let acknowledgement = if self.scan_type == TcpScanType::Ack {
+            sequence
+        } else {
+            0
+        };
+
+        let pkt_buf = match target_ip {
+            IpAddr::V4(dest_ipv4) => {
+                if self.scan_type == TcpScanType::Syn {
+                    SynPacketBuilder::default()
+                        .source_ip(self.interface.ipv4)
+                        .source_mac(self.interface.mac)
+                        .source_port(self.source_port)
+                        .dest_ip(dest_ipv4)
+                        .dest_mac(dest_mac)
+                        .dest_port(port)
+                        .sequence_number(sequence)
+                        .build()?
+                        .to_raw()
+                        .to_vec()
+                } else {
+                    TcpPacketBuilder::default()
+                        .source_ip(self.interface.ipv4)
+                        .source_mac(self.interface.mac)
+                        .source_port(self.source_port)
+                        .dest_ip(dest_ipv4)
+                        .dest_mac(dest_mac)
+                        .dest_port(port)
+                        .flags(self.scan_type.flags())
+                        .sequence_number(sequence)
+                        .acknowledgement_number(acknowledgement)
+                        .build()?
+                        .to_raw()
+                        .to_vec()
+                }
+            }
+            IpAddr::V6(dest_ipv6) if self.scan_type == TcpScanType::Syn => {
+                Syn6PacketBuilder::default()
+                    .source_ip(self.source_ipv6()?)
+                    .source_mac(self.interface.mac)
+                    .source_port(self.source_port)
+                    .dest_ip(dest_ipv6)
+                    .dest_mac(dest_mac)
+                    .dest_port(port)
+                    .sequence_number(sequence)
+                    .build()?
+                    .to_raw()
+                    .to_vec()
+            }
+            IpAddr::V6(dest_ipv6) => Tcp6PacketBuilder::default()
+                .source_ip(self.source_ipv6()?)
                 .source_mac(self.interface.mac)
                 .source_port(self.source_port)
-                .dest_ip(dest_ipv4)
+                .dest_ip(dest_ipv6)
                 .dest_mac(dest_mac)
                 .dest_port(port)
-                .build()?;
-
-            let pkt_buf = syn_packet.to_raw();
+                .flags(self.scan_type.flags())
+                .sequence_number(sequence)
+                .acknowledgement_number(acknowledgement)
+                .build()?
+                .to_raw()
+                .to_vec(),
+        };
 
-            // send info message to consumer
-            self.notifier
-                .send(ScanMessage::Info(Scanning {
-                    ip: device.ip,
-                    port: Some(port),
-                }))
-                .map_err(RLanLibError::from_channel_send_error)?;
+        let mut sender = self.wire.0.lock()?;
 
-            let mut sender = self.wire.0.lock()?;
+        // scan device @ port
+        sender.send(&pkt_buf).map_err(|e| RLanLibError::Scan {
+            ip: Some(target_ip.to_string()),
+            port: Some(port.to_string()),
+            error: e.to_string(),
+        })?;
 
-            // scan device @ port
-            sender.send(&pkt_buf).map_err(|e| RLanLibError::Scan {
-                ip: Some(device.ip.to_string()),
-                port: Some(port.to_string()),
-                error: e.to_string(),
-            })?;
-        }
+        drop(sender);
+        self.rate.sent();
+        progress.sent()?;
 
         Ok(())
     }
@@ -124,20 +473,56 @@ impl SYNScanner {
     fn process_incoming_packet(
         &self,
         pkt: &[u8],
-        device_map: &HashMap<Ipv4Addr, Device>,
+        device_map: &HashMap<IpAddr, Device>,
     ) -> Result<()> {
         let Some(eth) = ethernet::EthernetPacket::new(pkt) else {
             return Ok(());
         };
 
-        let Some(header) = ipv4::Ipv4Packet::new(eth.payload()) else {
+        // the hop limit of an IPv6 header is recorded in place of the TTL.
+        // IPv6 has no don't fragment bit as routers never fragment
+        let (device_ip, response_ttl, dont_fragment, protocol, header_len) =
+            match eth.get_ethertype() {
+                ethernet::EtherTypes::Ipv6 => {
+                    let Some(header) = ipv6::Ipv6Packet::new(eth.payload())
+                    else {
+                        return Ok(());
+                    };
+                    (
+                        IpAddr::V6(header.get_source()),
+                        header.get_hop_limit(),
+                        None,
+                        header.get_next_header(),
+                        ipv6::Ipv6Packet::minimum_packet_size(),
+                    )
+                }
+                _ => {
+                    let Some(header) = ipv4::Ipv4Packet::new(eth.payload())
+                    else {
+                        return Ok(());
+                    };
+                    (
+                        IpAddr::V4(header.get_source()),
+                        header.get_ttl(),
+                        Some(
+                            header.get_flags() & ipv4::Ipv4Flags::DontFragment
+                                != 0,
+                        ),
+                        header.get_next_level_protocol(),
+                        header.get_header_length() as usize * 4,
+                    )
+                }
+            };
+
+        let Some(payload) = eth.payload().get(header_len..) else {
             return Ok(());
         };
 
-        let device_ip = header.get_source();
-        let response_ttl = header.get_ttl();
-        let protocol = header.get_next_level_protocol();
-        let payload = header.payload();
+        if protocol == ip::IpNextHeaderProtocols::Icmp
+            || protocol == ip::IpNextHeaderProtocols::Icmpv6
+        {
+            return self.process_unreachable(protocol, payload, device_map);
+        }
 
         if protocol != ip::IpNextHeaderProtocols::Tcp {
             return Ok(());
@@ -150,10 +535,10 @@ impl SYNScanner {
         let destination_port = tcp_packet.get_destination();
         let matches_destination = destination_port == self.source_port;
         let flags: u8 = tcp_packet.get_flags();
-        let sequence = tcp_packet.get_sequence();
+        let acknowledgement = tcp_packet.get_acknowledgement();
         let is_syn_ack = flags == tcp::TcpFlags::SYN + tcp::TcpFlags::ACK;
 
-        if !matches_destination || !is_syn_ack {
+        if !matches_destination {
             return Ok(());
         }
 
@@ -163,45 +548,309 @@ impl SYNScanner {
 
         let port = tcp_packet.get_source();
 
+        // a closed port still answered so it needn't be retried
+        if !is_syn_ack {
+            if flags & tcp::TcpFlags::RST != 0 {
+                let sequence =
+                    self.cookie.sequence(device_ip, port, self.source_port);
+
+                // stray or spoofed RSTs can't answer our sequence number
+                if !self.scan_type.answered_by(sequence, &tcp_packet) {
+                    log::debug!(
+                        "rejecting RST from {}:{} answering {}/{}",
+                        device_ip,
+                        port,
+                        tcp_packet.get_sequence(),
+                        acknowledgement
+                    );
+                    self.rate.rejected();
+                    return Ok(());
+                }
+
+                self.rate.answered();
+                return self.report_non_open_port(
+                    device,
+                    device_ip,
+                    port,
+                    self.scan_type.rst_state(),
+                );
+            }
+            return Ok(());
+        }
+
+        // only SYN probes are answered with a SYN-ACK
+        if self.scan_type != TcpScanType::Syn {
+            return Ok(());
+        }
+
+        // stray or spoofed packets can't acknowledge our sequence number
+        if !self.cookie.acknowledges_syn(
+            device_ip,
+            port,
+            self.source_port,
+            acknowledgement,
+        ) {
+            log::debug!(
+                "rejecting SYN-ACK from {}:{} acknowledging {}",
+                device_ip,
+                port,
+                acknowledgement
+            );
+            self.rate.rejected();
+            return Ok(());
+        }
+
+        self.rate.answered();
+
         // send rst packet to prevent SYN Flooding
         // https://en.wikipedia.org/wiki/SYN_flood
         // https://security.stackexchange.com/questions/128196/whats-the-advantage-of-sending-an-rst-packet-after-getting-a-response-in-a-syn
-        let dest_ipv4 = device.ip;
         let dest_mac = device.mac;
 
-        let rst_packet = RstPacketBuilder::default()
-            .source_ip(self.interface.ipv4)
-            .source_mac(self.interface.mac)
-            .source_port(self.source_port)
-            .dest_ip(dest_ipv4)
-            .dest_mac(dest_mac)
-            .dest_port(port)
-            .sequence_number(sequence + 1)
-            .build()?;
-
-        let rst_packet = rst_packet.to_raw();
+        // the RST continues from the sequence number the SYN-ACK acknowledged
+        let rst_packet = match device_ip {
+            IpAddr::V4(dest_ipv4) => RstPacketBuilder::default()
+                .source_ip(self.interface.ipv4)
+                .source_mac(self.interface.mac)
+                .source_port(self.source_port)
+                .dest_ip(dest_ipv4)
+                .dest_mac(dest_mac)
+                .dest_port(port)
+                .sequence_number(acknowledgement)
+                .build()?
+                .to_raw()
+                .to_vec(),
+            IpAddr::V6(dest_ipv6) => Rst6PacketBuilder::default()
+                .source_ip(self.source_ipv6()?)
+                .source_mac(self.interface.mac)
+                .source_port(self.source_port)
+                .dest_ip(dest_ipv6)
+                .dest_mac(dest_mac)
+                .dest_port(port)
+                .sequence_number(acknowledgement)
+                .build()?
+                .to_raw()
+                .to_vec(),
+        };
 
         let mut rst_sender = self.wire.0.lock()?;
 
-        log::debug!("sending RST packet to {}:{}", device.ip, port);
+        log::debug!("sending RST packet to {}:{}", device_ip, port);
 
         rst_sender.send(&rst_packet)?;
+        drop(rst_sender);
+
+        // retried ports may answer every probe
+        if !self.mark_responded(device_ip, port) {
+            return Ok(());
+        }
+
+        let mut ports = PortSet::new();
+        ports.0.insert(Port {
+            id: port,
+            service: self.service_name(port),
+            state: PortState::Open,
+            ..Port::default()
+        });
+
+        let fingerprint =
+            TcpFingerprint::new(response_ttl, dont_fragment, &tcp_packet);
+
+        log::debug!("{} tcp fingerprint: {:?}", device_ip, fingerprint);
+
+        self.notifier.send(ScanMessage::SYNScanDevice(Device {
+            open_ports: ports,
+            response_ttl: Some(response_ttl),
+            os_guess: fingerprint::most_confident(
+                device.os_guess.clone(),
+                fingerprint.guess(),
+            ),
+            ..device.clone()
+        }))?;
+
+        Ok(())
+    }
+
+    // Extracts the device ip and quoted TCP header of a probe rejected by an
+    // ICMP unreachable
+    fn quoted_ipv4_probe(payload: &[u8]) -> Option<(IpAddr, &[u8])> {
+        let icmp_packet =
+            destination_unreachable::DestinationUnreachablePacket::new(
+                payload,
+            )?;
+
+        if icmp_packet.get_icmp_type() != IcmpTypes::DestinationUnreachable
+            || !FILTERED_CODES.contains(&icmp_packet.get_icmp_code())
+        {
+            return None;
+        }
+
+        // the quoted packet follows the type, code, checksum and 4 unused
+        // bytes
+        let quote = payload.get(8..)?;
+        let quoted = ipv4::Ipv4Packet::new(quote)?;
+
+        if quoted.get_next_level_protocol() != ip::IpNextHeaderProtocols::Tcp {
+            return None;
+        }
+
+        let header_len = quoted.get_header_length() as usize * 4;
+
+        Some((
+            IpAddr::V4(quoted.get_destination()),
+            quote.get(header_len..)?,
+        ))
+    }
+
+    // Extracts the device ip and quoted TCP header of a probe rejected by an
+    // ICMPv6 unreachable
+    fn quoted_ipv6_probe(payload: &[u8]) -> Option<(IpAddr, &[u8])> {
+        let icmp_packet = icmpv6::Icmpv6Packet::new(payload)?;
+
+        if icmp_packet.get_icmpv6_type() != Icmpv6Types::DestinationUnreachable
+            || !FILTERED_CODES_V6.contains(&icmp_packet.get_icmpv6_code())
+        {
+            return None;
+        }
+
+        let quote = payload.get(8..)?;
+        let quoted = ipv6::Ipv6Packet::new(quote)?;
+
+        if quoted.get_next_header() != ip::IpNextHeaderProtocols::Tcp {
+            return None;
+        }
+
+        Some((
+            IpAddr::V6(quoted.get_destination()),
+            quote.get(ipv6::Ipv6Packet::minimum_packet_size()..)?,
+        ))
+    }
+
+    // Extracts the (device ip, port, sequence number) of a probe rejected by
+    // an ICMP or ICMPv6 unreachable
+    fn classify_unreachable(
+        &self,
+        protocol: ip::IpNextHeaderProtocol,
+        payload: &[u8],
+    ) -> Option<(IpAddr, u16, u32)> {
+        let (device_ip, tcp_header) =
+            if protocol == ip::IpNextHeaderProtocols::Icmpv6 {
+                Self::quoted_ipv6_probe(payload)?
+            } else {
+                Self::quoted_ipv4_probe(payload)?
+            };
+
+        // only the first 8 bytes of the TCP header are quoted, which is too
+        // short for a TcpPacket but holds both ports and the sequence number
+        let header = tcp_header.get(..8)?;
+
+        if u16::from_be_bytes([header[0], header[1]]) != self.source_port {
+            return None;
+        }
+
+        Some((
+            device_ip,
+            u16::from_be_bytes([header[2], header[3]]),
+            u32::from_be_bytes([header[4], header[5], header[6], header[7]]),
+        ))
+    }
+
+    fn process_unreachable(
+        &self,
+        protocol: ip::IpNextHeaderProtocol,
+        payload: &[u8],
+        device_map: &HashMap<IpAddr, Device>,
+    ) -> Result<()> {
+        let Some((device_ip, port, sequence)) =
+            self.classify_unreachable(protocol, payload)
+        else {
+            return Ok(());
+        };
+
+        let Some(device) = device_map.get(&device_ip) else {
+            return Ok(());
+        };
 
-        let service = SERVICES
-            .get(&port)
-            .map(|s| s.to_string())
-            .unwrap_or_default();
+        // a genuine error quotes the probe we sent, cookie and all
+        if sequence != self.cookie.sequence(device_ip, port, self.source_port) {
+            log::debug!(
+                "rejecting ICMP unreachable for {}:{} quoting {}",
+                device_ip,
+                port,
+                sequence
+            );
+            self.rate.rejected();
+            return Ok(());
+        }
+
+        self.rate.answered();
+
+        self.report_non_open_port(device, device_ip, port, PortState::Filtered)
+    }
+
+    // Records the answer and reports the port if non-open ports are
+    // reported
+    fn report_non_open_port(
+        &self,
+        device: &Device,
+        device_ip: IpAddr,
+        port: u16,
+        state: PortState,
+    ) -> Result<()> {
+        // retried ports may answer every probe
+        if !self.mark_responded(device_ip, port) || !self.reports_non_open() {
+            return Ok(());
+        }
+
+        log::debug!("SYN port {}:{} is {}", device_ip, port, state);
 
         let mut ports = PortSet::new();
-        ports.0.insert(Port { id: port, service });
+        ports.0.insert(Port {
+            id: port,
+            service: self.service_name(port),
+            state,
+            ..Port::default()
+        });
 
-        self.notifier
-            .send(ScanMessage::SYNScanDevice(Device {
+        self.notifier.send(ScanMessage::SYNScanDevice(Device {
+            open_ports: ports,
+            ..device.clone()
+        }))
+    }
+
+    // Reports every probed port that never answered
+    fn report_unanswered(&self) -> Result<()> {
+        let responded =
+            self.responded.lock().map(|r| r.clone()).unwrap_or_default();
+
+        for device in self.targets.iter() {
+            let Some(ip) = self.target_ip(device) else {
+                continue;
+            };
+
+            let mut ports = PortSet::new();
+
+            self.ports.lazy_loop(|port| {
+                if !responded.contains(&(ip, port)) {
+                    ports.0.insert(Port {
+                        id: port,
+                        service: self.service_name(port),
+                        state: self.scan_type.silent_state(),
+                        ..Port::default()
+                    });
+                }
+                Ok(())
+            })?;
+
+            if ports.0.is_empty() {
+                continue;
+            }
+
+            self.notifier.send(ScanMessage::SYNScanDevice(Device {
                 open_ports: ports,
-                response_ttl: Some(response_ttl),
                 ..device.clone()
-            }))
-            .map_err(RLanLibError::from_channel_send_error)?;
+            }))?;
+        }
 
         Ok(())
     }
@@ -211,6 +860,7 @@ impl SYNScanner {
     fn read_packets(
         &self,
         done_rx: mpsc::Receiver<()>,
+        control: ScanControl,
     ) -> Result<JoinHandle<Result<()>>> {
         let self_clone = self.clone();
         let (heartbeat_tx, heartbeat_rx) = sync::mpsc::channel::<()>();
@@ -220,6 +870,8 @@ impl SYNScanner {
             .source_ipv4(self.interface.ipv4)
             .source_port(self.source_port)
             .packet_sender(Arc::clone(&self.wire.0))
+            .control(control.clone())
+            .rate(self.rate.clone())
             .build()?;
 
         let heart_handle = heartbeat.start_in_thread(heartbeat_rx)?;
@@ -228,14 +880,19 @@ impl SYNScanner {
             let mut reader = self_clone.wire.1.lock()?;
 
             // Build a HashMap for O(1) device lookups instead of O(n) linear search
-            let device_map: HashMap<Ipv4Addr, Device> = self_clone
+            let device_map: HashMap<IpAddr, Device> = self_clone
                 .targets
                 .iter()
-                .map(|d| (d.ip, d.clone()))
+                .filter_map(|d| {
+                    self_clone.target_ip(d).map(|ip| (ip, d.clone()))
+                })
                 .collect();
 
             loop {
-                if done_rx.try_recv().is_ok() {
+                // also stop if the scan thread went away without signalling
+                if control.is_cancelled()
+                    || !matches!(done_rx.try_recv(), Err(TryRecvError::Empty))
+                {
                     log::debug!("exiting syn packet reader");
                     if let Err(e) = heartbeat_tx.send(()) {
                         log::error!("failed to stop heartbeat: {}", e);
@@ -245,6 +902,16 @@ impl SYNScanner {
                 }
 
                 let pkt = reader.next_packet()?;
+
+                if is_heartbeat(
+                    pkt,
+                    self_clone.interface.mac,
+                    self_clone.source_port,
+                ) {
+                    self_clone.rate.heartbeat_seen();
+                    continue;
+                }
+
                 self_clone.process_incoming_packet(pkt, &device_map)?;
             }
 
@@ -253,42 +920,66 @@ impl SYNScanner {
             Ok(())
         }))
     }
-}
 
-// Implements the Scanner trait for SYNScanner
-impl Scanner for SYNScanner {
-    fn scan(&self) -> Result<JoinHandle<Result<()>>> {
+    // Starts the scan under the given control, shared with the FullScanner
+    pub(crate) fn start(
+        &self,
+        control: ScanControl,
+    ) -> Result<JoinHandle<Result<()>>> {
         log::debug!("performing SYN scan on targets: {:?}", self.targets);
 
+        if let Ok(mut responded) = self.responded.lock() {
+            responded.clear();
+        }
+
+        // targets were discovered up so open and closed ports both answer
+        // SYN and ACK probes, making a drop in answers a sign of loss. Open
+        // ports never answer FIN, NULL and Xmas probes
+        self.rate.reset(
+            self.throttle,
+            self.adaptive_rate,
+            matches!(self.scan_type, TcpScanType::Syn | TcpScanType::Ack),
+        );
+        self.rate.watch_drops(&self.interface.name);
+
         let self_clone = self.clone();
         let (done_tx, done_rx) = mpsc::channel::<()>();
 
         log::debug!("starting syn packet reader");
 
-        let read_handle = self.read_packets(done_rx)?;
+        let read_handle = self.read_packets(done_rx, control.clone())?;
 
         // prevent blocking thread so messages can be freely sent to consumer
         let handle = thread::spawn(move || -> Result<()> {
-            let mut scan_error: Option<RLanLibError> = None;
+            let mut scan_error = loop_error(self_clone.send_probes(&control));
+
+            control.sleep(self_clone.idle_timeout);
+
+            // ignore errors here as the thread may already be dead due to error
+            // we'll catch any errors from that thread below and report
+            let _ = done_tx.send(());
 
-            if let Err(err) =
-                self_clone.ports.lazy_loop(|p| self_clone.process_port(p))
+            // Done must still be sent if the reader panicked
+            let read_result = read_handle
+                .join()
+                .map_err(RLanLibError::from)
+                .and_then(|result| result);
+
+            // like UDP, the reader must be stopped before unanswered ports
+            // are reported so a late reply isn't reported twice
+            if self_clone.reports_non_open()
+                && scan_error.is_none()
+                && !control.is_cancelled()
+                && let Err(err) = self_clone.report_unanswered()
             {
                 scan_error = Some(err);
             }
 
-            thread::sleep(self_clone.idle_timeout);
-
             self_clone
                 .notifier
-                .send(ScanMessage::Done)
-                .map_err(RLanLibError::from_channel_send_error)?;
-
-            // ignore errors here as the thread may already be dead due to error
-            // we'll catch any errors from that thread below and report
-            let _ = done_tx.send(());
+                .send(ScanMessage::Stats(self_clone.rate.stats()))?;
 
-            let read_result = read_handle.join()?;
+            self_clone.notifier.send(ScanMessage::Done)?;
 
             if let Some(err) = scan_error {
                 return Err(err);
@@ -301,6 +992,15 @@ impl Scanner for SYNScanner {
     }
 }
 
+// Implements the Scanner trait for SYNScanner
+impl Scanner for SYNScanner {
+    fn scan(&self) -> Result<ScanHandle> {
+        let control = ScanControl::new();
+        let handle = self.start(control.clone())?;
+        Ok(ScanHandle::new(handle, control))
+    }
+}
+
 #[cfg(test)]
 #[path = "./syn_scanner_tests.rs"]
 mod tests;
@@ -1,6 +1,6 @@
 use super::*;
 use core::net;
-use pnet::packet::{arp, ethernet, ipv4, tcp};
+use pnet::packet::{arp, ethernet, ipv4, ipv6, tcp};
 use pnet::util;
 use std::str::FromStr;
 use std::sync::mpsc::channel;
@@ -10,7 +10,11 @@ use std::time::Duration;
 use crate::{
     network,
     packet::arp_packet::create_arp_reply,
-    packet::syn_packet::create_syn_reply,
+    packet::syn_packet::{create_syn_icmp_unreachable_reply, create_syn_reply},
+    packet::syn6_packet::{
+        create_syn6_icmp_unreachable_reply, create_syn6_reply,
+    },
+    scanners::ScanStats,
     wire::mocks::{MockPacketReader, MockPacketSender},
     wire::{Reader, Sender},
 };
@@ -23,6 +27,24 @@ const PKT_IP4_SIZE: usize = ipv4::Ipv4Packet::minimum_packet_size();
 const PKT_TCP_SIZE: usize = tcp::TcpPacket::minimum_packet_size();
 const PKT_TOTAL_SYN_SIZE: usize = PKT_ETH_SIZE + PKT_IP4_SIZE + PKT_TCP_SIZE;
 
+const PKT_TOTAL_ICMP_SIZE: usize =
+    PKT_ETH_SIZE + PKT_IP4_SIZE + 8 + PKT_IP4_SIZE + 8;
+
+const PKT_IP6_SIZE: usize = ipv6::Ipv6Packet::minimum_packet_size();
+const PKT_TOTAL_SYN6_SIZE: usize = PKT_ETH_SIZE + PKT_IP6_SIZE + PKT_TCP_SIZE;
+
+const PKT_TOTAL_ICMP6_SIZE: usize =
+    PKT_ETH_SIZE + PKT_IP6_SIZE + 8 + PKT_IP6_SIZE + 8;
+
+const COOKIE_KEY: u64 = 42;
+
+// Returns the acknowledgement a genuine SYN-ACK from ip:port carries
+fn syn_ack(ip: impl Into<IpAddr>, port: u16) -> u32 {
+    SequenceCookie::new(COOKIE_KEY)
+        .sequence(ip.into(), port, 54321)
+        .wrapping_add(1)
+}
+
 #[test]
 fn new() {
     let interface = Arc::new(network::get_default_interface().unwrap());
@@ -71,6 +93,7 @@ fn sends_and_reads_packets() {
         interface.mac,
         interface.ipv4,
         54321,
+        syn_ack(device_ip, device_port),
         #[allow(static_mut_refs)]
         unsafe {
             &mut PACKET
@@ -108,6 +131,7 @@ fn sends_and_reads_packets() {
         .targets(devices)
         .ports(ports)
         .source_port(54321_u16)
+        .cookie(SequenceCookie::new(COOKIE_KEY))
         .idle_timeout(idle_timeout)
         .notifier(tx)
         .build()
@@ -120,6 +144,7 @@ fn sends_and_reads_packets() {
     let expected_open_port = Port {
         id: device_port,
         service: "".to_string(),
+        ..Port::default()
     };
 
     loop {
@@ -146,6 +171,125 @@ fn sends_and_reads_packets() {
     assert_eq!(detected_device.vendor, device.vendor);
     assert_eq!(detected_device.is_current_host, device.is_current_host);
     assert!(detected_device.open_ports.0.contains(&expected_open_port));
+    // the reply carries no options so only its TTL can be matched
+    assert_eq!(
+        detected_device.os_guess.map(|g| g.os),
+        Some("Linux/macOS".to_string())
+    );
+}
+
+#[test]
+#[allow(warnings)]
+fn sends_and_reads_ipv6_packets() {
+    static mut PACKET: [u8; PKT_TOTAL_SYN6_SIZE] = [0u8; PKT_TOTAL_SYN6_SIZE];
+
+    let mut interface = network::get_default_interface().unwrap();
+    interface.ipv6 = Some(net::Ipv6Addr::from_str("fe80::2").unwrap());
+    let interface = Arc::new(interface);
+    let device_ipv6 = net::Ipv6Addr::from_str("fe80::3").unwrap();
+    let device_mac = util::MacAddr::new(0, 0, 0, 0, 0, 3);
+    let device_port = 2222;
+
+    create_syn6_reply(
+        device_mac,
+        device_ipv6,
+        device_port,
+        interface.mac,
+        interface.ipv6.unwrap(),
+        54321,
+        syn_ack(device_ipv6, device_port),
+        #[allow(static_mut_refs)]
+        unsafe {
+            &mut PACKET
+        },
+    );
+
+    // IPv6-only device as reported by the NDP scanner
+    let device = Device {
+        ipv6: vec![device_ipv6],
+        mac: device_mac,
+        ..Device::default()
+    };
+
+    let mut receiver = MockPacketReader::new();
+    let mut sender = MockPacketSender::new();
+
+    #[allow(static_mut_refs)]
+    receiver
+        .expect_next_packet()
+        .returning(|| Ok(unsafe { &PACKET }));
+
+    let sent_ipv6 = Arc::new(Mutex::new(false));
+    let sent_ipv6_clone = Arc::clone(&sent_ipv6);
+
+    sender.expect_send().returning(move |pkt| {
+        let eth = ethernet::EthernetPacket::new(pkt).unwrap();
+        if eth.get_ethertype() == ethernet::EtherTypes::Ipv6 {
+            *sent_ipv6_clone.lock().unwrap() = true;
+        }
+        Ok(())
+    });
+
+    let arc_receiver: Arc<Mutex<dyn Reader>> = Arc::new(Mutex::new(receiver));
+    let arc_sender: Arc<Mutex<dyn Sender>> = Arc::new(Mutex::new(sender));
+    let wire = Wire(arc_sender, arc_receiver);
+
+    let ports = PortTargets::new(vec!["2222".to_string()]).unwrap();
+    let (tx, rx) = channel();
+
+    let scanner = SYNScanner::builder()
+        .interface(interface)
+        .wire(wire)
+        .targets(vec![device.clone()])
+        .ports(ports)
+        .source_port(54321_u16)
+        .cookie(SequenceCookie::new(COOKIE_KEY))
+        .idle_timeout(Duration::from_secs(2))
+        .notifier(tx)
+        .include_info(true)
+        .build()
+        .unwrap();
+
+    let handle = scanner.scan().unwrap();
+
+    let mut detected_device = None;
+    let mut scanning = None;
+
+    loop {
+        if let Ok(msg) = rx.recv() {
+            match msg {
+                ScanMessage::Done => {
+                    break;
+                }
+                ScanMessage::Info(info) => {
+                    scanning = Some(info);
+                }
+                ScanMessage::SYNScanDevice(device) => {
+                    detected_device = Some(device);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let result = handle.join().unwrap();
+    let detected_device = detected_device.unwrap();
+    let scanning = scanning.unwrap();
+
+    assert!(result.is_ok());
+    assert!(*sent_ipv6.lock().unwrap());
+    assert_eq!(scanning.ip, IpAddr::V6(device_ipv6));
+    assert_eq!(scanning.port, Some(device_port));
+    assert_eq!(detected_device.ipv6, device.ipv6);
+    assert_eq!(detected_device.mac, device.mac);
+    assert_eq!(detected_device.response_ttl, Some(64));
+    assert!(
+        detected_device
+            .open_ports
+            .0
+            .iter()
+            .any(|p| p.id == device_port)
+    );
 }
 
 #[test]
@@ -180,6 +324,7 @@ fn ignores_unrelated_packets() {
         interface.mac,
         interface.ipv4,
         54322,
+        syn_ack(device_ip, 2222),
         #[allow(static_mut_refs)]
         unsafe {
             &mut SYN_PACKET1
@@ -199,6 +344,7 @@ fn ignores_unrelated_packets() {
         interface.mac,
         interface.ipv4,
         54321,
+        syn_ack(net::Ipv4Addr::from_str("192.168.2.2").unwrap(), 2222),
         #[allow(static_mut_refs)]
         unsafe {
             &mut SYN_PACKET2
@@ -244,6 +390,7 @@ fn ignores_unrelated_packets() {
         .targets(devices)
         .ports(ports)
         .source_port(54321_u16)
+        .cookie(SequenceCookie::new(COOKIE_KEY))
         .idle_timeout(idle_timeout)
         .notifier(tx)
         .build()
@@ -251,7 +398,7 @@ fn ignores_unrelated_packets() {
 
     let (done_tx, done_rx) = channel();
 
-    scanner.read_packets(done_rx);
+    scanner.read_packets(done_rx, ScanControl::new());
 
     let mut detected_devices: Vec<Device> = Vec::new();
 
@@ -323,6 +470,7 @@ fn reports_error_on_packet_reader_lock() {
         .targets(devices)
         .ports(ports)
         .source_port(54321_u16)
+        .cookie(SequenceCookie::new(COOKIE_KEY))
         .idle_timeout(idle_timeout)
         .notifier(tx)
         .build()
@@ -330,7 +478,7 @@ fn reports_error_on_packet_reader_lock() {
 
     let (_done_tx, done_rx) = channel();
 
-    let handle = scanner.read_packets(done_rx).unwrap();
+    let handle = scanner.read_packets(done_rx, ScanControl::new()).unwrap();
 
     let result = handle.join().unwrap();
 
@@ -358,6 +506,7 @@ fn reports_error_on_rst_packet_sender_lock() {
         interface.mac,
         interface.ipv4,
         54321,
+        syn_ack(device_ip, 2222),
         #[allow(static_mut_refs)]
         unsafe {
             &mut PACKET
@@ -399,6 +548,7 @@ fn reports_error_on_rst_packet_sender_lock() {
         .targets(devices)
         .ports(ports)
         .source_port(54321_u16)
+        .cookie(SequenceCookie::new(COOKIE_KEY))
         .idle_timeout(idle_timeout)
         .notifier(tx)
         .build()
@@ -406,7 +556,7 @@ fn reports_error_on_rst_packet_sender_lock() {
 
     let (_done_tx, done_rx) = channel();
 
-    let handle = scanner.read_packets(done_rx).unwrap();
+    let handle = scanner.read_packets(done_rx, ScanControl::new()).unwrap();
 
     let result = handle.join().unwrap();
 
@@ -434,6 +584,7 @@ fn reports_error_on_rst_packet_send_errors() {
         interface.mac,
         interface.ipv4,
         54321,
+        syn_ack(device_ip, 2222),
         #[allow(static_mut_refs)]
         unsafe {
             &mut PACKET
@@ -477,6 +628,7 @@ fn reports_error_on_rst_packet_send_errors() {
         .targets(devices)
         .ports(ports)
         .source_port(54321_u16)
+        .cookie(SequenceCookie::new(COOKIE_KEY))
         .idle_timeout(idle_timeout)
         .notifier(tx)
         .build()
@@ -484,7 +636,7 @@ fn reports_error_on_rst_packet_send_errors() {
 
     let (_done_tx, done_rx) = channel();
 
-    let handle = scanner.read_packets(done_rx).unwrap();
+    let handle = scanner.read_packets(done_rx, ScanControl::new()).unwrap();
 
     let result = handle.join().unwrap();
 
@@ -528,6 +680,7 @@ fn reports_error_on_packet_read_error() {
         .targets(devices)
         .ports(ports)
         .source_port(54321_u16)
+        .cookie(SequenceCookie::new(COOKIE_KEY))
         .idle_timeout(idle_timeout)
         .notifier(tx)
         .build()
@@ -535,7 +688,7 @@ fn reports_error_on_packet_read_error() {
 
     let (_done_tx, done_rx) = channel();
 
-    let handle = scanner.read_packets(done_rx).unwrap();
+    let handle = scanner.read_packets(done_rx, ScanControl::new()).unwrap();
 
     let result = handle.join().unwrap();
 
@@ -579,6 +732,7 @@ fn reports_error_on_notifier_send_errors() {
         .targets(devices)
         .ports(ports)
         .source_port(54321_u16)
+        .cookie(SequenceCookie::new(COOKIE_KEY))
         .idle_timeout(idle_timeout)
         .notifier(tx)
         .build()
@@ -631,6 +785,7 @@ fn reports_error_on_packet_sender_lock_errors() {
         .targets(devices)
         .ports(ports)
         .source_port(54321_u16)
+        .cookie(SequenceCookie::new(COOKIE_KEY))
         .idle_timeout(idle_timeout)
         .notifier(tx)
         .build()
@@ -685,6 +840,7 @@ fn reports_error_on_packet_send_errors() {
         .targets(devices)
         .ports(ports)
         .source_port(54321_u16)
+        .cookie(SequenceCookie::new(COOKIE_KEY))
         .idle_timeout(idle_timeout)
         .notifier(tx)
         .build()
@@ -740,6 +896,7 @@ fn reports_errors_from_read_handle() {
         .targets(devices)
         .ports(ports)
         .source_port(54321_u16)
+        .cookie(SequenceCookie::new(COOKIE_KEY))
         .idle_timeout(idle_timeout)
         .notifier(tx)
         .build()
@@ -757,3 +914,614 @@ fn reports_errors_from_read_handle() {
 
     assert!(result.is_err());
 }
+
+#[test]
+fn retries_unanswered_ports_and_reports_open_ports_once() {
+    static mut PACKET: [u8; PKT_TOTAL_SYN_SIZE] = [0u8; PKT_TOTAL_SYN_SIZE];
+
+    let interface = Arc::new(network::get_default_interface().unwrap());
+    let device_ip = net::Ipv4Addr::from_str("192.168.1.2").unwrap();
+    let device_mac = util::MacAddr::default();
+
+    create_syn_reply(
+        device_mac,
+        device_ip,
+        22,
+        interface.mac,
+        interface.ipv4,
+        54321,
+        syn_ack(device_ip, 22),
+        #[allow(static_mut_refs)]
+        unsafe {
+            &mut PACKET
+        },
+    );
+
+    let mut receiver = MockPacketReader::new();
+    let mut sender = MockPacketSender::new();
+
+    // port 22 answers every probe
+    #[allow(static_mut_refs)]
+    receiver.expect_next_packet().returning(|| {
+        thread::sleep(Duration::from_millis(1));
+        Ok(unsafe { &PACKET })
+    });
+
+    let probes: Arc<Mutex<HashMap<u16, usize>>> = Arc::default();
+    let sent = Arc::clone(&probes);
+
+    sender.expect_send().returning(move |pkt| {
+        if let Some(eth) = ethernet::EthernetPacket::new(pkt)
+            && let Some(ip) = ipv4::Ipv4Packet::new(eth.payload())
+            && let Some(tcp) = tcp::TcpPacket::new(ip.payload())
+            && tcp.get_flags() == tcp::TcpFlags::SYN
+            && ip.get_destination() == net::Ipv4Addr::new(192, 168, 1, 2)
+        {
+            *sent
+                .lock()
+                .unwrap()
+                .entry(tcp.get_destination())
+                .or_default() += 1;
+        }
+        Ok(())
+    });
+
+    let sender: Arc<Mutex<dyn Sender>> = Arc::new(Mutex::new(sender));
+    let receiver: Arc<Mutex<dyn Reader>> = Arc::new(Mutex::new(receiver));
+    let (tx, rx) = channel();
+
+    let scanner = SYNScanner::builder()
+        .interface(interface)
+        .wire(Wire(sender, receiver))
+        .targets(vec![Device {
+            ip: device_ip,
+            mac: device_mac,
+            ..Device::default()
+        }])
+        .ports(PortTargets::new(vec!["22-24".to_string()]).unwrap())
+        .source_port(54321_u16)
+        .cookie(SequenceCookie::new(COOKIE_KEY))
+        .idle_timeout(Duration::from_millis(50))
+        .retries(2)
+        .retry_delay(Duration::from_millis(100))
+        .notifier(tx)
+        .build()
+        .unwrap();
+
+    let handle = scanner.scan().unwrap();
+
+    let mut results = 0;
+    let mut stats = None;
+
+    loop {
+        match rx.recv().unwrap() {
+            ScanMessage::Done => break,
+            ScanMessage::SYNScanDevice(_) => results += 1,
+            ScanMessage::Stats(s) => stats = Some(s),
+            _ => {}
+        }
+    }
+
+    assert!(handle.join().unwrap().is_ok());
+    assert_eq!(results, 1);
+    assert_eq!(
+        stats,
+        Some(ScanStats {
+            sent: 7,
+            rate: Some(5_000),
+            backoffs: 0,
+            rejected: 0,
+        })
+    );
+
+    let probes = probes.lock().unwrap();
+    assert_eq!(probes.get(&22), Some(&1));
+    assert_eq!(probes.get(&23), Some(&3));
+    assert_eq!(probes.get(&24), Some(&3));
+}
+
+#[test]
+fn interleaves_hosts_and_ports_when_seeded() {
+    let interface = Arc::new(network::get_default_interface().unwrap());
+
+    let devices: Vec<Device> = (2..=4)
+        .map(|n| Device {
+            ip: net::Ipv4Addr::new(192, 168, 1, n),
+            mac: util::MacAddr::default(),
+            ..Device::default()
+        })
+        .collect();
+
+    let scan = |seed: Option<u64>| {
+        let mut receiver = MockPacketReader::new();
+        let mut sender = MockPacketSender::new();
+
+        receiver.expect_next_packet().returning(|| {
+            thread::sleep(Duration::from_millis(1));
+            Ok(&[1])
+        });
+
+        let probes: Arc<Mutex<Vec<(net::Ipv4Addr, u16)>>> = Arc::default();
+        let sent = Arc::clone(&probes);
+
+        sender.expect_send().returning(move |pkt| {
+            if let Some(eth) = ethernet::EthernetPacket::new(pkt)
+                && let Some(ip) = ipv4::Ipv4Packet::new(eth.payload())
+                && let Some(tcp) = tcp::TcpPacket::new(ip.payload())
+                && tcp.get_flags() == tcp::TcpFlags::SYN
+                && ip.get_destination() != ip.get_source()
+            {
+                sent.lock()
+                    .unwrap()
+                    .push((ip.get_destination(), tcp.get_destination()));
+            }
+            Ok(())
+        });
+
+        let sender: Arc<Mutex<dyn Sender>> = Arc::new(Mutex::new(sender));
+        let receiver: Arc<Mutex<dyn Reader>> = Arc::new(Mutex::new(receiver));
+        let (tx, rx) = channel();
+
+        let scanner = SYNScanner::builder()
+            .interface(Arc::clone(&interface))
+            .wire(Wire(sender, receiver))
+            .targets(devices.clone())
+            .ports(PortTargets::new(vec!["22-25".to_string()]).unwrap())
+            .source_port(54321_u16)
+            .cookie(SequenceCookie::new(COOKIE_KEY))
+            .idle_timeout(Duration::from_millis(10))
+            .seed(seed)
+            .notifier(tx)
+            .build()
+            .unwrap();
+
+        let handle = scanner.scan().unwrap();
+
+        while !matches!(rx.recv().unwrap(), ScanMessage::Done) {}
+
+        assert!(handle.join().unwrap().is_ok());
+
+        probes.lock().unwrap().clone()
+    };
+
+    let ordered = scan(None);
+    let shuffled = scan(Some(11));
+
+    assert_eq!(shuffled, scan(Some(11)));
+    assert_ne!(shuffled, ordered);
+
+    let mut sorted = shuffled.clone();
+    sorted.sort();
+    let mut expected = ordered.clone();
+    expected.sort();
+
+    assert_eq!(sorted, expected);
+    assert_eq!(sorted.len(), 12);
+}
+
+// Scans ports 22-24 where 22 answers with a RST, 23 is rejected by a
+// firewall and 24 is silent, returning the reported port states, the flags
+// of every probe sent and the number of rejected replies. Forged replies
+// don't answer the sequence numbers of the probes
+#[allow(warnings)]
+fn scan_rejecting_ports(
+    scan_type: TcpScanType,
+    report_non_open: bool,
+    forged: bool,
+) -> (Vec<(u16, PortState)>, HashSet<u8>, usize) {
+    static mut RST: [u8; PKT_TOTAL_SYN_SIZE] = [0u8; PKT_TOTAL_SYN_SIZE];
+    static mut ICMP: [u8; PKT_TOTAL_ICMP_SIZE] = [0u8; PKT_TOTAL_ICMP_SIZE];
+    // tests share the replies so only one may scan at a time
+    static SCANNING: Mutex<()> = Mutex::new(());
+
+    let _scanning = SCANNING.lock().unwrap_or_else(|e| e.into_inner());
+
+    let interface = Arc::new(network::get_default_interface().unwrap());
+    let device_ip = net::Ipv4Addr::from_str("192.168.1.2").unwrap();
+    let device_mac = util::MacAddr::default();
+    let cookie = SequenceCookie::new(COOKIE_KEY);
+    let forgery = if forged { 1 } else { 0 };
+    let rst_sequence = cookie
+        .sequence(device_ip.into(), 22, 54321)
+        .wrapping_add(forgery);
+    let icmp_sequence = cookie
+        .sequence(device_ip.into(), 23, 54321)
+        .wrapping_add(forgery);
+
+    #[allow(static_mut_refs)]
+    unsafe {
+        create_syn_reply(
+            device_mac,
+            device_ip,
+            22,
+            interface.mac,
+            interface.ipv4,
+            54321,
+            0,
+            &mut RST,
+        );
+
+        let mut rst =
+            tcp::MutableTcpPacket::new(&mut RST[PKT_ETH_SIZE + PKT_IP4_SIZE..])
+                .unwrap();
+
+        // ACK probes are reset with the acknowledgement they carried, others
+        // are acknowledged along with their SYN or FIN flag
+        match scan_type {
+            TcpScanType::Ack => {
+                rst.set_flags(tcp::TcpFlags::RST);
+                rst.set_sequence(rst_sequence);
+            }
+            TcpScanType::Null => {
+                rst.set_flags(tcp::TcpFlags::RST | tcp::TcpFlags::ACK);
+                rst.set_acknowledgement(rst_sequence);
+            }
+            _ => {
+                rst.set_flags(tcp::TcpFlags::RST | tcp::TcpFlags::ACK);
+                rst.set_acknowledgement(rst_sequence.wrapping_add(1));
+            }
+        }
+
+        create_syn_icmp_unreachable_reply(
+            device_mac,
+            device_ip,
+            interface.mac,
+            interface.ipv4,
+            54321,
+            23,
+            icmp_sequence,
+            destination_unreachable::IcmpCodes::HostAdministrativelyProhibited,
+            &mut ICMP,
+        );
+    }
+
+    let mut receiver = MockPacketReader::new();
+    let mut sender = MockPacketSender::new();
+    let reads = Arc::new(Mutex::new(0usize));
+
+    #[allow(static_mut_refs)]
+    receiver.expect_next_packet().returning(move || {
+        thread::sleep(Duration::from_millis(1));
+        let mut reads = reads.lock().unwrap();
+        *reads += 1;
+        if *reads % 2 == 0 {
+            Ok(unsafe { &RST })
+        } else {
+            Ok(unsafe { &ICMP })
+        }
+    });
+
+    let flags: Arc<Mutex<HashSet<u8>>> = Arc::default();
+    let sent = Arc::clone(&flags);
+
+    sender.expect_send().returning(move |pkt| {
+        if let Some(eth) = ethernet::EthernetPacket::new(pkt)
+            && let Some(ip) = ipv4::Ipv4Packet::new(eth.payload())
+            && ip.get_destination() == net::Ipv4Addr::new(192, 168, 1, 2)
+            && let Some(tcp) = tcp::TcpPacket::new(ip.payload())
+        {
+            sent.lock().unwrap().insert(tcp.get_flags());
+        }
+        Ok(())
+    });
+
+    let sender: Arc<Mutex<dyn Sender>> = Arc::new(Mutex::new(sender));
+    let receiver: Arc<Mutex<dyn Reader>> = Arc::new(Mutex::new(receiver));
+    let (tx, rx) = channel();
+
+    let scanner = SYNScanner::builder()
+        .interface(interface)
+        .wire(Wire(sender, receiver))
+        .targets(vec![Device {
+            ip: device_ip,
+            mac: device_mac,
+            ..Device::default()
+        }])
+        .ports(PortTargets::new(vec!["22-24".to_string()]).unwrap())
+        .source_port(54321_u16)
+        .cookie(SequenceCookie::new(COOKIE_KEY))
+        .idle_timeout(Duration::from_millis(100))
+        .report_non_open(report_non_open)
+        .scan_type(scan_type)
+        .notifier(tx)
+        .build()
+        .unwrap();
+
+    let handle = scanner.scan().unwrap();
+
+    let mut states = Vec::new();
+    let mut rejected = 0;
+
+    loop {
+        match rx.recv().unwrap() {
+            ScanMessage::Done => break,
+            ScanMessage::SYNScanDevice(device) => states
+                .extend(device.open_ports.0.iter().map(|p| (p.id, p.state))),
+            ScanMessage::Stats(s) => rejected = s.rejected,
+            _ => {}
+        }
+    }
+
+    assert!(handle.join().unwrap().is_ok());
+
+    states.sort_by_key(|(id, _)| *id);
+
+    let flags = flags.lock().unwrap().clone();
+    (states, flags, rejected)
+}
+
+#[test]
+fn reports_closed_and_filtered_ports_when_requested() {
+    let (states, flags, rejected) =
+        scan_rejecting_ports(TcpScanType::Syn, true, false);
+
+    assert_eq!(
+        states,
+        vec![
+            (22, PortState::Closed),
+            (23, PortState::Filtered),
+            (24, PortState::Filtered),
+        ]
+    );
+    assert!(flags.contains(&tcp::TcpFlags::SYN));
+    assert_eq!(rejected, 0);
+
+    let (states, ..) = scan_rejecting_ports(TcpScanType::Syn, false, false);
+    assert!(states.is_empty());
+}
+
+#[test]
+fn maps_firewall_states_for_each_scan_type() {
+    let (states, flags, rejected) =
+        scan_rejecting_ports(TcpScanType::Ack, false, false);
+
+    assert_eq!(
+        states,
+        vec![
+            (22, PortState::Unfiltered),
+            (23, PortState::Filtered),
+            (24, PortState::Filtered),
+        ]
+    );
+    assert!(flags.contains(&tcp::TcpFlags::ACK));
+    assert!(!flags.contains(&tcp::TcpFlags::SYN));
+    assert_eq!(rejected, 0);
+
+    for scan_type in [TcpScanType::Fin, TcpScanType::Null, TcpScanType::Xmas] {
+        let (states, flags, rejected) =
+            scan_rejecting_ports(scan_type, false, false);
+
+        assert_eq!(
+            states,
+            vec![
+                (22, PortState::Closed),
+                (23, PortState::Filtered),
+                (24, PortState::OpenFiltered),
+            ]
+        );
+        assert!(flags.contains(&scan_type.flags()));
+        assert!(!flags.contains(&tcp::TcpFlags::SYN));
+        assert_eq!(rejected, 0);
+    }
+}
+
+#[test]
+fn rejects_rsts_and_unreachables_that_do_not_answer_a_probe() {
+    for scan_type in [TcpScanType::Syn, TcpScanType::Ack, TcpScanType::Null] {
+        let (states, _, rejected) = scan_rejecting_ports(scan_type, true, true);
+
+        // every port is left unanswered
+        assert_eq!(
+            states,
+            vec![
+                (22, scan_type.silent_state()),
+                (23, scan_type.silent_state()),
+                (24, scan_type.silent_state()),
+            ]
+        );
+        assert!(rejected > 0);
+    }
+}
+
+#[test]
+#[allow(warnings)]
+fn classifies_icmpv6_unreachables_for_ipv6_targets() {
+    static mut GENUINE: [u8; PKT_TOTAL_ICMP6_SIZE] =
+        [0u8; PKT_TOTAL_ICMP6_SIZE];
+    static mut FORGED: [u8; PKT_TOTAL_ICMP6_SIZE] = [0u8; PKT_TOTAL_ICMP6_SIZE];
+
+    let mut interface = network::get_default_interface().unwrap();
+    interface.ipv6 = Some(net::Ipv6Addr::from_str("fe80::2").unwrap());
+    let interface = Arc::new(interface);
+    let device_ipv6 = net::Ipv6Addr::from_str("fe80::3").unwrap();
+    let device_mac = util::MacAddr::new(0, 0, 0, 0, 0, 3);
+    let cookie = SequenceCookie::new(COOKIE_KEY);
+
+    #[allow(static_mut_refs)]
+    unsafe {
+        // a firewall rejecting the probe sent to port 23
+        create_syn6_icmp_unreachable_reply(
+            device_mac,
+            device_ipv6,
+            interface.mac,
+            interface.ipv6.unwrap(),
+            54321,
+            23,
+            cookie.sequence(device_ipv6.into(), 23, 54321),
+            icmpv6::Icmpv6Code(1),
+            &mut GENUINE,
+        );
+
+        // a spoofed error quoting a probe never sent to port 24
+        create_syn6_icmp_unreachable_reply(
+            device_mac,
+            device_ipv6,
+            interface.mac,
+            interface.ipv6.unwrap(),
+            54321,
+            24,
+            cookie
+                .sequence(device_ipv6.into(), 24, 54321)
+                .wrapping_add(1),
+            icmpv6::Icmpv6Code(4),
+            &mut FORGED,
+        );
+    }
+
+    let mut receiver = MockPacketReader::new();
+    let mut sender = MockPacketSender::new();
+    let mut reads = 0;
+
+    #[allow(static_mut_refs)]
+    receiver.expect_next_packet().returning(move || {
+        thread::sleep(Duration::from_millis(1));
+        reads += 1;
+        if reads % 2 == 0 {
+            Ok(unsafe { &GENUINE })
+        } else {
+            Ok(unsafe { &FORGED })
+        }
+    });
+
+    sender.expect_send().returning(|_| Ok(()));
+
+    let sender: Arc<Mutex<dyn Sender>> = Arc::new(Mutex::new(sender));
+    let receiver: Arc<Mutex<dyn Reader>> = Arc::new(Mutex::new(receiver));
+    let (tx, rx) = channel();
+
+    // FIN probes tell a rejected port from a silent one
+    let scanner = SYNScanner::builder()
+        .interface(interface)
+        .wire(Wire(sender, receiver))
+        .targets(vec![Device {
+            ipv6: vec![device_ipv6],
+            mac: device_mac,
+            ..Device::default()
+        }])
+        .ports(PortTargets::new(vec!["23-24".to_string()]).unwrap())
+        .source_port(54321_u16)
+        .cookie(cookie)
+        .idle_timeout(Duration::from_millis(100))
+        .scan_type(TcpScanType::Fin)
+        .notifier(tx)
+        .build()
+        .unwrap();
+
+    let handle = scanner.scan().unwrap();
+
+    let mut states = Vec::new();
+    let mut rejected = 0;
+
+    loop {
+        match rx.recv().unwrap() {
+            ScanMessage::Done => break,
+            ScanMessage::SYNScanDevice(device) => states
+                .extend(device.open_ports.0.iter().map(|p| (p.id, p.state))),
+            ScanMessage::Stats(s) => rejected = s.rejected,
+            _ => {}
+        }
+    }
+
+    assert!(handle.join().unwrap().is_ok());
+
+    states.sort_by_key(|(id, _)| *id);
+
+    assert_eq!(
+        states,
+        vec![(23, PortState::Filtered), (24, PortState::OpenFiltered)]
+    );
+    assert!(rejected > 0);
+}
+
+#[test]
+fn rejects_syn_acks_that_do_not_acknowledge_a_probe() {
+    static mut PACKET: [u8; PKT_TOTAL_SYN_SIZE] = [0u8; PKT_TOTAL_SYN_SIZE];
+
+    let interface = Arc::new(network::get_default_interface().unwrap());
+    let device_ip = net::Ipv4Addr::from_str("192.168.1.2").unwrap();
+    let device_mac = util::MacAddr::default();
+
+    // a stray SYN-ACK acknowledging the SYN sent to another port
+    create_syn_reply(
+        device_mac,
+        device_ip,
+        22,
+        interface.mac,
+        interface.ipv4,
+        54321,
+        syn_ack(device_ip, 23),
+        #[allow(static_mut_refs)]
+        unsafe {
+            &mut PACKET
+        },
+    );
+
+    let mut receiver = MockPacketReader::new();
+    let mut sender = MockPacketSender::new();
+
+    #[allow(static_mut_refs)]
+    receiver.expect_next_packet().returning(|| {
+        thread::sleep(Duration::from_millis(1));
+        Ok(unsafe { &PACKET })
+    });
+
+    let sequences: Arc<Mutex<Vec<(u16, u32)>>> = Arc::default();
+    let sent = Arc::clone(&sequences);
+
+    sender.expect_send().returning(move |pkt| {
+        if let Some(eth) = ethernet::EthernetPacket::new(pkt)
+            && let Some(ip) = ipv4::Ipv4Packet::new(eth.payload())
+            && let Some(tcp) = tcp::TcpPacket::new(ip.payload())
+            && ip.get_destination() == net::Ipv4Addr::new(192, 168, 1, 2)
+        {
+            sent.lock()
+                .unwrap()
+                .push((tcp.get_destination(), tcp.get_sequence()));
+        }
+        Ok(())
+    });
+
+    let sender: Arc<Mutex<dyn Sender>> = Arc::new(Mutex::new(sender));
+    let receiver: Arc<Mutex<dyn Reader>> = Arc::new(Mutex::new(receiver));
+    let (tx, rx) = channel();
+
+    let scanner = SYNScanner::builder()
+        .interface(interface)
+        .wire(Wire(sender, receiver))
+        .targets(vec![Device {
+            ip: device_ip,
+            mac: device_mac,
+            ..Device::default()
+        }])
+        .ports(PortTargets::new(vec!["22".to_string()]).unwrap())
+        .source_port(54321_u16)
+        .cookie(SequenceCookie::new(COOKIE_KEY))
+        .idle_timeout(Duration::from_millis(50))
+        .notifier(tx)
+        .build()
+        .unwrap();
+
+    let handle = scanner.scan().unwrap();
+
+    let mut results = 0;
+    let mut stats = None;
+
+    loop {
+        match rx.recv().unwrap() {
+            ScanMessage::Done => break,
+            ScanMessage::SYNScanDevice(_) => results += 1,
+            ScanMessage::Stats(s) => stats = Some(s),
+            _ => {}
+        }
+    }
+
+    assert!(handle.join().unwrap().is_ok());
+    assert_eq!(results, 0);
+    assert!(stats.unwrap().rejected > 0);
+
+    // only the probe was sent, carrying the sequence number of its cookie
+    assert_eq!(
+        *sequences.lock().unwrap(),
+        vec![(22, syn_ack(device_ip, 22).wrapping_sub(1))]
+    );
+}
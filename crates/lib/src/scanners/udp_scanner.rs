@@ -0,0 +1,533 @@
+//! Provides Scanner implementation for UDP scanning
+//!
+//! UDP is connectionless so there is no handshake to tell us a port is open.
+//! Instead each port is probed with a protocol appropriate payload (when one
+//! is known) and classified by the response:
+//!
+//! - any UDP reply means the port is [`PortState::Open`]
+//! - an ICMP port-unreachable means the port is [`PortState::Closed`]
+//! - any other ICMP unreachable means the port is [`PortState::Filtered`]
+//! - silence means the port is [`PortState::OpenFiltered`]
+
+use derive_builder::Builder;
+use pnet::packet::{
+    Packet, ethernet,
+    icmp::{IcmpTypes, destination_unreachable},
+    ip, ipv4, udp,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    net::Ipv4Addr,
+    sync::{
+        self, Arc, LazyLock, Mutex,
+        mpsc::{self, TryRecvError},
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::{
+    error::{RLanLibError, Result},
+    network::NetworkInterface,
+    packet::{heartbeat_packet::is_heartbeat, udp_packet::UdpPacketBuilder},
+    scanners::{
+        PortSet, PortState, Scanning,
+        handle::{ScanControl, ScanHandle, loop_error},
+        heartbeat::HeartBeat,
+        progress::{DEFAULT_PROGRESS_INTERVAL, Progress},
+        rate::{RateBounds, RateController},
+    },
+    services::{
+        self,
+        traits::{Protocol, Services},
+    },
+    targets::ports::PortTargets,
+    wire::{DEFAULT_PACKET_SEND_TIMING, Wire},
+};
+
+use super::{
+    Device, Port, ScanMessage, Scanner, for_each_probe, notifier::Notifier,
+};
+
+// Most UDP services silently drop datagrams they cannot parse, so well-known
+// ports are sent a minimal valid request to coax a reply out of them
+static PAYLOADS: LazyLock<HashMap<u16, Vec<u8>>> = LazyLock::new(|| {
+    HashMap::from([
+        // DNS: standard recursive query for the root NS records
+        (
+            53,
+            vec![
+                0x12, 0x34, 0x01, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x01,
+            ],
+        ),
+        // TFTP: read request for a file that almost certainly does not
+        // exist, servers answer with an error packet
+        (69, b"\x00\x01r-lanscan\x00octet\x00".to_vec()),
+        // NTP: version 4 client request
+        (123, {
+            let mut pkt = vec![0u8; 48];
+            pkt[0] = 0xe3;
+            pkt
+        }),
+        // NetBIOS: node status request for the wildcard name
+        (137, {
+            let mut pkt = vec![
+                0x80, 0xf0, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x20, b'C', b'K',
+            ];
+            pkt.extend_from_slice(&[b'A'; 30]);
+            pkt.extend_from_slice(&[0x00, 0x00, 0x21, 0x00, 0x01]);
+            pkt
+        }),
+        // SNMP: v1 get-request for sysDescr.0 with the "public" community
+        (
+            161,
+            vec![
+                0x30, 0x29, 0x02, 0x01, 0x00, 0x04, 0x06, b'p', b'u', b'b',
+                b'l', b'i', b'c', 0xa0, 0x1c, 0x02, 0x04, 0x00, 0x00, 0x00,
+                0x01, 0x02, 0x01, 0x00, 0x02, 0x01, 0x00, 0x30, 0x0e, 0x30,
+                0x0c, 0x06, 0x08, 0x2b, 0x06, 0x01, 0x02, 0x01, 0x01, 0x01,
+                0x00, 0x05, 0x00,
+            ],
+        ),
+        // SSDP: discover all devices and services
+        (
+            1900,
+            b"M-SEARCH * HTTP/1.1\r\n\
+            HOST: 239.255.255.250:1900\r\n\
+            MAN: \"ssdp:discover\"\r\n\
+            MX: 1\r\n\
+            ST: ssdp:all\r\n\r\n"
+                .to_vec(),
+        ),
+        // mDNS: PTR query for _services._dns-sd._udp.local
+        (5353, {
+            let mut pkt = vec![
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00,
+            ];
+            pkt.extend_from_slice(&[0x00, 0x00]);
+            for label in ["_services", "_dns-sd", "_udp", "local"] {
+                pkt.push(label.len() as u8);
+                pkt.extend_from_slice(label.as_bytes());
+            }
+            pkt.extend_from_slice(&[0x00, 0x00, 0x0c, 0x00, 0x01]);
+            pkt
+        }),
+    ])
+});
+
+/// Returns the probe payload sent to the provided UDP port. Ports without a
+/// known protocol are sent an empty datagram
+pub fn probe_payload(port: u16) -> &'static [u8] {
+    PAYLOADS
+        .get(&port)
+        .map(|p| p.as_slice())
+        .unwrap_or_default()
+}
+
+/// Data structure representing a UDP scanner
+#[derive(Clone, Builder)]
+#[builder(setter(into), pattern = "owned")]
+pub struct UDPScanner {
+    /// Network interface to use for scanning
+    interface: Arc<NetworkInterface>,
+    /// Wire for reading and sending packets on the wire
+    wire: Wire,
+    /// Devices to scan for open ports
+    targets: Vec<Device>,
+    /// Port targets to scan on each device
+    ports: Arc<PortTargets>,
+    /// Source port for packet listener and incoming packet identification
+    source_port: u16,
+    /// Duration to wait for responses after scanning completes
+    idle_timeout: Duration,
+    /// Throttles speed at which packets are sent. Higher throttles result
+    /// in more accurate scans
+    #[builder(default = DEFAULT_PACKET_SEND_TIMING)]
+    throttle: Duration,
+    /// Enables adaptive rate control within the given bounds, replacing
+    /// the fixed throttle
+    #[builder(default)]
+    adaptive_rate: Option<RateBounds>,
+    /// Seeds a random order in which device and port pairs are probed,
+    /// interleaving hosts and ports. Each port is probed on every device
+    /// before moving to the next when None
+    #[builder(default)]
+    seed: Option<u64>,
+    /// Channel for sending scan results and status messages
+    notifier: Notifier,
+    /// Whether to send a ScanMessage::Info for every probe
+    #[builder(default)]
+    include_info: bool,
+    /// Interval between ScanMessage::Progress reports
+    #[builder(default = DEFAULT_PROGRESS_INTERVAL)]
+    progress_interval: Duration,
+    /// Tracks every (ip, port) pair that produced a response so the
+    /// remaining pairs can be reported as open|filtered once scanning ends
+    #[builder(setter(skip), default = "Arc::new(Mutex::new(HashSet::new()))")]
+    responded: Arc<Mutex<HashSet<(Ipv4Addr, u16)>>>,
+    /// Used to lookup the names of services on open ports
+    #[builder(default = "services::default()")]
+    services: Arc<dyn Services>,
+    /// Paces probes and collects the statistics reported when done
+    #[builder(setter(skip))]
+    rate: RateController,
+}
+
+impl UDPScanner {
+    /// Returns a builder for UDPScanner
+    pub fn builder() -> UDPScannerBuilder {
+        UDPScannerBuilder::default()
+    }
+
+    fn service_name(&self, port: u16) -> String {
+        self.services
+            .lookup(port, Protocol::Udp)
+            .unwrap_or_default()
+    }
+
+    fn send_probes(&self, control: &ScanControl) -> Result<()> {
+        let mut progress = Progress::start(
+            self.notifier.clone(),
+            self.targets.len() * self.ports.len(),
+            self.progress_interval,
+        )?;
+
+        for_each_probe(&self.targets, &self.ports, self.seed, |d, p| {
+            self.process_probe(d, p, control, &mut progress)
+        })?;
+
+        progress.finish()
+    }
+
+    fn process_probe(
+        &self,
+        device: &Device,
+        port: u16,
+        control: &ScanControl,
+        progress: &mut Progress,
+    ) -> Result<()> {
+        control.checkpoint()?;
+
+        // throttle packet sending to prevent packet loss
+        thread::sleep(self.rate.delay());
+
+        log::debug!("scanning UDP target: {}:{}", device.ip, port);
+
+        let udp_packet = UdpPacketBuilder::default()
+            .source_ip(self.interface.ipv4)
+            .source_mac(self.interface.mac)
+            .source_port(self.source_port)
+            .dest_ip(device.ip)
+            .dest_mac(device.mac)
+            .dest_port(port)
+            .payload(probe_payload(port).to_vec())
+            .build()?;
+
+        let pkt_buf = udp_packet.to_raw();
+
+        // send info message to consumer
+        if self.include_info {
+            self.notifier.send(ScanMessage::Info(Scanning {
+                ip: device.ip.into(),
+                port: Some(port),
+            }))?;
+        }
+
+        let mut sender = self.wire.0.lock()?;
+
+        // scan device @ port
+        sender.send(&pkt_buf).map_err(|e| RLanLibError::Scan {
+            ip: Some(device.ip.to_string()),
+            port: Some(port.to_string()),
+            error: e.to_string(),
+        })?;
+
+        drop(sender);
+        self.rate.sent();
+        progress.sent()?;
+
+        Ok(())
+    }
+
+    // Extracts the (device ip, port, state) classified by an incoming packet
+    fn classify_packet(
+        &self,
+        pkt: &[u8],
+    ) -> Option<(Ipv4Addr, u16, PortState)> {
+        let eth = ethernet::EthernetPacket::new(pkt)?;
+        let header = ipv4::Ipv4Packet::new(eth.payload())?;
+        let protocol = header.get_next_level_protocol();
+
+        if protocol == ip::IpNextHeaderProtocols::Udp {
+            let udp_packet = udp::UdpPacket::new(header.payload())?;
+
+            if udp_packet.get_destination() != self.source_port {
+                return None;
+            }
+
+            return Some((
+                header.get_source(),
+                udp_packet.get_source(),
+                PortState::Open,
+            ));
+        }
+
+        if protocol != ip::IpNextHeaderProtocols::Icmp {
+            return None;
+        }
+
+        let icmp_packet =
+            destination_unreachable::DestinationUnreachablePacket::new(
+                header.payload(),
+            )?;
+
+        if icmp_packet.get_icmp_type() != IcmpTypes::DestinationUnreachable {
+            return None;
+        }
+
+        // ICMP errors quote the IP header and first 8 bytes of the datagram
+        // that triggered them, which is exactly enough to recover the UDP
+        // ports of our original probe
+        let quoted = ipv4::Ipv4Packet::new(icmp_packet.payload())?;
+
+        if quoted.get_next_level_protocol() != ip::IpNextHeaderProtocols::Udp {
+            return None;
+        }
+
+        let quoted_udp = udp::UdpPacket::new(quoted.payload())?;
+
+        if quoted_udp.get_source() != self.source_port {
+            return None;
+        }
+
+        let code = icmp_packet.get_icmp_code();
+
+        let state = if code
+            == destination_unreachable::IcmpCodes::DestinationPortUnreachable
+        {
+            PortState::Closed
+        } else if [
+            destination_unreachable::IcmpCodes::DestinationHostUnreachable,
+            destination_unreachable::IcmpCodes::DestinationProtocolUnreachable,
+            destination_unreachable::IcmpCodes::NetworkAdministrativelyProhibited,
+            destination_unreachable::IcmpCodes::HostAdministrativelyProhibited,
+            destination_unreachable::IcmpCodes::CommunicationAdministrativelyProhibited,
+        ]
+        .contains(&code)
+        {
+            PortState::Filtered
+        } else {
+            return None;
+        };
+
+        Some((
+            quoted.get_destination(),
+            quoted_udp.get_destination(),
+            state,
+        ))
+    }
+
+    fn process_incoming_packet(
+        &self,
+        pkt: &[u8],
+        device_map: &HashMap<Ipv4Addr, Device>,
+    ) -> Result<()> {
+        let Some((device_ip, port, state)) = self.classify_packet(pkt) else {
+            return Ok(());
+        };
+
+        let Some(device) = device_map.get(&device_ip) else {
+            return Ok(());
+        };
+
+        self.rate.answered();
+
+        // only report the first response for each port
+        if let Ok(mut responded) = self.responded.lock()
+            && !responded.insert((device_ip, port))
+        {
+            return Ok(());
+        }
+
+        log::debug!("UDP port {}:{} is {}", device.ip, port, state);
+
+        let mut ports = PortSet::new();
+        ports.0.insert(Port {
+            id: port,
+            service: self.service_name(port),
+            state,
+            ..Port::default()
+        });
+
+        self.notifier.send(ScanMessage::UDPScanDevice(Device {
+            open_ports: ports,
+            ..device.clone()
+        }))?;
+
+        Ok(())
+    }
+
+    // Reports every probed port that never produced a response
+    fn report_unanswered(&self) -> Result<()> {
+        let responded =
+            self.responded.lock().map(|r| r.clone()).unwrap_or_default();
+
+        for device in self.targets.iter() {
+            let mut ports = PortSet::new();
+
+            self.ports.lazy_loop(|port| {
+                if !responded.contains(&(device.ip, port)) {
+                    ports.0.insert(Port {
+                        id: port,
+                        service: self.service_name(port),
+                        state: PortState::OpenFiltered,
+                        ..Port::default()
+                    });
+                }
+                Ok(())
+            })?;
+
+            if ports.0.is_empty() {
+                continue;
+            }
+
+            self.notifier.send(ScanMessage::UDPScanDevice(Device {
+                open_ports: ports,
+                ..device.clone()
+            }))?;
+        }
+
+        Ok(())
+    }
+
+    // Implements packet reading in a separate thread so we can send and
+    // receive packets simultaneously
+    fn read_packets(
+        &self,
+        done_rx: mpsc::Receiver<()>,
+        control: ScanControl,
+    ) -> Result<JoinHandle<Result<()>>> {
+        let self_clone = self.clone();
+        let (heartbeat_tx, heartbeat_rx) = sync::mpsc::channel::<()>();
+
+        let heartbeat = HeartBeat::builder()
+            .source_mac(self.interface.mac)
+            .source_ipv4(self.interface.ipv4)
+            .source_port(self.source_port)
+            .packet_sender(Arc::clone(&self.wire.0))
+            .control(control.clone())
+            .rate(self.rate.clone())
+            .build()?;
+
+        let heart_handle = heartbeat.start_in_thread(heartbeat_rx)?;
+
+        Ok(thread::spawn(move || -> Result<()> {
+            let mut reader = self_clone.wire.1.lock()?;
+
+            let device_map: HashMap<Ipv4Addr, Device> = self_clone
+                .targets
+                .iter()
+                .map(|d| (d.ip, d.clone()))
+                .collect();
+
+            loop {
+                // also stop if the scan thread went away without signalling
+                if control.is_cancelled()
+                    || !matches!(done_rx.try_recv(), Err(TryRecvError::Empty))
+                {
+                    log::debug!("exiting udp packet reader");
+                    if let Err(e) = heartbeat_tx.send(()) {
+                        log::error!("failed to stop heartbeat: {}", e);
+                    }
+
+                    break;
+                }
+
+                let pkt = reader.next_packet()?;
+
+                if is_heartbeat(
+                    pkt,
+                    self_clone.interface.mac,
+                    self_clone.source_port,
+                ) {
+                    self_clone.rate.heartbeat_seen();
+                    continue;
+                }
+
+                self_clone.process_incoming_packet(pkt, &device_map)?;
+            }
+
+            heart_handle.join()??;
+
+            Ok(())
+        }))
+    }
+}
+
+// Implements the Scanner trait for UDPScanner
+impl Scanner for UDPScanner {
+    fn scan(&self) -> Result<ScanHandle> {
+        let control = ScanControl::new();
+        log::debug!("performing UDP scan on targets: {:?}", self.targets);
+
+        if let Ok(mut responded) = self.responded.lock() {
+            responded.clear();
+        }
+
+        // like SYN, targets were discovered up
+        self.rate.reset(self.throttle, self.adaptive_rate, true);
+        self.rate.watch_drops(&self.interface.name);
+
+        let self_clone = self.clone();
+        let (done_tx, done_rx) = mpsc::channel::<()>();
+
+        log::debug!("starting udp packet reader");
+
+        let read_handle = self.read_packets(done_rx, control.clone())?;
+        let scan_control = control.clone();
+
+        // prevent blocking thread so messages can be freely sent to consumer
+        let handle = thread::spawn(move || -> Result<()> {
+            let mut scan_error =
+                loop_error(self_clone.send_probes(&scan_control));
+
+            scan_control.sleep(self_clone.idle_timeout);
+
+            // ignore errors here as the thread may already be dead due to error
+            // we'll catch any errors from that thread below and report
+            let _ = done_tx.send(());
+
+            let read_result = read_handle.join()?;
+
+            // the reader must be stopped before unanswered ports can be
+            // reported, otherwise a late reply could be reported twice. A
+            // cancelled scan never probed the rest so reports nothing more
+            if scan_error.is_none()
+                && !scan_control.is_cancelled()
+                && let Err(err) = self_clone.report_unanswered()
+            {
+                scan_error = Some(err);
+            }
+
+            self_clone
+                .notifier
+                .send(ScanMessage::Stats(self_clone.rate.stats()))?;
+
+            self_clone.notifier.send(ScanMessage::Done)?;
+
+            if let Some(err) = scan_error {
+                return Err(err);
+            }
+
+            read_result
+        });
+
+        Ok(ScanHandle::new(handle, control))
+    }
+}
+
+#[cfg(test)]
+#[path = "./udp_scanner_tests.rs"]
+mod tests;
@@ -0,0 +1,337 @@
+use super::*;
+use pnet::packet::{ethernet, ipv4, udp};
+use pnet::util;
+use std::net;
+use std::str::FromStr;
+use std::sync::mpsc::channel;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::{
+    network,
+    packet::udp_packet::{create_icmp_unreachable_reply, create_udp_reply},
+    wire::mocks::{MockPacketReader, MockPacketSender},
+    wire::{Reader, Sender},
+};
+
+const PKT_ETH_SIZE: usize = ethernet::EthernetPacket::minimum_packet_size();
+const PKT_IP4_SIZE: usize = ipv4::Ipv4Packet::minimum_packet_size();
+const PKT_UDP_SIZE: usize = udp::UdpPacket::minimum_packet_size();
+const PKT_TOTAL_UDP_SIZE: usize = PKT_ETH_SIZE + PKT_IP4_SIZE + PKT_UDP_SIZE;
+const PKT_TOTAL_ICMP_SIZE: usize =
+    PKT_ETH_SIZE + PKT_IP4_SIZE + 8 + PKT_IP4_SIZE + PKT_UDP_SIZE;
+
+fn collect_ports(rx: mpsc::Receiver<ScanMessage>) -> Vec<Port> {
+    let mut ports = Vec::new();
+
+    loop {
+        if let Ok(msg) = rx.recv() {
+            match msg {
+                ScanMessage::Done => {
+                    break;
+                }
+                ScanMessage::UDPScanDevice(device) => {
+                    ports.extend(device.open_ports.to_sorted_vec());
+                }
+                _ => {}
+            }
+        }
+    }
+
+    ports
+}
+
+#[test]
+fn new() {
+    let interface = Arc::new(network::get_default_interface().unwrap());
+
+    let arc_receiver: Arc<Mutex<dyn Reader>> =
+        Arc::new(Mutex::new(MockPacketReader::new()));
+    let arc_sender: Arc<Mutex<dyn Sender>> =
+        Arc::new(Mutex::new(MockPacketSender::new()));
+    let wire = Wire(arc_sender, arc_receiver);
+
+    let idle_timeout = Duration::from_secs(2);
+    let devices: Vec<Device> = Vec::new();
+    let ports = PortTargets::new(vec!["53".to_string()]).unwrap();
+    let (tx, _) = channel();
+
+    let scanner = UDPScanner::builder()
+        .interface(interface)
+        .wire(wire)
+        .targets(devices.clone())
+        .ports(ports)
+        .source_port(54321_u16)
+        .idle_timeout(idle_timeout)
+        .notifier(tx)
+        .build()
+        .unwrap();
+
+    assert_eq!(scanner.targets, devices);
+    assert_eq!(scanner.idle_timeout, idle_timeout);
+    assert_eq!(scanner.source_port, 54321);
+}
+
+#[test]
+fn returns_probe_payloads_for_well_known_ports() {
+    assert!(!probe_payload(53).is_empty());
+    assert_eq!(probe_payload(123).len(), 48);
+    assert!(probe_payload(1900).starts_with(b"M-SEARCH"));
+    assert!(probe_payload(9999).is_empty());
+}
+
+#[test]
+#[allow(warnings)]
+fn reports_open_port_on_udp_reply() {
+    static mut PACKET: [u8; PKT_TOTAL_UDP_SIZE] = [0u8; PKT_TOTAL_UDP_SIZE];
+
+    let interface = Arc::new(network::get_default_interface().unwrap());
+    let device_ip = net::Ipv4Addr::from_str("192.168.1.2").unwrap();
+    let device_mac = util::MacAddr::default();
+
+    create_udp_reply(
+        device_mac,
+        device_ip,
+        53,
+        interface.mac,
+        interface.ipv4,
+        54321,
+        #[allow(static_mut_refs)]
+        unsafe {
+            &mut PACKET
+        },
+    );
+
+    let device = Device {
+        ip: device_ip,
+        mac: device_mac,
+        ..Device::default()
+    };
+
+    let mut receiver = MockPacketReader::new();
+    let mut sender = MockPacketSender::new();
+
+    #[allow(static_mut_refs)]
+    receiver
+        .expect_next_packet()
+        .returning(|| Ok(unsafe { &PACKET }));
+
+    sender.expect_send().returning(|_| Ok(()));
+
+    let arc_receiver: Arc<Mutex<dyn Reader>> = Arc::new(Mutex::new(receiver));
+    let arc_sender: Arc<Mutex<dyn Sender>> = Arc::new(Mutex::new(sender));
+    let wire = Wire(arc_sender, arc_receiver);
+
+    let ports = PortTargets::new(vec!["53".to_string()]).unwrap();
+    let (tx, rx) = channel();
+
+    let scanner = UDPScanner::builder()
+        .interface(interface)
+        .wire(wire)
+        .targets(vec![device])
+        .ports(ports)
+        .source_port(54321_u16)
+        .idle_timeout(Duration::from_secs(2))
+        .notifier(tx)
+        .build()
+        .unwrap();
+
+    let handle = scanner.scan().unwrap();
+
+    let ports = collect_ports(rx);
+
+    let result = handle.join().unwrap();
+
+    assert!(result.is_ok());
+    // repeated replies are de-duplicated
+    assert_eq!(ports.len(), 1);
+    assert_eq!(ports[0].id, 53);
+    assert_eq!(ports[0].service, "dns");
+    assert_eq!(ports[0].state, PortState::Open);
+}
+
+#[test]
+#[allow(warnings)]
+fn reports_closed_port_on_icmp_port_unreachable() {
+    static mut PACKET: [u8; PKT_TOTAL_ICMP_SIZE] = [0u8; PKT_TOTAL_ICMP_SIZE];
+
+    let interface = Arc::new(network::get_default_interface().unwrap());
+    let device_ip = net::Ipv4Addr::from_str("192.168.1.2").unwrap();
+    let device_mac = util::MacAddr::default();
+
+    create_icmp_unreachable_reply(
+        device_mac,
+        device_ip,
+        interface.mac,
+        interface.ipv4,
+        54321,
+        161,
+        destination_unreachable::IcmpCodes::DestinationPortUnreachable,
+        #[allow(static_mut_refs)]
+        unsafe {
+            &mut PACKET
+        },
+    );
+
+    let device = Device {
+        ip: device_ip,
+        mac: device_mac,
+        ..Device::default()
+    };
+
+    let mut receiver = MockPacketReader::new();
+    let mut sender = MockPacketSender::new();
+
+    #[allow(static_mut_refs)]
+    receiver
+        .expect_next_packet()
+        .returning(|| Ok(unsafe { &PACKET }));
+
+    sender.expect_send().returning(|_| Ok(()));
+
+    let arc_receiver: Arc<Mutex<dyn Reader>> = Arc::new(Mutex::new(receiver));
+    let arc_sender: Arc<Mutex<dyn Sender>> = Arc::new(Mutex::new(sender));
+    let wire = Wire(arc_sender, arc_receiver);
+
+    let ports = PortTargets::new(vec!["161".to_string()]).unwrap();
+    let (tx, rx) = channel();
+
+    let scanner = UDPScanner::builder()
+        .interface(interface)
+        .wire(wire)
+        .targets(vec![device])
+        .ports(ports)
+        .source_port(54321_u16)
+        .idle_timeout(Duration::from_secs(2))
+        .notifier(tx)
+        .build()
+        .unwrap();
+
+    let handle = scanner.scan().unwrap();
+
+    let ports = collect_ports(rx);
+
+    let result = handle.join().unwrap();
+
+    assert!(result.is_ok());
+    assert_eq!(ports.len(), 1);
+    assert_eq!(ports[0].id, 161);
+    assert_eq!(ports[0].state, PortState::Closed);
+}
+
+#[test]
+#[allow(warnings)]
+fn reports_filtered_port_on_icmp_admin_prohibited() {
+    static mut PACKET: [u8; PKT_TOTAL_ICMP_SIZE] = [0u8; PKT_TOTAL_ICMP_SIZE];
+
+    let interface = network::get_default_interface().unwrap();
+    let device_ip = net::Ipv4Addr::from_str("192.168.1.2").unwrap();
+    let device_mac = util::MacAddr::default();
+
+    create_icmp_unreachable_reply(
+        device_mac,
+        device_ip,
+        interface.mac,
+        interface.ipv4,
+        54321,
+        123,
+        destination_unreachable::IcmpCodes::CommunicationAdministrativelyProhibited,
+        #[allow(static_mut_refs)]
+        unsafe {
+            &mut PACKET
+        },
+    );
+
+    let arc_receiver: Arc<Mutex<dyn Reader>> =
+        Arc::new(Mutex::new(MockPacketReader::new()));
+    let arc_sender: Arc<Mutex<dyn Sender>> =
+        Arc::new(Mutex::new(MockPacketSender::new()));
+    let wire = Wire(arc_sender, arc_receiver);
+
+    let ports = PortTargets::new(vec!["123".to_string()]).unwrap();
+    let (tx, _rx) = channel();
+
+    let scanner = UDPScanner::builder()
+        .interface(Arc::new(interface))
+        .wire(wire)
+        .targets(vec![])
+        .ports(ports)
+        .source_port(54321_u16)
+        .idle_timeout(Duration::from_secs(2))
+        .notifier(tx)
+        .build()
+        .unwrap();
+
+    #[allow(static_mut_refs)]
+    let classified = scanner.classify_packet(unsafe { &PACKET });
+
+    assert_eq!(classified, Some((device_ip, 123, PortState::Filtered)));
+}
+
+#[test]
+#[allow(warnings)]
+fn reports_unanswered_ports_as_open_filtered() {
+    static mut PACKET: [u8; PKT_TOTAL_UDP_SIZE] = [0u8; PKT_TOTAL_UDP_SIZE];
+
+    let interface = Arc::new(network::get_default_interface().unwrap());
+    let device_ip = net::Ipv4Addr::from_str("192.168.1.2").unwrap();
+    let device_mac = util::MacAddr::default();
+
+    // reply targets a different source port so it must be ignored
+    create_udp_reply(
+        device_mac,
+        device_ip,
+        53,
+        interface.mac,
+        interface.ipv4,
+        54322,
+        #[allow(static_mut_refs)]
+        unsafe {
+            &mut PACKET
+        },
+    );
+
+    let device = Device {
+        ip: device_ip,
+        mac: device_mac,
+        ..Device::default()
+    };
+
+    let mut receiver = MockPacketReader::new();
+    let mut sender = MockPacketSender::new();
+
+    #[allow(static_mut_refs)]
+    receiver
+        .expect_next_packet()
+        .returning(|| Ok(unsafe { &PACKET }));
+
+    sender.expect_send().returning(|_| Ok(()));
+
+    let arc_receiver: Arc<Mutex<dyn Reader>> = Arc::new(Mutex::new(receiver));
+    let arc_sender: Arc<Mutex<dyn Sender>> = Arc::new(Mutex::new(sender));
+    let wire = Wire(arc_sender, arc_receiver);
+
+    let ports = PortTargets::new(vec!["53-54".to_string()]).unwrap();
+    let (tx, rx) = channel();
+
+    let scanner = UDPScanner::builder()
+        .interface(interface)
+        .wire(wire)
+        .targets(vec![device])
+        .ports(ports)
+        .source_port(54321_u16)
+        .idle_timeout(Duration::from_secs(2))
+        .notifier(tx)
+        .build()
+        .unwrap();
+
+    let handle = scanner.scan().unwrap();
+
+    let ports = collect_ports(rx);
+
+    let result = handle.join().unwrap();
+
+    assert!(result.is_ok());
+    assert_eq!(ports.len(), 2);
+    assert!(ports.iter().all(|p| p.state == PortState::OpenFiltered));
+}
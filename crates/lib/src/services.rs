@@ -0,0 +1,21 @@
+//! Service name lookup for TCP and UDP ports.
+use std::sync::{Arc, LazyLock};
+
+use crate::services::{db::ServicesDb, traits::Services};
+
+/// Service name database embedded in the library, generated from the IANA
+/// registry, which can be extended or replaced by files in `/etc/services`
+/// format.
+pub mod db;
+/// [`Services`] trait definition and test mocks.
+pub mod traits;
+
+// parsed once and shared by every scanner using the default database
+static EMBEDDED: LazyLock<Arc<ServicesDb>> =
+    LazyLock::new(|| Arc::new(ServicesDb::default()));
+
+/// Returns the embedded database of the service names of every registered
+/// TCP and UDP port
+pub fn default() -> Arc<dyn Services> {
+    EMBEDDED.clone()
+}
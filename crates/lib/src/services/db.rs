@@ -0,0 +1,127 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use crate::{
+    error::{RLanLibError, Result},
+    services::traits::{Protocol, Services},
+};
+
+// the IANA service names registry, generated by data/generate-services.py
+const IANA_SERVICES: &str = include_str!("../../data/services");
+
+/// Service name database in `/etc/services` format
+#[derive(Debug, Clone)]
+pub struct ServicesDb {
+    entries: HashMap<(u16, Protocol), String>,
+}
+
+impl Default for ServicesDb {
+    /// Returns the IANA service names embedded in the library
+    fn default() -> Self {
+        Self::parse(IANA_SERVICES).unwrap_or_else(|e| {
+            log::error!("failed to parse embedded services: {e}");
+            Self::empty()
+        })
+    }
+}
+
+impl ServicesDb {
+    /// Returns a database without any entries
+    pub fn empty() -> Self {
+        Self {
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Returns a database containing only the entries in the file
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let mut db = Self::empty();
+        db.load_file(path)?;
+        Ok(db)
+    }
+
+    /// Parses entries in `/etc/services` format. Entries for protocols other
+    /// than TCP and UDP are ignored.
+    pub fn parse(data: &str) -> Result<Self> {
+        let mut db = Self::empty();
+        db.extend(data)?;
+        Ok(db)
+    }
+
+    /// Loads the entries in the file, replacing the names of ports already
+    /// in the database i.e. to apply internal port assignments on top of the
+    /// embedded database
+    pub fn load_file(&mut self, path: &Path) -> Result<()> {
+        let data = fs::read_to_string(path).map_err(|e| {
+            RLanLibError::Services(format!(
+                "failed to read {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+
+        self.extend(&data).map_err(|e| {
+            RLanLibError::Services(format!("{}: {}", path.display(), e))
+        })
+    }
+
+    /// Returns the number of port and protocol entries
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if the database has no entries
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn extend(&mut self, data: &str) -> Result<()> {
+        for (i, line) in data.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default();
+            let mut fields = line.split_whitespace();
+
+            // aliases following the port are not needed
+            let (Some(name), Some(port)) = (fields.next(), fields.next())
+            else {
+                if !line.trim().is_empty() {
+                    return Err(RLanLibError::Services(format!(
+                        "line {}: expected <name> <port>/<protocol>",
+                        i + 1
+                    )));
+                }
+                continue;
+            };
+
+            let invalid = || {
+                RLanLibError::Services(format!(
+                    "line {}: invalid port \"{}\"",
+                    i + 1,
+                    port
+                ))
+            };
+
+            let (number, protocol) =
+                port.split_once('/').ok_or_else(invalid)?;
+            let number = number.parse::<u16>().map_err(|_| invalid())?;
+
+            let protocol = match protocol.to_ascii_lowercase().as_str() {
+                "tcp" => Protocol::Tcp,
+                "udp" => Protocol::Udp,
+                _ => continue,
+            };
+
+            self.entries.insert((number, protocol), name.to_string());
+        }
+
+        Ok(())
+    }
+}
+
+impl Services for ServicesDb {
+    fn lookup(&self, port: u16, protocol: Protocol) -> Option<String> {
+        self.entries.get(&(port, protocol)).cloned()
+    }
+}
+
+#[cfg(test)]
+#[path = "./db_tests.rs"]
+mod tests;
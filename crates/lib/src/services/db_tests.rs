@@ -0,0 +1,69 @@
+use super::*;
+use std::io::Write;
+
+#[test]
+fn embeds_iana_services() {
+    let db = ServicesDb::default();
+
+    assert!(db.len() > 700);
+    assert_eq!(db.lookup(22, Protocol::Tcp), Some("ssh".to_string()));
+    assert_eq!(db.lookup(3389, Protocol::Tcp), Some("rdp".to_string()));
+    assert_eq!(
+        db.lookup(9100, Protocol::Tcp),
+        Some("pdl-datastream".into())
+    );
+    assert_eq!(db.lookup(123, Protocol::Udp), Some("ntp".to_string()));
+    assert_eq!(db.lookup(6379, Protocol::Udp), None);
+}
+
+#[test]
+fn embeds_registered_ports_above_well_known_range() {
+    let db = ServicesDb::default();
+
+    assert_eq!(db.lookup(6379, Protocol::Tcp), Some("redis".to_string()));
+    assert_eq!(db.lookup(5683, Protocol::Udp), Some("coap".to_string()));
+    assert_eq!(db.lookup(1883, Protocol::Tcp), Some("mqtt".to_string()));
+    assert_eq!(
+        db.lookup(5432, Protocol::Tcp),
+        Some("postgresql".to_string())
+    );
+}
+
+#[test]
+fn parses_services_format() {
+    let db = ServicesDb::parse(
+        "# internal assignments\n\
+         \n\
+         billing   7100/tcp   billing-api  # accounts team\n\
+         telemetry 7200/UDP\n\
+         appletalk 7300/ddp\n",
+    )
+    .unwrap();
+
+    assert_eq!(db.len(), 2);
+    assert_eq!(db.lookup(7100, Protocol::Tcp), Some("billing".into()));
+    assert_eq!(db.lookup(7200, Protocol::Udp), Some("telemetry".into()));
+    assert_eq!(db.lookup(7100, Protocol::Udp), None);
+
+    assert!(ServicesDb::parse("billing\n").is_err());
+    assert!(ServicesDb::parse("billing 70000/tcp\n").is_err());
+    assert!(ServicesDb::parse("billing tcp\n").is_err());
+}
+
+#[test]
+fn file_entries_override_embedded_names() {
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    writeln!(file, "internal-http 8080/tcp").unwrap();
+
+    let mut db = ServicesDb::default();
+    db.load_file(file.path()).unwrap();
+
+    assert_eq!(db.lookup(8080, Protocol::Tcp), Some("internal-http".into()));
+    assert_eq!(db.lookup(22, Protocol::Tcp), Some("ssh".to_string()));
+
+    let only_file = ServicesDb::from_file(file.path()).unwrap();
+    assert_eq!(only_file.len(), 1);
+
+    let missing = ServicesDb::from_file(Path::new("/does/not/exist"));
+    assert!(matches!(missing, Err(RLanLibError::Services(_))));
+}
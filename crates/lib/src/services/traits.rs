@@ -0,0 +1,30 @@
+/// Transport protocol a port is assigned for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Protocol {
+    /// Transmission Control Protocol
+    Tcp,
+    /// User Datagram Protocol
+    Udp,
+}
+
+/// Resolves a port to the name of the service assigned to it.
+pub trait Services: Send + Sync {
+    /// Returns the name of the service assigned to the port, or `None` if
+    /// the port is not in the database.
+    fn lookup(&self, port: u16, protocol: Protocol) -> Option<String>;
+}
+
+/// Provides services mocks for other modules in test
+#[cfg(test)]
+pub mod mocks {
+    use mockall::mock;
+
+    use super::*;
+
+    mock! {
+            pub ServicesDb {}
+            impl Services for ServicesDb {
+              fn lookup(&self, port: u16, protocol: Protocol) -> Option<String>;
+            }
+    }
+}
@@ -1,3 +1,5 @@
 //! Provides helpers for managing lists of IPs and Ports
 pub mod ips;
+pub mod permutation;
 pub mod ports;
+mod runs;
@@ -1,20 +1,43 @@
 //! Provides helpers for managing IP target lists
 
-use std::{net, str::FromStr, sync::Arc};
+use std::{
+    fs,
+    io::{self, Read},
+    net::{self, ToSocketAddrs},
+    str::FromStr,
+    sync::Arc,
+};
 
-use crate::error::{RLanLibError, Result};
+use crate::{
+    error::{Result, TargetError},
+    targets::runs::{Entry, Run, TargetList, invalid},
+};
 
 #[derive(Debug)]
 /// Represents a list of IP targets
 ///
 /// This wrapper is used to cut down on the memory needed to store entire
 /// network IP ranges. Rather than storing all 65536 IPs in a /16 CIDR block, or
-/// a range of IPS, this wrapper allows the storage of just CIDR or range in
-/// string form and then dynamically loops the IPs in that block when needed.
+/// a range of IPS, this wrapper allows the storage of just the bounds of each
+/// CIDR block or range, computes the length of the list and the IP at any
+/// index arithmetically from them and dynamically loops the IPs in that block
+/// when needed.
+///
+/// Entries may be IPs, ranges (`192.168.0.1-192.168.0.24`), CIDR blocks,
+/// nmap style octet ranges (`192.168.1-3.1-254`, `10.0.0.*`) or hostnames,
+/// which are resolved once when the list is created. An entry of `@path`
+/// reads further entries from a file, or from stdin for `@-`, where each line
+/// may hold several entries and anything after `#` is a comment.
+///
+/// Entries prefixed with `!` exclude IPs, ranges or CIDR blocks from the
+/// rest of the list. Excluded IPs are never looped over or counted
 ///
 /// # Errors
 ///
-/// Returns an error if an item in the list is not a valid IP, CIDR block, or range
+/// Returns [`RLanLibError::InvalidTarget`] naming the first entry that is not
+/// a valid target, or a target file that cannot be read
+///
+/// [`RLanLibError::InvalidTarget`]: crate::error::RLanLibError::InvalidTarget
 ///
 /// # Examples
 ///
@@ -31,90 +54,381 @@ use crate::error::{RLanLibError, Result};
 ///       "192.168.0.1".to_string(),
 ///       "172.17.0.1-172.17.0.24".to_string(),
 ///       "192.168.68.1/24".to_string(),
+///       "10.0.1-2.1-10".to_string(),
+///       "!192.168.68.100-192.168.68.110".to_string(),
 ///     ]
 /// ).unwrap();
 /// ips.lazy_loop(print_ip).unwrap();
 /// ```
-pub struct IPTargets(Vec<String>, usize);
-
-fn loop_ips<F: FnMut(net::Ipv4Addr) -> Result<()>>(
-    list: &[String],
-    mut cb: F,
-) -> Result<()> {
-    for target in list.iter() {
-        if target.contains("-") {
-            // target is range
-            let parts: Vec<&str> = target.split("-").collect();
-
-            let begin = net::Ipv4Addr::from_str(parts[0]).map_err(|e| {
-                RLanLibError::from_net_addr_parse_error(target, e)
-            })?;
+pub struct IPTargets {
+    list: TargetList<IpEntry>,
+}
 
-            let end = net::Ipv4Addr::from_str(parts[1]).map_err(|e| {
-                RLanLibError::from_net_addr_parse_error(target, e)
-            })?;
+// The IPs covered by an entry in the list
+#[derive(Debug, Clone, Copy)]
+enum IpEntry {
+    // A run of consecutive IPs
+    Block { first: u32, len: u64 },
+    // An inclusive range for each octet
+    Octets([(u8, u8); 4]),
+}
+
+impl IpEntry {
+    // Splits the entry into runs of consecutive IPs
+    fn runs(&self) -> Vec<Run<u32>> {
+        let run_len = match self {
+            Self::Block { len, .. } => *len,
+            Self::Octets(octets) => {
+                // octets after the last partial one are full, so each value
+                // of the partial octet and those after it is one run
+                match octets.iter().rposition(|o| *o != (0, 255)) {
+                    Some(i) => {
+                        let (lo, hi) = octets[i];
+                        ((hi - lo) as u64 + 1) << (8 * (3 - i))
+                    }
+                    None => self.len(),
+                }
+            }
+        };
+
+        (0..self.len() / run_len)
+            .map(|r| {
+                let start = self.nth(r * run_len);
+                Run {
+                    start,
+                    end: start + (run_len - 1) as u32,
+                }
+            })
+            .collect()
+    }
+}
+
+impl Entry for IpEntry {
+    type Point = u32;
+
+    fn len(&self) -> u64 {
+        match self {
+            Self::Block { len, .. } => *len,
+            Self::Octets(octets) => octets
+                .iter()
+                .map(|(lo, hi)| (*hi - *lo) as u64 + 1)
+                .product(),
+        }
+    }
 
-            let subnet = ipnet::Ipv4Subnets::new(begin, end, 32);
+    fn nth(&self, n: u64) -> u32 {
+        match self {
+            Self::Block { first, .. } => first + n as u32,
+            Self::Octets(octets) => {
+                // the last octet varies fastest
+                let mut n = n;
+                let mut ip = [0u8; 4];
 
-            for ip_net in subnet {
-                for ip in ip_net.hosts() {
-                    cb(ip)?;
+                for (i, (lo, hi)) in octets.iter().enumerate().rev() {
+                    let count = (*hi - *lo) as u64 + 1;
+                    ip[i] = *lo + (n % count) as u8;
+                    n /= count;
                 }
+
+                u32::from_be_bytes(ip)
             }
-        } else if target.contains("/") {
-            // target is cidr block
-            let ip_net = ipnet::Ipv4Net::from_str(target).map_err(|e| {
-                RLanLibError::from_ipnet_addr_parse_error(target, e)
-            })?;
+        }
+    }
+
+    fn rank(&self, ip: u32) -> u64 {
+        match self {
+            Self::Block { first, len } => {
+                if ip < *first {
+                    0
+                } else {
+                    ((ip - first) as u64 + 1).min(*len)
+                }
+            }
+            Self::Octets(octets) => {
+                let ip = net::Ipv4Addr::from(ip).octets();
+                let counts = octets.map(|(lo, hi)| (hi - lo) as u64 + 1);
+                let mut rank = 0;
+
+                for (i, (lo, hi)) in octets.iter().enumerate() {
+                    let below: u64 = counts[i + 1..].iter().product();
+
+                    if ip[i] < *lo {
+                        return rank;
+                    }
+
+                    if ip[i] > *hi {
+                        return rank + counts[i] * below;
+                    }
+
+                    rank += (ip[i] - lo) as u64 * below;
+                }
+
+                rank + 1
+            }
+        }
+    }
+}
+
+fn parse_ip(target: &str, entry: &str) -> Result<u32> {
+    net::Ipv4Addr::from_str(target)
+        .map(u32::from)
+        .map_err(|_| invalid(entry, TargetError::InvalidRange))
+}
+
+fn parse_octet(part: &str) -> Option<(u8, u8)> {
+    if part == "*" {
+        return Some((0, 255));
+    }
+
+    let (lo, hi) = part.split_once('-').unwrap_or((part, part));
+    let (lo, hi) = (lo.parse::<u8>().ok()?, hi.parse::<u8>().ok()?);
+
+    (lo <= hi).then_some((lo, hi))
+}
+
+fn is_hostname(target: &str) -> bool {
+    target.len() <= 253
+        && target.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
+
+fn resolve(target: &str) -> Result<IpEntry> {
+    if !is_hostname(target) {
+        return Err(invalid(target, TargetError::InvalidHostname));
+    }
+
+    let ip = (target, 0)
+        .to_socket_addrs()
+        .ok()
+        .and_then(|mut addrs| {
+            addrs.find_map(|addr| match addr.ip() {
+                net::IpAddr::V4(ip) => Some(ip),
+                net::IpAddr::V6(_) => None,
+            })
+        })
+        .ok_or_else(|| invalid(target, TargetError::UnresolvedHostname))?;
+
+    Ok(IpEntry::Block {
+        first: u32::from(ip),
+        len: 1,
+    })
+}
+
+fn parse_entry(target: &str) -> Result<IpEntry> {
+    if target.contains('/') {
+        // target is cidr block
+        let ip_net = ipnet::Ipv4Net::from_str(target)
+            .map_err(|_| invalid(target, TargetError::InvalidCidr))?;
+
+        let network = u32::from(ip_net.network());
+        let size = 1u64 << (32 - ip_net.prefix_len());
 
-            for ip in ip_net.hosts() {
-                cb(ip)?;
+        // like ipnet's hosts, skip the network and broadcast addresses of
+        // blocks that have them
+        return Ok(if ip_net.prefix_len() >= 31 {
+            IpEntry::Block {
+                first: network,
+                len: size,
             }
         } else {
-            // target is ip
-            let ip: net::Ipv4Addr =
-                net::Ipv4Addr::from_str(target).map_err(|e| {
-                    RLanLibError::from_net_addr_parse_error(target, e)
-                })?;
+            IpEntry::Block {
+                first: network + 1,
+                len: size - 2,
+            }
+        });
+    }
+
+    let is_numeric = target
+        .chars()
+        .all(|c| c.is_ascii_digit() || matches!(c, '.' | '-' | '*'));
+
+    if is_numeric && !target.contains(['-', '*']) {
+        // target is ip
+        let ip = net::Ipv4Addr::from_str(target)
+            .map_err(|_| invalid(target, TargetError::InvalidIp))?;
+
+        return Ok(IpEntry::Block {
+            first: u32::from(ip),
+            len: 1,
+        });
+    }
+
+    if is_numeric && target.matches('.').count() == 3 {
+        // target is octet range
+        let mut octets = [(0, 0); 4];
+
+        for (octet, part) in octets.iter_mut().zip(target.split('.')) {
+            *octet = parse_octet(part).ok_or_else(|| {
+                invalid(target, TargetError::InvalidOctetRange)
+            })?;
+        }
+
+        return Ok(IpEntry::Octets(octets));
+    }
+
+    if let Some((begin, end)) = target.split_once('-')
+        && (is_numeric
+            || net::Ipv4Addr::from_str(begin).is_ok()
+            || net::Ipv4Addr::from_str(end).is_ok())
+    {
+        // target is range
+        let (first, last) = (parse_ip(begin, target)?, parse_ip(end, target)?);
+
+        if first > last {
+            return Err(invalid(target, TargetError::InvalidRange));
+        }
+
+        return Ok(IpEntry::Block {
+            first,
+            len: (last - first) as u64 + 1,
+        });
+    }
+
+    resolve(target)
+}
+
+// Parses an exclusion, which unlike a target covers the network and
+// broadcast addresses of CIDR blocks too
+fn parse_exclusion(target: &str) -> Result<IpEntry> {
+    let target = target.trim_start_matches('!');
+
+    if target.contains('/') {
+        let ip_net = ipnet::Ipv4Net::from_str(target)
+            .map_err(|_| invalid(target, TargetError::InvalidCidr))?;
+
+        return Ok(IpEntry::Block {
+            first: u32::from(ip_net.network()),
+            len: 1u64 << (32 - ip_net.prefix_len()),
+        });
+    }
+
+    parse_entry(target)
+}
+
+// Replaces `@path` entries with the entries listed in that file, or in stdin
+// for `@-`
+fn expand_files(list: Vec<String>) -> Result<Vec<String>> {
+    let mut expanded = Vec::new();
+
+    for target in list {
+        let Some(path) = target.strip_prefix('@') else {
+            expanded.push(target);
+            continue;
+        };
+
+        let read_error =
+            |e: io::Error| invalid(&target, TargetError::File(e.to_string()));
+
+        let contents = if path == "-" {
+            let mut contents = String::new();
+            io::stdin()
+                .read_to_string(&mut contents)
+                .map_err(read_error)?;
+            contents
+        } else {
+            fs::read_to_string(path).map_err(read_error)?
+        };
 
-            cb(ip)?;
+        for line in contents.lines() {
+            let line = line.split('#').next().unwrap_or_default();
+
+            expanded.extend(
+                line.split(|c: char| c.is_whitespace() || c == ',')
+                    .filter(|t| !t.is_empty())
+                    .map(String::from),
+            );
         }
     }
-    Ok(())
+
+    Ok(expanded)
 }
 
 impl IPTargets {
     /// Returns a new instance of IPTargets using the provided list
     pub fn new(list: Vec<String>) -> Result<Arc<Self>> {
-        let mut len = 0;
+        Self::with_exclude(list, Vec::new())
+    }
 
-        loop_ips(&list, |_| {
-            len += 1;
-            Ok(())
-        })?;
+    /// Returns a new instance of IPTargets using the provided list, leaving
+    /// out every IP, range or CIDR block in `exclude`
+    pub fn with_exclude(
+        list: Vec<String>,
+        exclude: Vec<String>,
+    ) -> Result<Arc<Self>> {
+        let (excluded, list): (Vec<String>, Vec<String>) = expand_files(list)?
+            .into_iter()
+            .partition(|t| t.starts_with('!'));
 
-        Ok(Arc::new(Self(list, len)))
+        let mut runs = Vec::new();
+
+        for target in excluded.iter().chain(expand_files(exclude)?.iter()) {
+            runs.extend(parse_exclusion(target)?.runs());
+        }
+
+        let list = list
+            .iter()
+            .map(|t| parse_entry(t))
+            .collect::<Result<Vec<IpEntry>>>()?;
+
+        Ok(Arc::new(Self {
+            list: TargetList::new(list, runs),
+        }))
     }
 
-    /// Returns the true length of the target list. If the underlying
-    /// `Vec<String>` is just `["192.168.0.1/24"]`, then a call to "len" will
-    /// return 256
+    /// Returns the true length of the target list. If the provided
+    /// list is just `["192.168.0.1/24"]`, then a call to "len" will
+    /// return 254. The length is computed without looping the IPs
     pub fn len(&self) -> usize {
-        self.1
+        self.list.len()
     }
 
     /// Returns true if the list is empty
     pub fn is_empty(&self) -> bool {
-        self.1 == 0
+        self.list.len() == 0
     }
 
     /// loops over all targets including those that are not explicitly in the
     /// list but fall within a range or CIDR block defined in the list
     pub fn lazy_loop<F: FnMut(net::Ipv4Addr) -> Result<()>>(
         &self,
-        cb: F,
+        mut cb: F,
+    ) -> Result<()> {
+        self.list.lazy_loop(|ip| cb(net::Ipv4Addr::from(ip)))
+    }
+
+    /// Returns the target at `index` in the order `lazy_loop` visits them,
+    /// without looping the targets before it
+    pub fn nth(&self, index: usize) -> Option<net::Ipv4Addr> {
+        self.list.nth(index).map(net::Ipv4Addr::from)
+    }
+
+    /// Returns shard `k` of `n` disjoint, contiguous slices of the targets.
+    /// Together the `n` shards cover every target exactly once, so a sweep
+    /// can be split across scanners or machines
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TargetError::InvalidShard`] if `k` is not less than `n`
+    pub fn shard(&self, k: usize, n: usize) -> Result<Arc<Self>> {
+        Ok(Arc::new(Self {
+            list: self.list.shard(k, n)?,
+        }))
+    }
+
+    /// loops over all targets like `lazy_loop` but in a random order. The
+    /// same seed always produces the same order
+    pub fn lazy_loop_shuffled<F: FnMut(net::Ipv4Addr) -> Result<()>>(
+        &self,
+        seed: u64,
+        mut cb: F,
     ) -> Result<()> {
-        loop_ips(&self.0, cb)
+        self.list
+            .lazy_loop_shuffled(seed, |ip| cb(net::Ipv4Addr::from(ip)))
     }
 }
 
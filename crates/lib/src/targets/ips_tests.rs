@@ -1,4 +1,5 @@
 use super::*;
+use crate::error::RLanLibError;
 
 #[test]
 fn returns_new_ip_targets() {
@@ -8,7 +9,7 @@ fn returns_new_ip_targets() {
         String::from("192.128.28.3"),
     ];
     let targets = IPTargets::new(list).unwrap();
-    assert!(!targets.0.is_empty());
+    assert!(!targets.list.targets.is_empty());
 }
 
 #[test]
@@ -82,3 +83,317 @@ fn returns_error_for_malformed_range_end() {
     let result = IPTargets::new(list);
     assert!(result.is_err());
 }
+
+#[test]
+fn lazy_loops_ips_shuffled() {
+    let list = vec![
+        String::from("192.128.28.1"),
+        String::from("192.128.28.2-192.128.28.4"),
+        String::from("192.128.30.0/24"),
+    ];
+
+    let targets = IPTargets::new(list).unwrap();
+
+    let mut expected = Vec::new();
+    targets
+        .lazy_loop(|ip| {
+            expected.push(ip);
+            Ok(())
+        })
+        .unwrap();
+
+    let shuffle = |seed| {
+        let mut ips = Vec::new();
+        targets
+            .lazy_loop_shuffled(seed, |ip| {
+                ips.push(ip);
+                Ok(())
+            })
+            .unwrap();
+        ips
+    };
+
+    let shuffled = shuffle(9);
+    assert_eq!(shuffled, shuffle(9));
+    assert_ne!(shuffled, expected);
+
+    let mut sorted = shuffled.clone();
+    sorted.sort();
+    assert_eq!(sorted, expected);
+}
+
+#[test]
+fn excludes_ips_ranges_and_cidr_blocks() {
+    let list = vec![
+        String::from("10.0.0.0-10.0.1.255"),
+        String::from("!10.0.1.0/24"),
+        String::from("!10.0.0.7"),
+    ];
+
+    let targets = IPTargets::with_exclude(
+        list,
+        vec![String::from("10.0.0.100-10.0.0.199")],
+    )
+    .unwrap();
+
+    assert_eq!(targets.len(), 155);
+
+    let mut ips = Vec::new();
+    targets
+        .lazy_loop(|ip| {
+            ips.push(ip);
+            Ok(())
+        })
+        .unwrap();
+
+    assert_eq!(ips.len(), 155);
+    assert!(!ips.contains(&net::Ipv4Addr::new(10, 0, 0, 7)));
+    assert!(!ips.contains(&net::Ipv4Addr::new(10, 0, 0, 150)));
+    assert!(ips.iter().all(|ip| ip.octets()[2] == 0));
+
+    let mut shuffled = Vec::new();
+    targets
+        .lazy_loop_shuffled(5, |ip| {
+            shuffled.push(ip);
+            Ok(())
+        })
+        .unwrap();
+
+    shuffled.sort();
+    assert_eq!(shuffled, ips);
+}
+
+#[test]
+fn returns_error_for_malformed_exclusion() {
+    let list = vec![String::from("192.168.0.0/24"), String::from("!nope")];
+    let result = IPTargets::new(list);
+    assert!(result.is_err());
+}
+
+#[test]
+fn lazy_loops_octet_ranges() {
+    let list = vec![String::from("10.0.1-2.1-3"), String::from("10.9.9.*")];
+
+    let targets = IPTargets::new(list).unwrap();
+    assert_eq!(targets.len(), 262);
+
+    let mut ips = Vec::new();
+    targets
+        .lazy_loop(|ip| {
+            ips.push(ip);
+            Ok(())
+        })
+        .unwrap();
+
+    assert_eq!(
+        ips[..4],
+        [
+            net::Ipv4Addr::new(10, 0, 1, 1),
+            net::Ipv4Addr::new(10, 0, 1, 2),
+            net::Ipv4Addr::new(10, 0, 1, 3),
+            net::Ipv4Addr::new(10, 0, 2, 1),
+        ]
+    );
+    assert_eq!(ips[6], net::Ipv4Addr::new(10, 9, 9, 0));
+    assert_eq!(ips[261], net::Ipv4Addr::new(10, 9, 9, 255));
+
+    let excluded = IPTargets::new(vec![
+        String::from("10.0.0.0/24"),
+        String::from("!10.0.0.1-9"),
+    ])
+    .unwrap();
+    assert_eq!(excluded.len(), 245);
+}
+
+#[test]
+fn resolves_hostnames() {
+    let targets = IPTargets::new(vec![String::from("localhost")]).unwrap();
+
+    let mut ips = Vec::new();
+    targets
+        .lazy_loop(|ip| {
+            ips.push(ip);
+            Ok(())
+        })
+        .unwrap();
+
+    assert_eq!(ips.len(), 1);
+    assert!(ips[0].is_loopback());
+}
+
+#[test]
+fn reads_targets_from_file() {
+    let path = std::env::temp_dir()
+        .join(format!("r-lanlib-targets-{}.txt", std::process::id()));
+
+    fs::write(
+        &path,
+        "# office\n192.168.0.1 192.168.0.2 # printers\n\n192.168.1.0/30,\
+         !192.168.1.2\n",
+    )
+    .unwrap();
+
+    let targets = IPTargets::new(vec![format!("@{}", path.display())]).unwrap();
+
+    fs::remove_file(&path).unwrap();
+
+    let mut ips = Vec::new();
+    targets
+        .lazy_loop(|ip| {
+            ips.push(ip);
+            Ok(())
+        })
+        .unwrap();
+
+    assert_eq!(
+        ips,
+        vec![
+            net::Ipv4Addr::new(192, 168, 0, 1),
+            net::Ipv4Addr::new(192, 168, 0, 2),
+            net::Ipv4Addr::new(192, 168, 1, 1),
+        ]
+    );
+}
+
+#[test]
+fn returns_typed_errors_naming_the_entry() {
+    let reason = |target: &str| match IPTargets::new(vec![
+        String::from("192.168.0.1"),
+        target.to_string(),
+    ]) {
+        Err(RLanLibError::InvalidTarget { target: t, reason }) => {
+            assert_eq!(t, target);
+            reason
+        }
+        other => panic!("expected invalid target, got {other:?}"),
+    };
+
+    assert_eq!(reason("192.168.0.256"), TargetError::InvalidIp);
+    assert_eq!(reason("192.168.0.9-192.168.0.1"), TargetError::InvalidRange);
+    assert_eq!(reason("192.168.0.1-nope"), TargetError::InvalidRange);
+    assert_eq!(reason("192.168.3-1.1"), TargetError::InvalidOctetRange);
+    assert_eq!(reason("192.168.0.1-300"), TargetError::InvalidOctetRange);
+    assert_eq!(reason("192.168.0.0/33"), TargetError::InvalidCidr);
+    assert_eq!(reason("bad_host!"), TargetError::InvalidHostname);
+    assert!(matches!(
+        reason("@/nonexistent/r-lanlib/targets"),
+        TargetError::File(_)
+    ));
+}
+
+#[test]
+fn sizes_huge_ranges_without_looping() {
+    let targets = IPTargets::new(vec![
+        String::from("10.0.0.0/8"),
+        String::from("!10.1.0.0/16"),
+        String::from("!10.2.*.0"),
+    ])
+    .unwrap();
+
+    assert_eq!(targets.len(), 16_777_214 - 65_536 - 256);
+    assert_eq!(targets.nth(0), Some(net::Ipv4Addr::new(10, 0, 0, 1)));
+    assert_eq!(
+        targets.nth(65_534),
+        Some(net::Ipv4Addr::new(10, 0, 255, 255))
+    );
+    assert_eq!(targets.nth(65_535), Some(net::Ipv4Addr::new(10, 2, 0, 1)));
+    assert_eq!(targets.nth(65_790), Some(net::Ipv4Addr::new(10, 2, 1, 1)));
+    assert_eq!(targets.nth(targets.len()), None);
+    assert_eq!(
+        targets.nth(targets.len() - 1),
+        Some(net::Ipv4Addr::new(10, 255, 255, 254))
+    );
+}
+
+#[test]
+fn indexes_targets_in_loop_order() {
+    let targets = IPTargets::with_exclude(
+        vec![
+            String::from("10.0.0.0/23"),
+            String::from("10.0.1-3.250-255"),
+            String::from("10.0.0.5"),
+        ],
+        vec![
+            String::from("10.0.0.3-10.0.0.9"),
+            String::from("10.0.*.252"),
+        ],
+    )
+    .unwrap();
+
+    let mut ips = Vec::new();
+    targets
+        .lazy_loop(|ip| {
+            ips.push(ip);
+            Ok(())
+        })
+        .unwrap();
+
+    assert_eq!(ips.len(), targets.len());
+
+    for (i, ip) in ips.iter().enumerate() {
+        assert_eq!(targets.nth(i), Some(*ip));
+    }
+}
+
+#[test]
+fn shards_targets_into_disjoint_slices() {
+    let targets = IPTargets::new(vec![
+        String::from("10.0.0.0/24"),
+        String::from("10.0.1.1-10.0.1.100"),
+        String::from("!10.0.0.50-10.0.0.60"),
+    ])
+    .unwrap();
+
+    let mut expected = Vec::new();
+    targets
+        .lazy_loop(|ip| {
+            expected.push(ip);
+            Ok(())
+        })
+        .unwrap();
+
+    let mut ips = Vec::new();
+
+    for k in 0..7 {
+        let shard = targets.shard(k, 7).unwrap();
+        let mut shard_ips = Vec::new();
+
+        shard
+            .lazy_loop(|ip| {
+                shard_ips.push(ip);
+                Ok(())
+            })
+            .unwrap();
+
+        assert_eq!(shard_ips.len(), shard.len());
+        assert!(shard.len().abs_diff(targets.len() / 7) <= 1);
+        assert_eq!(shard.nth(0), shard_ips.first().copied());
+
+        let mut shuffled = Vec::new();
+        shard
+            .lazy_loop_shuffled(3, |ip| {
+                shuffled.push(ip);
+                Ok(())
+            })
+            .unwrap();
+        shuffled.sort();
+        assert_eq!(shuffled, shard_ips);
+
+        ips.extend(shard_ips);
+    }
+
+    assert_eq!(ips, expected);
+}
+
+#[test]
+fn rejects_out_of_range_shard() {
+    let targets = IPTargets::new(vec![String::from("10.0.0.1")]).unwrap();
+
+    assert!(matches!(
+        targets.shard(2, 2),
+        Err(RLanLibError::InvalidTarget {
+            reason: TargetError::InvalidShard,
+            ..
+        })
+    ));
+}
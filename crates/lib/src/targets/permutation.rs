@@ -0,0 +1,210 @@
+//! Provides a seeded random permutation of target indices
+//!
+//! Indices are visited by walking the multiplicative group of integers modulo
+//! the smallest prime above the number of targets, the cyclic group technique
+//! used by zmap. Every index is visited exactly once in an order determined by
+//! the seed, while only the current position is held in memory.
+
+// Bases making Miller-Rabin deterministic for every u64
+const WITNESSES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+
+/// Iterates the indices `0..len` in a random order that is the same for
+/// every permutation created with the same seed
+///
+/// # Examples
+///
+/// ```
+/// # use r_lanlib::targets::permutation::Permutation;
+/// let mut indices: Vec<usize> = Permutation::new(5, 42).collect();
+/// assert_eq!(indices, Permutation::new(5, 42).collect::<Vec<_>>());
+/// indices.sort();
+/// assert_eq!(indices, vec![0, 1, 2, 3, 4]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Permutation {
+    len: u64,
+    prime: u64,
+    generator: u64,
+    first: u64,
+    current: u64,
+    done: bool,
+}
+
+impl Permutation {
+    /// Returns a permutation of `0..len` seeded by `seed`
+    pub fn new(len: usize, seed: u64) -> Self {
+        let len = len as u64;
+
+        if len == 0 {
+            return Self {
+                len,
+                prime: 2,
+                generator: 1,
+                first: 1,
+                current: 1,
+                done: true,
+            };
+        }
+
+        let prime = next_prime(len + 1);
+        let mut rng = SplitMix64(seed);
+        let generator = find_generator(prime, &mut rng);
+        let first = 1 + rng.next() % (prime - 1);
+
+        Self {
+            len,
+            prime,
+            generator,
+            first,
+            current: first,
+            done: false,
+        }
+    }
+}
+
+impl Iterator for Permutation {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            let value = self.current;
+
+            self.current = mul_mod(self.current, self.generator, self.prime);
+            self.done = self.current == self.first;
+
+            // the group holds 1..prime so values above len are skipped
+            if value <= self.len {
+                return Some((value - 1) as usize);
+            }
+        }
+
+        None
+    }
+}
+
+// Small, fast and reproducible generator used to pick the walk
+pub(crate) struct SplitMix64(pub(crate) u64);
+
+impl SplitMix64 {
+    pub(crate) fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+fn mul_mod(a: u64, b: u64, m: u64) -> u64 {
+    ((a as u128 * b as u128) % m as u128) as u64
+}
+
+fn pow_mod(mut base: u64, mut exp: u64, m: u64) -> u64 {
+    let mut result = 1 % m;
+    base %= m;
+
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mul_mod(result, base, m);
+        }
+        base = mul_mod(base, base, m);
+        exp >>= 1;
+    }
+
+    result
+}
+
+fn is_prime(n: u64) -> bool {
+    if n < 2 {
+        return false;
+    }
+
+    for p in WITNESSES {
+        if n.is_multiple_of(p) {
+            return n == p;
+        }
+    }
+
+    let mut d = n - 1;
+    let mut s = 0;
+
+    while d.is_multiple_of(2) {
+        d /= 2;
+        s += 1;
+    }
+
+    'witness: for a in WITNESSES {
+        let mut x = pow_mod(a, d, n);
+
+        if x == 1 || x == n - 1 {
+            continue;
+        }
+
+        for _ in 1..s {
+            x = mul_mod(x, x, n);
+            if x == n - 1 {
+                continue 'witness;
+            }
+        }
+
+        return false;
+    }
+
+    true
+}
+
+// Returns the smallest prime at or above n
+fn next_prime(mut n: u64) -> u64 {
+    while !is_prime(n) {
+        n += 1;
+    }
+    n
+}
+
+// Returns the distinct prime factors of n
+fn prime_factors(mut n: u64) -> Vec<u64> {
+    let mut factors = Vec::new();
+    let mut p = 2;
+
+    while p * p <= n {
+        if n.is_multiple_of(p) {
+            factors.push(p);
+            while n.is_multiple_of(p) {
+                n /= p;
+            }
+        }
+        p += 1;
+    }
+
+    if n > 1 {
+        factors.push(n);
+    }
+
+    factors
+}
+
+// Picks a random generator of the multiplicative group modulo prime, i.e.
+// an element whose powers visit every member of the group
+fn find_generator(prime: u64, rng: &mut SplitMix64) -> u64 {
+    if prime == 2 {
+        return 1;
+    }
+
+    let order = prime - 1;
+    let factors = prime_factors(order);
+
+    loop {
+        let candidate = 2 + rng.next() % (prime - 2);
+
+        if factors
+            .iter()
+            .all(|q| pow_mod(candidate, order / q, prime) != 1)
+        {
+            return candidate;
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "./permutation_tests.rs"]
+mod tests;
@@ -0,0 +1,32 @@
+use super::*;
+
+#[test]
+fn visits_every_index_once() {
+    for len in [0, 1, 2, 3, 10, 256, 1000] {
+        let mut indices: Vec<usize> = Permutation::new(len, 7).collect();
+        indices.sort();
+        assert_eq!(indices, (0..len).collect::<Vec<_>>());
+    }
+}
+
+#[test]
+fn is_reproducible_for_a_seed() {
+    let first: Vec<usize> = Permutation::new(1000, 1234).collect();
+    let second: Vec<usize> = Permutation::new(1000, 1234).collect();
+    let other: Vec<usize> = Permutation::new(1000, 4321).collect();
+
+    assert_eq!(first, second);
+    assert_ne!(first, other);
+    assert_ne!(first, (0..1000).collect::<Vec<_>>());
+}
+
+#[test]
+fn finds_primes() {
+    let primes: Vec<u64> = (0..30).filter(|n| is_prime(*n)).collect();
+    assert_eq!(primes, vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29]);
+
+    assert!(is_prime(4_294_967_311));
+    assert!(!is_prime(4_294_967_297));
+    assert_eq!(next_prime(257), 257);
+    assert_eq!(next_prime(258), 263);
+}
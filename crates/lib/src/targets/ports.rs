@@ -2,19 +2,29 @@
 
 use std::sync::Arc;
 
-use crate::error::{RLanLibError, Result};
+use crate::{
+    error::{Result, TargetError},
+    targets::runs::{Entry, Run, TargetList, invalid},
+};
 
 #[derive(Debug)]
 /// Represents a list of Port targets
 ///
 /// This wrapper is used to cut down on the memory needed to store entire
-/// port ranges. Rather than storing all ports in a range of 1-65535, this
-/// wrapper allows the storage of just the range in string form and then
-/// dynamically loops the ports in that range when needed.
+/// port ranges. Rather than storing all ports in a range of 1-65535, each
+/// entry is parsed once into its first port and length, so the length of
+/// the list and the port at any index are computed arithmetically and ports
+/// are only generated as they are looped over.
+///
+/// Entries prefixed with `!` exclude ports or port ranges from the rest of
+/// the list. Excluded ports are never looped over or counted
 ///
 /// # Errors
 ///
-/// Returns an error if an item in the list does not parse to a valid port (u16)
+/// Returns [`RLanLibError::InvalidTarget`] naming the first entry that does
+/// not parse to a valid port (u16) or port range
+///
+/// [`RLanLibError::InvalidTarget`]: crate::error::RLanLibError::InvalidTarget
 ///
 /// # Examples
 ///
@@ -25,67 +35,149 @@ use crate::error::{RLanLibError, Result};
 ///   println!("port: {}", port);
 ///   Ok(())
 /// };
-/// let ports = PortTargets::new(vec!["1-65535".to_string()]).unwrap();
+/// let ports = PortTargets::new(
+///     vec!["1-65535".to_string(), "!9100".to_string()]
+/// ).unwrap();
 /// ports.lazy_loop(print_port).unwrap();
 /// ```
-pub struct PortTargets(Vec<String>, usize);
-
-fn loop_ports<F: FnMut(u16) -> Result<()>>(
-    list: &[String],
-    mut cb: F,
-) -> Result<()> {
-    for target in list.iter() {
-        if target.contains("-") {
-            let parts: Vec<&str> = target.split("-").collect();
-            let begin = parts[0].parse::<u16>().map_err(|e| {
-                RLanLibError::from_port_parse_int_err(&target.to_string(), e)
-            })?;
-            let end = parts[1].parse::<u16>().map_err(|e| {
-                RLanLibError::from_port_parse_int_err(&target.to_string(), e)
-            })?;
-
-            for port in begin..=end {
-                cb(port)?;
-            }
-        } else {
-            let port = target.parse::<u16>().map_err(|e| {
-                RLanLibError::from_port_parse_int_err(&target.to_string(), e)
-            })?;
+pub struct PortTargets {
+    list: TargetList<PortBlock>,
+}
 
-            cb(port)?;
+// A run of consecutive ports covered by an entry in the list
+#[derive(Debug, Clone, Copy)]
+struct PortBlock {
+    first: u16,
+    len: u64,
+}
+
+impl PortBlock {
+    fn run(&self) -> Run<u16> {
+        Run {
+            start: self.first,
+            end: self.first + (self.len - 1) as u16,
         }
     }
+}
+
+impl Entry for PortBlock {
+    type Point = u16;
+
+    fn len(&self) -> u64 {
+        self.len
+    }
+
+    fn nth(&self, n: u64) -> u16 {
+        self.first + n as u16
+    }
 
-    Ok(())
+    fn rank(&self, port: u16) -> u64 {
+        if port < self.first {
+            0
+        } else {
+            ((port - self.first) as u64 + 1).min(self.len)
+        }
+    }
+}
+
+fn parse_block(target: &str) -> Result<PortBlock> {
+    if let Some((begin, end)) = target.split_once('-') {
+        let (begin, end) = match (begin.parse::<u16>(), end.parse::<u16>()) {
+            (Ok(begin), Ok(end)) if begin <= end => (begin, end),
+            _ => return Err(invalid(target, TargetError::InvalidPortRange)),
+        };
+
+        Ok(PortBlock {
+            first: begin,
+            len: (end - begin) as u64 + 1,
+        })
+    } else {
+        let port = target
+            .parse::<u16>()
+            .map_err(|_| invalid(target, TargetError::InvalidPort))?;
+
+        Ok(PortBlock {
+            first: port,
+            len: 1,
+        })
+    }
 }
 
 impl PortTargets {
     /// Returns a new instance of PortTargets using the provided list
     pub fn new(list: Vec<String>) -> Result<Arc<Self>> {
-        let mut len = 0;
-        loop_ports(&list, |_| {
-            len += 1;
-            Ok(())
-        })?;
-        Ok(Arc::new(Self(list, len)))
+        Self::with_exclude(list, Vec::new())
+    }
+
+    /// Returns a new instance of PortTargets using the provided list,
+    /// leaving out every port or port range in `exclude`
+    pub fn with_exclude(
+        list: Vec<String>,
+        exclude: Vec<String>,
+    ) -> Result<Arc<Self>> {
+        let (excluded, list): (Vec<String>, Vec<String>) =
+            list.into_iter().partition(|t| t.starts_with('!'));
+
+        let exclude = excluded
+            .iter()
+            .chain(exclude.iter())
+            .map(|t| Ok(parse_block(t.trim_start_matches('!'))?.run()))
+            .collect::<Result<Vec<Run<u16>>>>()?;
+
+        let list = list
+            .iter()
+            .map(|t| parse_block(t))
+            .collect::<Result<Vec<PortBlock>>>()?;
+
+        Ok(Arc::new(Self {
+            list: TargetList::new(list, exclude),
+        }))
     }
 
     /// Returns true if the list is empty
     pub fn is_empty(&self) -> bool {
-        self.1 == 0
+        self.list.len() == 0
     }
 
-    /// Returns the true length of the target list. If the underlying
-    /// `Vec<String>` is just `["22-24"]`, then a call to "len" will
-    /// return 3
+    /// Returns the true length of the target list. If the provided
+    /// list is just `["22-24"]`, then a call to "len" will
+    /// return 3. The length is computed without looping the ports
     pub fn len(&self) -> usize {
-        self.1
+        self.list.len()
     }
 
     /// loops over all targets including those that are not explicitly in the
     /// list but fall within a range defined in the list
     pub fn lazy_loop<F: FnMut(u16) -> Result<()>>(&self, cb: F) -> Result<()> {
-        loop_ports(&self.0, cb)
+        self.list.lazy_loop(cb)
+    }
+
+    /// Returns the target at `index` in the order `lazy_loop` visits them,
+    /// without looping the targets before it
+    pub fn nth(&self, index: usize) -> Option<u16> {
+        self.list.nth(index)
+    }
+
+    /// Returns shard `k` of `n` disjoint, contiguous slices of the targets.
+    /// Together the `n` shards cover every target exactly once
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TargetError::InvalidShard`] if `k` is not less than `n`
+    pub fn shard(&self, k: usize, n: usize) -> Result<Arc<Self>> {
+        Ok(Arc::new(Self {
+            list: self.list.shard(k, n)?,
+        }))
+    }
+
+    /// loops over all targets like `lazy_loop` but in a random order. The
+    /// same seed always produces the same order
+    pub fn lazy_loop_shuffled<F: FnMut(u16) -> Result<()>>(
+        &self,
+        seed: u64,
+        cb: F,
+    ) -> Result<()> {
+        self.list.lazy_loop_shuffled(seed, cb)
     }
 }
 
@@ -1,10 +1,11 @@
 use super::*;
+use crate::error::RLanLibError;
 
 #[test]
 fn returns_new_port_targets() {
     let list = vec![String::from("1"), String::from("2"), String::from("3")];
     let targets = PortTargets::new(list).unwrap();
-    assert!(!targets.0.is_empty());
+    assert!(!targets.list.targets.is_empty());
 }
 
 #[test]
@@ -53,3 +54,109 @@ fn returns_error_for_malformed_range_end() {
     let result = PortTargets::new(list);
     assert!(result.is_err());
 }
+
+#[test]
+fn lazy_loops_ports_shuffled() {
+    let list = vec![String::from("1"), String::from("2-100")];
+    let targets = PortTargets::new(list).unwrap();
+
+    let shuffle = |seed| {
+        let mut ports = Vec::new();
+        targets
+            .lazy_loop_shuffled(seed, |p| {
+                ports.push(p);
+                Ok(())
+            })
+            .unwrap();
+        ports
+    };
+
+    let shuffled = shuffle(3);
+    assert_eq!(shuffled, shuffle(3));
+    assert_ne!(shuffled, (1..=100).collect::<Vec<u16>>());
+
+    let mut sorted = shuffled.clone();
+    sorted.sort();
+    assert_eq!(sorted, (1..=100).collect::<Vec<u16>>());
+}
+
+#[test]
+fn excludes_ports_and_ranges() {
+    let list = vec![String::from("1-100"), String::from("!10-19")];
+    let targets =
+        PortTargets::with_exclude(list, vec![String::from("50")]).unwrap();
+
+    assert_eq!(targets.len(), 89);
+
+    let mut ports = Vec::new();
+    targets
+        .lazy_loop(|p| {
+            ports.push(p);
+            Ok(())
+        })
+        .unwrap();
+
+    let expected: Vec<u16> = (1..=100)
+        .filter(|p| !(10..=19).contains(p) && *p != 50)
+        .collect();
+
+    assert_eq!(ports, expected);
+}
+
+#[test]
+fn returns_typed_errors_naming_the_entry() {
+    let reason = |target: &str| match PortTargets::new(vec![
+        String::from("22"),
+        target.to_string(),
+    ]) {
+        Err(RLanLibError::InvalidTarget { target: t, reason }) => {
+            assert_eq!(t, target);
+            reason
+        }
+        other => panic!("expected invalid target, got {other:?}"),
+    };
+
+    assert_eq!(reason("65536"), TargetError::InvalidPort);
+    assert_eq!(reason("80-nope"), TargetError::InvalidPortRange);
+    assert_eq!(reason("443-80"), TargetError::InvalidPortRange);
+}
+
+#[test]
+fn indexes_and_shards_ports() {
+    let targets = PortTargets::new(vec![
+        String::from("1-1000"),
+        String::from("!20-30"),
+        String::from("!25-40"),
+        String::from("8080"),
+    ])
+    .unwrap();
+
+    assert_eq!(targets.len(), 980);
+    assert_eq!(targets.nth(18), Some(19));
+    assert_eq!(targets.nth(19), Some(41));
+    assert_eq!(targets.nth(979), Some(8080));
+    assert_eq!(targets.nth(980), None);
+
+    let mut expected = Vec::new();
+    targets
+        .lazy_loop(|port| {
+            expected.push(port);
+            Ok(())
+        })
+        .unwrap();
+
+    let mut ports = Vec::new();
+
+    for k in 0..3 {
+        let shard = targets.shard(k, 3).unwrap();
+
+        shard
+            .lazy_loop(|port| {
+                ports.push(port);
+                Ok(())
+            })
+            .unwrap();
+    }
+
+    assert_eq!(ports, expected);
+}
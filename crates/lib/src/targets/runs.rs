@@ -0,0 +1,254 @@
+//! Provides the entries, runs and exclusions shared by IP and port target
+//! lists
+//!
+//! Both lists hold the entries they were given along with a sorted list of
+//! excluded runs, and index the targets arithmetically from them.
+
+use std::fmt::Debug;
+
+use crate::{
+    error::{RLanLibError, Result, TargetError},
+    targets::permutation::Permutation,
+};
+
+// A value in a target list, i.e. an IP as u32 or a port
+pub(crate) trait Point: Copy + Ord + Debug + Into<u64> {
+    // Returns the value before this one, if any
+    fn prev(self) -> Option<Self>;
+}
+
+impl Point for u16 {
+    fn prev(self) -> Option<Self> {
+        self.checked_sub(1)
+    }
+}
+
+impl Point for u32 {
+    fn prev(self) -> Option<Self> {
+        self.checked_sub(1)
+    }
+}
+
+// An inclusive run of consecutive points
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct Run<P> {
+    pub(crate) start: P,
+    pub(crate) end: P,
+}
+
+// The points covered by an entry in a target list, in loop order
+pub(crate) trait Entry: Copy + Debug {
+    type Point: Point;
+
+    fn len(&self) -> u64;
+
+    fn nth(&self, n: u64) -> Self::Point;
+
+    // Returns how many points in the entry are at or below point
+    fn rank(&self, point: Self::Point) -> u64;
+
+    // Returns how many points in the entry are below point
+    fn rank_below(&self, point: Self::Point) -> u64 {
+        point.prev().map_or(0, |point| self.rank(point))
+    }
+}
+
+// An entry in the list along with how many of its points are not excluded
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Target<E> {
+    entry: E,
+    len: u64,
+}
+
+impl<E: Entry> Target<E> {
+    fn new(entry: E, exclude: &[Run<E::Point>]) -> Self {
+        let excluded: u64 = exclude
+            .iter()
+            .map(|run| entry.rank(run.end) - entry.rank_below(run.start))
+            .sum();
+
+        Self {
+            entry,
+            len: entry.len() - excluded,
+        }
+    }
+
+    // Returns the position within the entry of the nth point that is not
+    // excluded
+    fn position(&self, exclude: &[Run<E::Point>], mut n: u64) -> u64 {
+        if self.len == self.entry.len() {
+            return n;
+        }
+
+        // walk the gaps between excluded runs
+        let mut before = 0;
+
+        for run in exclude.iter() {
+            let gap = self.entry.rank_below(run.start) - before;
+
+            if n < gap {
+                break;
+            }
+
+            n -= gap;
+            before = self.entry.rank(run.end);
+        }
+
+        before + n
+    }
+}
+
+// The entries of a target list, less its exclusions, optionally narrowed to
+// a contiguous slice by sharding
+#[derive(Debug)]
+pub(crate) struct TargetList<E: Entry> {
+    pub(crate) targets: Vec<Target<E>>,
+    exclude: Vec<Run<E::Point>>,
+    len: usize,
+    offset: u64,
+}
+
+impl<E: Entry> TargetList<E> {
+    pub(crate) fn new(entries: Vec<E>, exclude: Vec<Run<E::Point>>) -> Self {
+        let exclude = merge(exclude);
+
+        let targets: Vec<Target<E>> = entries
+            .into_iter()
+            .map(|entry| Target::new(entry, &exclude))
+            .collect();
+
+        let len = targets.iter().map(|t| t.len as usize).sum();
+
+        Self {
+            targets,
+            exclude,
+            len,
+            offset: 0,
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(crate) fn lazy_loop<F: FnMut(E::Point) -> Result<()>>(
+        &self,
+        mut cb: F,
+    ) -> Result<()> {
+        let mut skip = self.offset;
+        let mut remaining = self.len;
+
+        for target in self.targets.iter() {
+            if remaining == 0 {
+                break;
+            }
+
+            if skip >= target.len {
+                skip -= target.len;
+                continue;
+            }
+
+            let first = target.position(&self.exclude, skip);
+            skip = 0;
+
+            for n in first..target.entry.len() {
+                if remaining == 0 {
+                    break;
+                }
+
+                let point = target.entry.nth(n);
+
+                if !is_excluded(&self.exclude, point) {
+                    cb(point)?;
+                    remaining -= 1;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn nth(&self, index: usize) -> Option<E::Point> {
+        if index >= self.len {
+            return None;
+        }
+
+        let mut n = self.offset + index as u64;
+
+        for target in self.targets.iter() {
+            if n < target.len {
+                let position = target.position(&self.exclude, n);
+                return Some(target.entry.nth(position));
+            }
+            n -= target.len;
+        }
+
+        None
+    }
+
+    // Returns shard k of n contiguous slices that differ in size by at most
+    // one
+    pub(crate) fn shard(&self, k: usize, n: usize) -> Result<Self> {
+        if k >= n {
+            return Err(invalid(
+                &format!("{k}/{n}"),
+                TargetError::InvalidShard,
+            ));
+        }
+
+        let bound =
+            |k: usize| (self.len as u128 * k as u128 / n as u128) as usize;
+        let (start, end) = (bound(k), bound(k + 1));
+
+        Ok(Self {
+            targets: self.targets.clone(),
+            exclude: self.exclude.clone(),
+            len: end - start,
+            offset: self.offset + start as u64,
+        })
+    }
+
+    pub(crate) fn lazy_loop_shuffled<F: FnMut(E::Point) -> Result<()>>(
+        &self,
+        seed: u64,
+        mut cb: F,
+    ) -> Result<()> {
+        for index in Permutation::new(self.len, seed) {
+            if let Some(point) = self.nth(index) {
+                cb(point)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Sorts runs and merges those that overlap or touch
+fn merge<P: Point>(mut runs: Vec<Run<P>>) -> Vec<Run<P>> {
+    runs.sort_unstable();
+
+    let mut merged: Vec<Run<P>> = Vec::with_capacity(runs.len());
+
+    for run in runs {
+        match merged.last_mut() {
+            Some(last) if run.start.into() <= last.end.into() + 1 => {
+                last.end = last.end.max(run.end);
+            }
+            _ => merged.push(run),
+        }
+    }
+
+    merged
+}
+
+fn is_excluded<P: Point>(exclude: &[Run<P>], point: P) -> bool {
+    let i = exclude.partition_point(|run| run.end < point);
+    exclude.get(i).is_some_and(|run| run.start <= point)
+}
+
+pub(crate) fn invalid(target: &str, reason: TargetError) -> RLanLibError {
+    RLanLibError::InvalidTarget {
+        target: target.to_string(),
+        reason,
+    }
+}
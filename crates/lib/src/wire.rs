@@ -2,6 +2,7 @@
 
 use pnet::datalink::{self, PacketMetadata as PnetPacketMetadata};
 use std::{
+    io::ErrorKind,
     sync::{Arc, Mutex},
     time,
 };
@@ -18,14 +19,20 @@ use crate::{
 pub const DEFAULT_PACKET_SEND_TIMING: time::Duration =
     time::Duration::from_micros(200);
 
+/// How long the default wire waits for a packet before returning an empty
+/// one, so readers on a quiet network still get to check whether to stop
+pub const READ_TIMEOUT: time::Duration = time::Duration::from_millis(250);
+
 /// PacketMetadata from wire
 pub type PacketMetadata = PnetPacketMetadata;
 
 /// Trait describing a packet reader
 pub trait Reader: Send {
-    /// Returns the next packet off of the wire
+    /// Returns the next packet off of the wire, or an empty packet if none
+    /// arrived within the read timeout
     fn next_packet(&mut self) -> Result<&[u8]>;
-    /// Returns the next packet off of the wire along with metadata
+    /// Returns the next packet off of the wire along with metadata, or an
+    /// empty packet if none arrived within the read timeout
     fn next_packet_with_metadata(&mut self) -> Result<(&[u8], PacketMetadata)>;
 }
 
@@ -47,15 +54,19 @@ pub struct PNetReader {
 // Implements the Reader trait for our PNet implementation
 impl Reader for PNetReader {
     fn next_packet(&mut self) -> Result<&[u8]> {
-        self.receiver
-            .next()
-            .map_err(|e| RLanLibError::Wire(e.to_string()))
+        match self.receiver.next() {
+            Err(e) if e.kind() == ErrorKind::TimedOut => Ok(&[]),
+            res => res.map_err(|e| RLanLibError::Wire(e.to_string())),
+        }
     }
 
     fn next_packet_with_metadata(&mut self) -> Result<(&[u8], PacketMetadata)> {
-        self.receiver
-            .next_with_metadata()
-            .map_err(|e| RLanLibError::Wire(e.to_string()))
+        match self.receiver.next_with_metadata() {
+            Err(e) if e.kind() == ErrorKind::TimedOut => {
+                Ok((&[], PacketMetadata { timestamp: None }))
+            }
+            res => res.map_err(|e| RLanLibError::Wire(e.to_string())),
+        }
     }
 }
 
@@ -92,6 +103,7 @@ pub fn default(interface: &NetworkInterface) -> Result<Wire> {
         enable_timestamps: true,
         read_buffer_size: 65536, // 64 KB — holds ~43 max-size frames
         write_buffer_size: 65536, // 64 KB — consistent with raw socket convention
+        read_timeout: Some(READ_TIMEOUT),
         ..pnet::datalink::Config::default()
     };
 
@@ -32,6 +32,13 @@ pub struct Config {
     pub default_ssh_port: u16,
     pub default_ssh_identity: String,
     pub device_configs: HashMap<String, DeviceConfig>,
+    /// Only listen for traffic instead of actively scanning
+    #[serde(default)]
+    pub passive: bool,
+    /// Fall back to NetBIOS and LLMNR queries for hosts reverse DNS has no
+    /// name for
+    #[serde(default)]
+    pub netbios_names: bool,
 }
 
 /// Returns the default ports to scan as a vector of strings.
@@ -55,6 +62,8 @@ impl Config {
             default_ssh_port: 22,
             default_ssh_user: user,
             device_configs: HashMap::new(),
+            passive: false,
+            netbios_names: false,
         }
     }
 }
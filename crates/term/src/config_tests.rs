@@ -26,6 +26,8 @@ fn setup() -> (ConfigManager, Config, String) {
         default_ssh_port: 2222,
         default_ssh_user: "user".to_string(),
         device_configs: HashMap::new(),
+        passive: false,
+        netbios_names: false,
         ports: vec![],
         theme: "Emerald".to_string(),
     };
@@ -79,3 +81,19 @@ fn update_config() {
     assert_eq!(c.cidr, "10.10.10.1/24");
     tear_down(conf_path);
 }
+
+#[test]
+fn netbios_names_default_off_for_saved_configs() {
+    let yaml = "id: home\n\
+                cidr: 192.168.1.0/24\n\
+                theme: Blue\n\
+                ports: []\n\
+                default_ssh_user: user\n\
+                default_ssh_port: 22\n\
+                default_ssh_identity: id_rsa\n\
+                device_configs: {}\n";
+
+    let config: Config = serde_yaml::from_str(yaml).unwrap();
+
+    assert!(!config.netbios_names);
+}
@@ -6,7 +6,7 @@ use std::{fmt::Display, process::Output};
 use crate::{
     config::{Config, DeviceConfig},
     shell::traits::BrowseArgs,
-    store::action::Action,
+    store::{action::Action, state::ScanProgress},
 };
 
 /// External commands that can be executed (SSH, traceroute, browse).
@@ -54,12 +54,22 @@ pub enum MainMessage {
     ArpUpdate(Device),
     /// Informs that ARP scanning finished
     ArpDone,
+    /// Network mDNS update carrying hostname and advertised services
+    MdnsUpdate(Device),
+    /// Network SSDP update carrying a UPnP device description
+    SsdpUpdate(Device),
+    /// Informs that passive listening is beginning
+    PassiveStart,
+    /// Informs that passive listening finished
+    PassiveDone,
     /// Informs that SYN scanning is beginning
     SynStart,
     /// Network SYN update
     SynUpdate(Device),
     /// Informs that SYN scanning finished
     SynDone,
+    /// Progress of the running ARP, SYN or connect scan
+    ScanProgress(ScanProgress),
     /// Request to execute an external command.
     ExecCommand(Command),
     /// A background command completed with output or error string.
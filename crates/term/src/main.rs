@@ -205,7 +205,12 @@ fn start_network_monitoring_thread(
     tx: Sender<MainMessage>,
     rx: Receiver<NetworkMessage>,
 ) -> Result<JoinHandle<Result<()>>> {
-    let wire = r_lanlib::wire::default(&interface)?;
+    let wire = if is_root() {
+        Some(r_lanlib::wire::default(&interface)?)
+    } else {
+        log::warn!("not running as root: falling back to TCP connect scanning");
+        None
+    };
 
     let main_tx = tx.clone();
 
@@ -349,10 +354,6 @@ fn main() -> Result<()> {
 
     initialize_logger(&args)?;
 
-    if !is_root() {
-        return Err(eyre!("permission denied: must run with root privileges"));
-    }
-
     let interface = network::get_default_interface()?;
 
     let (config_manager, initial_state) = init(&args, &interface)?;
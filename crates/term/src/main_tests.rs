@@ -21,6 +21,7 @@ fn mock_interface() -> NetworkInterface {
         index: 0,
         ips: vec![],
         ipv4: Ipv4Addr::from_str("192.168.1.2").unwrap(),
+        ipv6: None,
         mac: MacAddr::default(),
         name: "test_interface".to_string(),
     }
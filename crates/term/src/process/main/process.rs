@@ -70,6 +70,30 @@ impl MainProcess {
                     MainMessage::ArpUpdate(device) => {
                         self.store.dispatch(Action::AddDevice(device));
                     }
+                    MainMessage::ArpDone => {
+                        self.store.dispatch(Action::UpdateScanProgress(None));
+                    }
+                    MainMessage::ScanProgress(progress) => {
+                        self.store.dispatch(Action::UpdateScanProgress(Some(
+                            progress,
+                        )));
+                    }
+                    MainMessage::MdnsUpdate(device) => {
+                        self.store
+                            .dispatch(Action::UpdateDeviceServices(device));
+                    }
+                    MainMessage::SsdpUpdate(device) => {
+                        self.store
+                            .dispatch(Action::UpdateDeviceDescription(device));
+                    }
+                    MainMessage::PassiveStart => {
+                        self.store.dispatch(Action::UpdateMessage(Some(
+                            "Passively listening for devices...".into(),
+                        )));
+                    }
+                    MainMessage::PassiveDone => {
+                        self.store.dispatch(Action::UpdateMessage(None));
+                    }
                     MainMessage::SynStart => {
                         self.store.dispatch(Action::UpdateMessage(Some(
                             "SYN scanning in progress...".into(),
@@ -79,6 +103,7 @@ impl MainProcess {
                         self.store.dispatch(Action::UpdateDevicePorts(device));
                     }
                     MainMessage::SynDone => {
+                        self.store.dispatch(Action::UpdateScanProgress(None));
                         self.store.dispatch(Action::UpdateMessage(None));
                     }
                     MainMessage::ActionSync(action) => {
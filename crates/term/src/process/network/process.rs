@@ -1,7 +1,10 @@
 //! Network monitoring and scanning orchestration.
 //!
 //! Runs continuous ARP and SYN scans to discover devices and open ports on
-//! the local network.
+//! the local network. Without root privileges it falls back to TCP connect
+//! scanning. Each scan also browses mDNS for hostnames and advertised
+//! services and SSDP for UPnP device descriptions. In passive mode it only
+//! listens for traffic.
 
 use color_eyre::eyre::{Result, eyre};
 use derive_builder::Builder;
@@ -10,18 +13,21 @@ use r_lanlib::{
     oui::traits::Oui,
     scanners::{
         Device, IDLE_TIMEOUT, PortSet, ScanMessage, Scanner,
-        arp_scanner::ARPScanner, syn_scanner::SYNScanner,
+        arp_scanner::ARPScanner, connect_scanner::ConnectScanner,
+        handle::ScanHandle, mdns_scanner::MDNSScanner,
+        passive_scanner::PassiveScanner, ssdp_scanner::SSDPScanner,
+        syn_scanner::SYNScanner,
     },
     targets::{ips::IPTargets, ports::PortTargets},
     wire::Wire,
 };
 use std::{
-    cell::RefCell,
+    cell::{Cell, RefCell},
     collections::HashMap,
     net::Ipv4Addr,
     sync::{
         Arc,
-        mpsc::{self, Receiver},
+        mpsc::{self, Receiver, RecvTimeoutError},
     },
     thread,
     time::{self, Duration},
@@ -35,6 +41,7 @@ use crate::{
         network::NetworkIpc,
     },
     process::network::traits::NetworkMonitor,
+    store::state::ScanProgress,
 };
 
 /// Tracks how many scans a device has been missing from.
@@ -42,12 +49,19 @@ pub type MissedCount = i8;
 
 const MAX_ARP_MISS: i8 = 3;
 
+/// Time between scans, also used as the length of each passive listen
+const SCAN_INTERVAL: Duration = Duration::from_secs(15);
+
+/// How often the main thread is checked for messages while scanning
+const IPC_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
 /// Data type for monitoring network for devices and open ports.
 /// Relays info back to the main thread via ipc.
 #[derive(Builder)]
 #[builder(pattern = "owned")]
 pub struct NetworkProcess {
-    wire: Wire,
+    /// Packet wire, None when running without root privileges
+    wire: Option<Wire>,
     interface: Arc<NetworkInterface>,
     ipc: NetworkIpc,
     config: RefCell<Config>,
@@ -57,6 +71,9 @@ pub struct NetworkProcess {
     gateway: Option<Ipv4Addr>,
     #[builder(default)]
     arp_history: RefCell<HashMap<Ipv4Addr, (Device, MissedCount)>>,
+    /// Set once the main thread asks the process to quit
+    #[builder(default)]
+    quit: Cell<bool>,
 }
 
 impl NetworkProcess {
@@ -85,6 +102,63 @@ impl NetworkProcess {
             .collect()
     }
 
+    /// Applies any pending message from the main thread
+    fn check_ipc(&self) {
+        if let Ok(msg) = self.ipc.rx.try_recv() {
+            match msg {
+                NetworkMessage::Quit => self.quit.set(true),
+                NetworkMessage::ConfigUpdate(config) => {
+                    self.config.replace(config);
+                }
+            }
+        }
+    }
+
+    /// Waits for the next message from a running scan, cancelling the scan
+    /// once the main thread has asked to quit. Cancelled scans still report
+    /// done so the rest of the cycle winds down as usual.
+    fn next_message(
+        &self,
+        rx: &Receiver<ScanMessage>,
+        handle: &ScanHandle,
+    ) -> Result<ScanMessage> {
+        loop {
+            if self.quit.get() {
+                handle.cancel();
+            }
+
+            match rx.recv_timeout(IPC_POLL_INTERVAL) {
+                Ok(msg) => return Ok(msg),
+                Err(RecvTimeoutError::Timeout) => self.check_ipc(),
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    /// Relays a scan's progress report to the main thread
+    fn send_progress(
+        &self,
+        sent: usize,
+        total: usize,
+        eta: Option<Duration>,
+    ) -> Result<()> {
+        self.ipc.tx.send(MainMessage::ScanProgress(ScanProgress {
+            sent,
+            total,
+            eta,
+        }))
+    }
+
+    /// Sleeps between scans, returning early when asked to quit
+    fn wait(&self, duration: Duration) {
+        let started = time::Instant::now();
+
+        while !self.quit.get() && started.elapsed() < duration {
+            thread::sleep(IPC_POLL_INTERVAL);
+            self.check_ipc();
+        }
+    }
+
     /// Runs an ARP scan and dispatches discovered devices to the store.
     fn process_arp(
         &self,
@@ -98,12 +172,17 @@ impl NetworkProcess {
         let handle = scanner.scan()?;
 
         loop {
-            let msg = rx.recv()?;
+            let msg = self.next_message(&rx, &handle)?;
 
             match msg {
                 ScanMessage::Done => {
                     break;
                 }
+                ScanMessage::Progress {
+                    sent, total, eta, ..
+                } => {
+                    self.send_progress(sent, total, eta)?;
+                }
                 ScanMessage::ARPScanDevice(d) => {
                     arp_results.insert(d.ip, d.clone());
                     self.arp_history.borrow_mut().insert(d.ip, (d.clone(), 0));
@@ -149,6 +228,7 @@ impl NetworkProcess {
                 Device {
                     hostname: d.hostname.to_owned(),
                     ip: d.ip.to_owned(),
+                    ipv6: d.ipv6.clone(),
                     mac: d.mac.to_owned(),
                     vendor: d.vendor.to_owned(),
                     is_current_host: d.is_current_host,
@@ -156,6 +236,9 @@ impl NetworkProcess {
                     open_ports: PortSet::new(),
                     latency_ms: d.latency_ms,
                     response_ttl: None,
+                    services: d.services.clone(),
+                    upnp: d.upnp.clone(),
+                    os_guess: d.os_guess.clone(),
                 },
             );
         }
@@ -163,12 +246,17 @@ impl NetworkProcess {
         let handle = scanner.scan()?;
 
         loop {
-            let msg = rx.recv()?;
+            let msg = self.next_message(&rx, &handle)?;
 
             match msg {
                 ScanMessage::Done => {
                     break;
                 }
+                ScanMessage::Progress {
+                    sent, total, eta, ..
+                } => {
+                    self.send_progress(sent, total, eta)?;
+                }
                 ScanMessage::SYNScanDevice(device) => {
                     self.ipc.tx.send(MainMessage::SynUpdate(device.clone()))?;
                 }
@@ -180,6 +268,154 @@ impl NetworkProcess {
 
         self.ipc.tx.send(MainMessage::SynDone)
     }
+
+    /// Listens for traffic and dispatches newly observed devices to the
+    /// store. Absence from a listen says nothing about whether a device is
+    /// still present, so miss counts are left untouched.
+    fn process_passive(
+        &self,
+        scanner: PassiveScanner,
+        rx: Receiver<ScanMessage>,
+    ) -> Result<()> {
+        self.ipc.tx.send(MainMessage::PassiveStart)?;
+
+        let handle = scanner.scan()?;
+
+        loop {
+            let msg = self.next_message(&rx, &handle)?;
+
+            match msg {
+                ScanMessage::Done => {
+                    break;
+                }
+                ScanMessage::ARPScanDevice(d) => {
+                    self.arp_history.borrow_mut().insert(d.ip, (d.clone(), 0));
+                    self.ipc.tx.send(MainMessage::ArpUpdate(d))?;
+                }
+                _ => {}
+            }
+        }
+
+        handle.join().map_err(error::report_from_thread_panic)??;
+
+        self.ipc.tx.send(MainMessage::PassiveDone)
+    }
+
+    /// Runs an unprivileged connect scan and dispatches devices with open
+    /// ports to the store. Without ARP these are the only devices known.
+    fn process_connect(
+        &self,
+        scanner: ConnectScanner,
+        rx: Receiver<ScanMessage>,
+    ) -> Result<()> {
+        self.ipc.tx.send(MainMessage::SynStart)?;
+
+        let handle = scanner.scan()?;
+
+        loop {
+            let msg = self.next_message(&rx, &handle)?;
+
+            match msg {
+                ScanMessage::Done => {
+                    break;
+                }
+                ScanMessage::Progress {
+                    sent, total, eta, ..
+                } => {
+                    self.send_progress(sent, total, eta)?;
+                }
+                ScanMessage::SYNScanDevice(device) => {
+                    self.ipc.tx.send(MainMessage::ArpUpdate(device))?;
+                }
+                _ => {}
+            }
+        }
+
+        handle.join().map_err(error::report_from_thread_panic)??;
+
+        self.ipc.tx.send(MainMessage::SynDone)
+    }
+
+    /// Runs an mDNS scan and dispatches hostnames and advertised services to
+    /// the store. Results carry no MAC so the store merges them by IP.
+    fn process_mdns(
+        &self,
+        scanner: MDNSScanner,
+        rx: Receiver<ScanMessage>,
+    ) -> Result<()> {
+        let handle = scanner.scan()?;
+
+        loop {
+            let msg = self.next_message(&rx, &handle)?;
+
+            match msg {
+                ScanMessage::Done => {
+                    break;
+                }
+                ScanMessage::MDNSScanDevice(device) => {
+                    self.ipc.tx.send(MainMessage::MdnsUpdate(device))?;
+                }
+                _ => {}
+            }
+        }
+
+        handle.join().map_err(error::report_from_thread_panic)??;
+
+        Ok(())
+    }
+
+    /// Browses mDNS for the devices found by the preceding scan
+    fn discover_mdns(&self) -> Result<()> {
+        let (tx, rx) = mpsc::channel::<ScanMessage>();
+
+        let mdns_scanner = MDNSScanner::builder()
+            .interface(Arc::clone(&self.interface))
+            .notifier(tx)
+            .build()?;
+
+        self.process_mdns(mdns_scanner, rx)
+    }
+
+    /// Runs an SSDP scan and dispatches UPnP device descriptions to the
+    /// store, which merges them by IP.
+    fn process_ssdp(
+        &self,
+        scanner: SSDPScanner,
+        rx: Receiver<ScanMessage>,
+    ) -> Result<()> {
+        let handle = scanner.scan()?;
+
+        loop {
+            let msg = self.next_message(&rx, &handle)?;
+
+            match msg {
+                ScanMessage::Done => {
+                    break;
+                }
+                ScanMessage::SSDPScanDevice(device) => {
+                    self.ipc.tx.send(MainMessage::SsdpUpdate(device))?;
+                }
+                _ => {}
+            }
+        }
+
+        handle.join().map_err(error::report_from_thread_panic)??;
+
+        Ok(())
+    }
+
+    /// Searches SSDP for descriptions of the devices found by the preceding
+    /// scan
+    fn discover_ssdp(&self) -> Result<()> {
+        let (tx, rx) = mpsc::channel::<ScanMessage>();
+
+        let ssdp_scanner = SSDPScanner::builder()
+            .interface(Arc::clone(&self.interface))
+            .notifier(tx)
+            .build()?;
+
+        self.process_ssdp(ssdp_scanner, rx)
+    }
 }
 
 impl NetworkMonitor for NetworkProcess {
@@ -187,28 +423,69 @@ impl NetworkMonitor for NetworkProcess {
     /// notifying main thread with discovered devices.
     fn monitor(&self, oui: Arc<dyn Oui>) -> Result<()> {
         loop {
-            if let Ok(msg) = self.ipc.rx.try_recv() {
-                match msg {
-                    NetworkMessage::Quit => return Ok(()),
-                    NetworkMessage::ConfigUpdate(config) => {
-                        self.config.replace(config);
-                    }
-                }
+            self.check_ipc();
+
+            if self.quit.get() {
+                return Ok(());
             }
 
             let ip_targets =
                 IPTargets::new(vec![Arc::clone(&self.interface).cidr.clone()])
                     .map_err(|e| eyre!("Invalid IP targets: {}", e))?;
 
-            let source_port = network::get_available_port()?;
-
             let (tx, rx) = mpsc::channel::<ScanMessage>();
 
+            if self.config.borrow().passive {
+                let Some(wire) = self.wire.as_ref() else {
+                    log::warn!("passive mode requires root privileges");
+                    self.wait(SCAN_INTERVAL);
+                    continue;
+                };
+
+                let passive_scanner = PassiveScanner::builder()
+                    .interface(Arc::clone(&self.interface))
+                    .wire(wire.clone())
+                    .duration(SCAN_INTERVAL)
+                    .include_vendor(true)
+                    .notifier(tx)
+                    .gateway(self.gateway)
+                    .oui(Arc::clone(&oui))
+                    .build()?;
+
+                // listening runs back to back so no sleep is needed
+                self.process_passive(passive_scanner, rx)?;
+                continue;
+            }
+
+            let Some(wire) = self.wire.as_ref() else {
+                let port_targets =
+                    PortTargets::new(self.config.borrow().ports.clone())
+                        .map_err(|e| eyre!("Invalid port targets: {}", e))?;
+
+                let connect_scanner = ConnectScanner::builder()
+                    .targets(Arc::clone(&ip_targets))
+                    .interface(Arc::clone(&self.interface))
+                    .gateway(self.gateway)
+                    .ports(port_targets)
+                    .notifier(tx)
+                    .build()?;
+
+                self.process_connect(connect_scanner, rx)?;
+                self.discover_mdns()?;
+                self.discover_ssdp()?;
+
+                self.wait(SCAN_INTERVAL);
+                continue;
+            };
+
+            let source_port = network::get_available_port()?;
+
             let arp_scanner = ARPScanner::builder()
                 .interface(Arc::clone(&self.interface))
-                .wire(self.wire.clone())
+                .wire(wire.clone())
                 .targets(ip_targets)
                 .include_host_names(true)
+                .include_netbios_names(self.config.borrow().netbios_names)
                 .include_vendor(true)
                 .idle_timeout(time::Duration::from_millis(IDLE_TIMEOUT.into()))
                 .source_port(source_port)
@@ -220,6 +497,9 @@ impl NetworkMonitor for NetworkProcess {
 
             let rx = self.process_arp(arp_scanner, rx)?;
 
+            self.discover_mdns()?;
+            self.discover_ssdp()?;
+
             let arp_devices = self.get_latest_detected_arp_devices();
 
             let port_targets =
@@ -228,7 +508,7 @@ impl NetworkMonitor for NetworkProcess {
 
             let syn_scanner = SYNScanner::builder()
                 .interface(Arc::clone(&self.interface))
-                .wire(self.wire.clone())
+                .wire(wire.clone())
                 .targets(arp_devices)
                 .ports(port_targets)
                 .source_port(source_port)
@@ -239,7 +519,7 @@ impl NetworkMonitor for NetworkProcess {
 
             self.process_syn(syn_scanner, rx)?;
 
-            thread::sleep(time::Duration::from_secs(15));
+            self.wait(SCAN_INTERVAL);
         }
     }
 }
@@ -1,5 +1,5 @@
 use std::{
-    cell::RefCell,
+    cell::{Cell, RefCell},
     collections::{HashMap, HashSet},
     net::Ipv4Addr,
     sync::{Arc, Mutex, mpsc},
@@ -10,6 +10,8 @@ use mockall::Sequence;
 use r_lanlib::{
     scanners::{
         Device, Port, ScanMessage, arp_scanner::ARPScanner,
+        connect_scanner::ConnectScanner, mdns_scanner::MDNSScanner,
+        passive_scanner::PassiveScanner, ssdp_scanner::SSDPScanner,
         syn_scanner::SYNScanner,
     },
     targets::{ips::IPTargets, ports::PortTargets},
@@ -90,7 +92,7 @@ fn setup(
     let ipc = NetworkIpc::new(Box::new(mock_sender), Box::new(mock_receiver));
 
     NetworkProcess {
-        wire: stub_wire(),
+        wire: Some(stub_wire()),
         interface: Arc::new(
             r_lanlib::network::get_default_interface().unwrap(),
         ),
@@ -99,6 +101,7 @@ fn setup(
         gateway: None,
         throttle: DEFAULT_PACKET_SEND_TIMING,
         arp_history: RefCell::new(HashMap::new()),
+        quit: Cell::new(false),
     }
 }
 
@@ -308,6 +311,22 @@ fn process_arp_sends_messages_and_updates_history() {
         .withf(|m| matches!(m, MainMessage::ArpStart))
         .returning(|_| Ok(()));
 
+    mock_sender
+        .expect_send()
+        .once()
+        .in_sequence(&mut seq)
+        .withf(|m| {
+            matches!(
+                m,
+                MainMessage::ScanProgress(ScanProgress {
+                    sent: 1,
+                    total: 256,
+                    ..
+                })
+            )
+        })
+        .returning(|_| Ok(()));
+
     mock_sender
         .expect_send()
         .once()
@@ -330,6 +349,13 @@ fn process_arp_sends_messages_and_updates_history() {
 
     // pre-load messages so the loop exits before the scanner's
     // own thread produces anything
+    tx.send(ScanMessage::Progress {
+        sent: 1,
+        total: 256,
+        elapsed: Duration::from_millis(10),
+        eta: None,
+    })
+    .unwrap();
     tx.send(ScanMessage::ARPScanDevice(device.clone())).unwrap();
     tx.send(ScanMessage::Done).unwrap();
 
@@ -355,6 +381,40 @@ fn process_arp_sends_messages_and_updates_history() {
     assert_eq!(*miss_count, 0);
 }
 
+#[test]
+fn quit_cancels_running_scan() {
+    let mut mock_sender = MockIpcSender::<MainMessage>::new();
+    let mut mock_receiver = MockIpcReceiver::<NetworkMessage>::new();
+
+    mock_sender.expect_send().returning(|_| Ok(()));
+    mock_receiver
+        .expect_try_recv()
+        .returning(|| Ok(NetworkMessage::Quit));
+
+    let process = setup(mock_sender, mock_receiver);
+
+    let (tx, rx) = mpsc::channel::<ScanMessage>();
+
+    // nothing answers so the scan would otherwise idle for a minute
+    let scanner = ARPScanner::builder()
+        .interface(Arc::clone(&process.interface))
+        .wire(stub_wire())
+        .targets(IPTargets::new(vec!["192.168.1.0/24".to_string()]).unwrap())
+        .include_host_names(false)
+        .include_vendor(false)
+        .idle_timeout(Duration::from_secs(60))
+        .source_port(54321_u16)
+        .notifier(tx)
+        .build()
+        .unwrap();
+
+    let started = std::time::Instant::now();
+
+    assert!(process.process_arp(scanner, rx).is_ok());
+    assert!(process.quit.get());
+    assert!(started.elapsed() < Duration::from_secs(10));
+}
+
 #[test]
 fn process_syn_sends_messages() {
     let mut seq = Sequence::new();
@@ -392,6 +452,7 @@ fn process_syn_sends_messages() {
     let port = Port {
         id: 80,
         service: "http".to_string(),
+        ..Port::default()
     };
 
     let mut open_ports = HashSet::new();
@@ -421,3 +482,189 @@ fn process_syn_sends_messages() {
 
     process.process_syn(scanner, rx).unwrap();
 }
+
+#[test]
+fn process_connect_sends_open_devices() {
+    let mut seq = Sequence::new();
+    let mut mock_sender = MockIpcSender::<MainMessage>::new();
+    let mock_receiver = MockIpcReceiver::<NetworkMessage>::new();
+
+    mock_sender
+        .expect_send()
+        .once()
+        .in_sequence(&mut seq)
+        .withf(|m| matches!(m, MainMessage::SynStart))
+        .returning(|_| Ok(()));
+
+    mock_sender
+        .expect_send()
+        .once()
+        .in_sequence(&mut seq)
+        .withf(|m| matches!(m, MainMessage::ArpUpdate(_)))
+        .returning(|_| Ok(()));
+
+    // reported when sending starts and ends
+    mock_sender
+        .expect_send()
+        .times(2)
+        .in_sequence(&mut seq)
+        .withf(|m| {
+            matches!(
+                m,
+                MainMessage::ScanProgress(ScanProgress {
+                    sent: 0,
+                    total: 0,
+                    ..
+                })
+            )
+        })
+        .returning(|_| Ok(()));
+
+    mock_sender
+        .expect_send()
+        .once()
+        .in_sequence(&mut seq)
+        .withf(|m| matches!(m, MainMessage::SynDone))
+        .returning(|_| Ok(()));
+
+    let process = setup(mock_sender, mock_receiver);
+
+    let device = make_device(Ipv4Addr::new(192, 168, 1, 10));
+
+    let (tx, rx) = mpsc::channel::<ScanMessage>();
+
+    // pre-load a result; the scanner has no targets so it only reports
+    // progress and Done
+    tx.send(ScanMessage::SYNScanDevice(device)).unwrap();
+
+    let scanner = ConnectScanner::builder()
+        .targets(vec![])
+        .ports(PortTargets::new(vec!["80".to_string()]).unwrap())
+        .notifier(tx)
+        .build()
+        .unwrap();
+
+    process.process_connect(scanner, rx).unwrap();
+}
+
+#[test]
+fn process_passive_sends_devices_and_records_history() {
+    let mut seq = Sequence::new();
+    let mut mock_sender = MockIpcSender::<MainMessage>::new();
+    let mock_receiver = MockIpcReceiver::<NetworkMessage>::new();
+
+    mock_sender
+        .expect_send()
+        .once()
+        .in_sequence(&mut seq)
+        .withf(|m| matches!(m, MainMessage::PassiveStart))
+        .returning(|_| Ok(()));
+
+    mock_sender
+        .expect_send()
+        .once()
+        .in_sequence(&mut seq)
+        .withf(|m| matches!(m, MainMessage::ArpUpdate(_)))
+        .returning(|_| Ok(()));
+
+    mock_sender
+        .expect_send()
+        .once()
+        .in_sequence(&mut seq)
+        .withf(|m| matches!(m, MainMessage::PassiveDone))
+        .returning(|_| Ok(()));
+
+    let process = setup(mock_sender, mock_receiver);
+
+    let device = make_device(Ipv4Addr::new(192, 168, 1, 10));
+
+    let (tx, rx) = mpsc::channel::<ScanMessage>();
+
+    // pre-load a result; the scanner's own Done follows once it finishes
+    tx.send(ScanMessage::ARPScanDevice(device.clone())).unwrap();
+
+    let scanner = PassiveScanner::builder()
+        .interface(Arc::clone(&process.interface))
+        .wire(stub_wire())
+        .duration(Duration::from_millis(50))
+        .notifier(tx)
+        .build()
+        .unwrap();
+
+    process.process_passive(scanner, rx).unwrap();
+
+    let history = process.arp_history.borrow();
+    assert_eq!(history.get(&device.ip).unwrap().1, 0);
+}
+
+#[test]
+fn process_mdns_sends_discovered_devices() {
+    let mut mock_sender = MockIpcSender::<MainMessage>::new();
+    let mut mock_receiver = MockIpcReceiver::<NetworkMessage>::new();
+
+    // nothing from the main thread while waiting on the scanner
+    mock_receiver
+        .expect_try_recv()
+        .returning(|| Err(eyre!("empty")));
+
+    mock_sender
+        .expect_send()
+        .once()
+        .withf(|m| matches!(m, MainMessage::MdnsUpdate(_)))
+        .returning(|_| Ok(()));
+
+    let process = setup(mock_sender, mock_receiver);
+
+    let device = make_device(Ipv4Addr::new(192, 168, 1, 10));
+
+    let (tx, rx) = mpsc::channel::<ScanMessage>();
+
+    // pre-load a result; nothing answers the scanner so it only sends Done
+    tx.send(ScanMessage::MDNSScanDevice(device)).unwrap();
+
+    let scanner = MDNSScanner::builder()
+        .interface(Arc::clone(&process.interface))
+        .idle_timeout(Duration::from_millis(50))
+        .destination("127.0.0.1:9".parse::<std::net::SocketAddr>().unwrap())
+        .notifier(tx)
+        .build()
+        .unwrap();
+
+    process.process_mdns(scanner, rx).unwrap();
+}
+
+#[test]
+fn process_ssdp_sends_discovered_devices() {
+    let mut mock_sender = MockIpcSender::<MainMessage>::new();
+    let mut mock_receiver = MockIpcReceiver::<NetworkMessage>::new();
+
+    // nothing from the main thread while waiting on the scanner
+    mock_receiver
+        .expect_try_recv()
+        .returning(|| Err(eyre!("empty")));
+
+    mock_sender
+        .expect_send()
+        .once()
+        .withf(|m| matches!(m, MainMessage::SsdpUpdate(_)))
+        .returning(|_| Ok(()));
+
+    let process = setup(mock_sender, mock_receiver);
+
+    let device = make_device(Ipv4Addr::new(192, 168, 1, 10));
+
+    let (tx, rx) = mpsc::channel::<ScanMessage>();
+
+    // pre-load a result; nothing answers the scanner so it only sends Done
+    tx.send(ScanMessage::SSDPScanDevice(device)).unwrap();
+
+    let scanner = SSDPScanner::builder()
+        .interface(Arc::clone(&process.interface))
+        .idle_timeout(Duration::from_millis(50))
+        .destination("127.0.0.1:9".parse::<std::net::SocketAddr>().unwrap())
+        .notifier(tx)
+        .build()
+        .unwrap();
+
+    process.process_ssdp(scanner, rx).unwrap();
+}
@@ -7,6 +7,7 @@ use r_lanlib::scanners::Device;
 use crate::{
     config::{Config, DeviceConfig},
     ipc::message::Command,
+    store::state::ScanProgress,
     ui::colors::Theme,
 };
 
@@ -19,10 +20,13 @@ pub enum Action {
     UpdateCommandOutput((Command, Output)),
     ClearCommandOutput,
     UpdateMessage(Option<String>),
+    UpdateScanProgress(Option<ScanProgress>),
     UpdatePopoverMessage(Option<String>),
     PreviewTheme(Theme),
     AddDevice(Device),
     UpdateDevicePorts(Device),
+    UpdateDeviceServices(Device),
+    UpdateDeviceDescription(Device),
     UpdateConfig(Config),
     RemoveDeviceConfig(String),
     UpdateDeviceConfig(DeviceConfig),
@@ -50,6 +50,10 @@ impl Reducer for StoreReducer {
                 self.log_action("UpdateMessage", &message, state);
                 reducers::ui::update_message(state, message);
             }
+            Action::UpdateScanProgress(progress) => {
+                self.log_action("UpdateScanProgress", &progress, state);
+                reducers::ui::update_scan_progress(state, progress);
+            }
             Action::UpdatePopoverMessage(message) => {
                 self.log_action("UpdatePopoverMessage", &message, state);
                 reducers::ui::update_popover_message(state, message);
@@ -68,6 +72,14 @@ impl Reducer for StoreReducer {
                 self.log_action("UpdateDevicePorts", &device, state);
                 reducers::device::update_device_ports(state, device);
             }
+            Action::UpdateDeviceServices(device) => {
+                self.log_action("UpdateDeviceServices", &device, state);
+                reducers::device::update_device_services(state, device);
+            }
+            Action::UpdateDeviceDescription(device) => {
+                self.log_action("UpdateDeviceDescription", &device, state);
+                reducers::device::update_device_description(state, device);
+            }
 
             // Config actions
             Action::UpdateConfig(config) => {
@@ -1,6 +1,9 @@
 //! Device state reducers for managing discovered network devices.
 
-use r_lanlib::scanners::{Device, Port};
+use r_lanlib::{
+    fingerprint,
+    scanners::{Device, Port},
+};
 use std::collections::HashSet;
 
 use crate::store::state::{MAX_LATENCY_HISTORY, State};
@@ -9,6 +12,7 @@ use crate::store::state::{MAX_LATENCY_HISTORY, State};
 /// existing device. Does not update latency_ms or latency_history —
 /// those are ARP-only. TTL is the same across all port replies from a
 /// given device (IP-level field), so we take the first observed value.
+/// The most confident OS guess is kept.
 pub fn update_device_ports(state: &mut State, device: Device) {
     if let Some(found_device) = state.device_map.get_mut(&device.ip) {
         let ports: HashSet<Port> = device
@@ -21,11 +25,35 @@ pub fn update_device_ports(state: &mut State, device: Device) {
         if found_device.response_ttl.is_none() {
             found_device.response_ttl = device.response_ttl;
         }
+        found_device.os_guess = fingerprint::most_confident(
+            found_device.os_guess.take(),
+            device.os_guess,
+        );
+    }
+}
+
+/// Merges advertised services and the .local hostname from an mDNS result
+/// into an existing device. Hostnames from reverse DNS take precedence.
+pub fn update_device_services(state: &mut State, device: Device) {
+    if let Some(found_device) = state.device_map.get_mut(&device.ip) {
+        if found_device.hostname.is_empty() {
+            found_device.hostname = device.hostname;
+        }
+        found_device.services = device.services;
+    }
+}
+
+/// Sets the UPnP description from an SSDP result on an existing device.
+pub fn update_device_description(state: &mut State, device: Device) {
+    if let Some(found_device) = state.device_map.get_mut(&device.ip) {
+        found_device.upnp = device.upnp;
     }
 }
 
 /// Adds or updates a single device from an ARP scan, merging open ports,
 /// updating latency_ms, and appending to latency_history when available.
+/// Hostnames and OS guesses learned passively from DHCP fill in what is
+/// missing.
 pub fn add_device(state: &mut State, device: Device) {
     if let Some(latency) = device.latency_ms {
         let history = state.latency_history.entry(device.ip).or_default();
@@ -46,6 +74,13 @@ pub fn add_device(state: &mut State, device: Device) {
         if device.latency_ms.is_some() {
             found_device.latency_ms = device.latency_ms;
         }
+        if found_device.hostname.is_empty() {
+            found_device.hostname = device.hostname;
+        }
+        found_device.os_guess = fingerprint::most_confident(
+            found_device.os_guess.take(),
+            device.os_guess,
+        );
     } else {
         state.device_map.insert(device.ip, device);
     }
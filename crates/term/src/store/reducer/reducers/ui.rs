@@ -1,7 +1,7 @@
 //! UI state reducers for pausing, errors, views, and themes.
 
 use crate::{
-    store::state::State,
+    store::state::{ScanProgress, State},
     ui::colors::{Colors, Theme},
 };
 
@@ -20,6 +20,11 @@ pub fn update_message(state: &mut State, message: Option<String>) {
     state.message = message;
 }
 
+/// Sets or clears the progress of the running scan.
+pub fn update_scan_progress(state: &mut State, progress: Option<ScanProgress>) {
+    state.scan_progress = progress;
+}
+
 /// Sets or clears popover messages (e.g., Downloading vendor data).
 pub fn update_popover_message(state: &mut State, message: Option<String>) {
     state.popover_message = message;
@@ -1,18 +1,22 @@
 use std::{
     collections::HashMap, net::Ipv4Addr, os::unix::process::ExitStatusExt,
-    process::Output,
+    process::Output, time::Duration,
 };
 
 use r_lanlib::{
     MacAddr,
-    scanners::{Device, Port},
+    scanners::{AdvertisedService, Device, OsGuess, Port, UpnpDescription},
 };
 
 use crate::{
     config::Config,
     ipc::message::Command,
     shell::traits::BrowseArgs,
-    store::{action::Action, reducer::StoreReducer, state::State},
+    store::{
+        action::Action,
+        reducer::StoreReducer,
+        state::{ScanProgress, State},
+    },
     ui::colors::{Colors, Theme},
 };
 
@@ -55,6 +59,23 @@ fn test_update_message() {
     assert_eq!(state.message.unwrap(), "message".to_string());
 }
 
+#[test]
+fn test_update_scan_progress() {
+    let (mut state, reducer) = setup();
+    let progress = ScanProgress {
+        sent: 1,
+        total: 4,
+        eta: Some(Duration::from_secs(3)),
+    };
+
+    reducer.reduce(&mut state, Action::UpdateScanProgress(Some(progress)));
+    assert_eq!(state.scan_progress, Some(progress));
+    assert_eq!(progress.ratio(), 0.25);
+
+    reducer.reduce(&mut state, Action::UpdateScanProgress(None));
+    assert!(state.scan_progress.is_none());
+}
+
 #[test]
 fn test_update_popover_message() {
     let (mut state, reducer) = setup();
@@ -97,6 +118,8 @@ fn test_update_config() {
         default_ssh_port: 2222,
         default_ssh_user: "user".to_string(),
         device_configs: HashMap::new(),
+        passive: false,
+        netbios_names: false,
         id: "config_id".to_string(),
         ports: vec!["80".to_string(), "443".to_string()],
         theme: "Emerald".to_string(),
@@ -151,6 +174,168 @@ fn test_add_device() {
     assert_eq!(device, &dev3);
 }
 
+#[test]
+fn test_update_device_services() {
+    let (mut state, reducer) = setup();
+
+    let dev = Device {
+        ip: Ipv4Addr::new(10, 10, 10, 3),
+        mac: MacAddr::new(0, 0, 0, 0, 0, 3),
+        ..Device::default()
+    };
+
+    reducer.reduce(&mut state, Action::AddDevice(dev.clone()));
+
+    let service = AdvertisedService {
+        instance: "Office Printer".to_string(),
+        service_type: "_ipp._tcp".to_string(),
+        port: 631,
+        txt: vec![],
+    };
+
+    reducer.reduce(
+        &mut state,
+        Action::UpdateDeviceServices(Device {
+            hostname: "printer.local".to_string(),
+            ip: dev.ip,
+            services: vec![service.clone()],
+            ..Device::default()
+        }),
+    );
+
+    let device = state.device_map.get(&dev.ip).unwrap();
+
+    assert_eq!(device.mac, dev.mac);
+    assert_eq!(device.hostname, "printer.local");
+    assert_eq!(device.services, vec![service]);
+}
+
+#[test]
+fn test_update_device_description() {
+    let (mut state, reducer) = setup();
+
+    let dev = Device {
+        ip: Ipv4Addr::new(10, 10, 10, 4),
+        mac: MacAddr::new(0, 0, 0, 0, 0, 4),
+        ..Device::default()
+    };
+
+    reducer.reduce(&mut state, Action::AddDevice(dev.clone()));
+
+    let upnp = UpnpDescription {
+        friendly_name: "Living Room TV".to_string(),
+        manufacturer: "Acme".to_string(),
+        model_name: "AX-55".to_string(),
+        serial_number: "SN1234".to_string(),
+    };
+
+    reducer.reduce(
+        &mut state,
+        Action::UpdateDeviceDescription(Device {
+            ip: dev.ip,
+            upnp: Some(upnp.clone()),
+            ..Device::default()
+        }),
+    );
+
+    let device = state.device_map.get(&dev.ip).unwrap();
+
+    assert_eq!(device.mac, dev.mac);
+    assert_eq!(device.upnp, Some(upnp));
+}
+
+#[test]
+fn test_add_device_merges_dhcp_fingerprint() {
+    let (mut state, reducer) = setup();
+
+    let dev = Device {
+        ip: Ipv4Addr::new(10, 10, 10, 5),
+        mac: MacAddr::new(0, 0, 0, 0, 0, 5),
+        latency_ms: Some(3),
+        ..Device::default()
+    };
+
+    reducer.reduce(&mut state, Action::AddDevice(dev.clone()));
+
+    let os_guess = OsGuess {
+        os: "Windows".to_string(),
+        device_type: "computer".to_string(),
+        confidence: 90,
+    };
+
+    reducer.reduce(
+        &mut state,
+        Action::AddDevice(Device {
+            hostname: "DESKTOP-42".to_string(),
+            os_guess: Some(os_guess.clone()),
+            ..dev.clone()
+        }),
+    );
+
+    reducer.reduce(&mut state, Action::AddDevice(dev.clone()));
+
+    let device = state.device_map.get(&dev.ip).unwrap();
+
+    assert_eq!(device.hostname, "DESKTOP-42");
+    assert_eq!(device.os_guess, Some(os_guess));
+}
+
+#[test]
+fn test_update_device_ports_keeps_most_confident_os_guess() {
+    let (mut state, reducer) = setup();
+
+    let dhcp_guess = OsGuess {
+        os: "Windows".to_string(),
+        device_type: "computer".to_string(),
+        confidence: 90,
+    };
+
+    let dev = Device {
+        ip: Ipv4Addr::new(10, 10, 10, 6),
+        mac: MacAddr::new(0, 0, 0, 0, 0, 6),
+        os_guess: Some(dhcp_guess.clone()),
+        ..Device::default()
+    };
+
+    reducer.reduce(&mut state, Action::AddDevice(dev.clone()));
+
+    let syn_result = |os: &str, confidence| Device {
+        ip: dev.ip,
+        os_guess: Some(OsGuess {
+            os: os.to_string(),
+            device_type: String::new(),
+            confidence,
+        }),
+        ..Device::default()
+    };
+
+    reducer.reduce(
+        &mut state,
+        Action::UpdateDevicePorts(syn_result("Windows", 30)),
+    );
+
+    assert_eq!(
+        state.device_map.get(&dev.ip).unwrap().os_guess,
+        Some(dhcp_guess)
+    );
+
+    reducer.reduce(
+        &mut state,
+        Action::UpdateDevicePorts(syn_result("Windows", 100)),
+    );
+
+    assert_eq!(
+        state
+            .device_map
+            .get(&dev.ip)
+            .unwrap()
+            .os_guess
+            .as_ref()
+            .map(|g| g.confidence),
+        Some(100)
+    );
+}
+
 #[test]
 fn test_set_command_in_progress() {
     let (mut state, reducer) = setup();
@@ -279,6 +464,7 @@ fn test_update_device_ports_sets_response_ttl() {
         open_ports: vec![Port {
             id: 22,
             service: "ssh".to_string(),
+            ..Port::default()
         }]
         .into_iter()
         .collect::<std::collections::HashSet<_>>()
@@ -295,6 +481,7 @@ fn test_update_device_ports_sets_response_ttl() {
         open_ports: vec![Port {
             id: 80,
             service: "http".to_string(),
+            ..Port::default()
         }]
         .into_iter()
         .collect::<std::collections::HashSet<_>>()
@@ -322,6 +509,7 @@ fn test_updates_device_with_new_info() {
     let port = Port {
         id: 80,
         service: "http".to_string(),
+        ..Port::default()
     };
 
     reducer.reduce(&mut state, Action::AddDevice(dev.clone()));
@@ -4,6 +4,7 @@ use std::{
     collections::{BTreeMap, HashMap, VecDeque},
     net::Ipv4Addr,
     process::Output,
+    time::Duration,
 };
 
 use r_lanlib::scanners::Device;
@@ -18,6 +19,25 @@ pub const MAX_LOGS: usize = 100;
 /// Maximum latency history entries to store per device
 pub const MAX_LATENCY_HISTORY: usize = 100;
 
+/// Progress of the running scan, shown as a gauge in the header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScanProgress {
+    pub sent: usize,
+    pub total: usize,
+    pub eta: Option<Duration>,
+}
+
+impl ScanProgress {
+    /// Returns the fraction of probes sent, between 0 and 1
+    pub fn ratio(&self) -> f64 {
+        if self.total == 0 {
+            return 1.0;
+        }
+
+        (self.sent as f64 / self.total as f64).min(1.0)
+    }
+}
+
 /// Complete application state for the terminal UI.
 #[derive(Debug, Clone)]
 pub struct State {
@@ -31,6 +51,7 @@ pub struct State {
     pub latency_history: HashMap<Ipv4Addr, Vec<u64>>,
     pub colors: Colors,
     pub message: Option<String>,
+    pub scan_progress: Option<ScanProgress>,
     pub cmd_in_progress: Option<Command>,
     pub cmd_output: Option<(Command, Output)>,
     pub popover_message: Option<String>,
@@ -49,6 +70,7 @@ impl Default for State {
             latency_history: Default::default(),
             colors: Default::default(),
             message: Default::default(),
+            scan_progress: Default::default(),
             cmd_in_progress: Default::default(),
             cmd_output: Default::default(),
             popover_message: Default::default(),
@@ -86,6 +108,7 @@ impl State {
             latency_history: HashMap::new(),
             colors,
             message: None,
+            scan_progress: None,
             cmd_in_progress: None,
             cmd_output: None,
             popover_message: None,
@@ -10,12 +10,13 @@ use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Modifier, Style, Stylize},
     text::{Line, Text},
-    widgets::{Block, BorderType, Padding, Paragraph, Tabs, Widget},
+    widgets::{Block, BorderType, LineGauge, Padding, Paragraph, Tabs, Widget},
 };
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, rc::Rc, time::Duration};
 use strum::{Display, EnumIter, FromRepr, IntoEnumIterator};
 
 use crate::{
+    config::Config,
     store::{action::Action, state::State},
     ui::{
         colors::Theme,
@@ -36,6 +37,9 @@ use crate::{
     },
 };
 
+/// Width of the scan progress gauge shown above the status message
+const PROGRESS_GAUGE_WIDTH: u16 = 36;
+
 const LOGO: &str = indoc! {"
 ▖     ▄▖
 ▌ ▀▌▛▌▚ ▛▘▀▌▛▌
@@ -143,9 +147,13 @@ impl App {
             .constraints([Constraint::Min(0), Constraint::Length(1)])
             .areas(network_and_tabs_area);
 
-        let [_, message_area] = Layout::default()
+        let [_, progress_area, message_area] = Layout::default()
             .direction(Direction::Vertical)
-            .constraints([Constraint::Min(0), Constraint::Length(1)])
+            .constraints([
+                Constraint::Min(0),
+                Constraint::Length(1),
+                Constraint::Length(1),
+            ])
             .areas(right);
 
         Block::new().render(left_padding, buf);
@@ -156,6 +164,8 @@ impl App {
 
         self.render_logo(middle, buf, ctx);
 
+        self.render_progress(progress_area, buf, ctx);
+
         self.render_message(message_area, buf, ctx);
     }
 
@@ -244,6 +254,42 @@ impl App {
         }
     }
 
+    fn render_progress(
+        &self,
+        area: Rect,
+        buf: &mut ratatui::prelude::Buffer,
+        ctx: &CustomWidgetContext,
+    ) {
+        let Some(progress) = ctx.state.scan_progress.as_ref() else {
+            return;
+        };
+
+        // line up with the message below, which is padded on the right
+        let [_, gauge_area, _] = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Min(0),
+                Constraint::Length(PROGRESS_GAUGE_WIDTH),
+                Constraint::Length(4),
+            ])
+            .areas(area);
+
+        let eta = progress
+            .eta
+            .map(|d| {
+                humantime::format_duration(Duration::from_secs(d.as_secs()))
+                    .to_string()
+            })
+            .unwrap_or_else(|| "?".into());
+
+        LineGauge::default()
+            .ratio(progress.ratio())
+            .label(format!("{:>3.0}% eta {}", progress.ratio() * 100.0, eta))
+            .filled_style(Style::new().fg(ctx.state.colors.selected_row_fg))
+            .unfilled_style(Style::new().fg(ctx.state.colors.gray))
+            .render(gauge_area, buf);
+    }
+
     fn render_error_popover(
         &self,
         area: Rect,
@@ -292,8 +338,15 @@ impl App {
             let footer = InfoFooter::new(legend.to_string());
             footer.render(area, buf, ctx);
         } else {
-            let mut info =
-                String::from("(ctrl-c) quit | (f) next tab | (d) previous tab");
+            let passive = if ctx.state.config.passive {
+                "on"
+            } else {
+                "off"
+            };
+
+            let mut info = format!(
+                "(ctrl-c) quit | (f) next tab | (d) previous tab | (p) passive: {passive}"
+            );
 
             if !legend.is_empty() {
                 info = format!("{info} | {legend}");
@@ -452,6 +505,13 @@ impl EventHandler for App {
                     self.previous_tab();
                     return Ok(true);
                 }
+                KeyCode::Char('p') => {
+                    ctx.dispatcher.dispatch(Action::UpdateConfig(Config {
+                        passive: !ctx.state.config.passive,
+                        ..ctx.state.config.clone()
+                    }));
+                    return Ok(true);
+                }
                 _ => {}
             }
         }
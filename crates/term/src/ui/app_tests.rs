@@ -6,7 +6,9 @@ use r_lanlib::{
 use ratatui::{Terminal, backend::TestBackend};
 use std::{collections::HashSet, net::Ipv4Addr};
 
-use crate::store::{Dispatcher, StateGetter, Store, reducer::StoreReducer};
+use crate::store::{
+    Dispatcher, StateGetter, Store, reducer::StoreReducer, state::ScanProgress,
+};
 
 use super::*;
 
@@ -18,6 +20,7 @@ fn setup() -> (App, Store) {
     open_ports.insert(Port {
         id: 80,
         service: "http".to_string(),
+        ..Port::default()
     });
 
     let device_1 = Device {
@@ -91,3 +94,35 @@ fn test_app_view_with_popover() {
 
     assert_snapshot!(terminal.backend());
 }
+
+#[test]
+fn test_app_view_with_scan_progress() {
+    let (main_view, store) = setup();
+    let mut terminal = Terminal::new(TestBackend::new(150, 15)).unwrap();
+
+    store.dispatch(Action::UpdateMessage(Some(
+        "SYN scanning in progress...".into(),
+    )));
+    store.dispatch(Action::UpdateScanProgress(Some(ScanProgress {
+        sent: 250,
+        total: 1000,
+        eta: Some(Duration::from_secs(90)),
+    })));
+
+    let state = store.get_state();
+
+    terminal
+        .draw(|frame| {
+            let ctx = CustomWidgetContext {
+                state: &state,
+                app_area: frame.area(),
+            };
+
+            main_view
+                .render_ref(frame.area(), frame.buffer_mut(), &ctx)
+                .unwrap();
+        })
+        .unwrap();
+
+    assert_snapshot!(terminal.backend());
+}
@@ -17,6 +17,8 @@ fn setup() -> (ConfigView, Store) {
         default_ssh_port: 22,
         default_ssh_user: "user".to_string(),
         device_configs: HashMap::new(),
+        passive: false,
+        netbios_names: false,
         ports: vec![],
         theme: "Blue".to_string(),
     };
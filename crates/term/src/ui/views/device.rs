@@ -229,8 +229,28 @@ impl DeviceView {
         let ip = Line::from(ip_str);
         let mac = Line::from(mac_str);
         let vendor = Line::from(vendor_str);
-        let open_ports = Paragraph::new(vec![Line::from(open_ports_str)])
-            .wrap(Wrap { trim: true });
+        let mut port_lines = vec![Line::from(open_ports_str)];
+        if let Some(os_guess) = &self.device.os_guess {
+            port_lines.push(Line::from(format!("OS: {os_guess}")));
+        }
+        if let Some(upnp) = &self.device.upnp {
+            port_lines.push(Line::from(format!(
+                "UPnP: {0} ({1} {2})",
+                upnp.friendly_name, upnp.manufacturer, upnp.model_name
+            )));
+        }
+        if !self.device.services.is_empty() {
+            port_lines.push(Line::from(format!(
+                "Services: {0}",
+                self.device
+                    .services
+                    .iter()
+                    .map(|s| s.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            )));
+        }
+        let open_ports = Paragraph::new(port_lines).wrap(Wrap { trim: true });
         label.render(label_area, buf, ctx);
         host.render(host_area, buf);
         ip.render(ip_area, buf);
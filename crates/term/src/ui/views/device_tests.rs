@@ -22,6 +22,7 @@ fn setup() -> (DeviceView, Store) {
     open_ports.insert(Port {
         id: 80,
         service: "http".to_string(),
+        ..Port::default()
     });
 
     let mut device = Device {
@@ -20,6 +20,7 @@ fn setup() -> (DevicesView, Store) {
     open_ports.insert(Port {
         id: 80,
         service: "http".to_string(),
+        ..Port::default()
     });
 
     let device_1 = Device {
@@ -252,7 +252,15 @@ impl TopologyView {
                 let host_tag =
                     if device.is_current_host { " [YOU]" } else { "" };
 
-                let os_str = device.response_ttl.map(os_class).unwrap_or("—");
+                // fingerprints are far more reliable than the TTL heuristic
+                let os_str = match &device.os_guess {
+                    Some(guess) => truncate(&guess.os, 12),
+                    None => device
+                        .response_ttl
+                        .map(os_class)
+                        .unwrap_or("—")
+                        .to_string(),
+                };
 
                 let hops_str = device
                     .response_ttl